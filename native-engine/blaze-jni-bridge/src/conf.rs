@@ -13,8 +13,9 @@
 // limitations under the License.
 
 use datafusion::common::Result;
+use jni::objects::JObject;
 
-use crate::{jni_call_static, jni_new_string};
+use crate::{jni_call_static, jni_get_string, jni_new_string};
 
 macro_rules! define_conf {
     ($conftype:ty, $name:ident) => {
@@ -43,6 +44,31 @@ define_conf!(DoubleConf, PARTIAL_AGG_SKIPPING_RATIO);
 define_conf!(IntConf, PARTIAL_AGG_SKIPPING_MIN_ROWS);
 define_conf!(BooleanConf, PARQUET_ENABLE_PAGE_FILTERING);
 define_conf!(BooleanConf, PARQUET_ENABLE_BLOOM_FILTER);
+define_conf!(BooleanConf, PARQUET_STRICT_SCHEMA);
+define_conf!(IntConf, PARQUET_METADATA_FETCH_CONCURRENCY);
+define_conf!(LongConf, PARQUET_MAX_METADATA_BYTES);
+define_conf!(IntConf, PARQUET_MAX_OPEN_FILES_PER_PARTITION);
+define_conf!(IntConf, PARQUET_INLINE_READ_THRESHOLD_BYTES);
+define_conf!(LongConf, PARQUET_MAX_BYTES_PER_PARTITION);
+define_conf!(BooleanConf, PARQUET_GEOMETRY_AS_BINARY);
+define_conf!(IntConf, PARQUET_PROJECTED_FOOTER_MIN_COLUMNS);
+define_conf!(LongConf, PARQUET_PREFETCH_MAX_BYTES);
+define_conf!(BooleanConf, PARQUET_QUARANTINE_CORRUPTED_ROW_GROUPS);
+define_conf!(StringConf, PARQUET_FIELD_ID_RESOLUTION);
+define_conf!(LongConf, PARQUET_OUTPUT_BATCH_BYTES);
+define_conf!(BooleanConf, PARQUET_SKIP_NON_NULLABLE_VALIDITY);
+define_conf!(BooleanConf, PARQUET_PICK_FIRST_DUPLICATE_COLUMN);
+define_conf!(IntConf, PARQUET_MAX_ROW_GROUPS_PER_BATCH);
+define_conf!(BooleanConf, PARQUET_NATIVE_OBJECT_STORE);
+define_conf!(BooleanConf, PARQUET_VERIFY_PAGE_CHECKSUMS);
+define_conf!(BooleanConf, PARQUET_WARN_EXTRA_COLUMNS);
+define_conf!(BooleanConf, PARQUET_DICTIONARY_FILTER);
+define_conf!(StringConf, PARQUET_DECODE_CPU_SETS);
+define_conf!(BooleanConf, PARQUET_REUSE_SCRATCH_BUFFERS);
+define_conf!(BooleanConf, CAST_STRING_TO_NUMERIC_LENIENT);
+define_conf!(StringConf, CAST_STRING_TO_NUMERIC_GROUPING_SEPARATOR);
+define_conf!(IntConf, CAST_TIMESTAMP_TO_STRING_FRACTION_DIGITS);
+define_conf!(StringConf, CAST_TIMESTAMP_EPOCH_UNIT);
 
 pub trait BooleanConf {
     fn key(&self) -> &'static str;
@@ -75,3 +101,12 @@ pub trait DoubleConf {
         jni_call_static!(BlazeConf.doubleConf(key.as_obj()) -> f64)
     }
 }
+
+pub trait StringConf {
+    fn key(&self) -> &'static str;
+    fn value(&self) -> Result<String> {
+        let key = jni_new_string!(self.key())?;
+        let value = jni_call_static!(BlazeConf.stringConf(key.as_obj()) -> JObject)?;
+        jni_get_string!(value.as_obj().into())
+    }
+}
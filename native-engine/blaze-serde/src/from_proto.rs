@@ -15,6 +15,7 @@
 //! Serde code to convert from protocol buffers to Rust data structures.
 
 use std::{
+    any::Any,
     convert::{TryFrom, TryInto},
     sync::Arc,
 };
@@ -49,7 +50,7 @@ use datafusion::{
         ColumnStatistics, ExecutionPlan, Partitioning, PhysicalExpr, Statistics,
     },
 };
-use datafusion_ext_commons::downcast_any;
+use datafusion_ext_commons::{cast::TimeParserPolicy, downcast_any};
 use datafusion_ext_exprs::{
     cast::TryCastExpr, get_indexed_field::GetIndexedFieldExpr, get_map_value::GetMapValueExpr,
     named_struct::NamedStructExpr, row_num::RowNumExpr,
@@ -72,7 +73,7 @@ use datafusion_ext_plans::{
     ipc_reader_exec::IpcReaderExec,
     ipc_writer_exec::IpcWriterExec,
     limit_exec::LimitExec,
-    parquet_exec::ParquetExec,
+    parquet_exec::{FileFsResourceId, ParquetExec},
     parquet_sink_exec::ParquetSinkExec,
     project_exec::ProjectExec,
     rename_columns_exec::RenameColumnsExec,
@@ -174,11 +175,15 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     .fold(phys_expr::lit(true), |a, b| {
                         Arc::new(BinaryExpr::new(a, Operator::And, b))
                     });
-                Ok(Arc::new(ParquetExec::new(
-                    conf,
-                    scan.fs_resource_id.clone(),
-                    Some(predicate),
-                )))
+                let mut parquet_exec =
+                    ParquetExec::new(conf, scan.fs_resource_id.clone(), Some(predicate))
+                        .with_case_sensitive(scan.case_sensitive)
+                        .with_record_level_filter(scan.record_level_filter);
+                if !scan.deletion_vectors_resource_id.is_empty() {
+                    parquet_exec = parquet_exec
+                        .with_deletion_vectors(scan.deletion_vectors_resource_id.clone());
+                }
+                Ok(Arc::new(parquet_exec))
             }
             PhysicalPlanType::SortMergeJoin(sort_merge_join) => {
                 let schema = Arc::new(convert_required!(sort_merge_join.schema)?);
@@ -925,7 +930,12 @@ fn try_parse_physical_expr(
             ExprType::TryCast(e) => {
                 let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
                 let cast_type = convert_required!(e.arrow_type)?;
-                Arc::new(TryCastExpr::new(expr, cast_type))
+                let mut try_cast = TryCastExpr::new(expr, cast_type);
+                if !e.time_parser_policy.is_empty() {
+                    let time_parser_policy = TimeParserPolicy::parse(&e.time_parser_policy)?;
+                    try_cast = try_cast.with_time_parser_policy(time_parser_policy);
+                }
+                Arc::new(try_cast)
             }
             ExprType::ScalarFunction(e) => {
                 let scalar_function =
@@ -1073,6 +1083,10 @@ impl TryFrom<&protobuf::PartitionedFile> for PartitionedFile {
     type Error = PlanSerDeError;
 
     fn try_from(val: &protobuf::PartitionedFile) -> Result<Self, Self::Error> {
+        let mut file_extensions: Option<Arc<dyn Any + Send + Sync>> = None;
+        if !val.fs_resource_id.is_empty() {
+            file_extensions = Some(Arc::new(FileFsResourceId(val.fs_resource_id.clone())));
+        }
         Ok(PartitionedFile {
             object_meta: ObjectMeta {
                 location: Path::from(format!("/{}", BASE64_URL_SAFE_NO_PAD.encode(&val.path))),
@@ -1087,7 +1101,7 @@ impl TryFrom<&protobuf::PartitionedFile> for PartitionedFile {
                 .map(|v| v.try_into())
                 .collect::<Result<Vec<_>, _>>()?,
             range: val.range.as_ref().map(|v| v.try_into()).transpose()?,
-            extensions: None,
+            extensions: file_extensions,
         })
     }
 }
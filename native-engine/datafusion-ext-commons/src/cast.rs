@@ -12,37 +12,125 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{str::FromStr, sync::Arc};
+use std::{fmt::Write, str::FromStr, sync::Arc};
 
-use arrow::{array::*, datatypes::*};
-use bigdecimal::{FromPrimitive, ToPrimitive};
+use arrow::{
+    array::{
+        temporal_conversions::{as_datetime, as_datetime_with_timezone},
+        timezone::Tz,
+        *,
+    },
+    buffer::{NullBuffer, OffsetBuffer, ScalarBuffer},
+    datatypes::*,
+};
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode, ToPrimitive};
+use blaze_jni_bridge::{
+    conf,
+    conf::{BooleanConf, IntConf, StringConf},
+    is_jni_bridge_inited,
+};
+use chrono::{DateTime, LocalResult, Offset, TimeDelta, TimeZone};
 use datafusion::common::{
     cast::{as_float32_array, as_float64_array},
     Result,
 };
+use half::f16;
 use num::{cast::AsPrimitive, Bounded, Integer, Signed};
+use once_cell::sync::OnceCell;
 use paste::paste;
 
-use crate::df_execution_err;
+use crate::{df_execution_err, df_unimplemented_err};
 
+/// Casts arrays for internal use, like widening inputs of `agg.sum()`.
+/// timestamps are casted from/to numbers of their raw values, use
+/// [`spark_cast`] for the semantics of spark's `Cast`.
 pub fn cast(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
     return cast_impl(array, cast_type, false);
 }
 
-pub fn cast_scan_input_array(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
-    return cast_impl(array, cast_type, true);
+/// Casts arrays like spark's `Cast`, numbers casted from/to timestamps are
+/// epoch seconds (or millis, see spark.blaze.cast.timestampEpochUnit), and
+/// strings are parsed to dates/timestamps with the given policy of the
+/// session (see spark.sql.legacy.timeParserPolicy).
+pub fn spark_cast(
+    array: &dyn Array,
+    cast_type: &DataType,
+    time_parser_policy: TimeParserPolicy,
+) -> Result<ArrayRef> {
+    let options = CastOptions {
+        match_struct_fields: false,
+        case_sensitive: false,
+        timestamp_epoch_unit: Some(timestamp_epoch_unit()),
+        time_parser_policy,
+    };
+    cast_with_options(array, cast_type, options)
+}
+
+/// Casts arrays read by scans to the table types, struct fields are matched
+/// by names, case-insensitively unless `case_sensitive` (see
+/// spark.sql.caseSensitive).
+pub fn cast_scan_input_array(
+    array: &dyn Array,
+    cast_type: &DataType,
+    case_sensitive: bool,
+) -> Result<ArrayRef> {
+    let options = CastOptions {
+        match_struct_fields: true,
+        case_sensitive,
+        timestamp_epoch_unit: None,
+        time_parser_policy: TimeParserPolicy::default(),
+    };
+    cast_with_options(array, cast_type, options)
 }
 
 pub fn cast_impl(
     array: &dyn Array,
     cast_type: &DataType,
     match_struct_fields: bool,
+) -> Result<ArrayRef> {
+    let options = CastOptions {
+        match_struct_fields,
+        case_sensitive: false,
+        timestamp_epoch_unit: None,
+        time_parser_policy: TimeParserPolicy::default(),
+    };
+    cast_with_options(array, cast_type, options)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CastOptions {
+    /// struct fields are matched by names instead of positions
+    match_struct_fields: bool,
+    /// struct fields are matched case-sensitively by names
+    case_sensitive: bool,
+    /// unit of epoch numbers casted from/to timestamps, or None for raw
+    /// values of timestamps
+    timestamp_epoch_unit: Option<TimestampEpochUnit>,
+    /// policy of parsing strings to dates/timestamps
+    time_parser_policy: TimeParserPolicy,
+}
+
+fn cast_with_options(
+    array: &dyn Array,
+    cast_type: &DataType,
+    options: CastOptions,
 ) -> Result<ArrayRef> {
     Ok(match (&array.data_type(), cast_type) {
         (&t1, t2) if t1 == t2 => make_array(array.to_data()),
 
         (_, &DataType::Null) => Arc::new(NullArray::new(array.len())),
 
+        // arrow 50 only declares the view types, there are no view arrays to
+        // cast from or to
+        (&DataType::Utf8View | &DataType::BinaryView, _)
+        | (_, &DataType::Utf8View | &DataType::BinaryView) => {
+            return df_unimplemented_err!(
+                "cast from {} to {cast_type} is not supported: view arrays are not \
+                 implemented in arrow 50",
+                array.data_type(),
+            );
+        }
+
         // float to int
         (&DataType::Float32, &DataType::Int8) => Arc::new(cast_float_to_integer::<_, Int8Type>(
             as_float32_array(array)?,
@@ -69,35 +157,175 @@ pub fn cast_impl(
             as_float64_array(array)?,
         )),
 
+        // half floats are widened to float32 exactly (including subnormals,
+        // infinities and nans), then casted like float32
+        (&DataType::Float16, _) => {
+            let widened: Float32Array = array.as_primitive::<Float16Type>().unary(|v| v.to_f32());
+            cast_with_options(&widened, cast_type, options)?
+        }
+        (_, &DataType::Float16) => {
+            // rounded to the nearest half float from float64, values out of
+            // range become infinities and tiny values become subnormals/zeros
+            let widened = cast_with_options(array, &DataType::Float64, options)?;
+            let narrowed: Float16Array = widened.as_primitive::<Float64Type>().unary(f16::from_f64);
+            Arc::new(narrowed)
+        }
+
         (&DataType::Utf8, &DataType::Int8)
         | (&DataType::Utf8, &DataType::Int16)
         | (&DataType::Utf8, &DataType::Int32)
         | (&DataType::Utf8, &DataType::Int64) => {
             // spark compatible string to integer cast
-            try_cast_string_array_to_integer(array, cast_type)?
+            let array = trim_numeric_string(array);
+            let array = strip_grouping_separator_if_lenient(&array);
+            try_cast_string_array_to_integer(&array, cast_type)?
         }
-        (&DataType::Utf8, &DataType::Decimal128(..)) => {
+        (&DataType::Utf8, &DataType::Decimal128(..))
+        | (&DataType::Utf8, &DataType::Decimal256(..)) => {
             // spark compatible string to decimal cast
-            try_cast_string_array_to_decimal(array, cast_type)?
+            let array = trim_numeric_string(array);
+            let array = strip_grouping_separator_if_lenient(&array);
+            try_cast_string_array_to_decimal(&array, cast_type)?
+        }
+        (&DataType::Utf8, &DataType::Float32) | (&DataType::Utf8, &DataType::Float64) => {
+            let array = trim_numeric_string(array);
+            let array = strip_grouping_separator_if_lenient(&array);
+            try_cast_string_array_to_float(&array, cast_type)?
         }
-        (&DataType::Decimal128(..), DataType::Utf8) => {
+        (&DataType::Decimal128(..), DataType::Utf8)
+        | (&DataType::Decimal256(..), DataType::Utf8) => {
             // spark compatible decimal to string cast
             try_cast_decimal_array_to_string(array, cast_type)?
         }
-        (&DataType::Timestamp(..), DataType::Float64) => {
-            // timestamp to f64 = timestamp to i64 to f64, only used in agg.sum()
-            arrow::compute::cast(
-                &arrow::compute::cast(array, &DataType::Int64)?,
-                &DataType::Float64,
-            )?
+        (&DataType::Timestamp(unit, _), &DataType::Int8)
+        | (&DataType::Timestamp(unit, _), &DataType::Int16)
+        | (&DataType::Timestamp(unit, _), &DataType::Int32)
+        | (&DataType::Timestamp(unit, _), &DataType::Int64)
+        | (&DataType::Timestamp(unit, _), &DataType::Float32)
+        | (&DataType::Timestamp(unit, _), &DataType::Float64) => {
+            match options.timestamp_epoch_unit {
+                // spark compatible timestamp to epoch seconds (or legacy millis) cast
+                Some(epoch_unit) => cast_timestamp_to_numeric(array, unit, cast_type, epoch_unit)?,
+                // raw values in the unit of the timestamp, like inputs of agg.sum()
+                None => cast_raw_timestamp_values(array, cast_type)?,
+            }
+        }
+        (&DataType::Int8, &DataType::Timestamp(..))
+        | (&DataType::Int16, &DataType::Timestamp(..))
+        | (&DataType::Int32, &DataType::Timestamp(..))
+        | (&DataType::Int64, &DataType::Timestamp(..))
+        | (&DataType::Float32, &DataType::Timestamp(..))
+        | (&DataType::Float64, &DataType::Timestamp(..)) => {
+            match options.timestamp_epoch_unit {
+                // spark compatible epoch seconds (or legacy millis) to timestamp cast
+                Some(epoch_unit) => cast_numeric_to_timestamp(array, cast_type, epoch_unit)?,
+                None => cast_raw_timestamp_values(array, cast_type)?,
+            }
+        }
+        (&DataType::Timestamp(from_unit, _), &DataType::Timestamp(to_unit, _))
+            if from_unit == to_unit =>
+        {
+            // timezone-only change, values are instants since epoch in utc
+            // and are kept as-is like spark, only the timezone is replaced
+            make_array(
+                array
+                    .to_data()
+                    .into_builder()
+                    .data_type(cast_type.clone())
+                    .build()?,
+            )
+        }
+        (&DataType::Timestamp(from_unit, _), &DataType::Timestamp(..)) => {
+            // rescale values between units, overflowed values are casted to null
+            cast_timestamp_unit(array, from_unit, cast_type)?
+        }
+        (&DataType::Timestamp(..), &DataType::Utf8) => {
+            // spark compatible timestamp to string cast
+            cast_timestamp_to_string(array, timestamp_string_fraction_digits())?
+        }
+        (&DataType::Utf8, &DataType::Timestamp(..)) => {
+            // spark compatible string to timestamp/timestamp_ntz cast
+            cast_string_to_timestamp(array, cast_type, options.time_parser_policy)?
+        }
+        (&DataType::Utf8, &DataType::Date32) => {
+            // spark compatible string to date cast
+            cast_string_to_date(array, options.time_parser_policy)?
+        }
+        (&DataType::Utf8, &DataType::Duration(unit)) => {
+            // spark compatible string to interval day to second cast
+            let micros = cast_string_to_day_time_interval(
+                array,
+                DayTimeIntervalField::Day,
+                DayTimeIntervalField::Second,
+            )?;
+            let micros = micros.as_primitive::<DurationMicrosecondType>();
+            match unit {
+                TimeUnit::Second => {
+                    Arc::new(micros.unary::<_, DurationSecondType>(|v| v.div_euclid(1_000_000)))
+                }
+                TimeUnit::Millisecond => {
+                    Arc::new(micros.unary::<_, DurationMillisecondType>(|v| v.div_euclid(1_000)))
+                }
+                TimeUnit::Microsecond => Arc::new(micros.clone()),
+                TimeUnit::Nanosecond => Arc::new(
+                    micros.unary_opt::<_, DurationNanosecondType>(|v| v.checked_mul(1_000)),
+                ),
+            }
+        }
+        (
+            &DataType::Duration(_),
+            &DataType::Int8
+            | &DataType::Int16
+            | &DataType::Int32
+            | &DataType::Int64
+            | &DataType::Float32
+            | &DataType::Float64
+            | &DataType::Decimal128(..)
+            | &DataType::Utf8,
+        ) => {
+            // spark compatible interval day to second (like timestamp
+            // differences) to numeric/string cast
+            cast_day_time_interval(array, cast_type)?
+        }
+        (&DataType::Float32, &DataType::Utf8) | (&DataType::Float64, &DataType::Utf8) => {
+            // spark compatible float to string cast, formatted like java
+            cast_float_to_string(array)?
+        }
+        (
+            &DataType::Int8
+            | &DataType::Int16
+            | &DataType::Int32
+            | &DataType::Int64
+            | &DataType::UInt8
+            | &DataType::UInt16
+            | &DataType::UInt32
+            | &DataType::UInt64
+            | &DataType::Float16
+            | &DataType::Float32
+            | &DataType::Float64
+            | &DataType::Decimal128(..)
+            | &DataType::Decimal256(..),
+            &DataType::Boolean,
+        ) => {
+            // spark compatible numeric to boolean cast
+            cast_numeric_to_boolean(array)?
         }
         (&DataType::Boolean, DataType::Utf8) => {
             // spark compatible boolean to string cast
             try_cast_boolean_array_to_string(array, cast_type)?
         }
+        (&DataType::Utf8, DataType::Boolean) => {
+            // spark compatible string to boolean cast
+            try_cast_string_array_to_boolean(array, cast_type)?
+        }
         (&DataType::List(_), DataType::List(to_field)) => {
             let list = as_list_array(array);
-            let items = cast_impl(list.values(), to_field.data_type(), match_struct_fields)?;
+            let items = cast_with_options(list.values(), to_field.data_type(), options)?;
+            if !to_field.is_nullable() && items.null_count() > 0 {
+                // spark try_cast: lists containing nulls cannot be cast to lists
+                // with non-nullable items, they become nulls
+                return null_lists_with_null_items(list, &items, to_field.clone());
+            }
             make_array(
                 list.to_data()
                     .into_builder()
@@ -109,25 +337,30 @@ pub fn cast_impl(
         (&DataType::Struct(_), DataType::Struct(to_fields)) => {
             let struct_ = as_struct_array(array);
 
-            if !match_struct_fields {
+            if !options.match_struct_fields {
                 if to_fields.len() != struct_.num_columns() {
                     df_execution_err!("cannot cast structs with different numbers of fields")?;
                 }
 
+                let mut null_column_name = vec![];
                 let casted_arrays = struct_
                     .columns()
                     .iter()
                     .zip(to_fields)
                     .map(|(column, to_field)| {
-                        cast_impl(column, to_field.data_type(), match_struct_fields)
+                        try_cast_struct_field(column, to_field, options).unwrap_or_else(|| {
+                            null_column_name.push(to_field.name().clone());
+                            new_null_array(to_field.data_type(), struct_.len())
+                        })
                     })
-                    .collect::<Result<Vec<_>>>()?;
+                    .collect::<Vec<_>>();
+                let casted_fields = nullable_struct_fields(to_fields, &null_column_name);
 
                 make_array(
                     struct_
                         .to_data()
                         .into_builder()
-                        .data_type(DataType::Struct(to_fields.clone()))
+                        .data_type(DataType::Struct(casted_fields))
                         .child_data(
                             casted_arrays
                                 .into_iter()
@@ -141,32 +374,22 @@ pub fn cast_impl(
                 let casted_arrays = to_fields
                     .iter()
                     .map(|field| {
-                        let col = struct_.column_by_name(field.name().as_str());
-                        if col.is_some() {
-                            cast_impl(col.unwrap(), field.data_type(), match_struct_fields)
-                        } else {
+                        let col =
+                            find_struct_column(struct_, field.name(), options.case_sensitive)?;
+                        let casted = col.and_then(|col| try_cast_struct_field(col, field, options));
+                        Ok(casted.unwrap_or_else(|| {
                             null_column_name.push(field.name().clone());
-                            Ok(new_null_array(field.data_type(), struct_.len()))
-                        }
+                            new_null_array(field.data_type(), struct_.len())
+                        }))
                     })
                     .collect::<Result<Vec<_>>>()?;
-
-                let casted_fields = to_fields
-                    .iter()
-                    .map(|field: &FieldRef| {
-                        if null_column_name.contains(field.name()) {
-                            Arc::new(Field::new(field.name(), field.data_type().clone(), true))
-                        } else {
-                            field.clone()
-                        }
-                    })
-                    .collect::<Vec<_>>();
+                let casted_fields = nullable_struct_fields(to_fields, &null_column_name);
 
                 make_array(
                     struct_
                         .to_data()
                         .into_builder()
-                        .data_type(DataType::Struct(Fields::from(casted_fields)))
+                        .data_type(DataType::Struct(casted_fields))
                         .child_data(
                             casted_arrays
                                 .into_iter()
@@ -179,11 +402,7 @@ pub fn cast_impl(
         }
         (&DataType::Map(..), &DataType::Map(ref to_entries_field, to_sorted)) => {
             let map = as_map_array(array);
-            let entries = cast_impl(
-                map.entries(),
-                to_entries_field.data_type(),
-                match_struct_fields,
-            )?;
+            let entries = cast_with_options(map.entries(), to_entries_field.data_type(), options)?;
             make_array(
                 map.to_data()
                     .into_builder()
@@ -192,6 +411,68 @@ pub fn cast_impl(
                     .build()?,
             )
         }
+        (
+            &DataType::Time32(_) | &DataType::Time64(_),
+            &DataType::Time32(_) | &DataType::Time64(_),
+        ) => cast_time_unit(array, cast_type)?,
+        (
+            &DataType::Decimal128(..) | &DataType::Decimal256(..),
+            &DataType::Decimal128(..) | &DataType::Decimal256(..),
+        ) => cast_decimal_to_decimal(array, cast_type)?,
+        (
+            &DataType::Float32 | &DataType::Float64,
+            &DataType::Decimal128(..) | &DataType::Decimal256(..),
+        ) => cast_float_to_decimal(array, cast_type)?,
+        (
+            &DataType::Decimal128(..) | &DataType::Decimal256(..),
+            &DataType::Float32 | &DataType::Float64,
+        ) => cast_decimal_to_float(array, cast_type)?,
+        (
+            &DataType::Int8
+            | &DataType::Int16
+            | &DataType::Int32
+            | &DataType::Int64
+            | &DataType::UInt8
+            | &DataType::UInt16
+            | &DataType::UInt32
+            | &DataType::UInt64,
+            &DataType::Int8 | &DataType::Int16 | &DataType::Int32 | &DataType::Int64,
+        ) => cast_integer_checked(array, cast_type)?,
+        (
+            &DataType::Decimal128(..) | &DataType::Decimal256(..),
+            &DataType::Int8 | &DataType::Int16 | &DataType::Int32 | &DataType::Int64,
+        ) => cast_decimal_to_integer(array, cast_type)?,
+        (&DataType::Binary, DataType::List(to_field))
+            if to_field.data_type() == &DataType::Int8 =>
+        {
+            cast_binary_to_byte_list(array, to_field.clone())?
+        }
+        (&DataType::List(from_field), &DataType::Binary)
+            if from_field.data_type() == &DataType::Int8 =>
+        {
+            cast_byte_list_to_binary(array)?
+        }
+        (&DataType::Utf8, &DataType::LargeUtf8) => {
+            cast_byte_array_offsets::<Utf8Type, LargeUtf8Type>(array)?
+        }
+        (&DataType::LargeUtf8, &DataType::Utf8) => {
+            cast_byte_array_offsets::<LargeUtf8Type, Utf8Type>(array)?
+        }
+        (&DataType::Binary, &DataType::LargeBinary) => {
+            cast_byte_array_offsets::<BinaryType, LargeBinaryType>(array)?
+        }
+        (&DataType::LargeBinary, &DataType::Binary) => {
+            cast_byte_array_offsets::<LargeBinaryType, BinaryType>(array)?
+        }
+        (&DataType::Date64, &DataType::Date32 | &DataType::Timestamp(..) | &DataType::Utf8) => {
+            // date64 is midnight millis of a date, casted like date32
+            let days = cast_date64_to_date32(array);
+            cast_with_options(&days, cast_type, options)?
+        }
+        (&DataType::Date32 | &DataType::Timestamp(..) | &DataType::Utf8, &DataType::Date64) => {
+            let days = cast_with_options(array, &DataType::Date32, options)?;
+            cast_date32_to_date64(&days)
+        }
         _ => {
             // default cast
             arrow::compute::kernels::cast::cast(array, cast_type)?
@@ -199,6 +480,537 @@ pub fn cast_impl(
     })
 }
 
+// casts between string/binary types of different offset widths, values are
+// shared without copying. narrowing fails if the values exceed i32 offsets.
+fn cast_byte_array_offsets<F, T>(array: &dyn Array) -> Result<ArrayRef>
+where
+    F: ByteArrayType,
+    T: ByteArrayType<Native = F::Native>,
+{
+    let array = array.as_bytes::<F>();
+    let offsets = array.offsets();
+    let first = offsets[0].as_usize();
+    let last = offsets[offsets.len() - 1].as_usize();
+    if T::Offset::from_usize(last - first).is_none() {
+        return df_execution_err!(
+            "cannot cast {} to {}: values size {} exceeds the offset limit",
+            F::DATA_TYPE,
+            T::DATA_TYPE,
+            last - first,
+        );
+    }
+    let new_offsets = offsets
+        .iter()
+        .map(|offset| T::Offset::usize_as(offset.as_usize() - first))
+        .collect::<Vec<_>>();
+    let values = array.values().slice_with_length(first, last - first);
+
+    // safety: offsets and values are from a valid array of the same native type
+    Ok(Arc::new(unsafe {
+        GenericByteArray::<T>::new_unchecked(
+            OffsetBuffer::new_unchecked(ScalarBuffer::from(new_offsets)),
+            values,
+            array.nulls().cloned(),
+        )
+    }))
+}
+
+// converts binary to array<tinyint>, bytes are reinterpreted as signed values
+fn cast_binary_to_byte_list(array: &dyn Array, to_field: FieldRef) -> Result<ArrayRef> {
+    let binary = array.as_binary::<i32>();
+    let bytes = binary.values().clone();
+    let items = Int8Array::new(ScalarBuffer::new(bytes.clone(), 0, bytes.len()), None);
+    Ok(Arc::new(ListArray::try_new(
+        to_field,
+        binary.offsets().clone(),
+        Arc::new(items),
+        binary.nulls().cloned(),
+    )?))
+}
+
+// converts array<tinyint> to binary. lists containing null items cannot be
+// represented as bytes, they become nulls
+fn cast_byte_list_to_binary(array: &dyn Array) -> Result<ArrayRef> {
+    let list = as_list_array(array);
+    let items = list.values().as_primitive::<Int8Type>();
+    let offsets = list.offsets();
+    let nulls = match items
+        .nulls()
+        .filter(|item_nulls| item_nulls.null_count() > 0)
+    {
+        Some(item_nulls) => {
+            let valids = (0..list.len())
+                .map(|i| {
+                    let start = offsets[i] as usize;
+                    let len = offsets[i + 1] as usize - start;
+                    list.is_valid(i) && item_nulls.inner().slice(start, len).count_set_bits() == len
+                })
+                .collect::<Vec<_>>();
+            Some(NullBuffer::from(valids))
+        }
+        None => list.nulls().cloned(),
+    };
+    Ok(Arc::new(BinaryArray::try_new(
+        offsets.clone(),
+        items.values().inner().clone(),
+        nulls,
+    )?))
+}
+
+// casts between decimals of any precision and scale. the unscaled value is
+// rescaled with HALF_UP rounding (like spark's Decimal.changePrecision), and
+// values overflowing the target precision become null.
+fn cast_decimal_to_decimal(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let (from_scale, values): (i8, Vec<Option<i256>>) = match array.data_type() {
+        DataType::Decimal128(_, scale) => (
+            *scale,
+            array
+                .as_primitive::<Decimal128Type>()
+                .iter()
+                .map(|v| v.map(i256::from_i128))
+                .collect(),
+        ),
+        DataType::Decimal256(_, scale) => (
+            *scale,
+            array.as_primitive::<Decimal256Type>().iter().collect(),
+        ),
+        other => return df_execution_err!("unsupported decimal type: {other}"),
+    };
+    let (to_precision, to_scale) = match cast_type {
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            (*precision, *scale)
+        }
+        other => return df_execution_err!("unsupported decimal type: {other}"),
+    };
+
+    let scale_delta = to_scale as i32 - from_scale as i32;
+    let factor = i256_pow10(scale_delta.unsigned_abs());
+    let max_unscaled = i256_pow10(to_precision as u32).expect("decimal precision overflow");
+    let two = i256::from_i128(2);
+    let rescale = |v: i256| -> Option<i256> {
+        let rescaled = match factor {
+            Some(factor) if scale_delta >= 0 => v.checked_mul(factor)?,
+            Some(factor) => {
+                let quotient = v.wrapping_div(factor);
+                let remainder = v.wrapping_rem(factor);
+
+                // round half away from zero
+                if remainder.wrapping_abs().wrapping_mul(two) >= factor {
+                    if v.is_negative() {
+                        quotient.wrapping_sub(i256::ONE)
+                    } else {
+                        quotient.wrapping_add(i256::ONE)
+                    }
+                } else {
+                    quotient
+                }
+            }
+            None if scale_delta >= 0 => return None, // overflow
+            None => i256::ZERO,                      // all digits are truncated
+        };
+        let fits = rescaled < max_unscaled && rescaled > max_unscaled.wrapping_neg();
+        fits.then_some(rescaled)
+    };
+
+    Ok(match cast_type {
+        DataType::Decimal128(precision, scale) => Arc::new(
+            values
+                .into_iter()
+                .map(|v| v.and_then(rescale).and_then(|v| v.to_i128()))
+                .collect::<Decimal128Array>()
+                .with_precision_and_scale(*precision, *scale)?,
+        ),
+        DataType::Decimal256(precision, scale) => Arc::new(
+            values
+                .into_iter()
+                .map(|v| v.and_then(rescale))
+                .collect::<Decimal256Array>()
+                .with_precision_and_scale(*precision, *scale)?,
+        ),
+        _ => unreachable!(),
+    })
+}
+
+// casts floats to decimals like spark: a float is converted through its
+// shortest decimal representation (like java's Double.toString) instead of its
+// exact binary value, so 0.1 is rounded to 0.10 and not 0.09. values are
+// rounded HALF_UP to the target scale, and NaN, infinity and values
+// overflowing the target precision become null.
+fn cast_float_to_decimal(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let values: Vec<Option<f64>> = match array.data_type() {
+        // spark casts floats to doubles before converting to decimals
+        DataType::Float32 => array
+            .as_primitive::<Float32Type>()
+            .iter()
+            .map(|v| v.map(|v| v as f64))
+            .collect(),
+        DataType::Float64 => array.as_primitive::<Float64Type>().iter().collect(),
+        other => return df_execution_err!("unsupported float type: {other}"),
+    };
+    let (precision, scale) = match cast_type {
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            (*precision, *scale)
+        }
+        other => return df_execution_err!("unsupported decimal type: {other}"),
+    };
+    let to_decimal = |v: f64| -> Option<BigDecimal> {
+        if !v.is_finite() {
+            return None;
+        }
+        let decimal = BigDecimal::from_str(&v.to_string())
+            .ok()?
+            .with_scale_round(scale as i64, RoundingMode::HalfUp);
+        (decimal.digits() <= precision as u64).then_some(decimal)
+    };
+
+    Ok(match cast_type {
+        DataType::Decimal128(..) => Arc::new(
+            values
+                .into_iter()
+                .map(|v| {
+                    let decimal = to_decimal(v?)?;
+                    decimal.as_bigint_and_exponent().0.to_i128()
+                })
+                .collect::<Decimal128Array>()
+                .with_precision_and_scale(precision, scale)?,
+        ),
+        DataType::Decimal256(..) => Arc::new(
+            values
+                .into_iter()
+                .map(|v| {
+                    let decimal = to_decimal(v?)?;
+                    i256::from_string(&decimal.as_bigint_and_exponent().0.to_string())
+                })
+                .collect::<Decimal256Array>()
+                .with_precision_and_scale(precision, scale)?,
+        ),
+        _ => unreachable!(),
+    })
+}
+
+// casts decimals to the nearest floats like spark's BigDecimal.doubleValue,
+// instead of dividing the unscaled value as a float which may be off by
+// rounding errors.
+fn cast_decimal_to_float(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let (scale, values): (i8, Vec<Option<String>>) = match array.data_type() {
+        DataType::Decimal128(_, scale) => (
+            *scale,
+            array
+                .as_primitive::<Decimal128Type>()
+                .iter()
+                .map(|v| v.map(|v| v.to_string()))
+                .collect(),
+        ),
+        DataType::Decimal256(_, scale) => (
+            *scale,
+            array
+                .as_primitive::<Decimal256Type>()
+                .iter()
+                .map(|v| v.map(|v| v.to_string()))
+                .collect(),
+        ),
+        other => return df_execution_err!("unsupported decimal type: {other}"),
+    };
+
+    // parsing a decimal string is correctly rounded to the nearest float
+    let scientific = |unscaled: String| format!("{unscaled}e{}", -(scale as i32));
+    Ok(match cast_type {
+        DataType::Float32 => Arc::new(
+            values
+                .into_iter()
+                .map(|v| scientific(v?).parse::<f32>().ok())
+                .collect::<Float32Array>(),
+        ),
+        DataType::Float64 => Arc::new(
+            values
+                .into_iter()
+                .map(|v| scientific(v?).parse::<f64>().ok())
+                .collect::<Float64Array>(),
+        ),
+        other => return df_execution_err!("unsupported float type: {other}"),
+    })
+}
+
+// casts integers to signed integers, values not fitting in the target type
+// become null instead of wrapping. unsigned parquet columns are read as wider
+// signed types like spark (UINT_32 as long, UINT_64 as decimal(20, 0)), so
+// values above the signed range of the original type are nulled by narrowing
+// casts, the same as values out of range of the target type.
+fn cast_integer_checked(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    macro_rules! cast_to {
+        ($from:ty) => {{
+            let array = array.as_primitive::<$from>();
+            match cast_type {
+                DataType::Int8 => Arc::new(array.unary_opt::<_, Int8Type>(num::cast)) as ArrayRef,
+                DataType::Int16 => Arc::new(array.unary_opt::<_, Int16Type>(num::cast)),
+                DataType::Int32 => Arc::new(array.unary_opt::<_, Int32Type>(num::cast)),
+                DataType::Int64 => Arc::new(array.unary_opt::<_, Int64Type>(num::cast)),
+                other => return df_execution_err!("unsupported integer type: {other}"),
+            }
+        }};
+    }
+    Ok(match array.data_type() {
+        DataType::Int8 => cast_to!(Int8Type),
+        DataType::Int16 => cast_to!(Int16Type),
+        DataType::Int32 => cast_to!(Int32Type),
+        DataType::Int64 => cast_to!(Int64Type),
+        DataType::UInt8 => cast_to!(UInt8Type),
+        DataType::UInt16 => cast_to!(UInt16Type),
+        DataType::UInt32 => cast_to!(UInt32Type),
+        DataType::UInt64 => cast_to!(UInt64Type),
+        other => return df_execution_err!("unsupported integer type: {other}"),
+    })
+}
+
+// casts decimals to integers, truncating the fraction like spark. values not
+// fitting in the target type become null, like decimal(20, 0) values of
+// UINT_64 columns above i64::MAX.
+fn cast_decimal_to_integer(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let integral: Int64Array = match array.data_type() {
+        &DataType::Decimal128(_, scale) => {
+            let array = array.as_primitive::<Decimal128Type>();
+            let pow10 = 10i128.checked_pow(scale.unsigned_abs() as u32);
+            array.unary_opt::<_, Int64Type>(|v| {
+                let integral = match scale {
+                    0.. => v / pow10?,
+                    _ => v.checked_mul(pow10?)?,
+                };
+                num::cast(integral)
+            })
+        }
+        &DataType::Decimal256(_, scale) => {
+            let array = array.as_primitive::<Decimal256Type>();
+            let pow10 = i256_pow10(scale.unsigned_abs() as u32);
+            array.unary_opt::<_, Int64Type>(|v| {
+                let integral = match scale {
+                    0.. => v.checked_div(pow10?)?,
+                    _ => v.checked_mul(pow10?)?,
+                };
+                num::cast(integral.to_i128()?)
+            })
+        }
+        other => return df_execution_err!("unsupported decimal type: {other}"),
+    };
+    cast_integer_checked(&integral, cast_type)
+}
+
+// returns 10^n, or None if overflowing i256
+fn i256_pow10(n: u32) -> Option<i256> {
+    let ten = i256::from_i128(10);
+    (0..n).try_fold(i256::ONE, |acc, _| acc.checked_mul(ten))
+}
+
+// casts time of day between units, truncating to the lower precision
+fn cast_time_unit(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let nanos_of_day: Int64Array = match array.data_type() {
+        DataType::Time32(TimeUnit::Second) => array
+            .as_primitive::<Time32SecondType>()
+            .unary(|v| v as i64 * 1_000_000_000),
+        DataType::Time32(TimeUnit::Millisecond) => array
+            .as_primitive::<Time32MillisecondType>()
+            .unary(|v| v as i64 * 1_000_000),
+        DataType::Time64(TimeUnit::Microsecond) => array
+            .as_primitive::<Time64MicrosecondType>()
+            .unary(|v| v * 1_000),
+        DataType::Time64(TimeUnit::Nanosecond) => {
+            array.as_primitive::<Time64NanosecondType>().unary(|v| v)
+        }
+        other => return df_execution_err!("unsupported time type: {other}"),
+    };
+    Ok(match cast_type {
+        DataType::Time32(TimeUnit::Second) => Arc::new(
+            nanos_of_day.unary::<_, Time32SecondType>(|v| v.div_euclid(1_000_000_000) as i32),
+        ),
+        DataType::Time32(TimeUnit::Millisecond) => Arc::new(
+            nanos_of_day.unary::<_, Time32MillisecondType>(|v| v.div_euclid(1_000_000) as i32),
+        ),
+        DataType::Time64(TimeUnit::Microsecond) => {
+            Arc::new(nanos_of_day.unary::<_, Time64MicrosecondType>(|v| v.div_euclid(1_000)))
+        }
+        DataType::Time64(TimeUnit::Nanosecond) => {
+            Arc::new(nanos_of_day.unary::<_, Time64NanosecondType>(|v| v))
+        }
+        other => return df_execution_err!("unsupported time type: {other}"),
+    })
+}
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+fn cast_date64_to_date32(array: &dyn Array) -> ArrayRef {
+    let days: Date32Array = array
+        .as_primitive::<Date64Type>()
+        .unary(|millis| millis.div_euclid(MILLIS_PER_DAY) as i32);
+    Arc::new(days)
+}
+
+fn cast_date32_to_date64(array: &dyn Array) -> ArrayRef {
+    let millis: Date64Array = array
+        .as_primitive::<Date32Type>()
+        .unary(|days| days as i64 * MILLIS_PER_DAY);
+    Arc::new(millis)
+}
+
+// casts a struct field like a scalar try-cast: widening always succeeds,
+// narrowing produces nulls on overflow, and incompatible fields (like struct
+// to int) are casted to null fields instead of failing the whole struct.
+fn try_cast_struct_field(
+    column: &ArrayRef,
+    to_field: &FieldRef,
+    options: CastOptions,
+) -> Option<ArrayRef> {
+    cast_with_options(column, to_field.data_type(), options).ok()
+}
+
+// makes fields filled with nulls nullable
+fn nullable_struct_fields(to_fields: &Fields, null_column_names: &[String]) -> Fields {
+    to_fields
+        .iter()
+        .map(|field: &FieldRef| {
+            if null_column_names.contains(field.name()) {
+                Arc::new(Field::new(field.name(), field.data_type().clone(), true))
+            } else {
+                field.clone()
+            }
+        })
+        .collect()
+}
+
+/// finds the column of a struct by name. in case-insensitive mode, fields
+/// differing only in case are ambiguous and cannot be matched.
+fn find_struct_column<'a>(
+    struct_: &'a StructArray,
+    name: &str,
+    case_sensitive: bool,
+) -> Result<Option<&'a ArrayRef>> {
+    if case_sensitive {
+        return Ok(struct_.column_by_name(name));
+    }
+    let name = name.to_lowercase();
+    let mut matched = struct_
+        .column_names()
+        .into_iter()
+        .zip(struct_.columns())
+        .filter(|(column_name, _)| column_name.to_lowercase() == name);
+
+    match (matched.next(), matched.next()) {
+        (Some((_, column)), None) => Ok(Some(column)),
+        (None, _) => Ok(None),
+        (Some((name1, _)), Some((name2, _))) => {
+            df_execution_err!(
+                "found ambiguous struct fields in case-insensitive mode: {name1}, {name2}"
+            )
+        }
+    }
+}
+
+/// grouping separator stripped from strings before casting to numeric types.
+/// spark returns null for strings like "1,234", so it is only enabled in
+/// lenient mode.
+fn numeric_grouping_separator() -> Option<char> {
+    static GROUPING_SEPARATOR: OnceCell<Option<char>> = OnceCell::new();
+    *GROUPING_SEPARATOR.get_or_init(|| {
+        if !is_jni_bridge_inited() {
+            return None; // for testing
+        }
+        let lenient = conf::CAST_STRING_TO_NUMERIC_LENIENT
+            .value()
+            .expect("error getting conf CAST_STRING_TO_NUMERIC_LENIENT");
+        if !lenient {
+            return None;
+        }
+        conf::CAST_STRING_TO_NUMERIC_GROUPING_SEPARATOR
+            .value()
+            .expect("error getting conf CAST_STRING_TO_NUMERIC_GROUPING_SEPARATOR")
+            .chars()
+            .next()
+    })
+}
+
+// trims leading/trailing whitespaces and control characters like spark's
+// UTF8String.trimAll, whitespace-only strings are casted to null
+fn trim_numeric_string(array: &dyn Array) -> ArrayRef {
+    let array = array.as_string::<i32>();
+    let trimmed: StringArray = array
+        .iter()
+        .map(|s| {
+            s.map(|s| s.trim_matches(|c: char| c.is_ascii_whitespace() || c.is_ascii_control()))
+                .filter(|s| !s.is_empty())
+        })
+        .collect();
+    Arc::new(trimmed)
+}
+
+fn strip_grouping_separator_if_lenient(array: &dyn Array) -> ArrayRef {
+    match numeric_grouping_separator() {
+        Some(separator) => Arc::new(strip_grouping_separator(array.as_string(), separator)),
+        None => make_array(array.to_data()),
+    }
+}
+
+fn strip_grouping_separator(array: &StringArray, separator: char) -> StringArray {
+    array
+        .iter()
+        .map(|s| s.map(|s| s.replace(separator, "")))
+        .collect()
+}
+
+// casts strings to floats like spark, accepting special literals of infinity
+// and NaN, other non-numeric forms become nulls
+fn try_cast_string_array_to_float(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let casted = arrow::compute::cast(array, cast_type)?;
+    let strings = array.as_string::<i32>();
+    Ok(match cast_type {
+        DataType::Float32 => Arc::new(with_float_special_literals::<Float32Type>(
+            strings,
+            casted.as_primitive(),
+            |v| v as f32,
+        )),
+        DataType::Float64 => Arc::new(with_float_special_literals::<Float64Type>(
+            strings,
+            casted.as_primitive(),
+            |v| v,
+        )),
+        other => return df_execution_err!("unsupported float type: {other}"),
+    })
+}
+
+fn with_float_special_literals<T: ArrowPrimitiveType>(
+    strings: &StringArray,
+    casted: &PrimitiveArray<T>,
+    from_f64: impl Fn(f64) -> T::Native,
+) -> PrimitiveArray<T> {
+    strings
+        .iter()
+        .zip(casted.iter())
+        .map(|(s, v)| {
+            let s = s?;
+            if let Some(special) = parse_float_special_literal(s) {
+                return Some(from_f64(special));
+            }
+            // special forms not accepted by spark, like `-nan`
+            if !s.bytes().any(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            v
+        })
+        .collect()
+}
+
+// special literals accepted by spark: case-insensitive forms from
+// Cast.processFloatingPointSpecialLiterals, and signed NaN accepted by
+// java's Double.parseDouble
+fn parse_float_special_literal(s: &str) -> Option<f64> {
+    if s == "+NaN" || s == "-NaN" {
+        return Some(f64::NAN);
+    }
+    match s.to_ascii_lowercase().as_str() {
+        "inf" | "+inf" | "infinity" | "+infinity" => Some(f64::INFINITY),
+        "-inf" | "-infinity" => Some(f64::NEG_INFINITY),
+        "nan" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
 fn try_cast_string_array_to_integer(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
     macro_rules! cast {
         ($target_type:ident) => {{
@@ -245,16 +1057,37 @@ fn try_cast_string_array_to_decimal(array: &dyn Array, cast_type: &DataType) ->
                 .with_precision_and_scale(precision, scale)?,
         ));
     }
+    if let &DataType::Decimal256(precision, scale) = cast_type {
+        let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        return Ok(Arc::new(
+            array
+                .iter()
+                .map(|v| to_decimal256(v?, precision, scale))
+                .collect::<Decimal256Array>()
+                .with_precision_and_scale(precision, scale)?,
+        ));
+    }
     unreachable!("cast_type must be DataType::Decimal")
 }
 
 fn try_cast_decimal_array_to_string(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
     if let &DataType::Utf8 = cast_type {
+        if let DataType::Decimal256(_, scale) = array.data_type() {
+            let scale = *scale;
+            return Ok(Arc::new(
+                array
+                    .as_primitive::<Decimal256Type>()
+                    .iter()
+                    .map(|v| Some(decimal_to_string(v?, scale)))
+                    .collect::<StringArray>(),
+            ));
+        }
         let array = array.as_any().downcast_ref::<Decimal128Array>().unwrap();
+        let scale = array.scale();
         let mut builder = StringBuilder::new();
         for v in 0..array.len() {
             if array.is_valid(v) {
-                builder.append_value(array.value_as_string(v))
+                builder.append_value(decimal_to_string(array.value(v), scale))
             } else {
                 builder.append_null()
             }
@@ -264,6 +1097,121 @@ fn try_cast_decimal_array_to_string(array: &dyn Array, cast_type: &DataType) ->
     unreachable!("cast_type must be DataType::Utf8")
 }
 
+// renders unscaled value with exactly the declared number of fractional
+// digits, like spark's Decimal.toString (1.50 for 150 with scale 2)
+fn decimal_to_string(unscaled: impl ToString, scale: i8) -> String {
+    let unscaled = unscaled.to_string();
+    let (sign, digits) = match unscaled.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", unscaled.as_str()),
+    };
+
+    if scale <= 0 {
+        if digits == "0" {
+            return unscaled;
+        }
+        return format!("{sign}{digits}{}", "0".repeat(-scale as usize));
+    }
+    let scale = scale as usize;
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (integral, fractional) = digits.split_at(digits.len() - scale);
+    format!("{sign}{integral}.{fractional}")
+}
+
+fn cast_float_to_string(array: &dyn Array) -> Result<ArrayRef> {
+    let strings: StringArray = match array.data_type() {
+        DataType::Float32 => array
+            .as_primitive::<Float32Type>()
+            .iter()
+            .map(|v| v.map(|v| java_float_to_string(v as f64, &format!("{v:e}"))))
+            .collect(),
+        DataType::Float64 => array
+            .as_primitive::<Float64Type>()
+            .iter()
+            .map(|v| v.map(|v| java_float_to_string(v, &format!("{v:e}"))))
+            .collect(),
+        _ => unreachable!("array must be Float32 or Float64"),
+    };
+    Ok(Arc::new(strings))
+}
+
+// formats a float like java's Double.toString/Float.toString, using the
+// shortest digits (`sci`, formatted with `{:e}`) of the original float type.
+// magnitudes in [1e-3, 1e7) are in decimal notation (like 100.0), others are
+// in scientific notation (like 1.0E10).
+fn java_float_to_string(value: f64, sci: &str) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if value == 0.0 {
+        return if value.is_sign_negative() {
+            "-0.0"
+        } else {
+            "0.0"
+        }
+        .to_string();
+    }
+
+    let (mantissa, exp) = sci.split_once('e').expect("invalid scientific notation");
+    let exp: i32 = exp.parse().expect("invalid scientific notation");
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+
+    if (1e-3..1e7).contains(&value.abs()) {
+        if exp < 0 {
+            return format!("{sign}0.{}{digits}", "0".repeat((-exp - 1) as usize));
+        }
+        let integral_len = exp as usize + 1;
+        let digits = format!("{digits:0<integral_len$}");
+        let (integral, fractional) = digits.split_at(integral_len);
+        let fractional = if fractional.is_empty() {
+            "0"
+        } else {
+            fractional
+        };
+        return format!("{sign}{integral}.{fractional}");
+    }
+    let (first, rest) = digits.split_at(1);
+    let rest = if rest.is_empty() { "0" } else { rest };
+    format!("{sign}{first}.{rest}E{exp}")
+}
+
+// only exact zeros (including -0.0) are false like spark, other values
+// including NaN are true
+fn cast_numeric_to_boolean(array: &dyn Array) -> Result<ArrayRef> {
+    fn nonzero<T: ArrowPrimitiveType>(
+        array: &dyn Array,
+        is_nonzero: impl Fn(T::Native) -> bool,
+    ) -> ArrayRef {
+        Arc::new(BooleanArray::from_unary(
+            array.as_primitive::<T>(),
+            is_nonzero,
+        ))
+    }
+    Ok(match array.data_type() {
+        DataType::Int8 => nonzero::<Int8Type>(array, |v| v != 0),
+        DataType::Int16 => nonzero::<Int16Type>(array, |v| v != 0),
+        DataType::Int32 => nonzero::<Int32Type>(array, |v| v != 0),
+        DataType::Int64 => nonzero::<Int64Type>(array, |v| v != 0),
+        DataType::UInt8 => nonzero::<UInt8Type>(array, |v| v != 0),
+        DataType::UInt16 => nonzero::<UInt16Type>(array, |v| v != 0),
+        DataType::UInt32 => nonzero::<UInt32Type>(array, |v| v != 0),
+        DataType::UInt64 => nonzero::<UInt64Type>(array, |v| v != 0),
+        DataType::Float16 => nonzero::<Float16Type>(array, |v| v.to_f32() != 0.0),
+        DataType::Float32 => nonzero::<Float32Type>(array, |v| v != 0.0),
+        DataType::Float64 => nonzero::<Float64Type>(array, |v| v != 0.0),
+        DataType::Decimal128(..) => nonzero::<Decimal128Type>(array, |v| v != 0),
+        DataType::Decimal256(..) => nonzero::<Decimal256Type>(array, |v| v != i256::ZERO),
+        other => return df_execution_err!("unsupported numeric type: {other}"),
+    })
+}
+
 fn try_cast_boolean_array_to_string(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
     if let &DataType::Utf8 = cast_type {
         let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
@@ -277,53 +1225,839 @@ fn try_cast_boolean_array_to_string(array: &dyn Array, cast_type: &DataType) ->
     unreachable!("cast_type must be DataType::Utf8")
 }
 
-fn cast_float_to_integer<F: ArrowPrimitiveType, T: ArrowPrimitiveType>(
-    array: &PrimitiveArray<F>,
-) -> PrimitiveArray<T>
-where
-    F::Native: AsPrimitive<T::Native>,
-{
-    arrow::compute::unary(array, |v| v.as_())
+fn try_cast_string_array_to_boolean(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    if let &DataType::Boolean = cast_type {
+        let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        return Ok(Arc::new(
+            array
+                .iter()
+                .map(|value| value.and_then(to_boolean))
+                .collect::<BooleanArray>(),
+        ));
+    }
+    unreachable!("cast_type must be DataType::Boolean")
 }
 
-// this implementation is original copied from spark UTF8String.scala
-fn to_integer<T: Bounded + FromPrimitive + Integer + Signed + Copy>(input: &str) -> Option<T> {
-    let bytes = input.as_bytes();
-
-    if bytes.is_empty() {
-        return None;
+// see org.apache.spark.sql.catalyst.util.StringUtils.isTrueString/isFalseString
+fn to_boolean(input: &str) -> Option<bool> {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "t" | "true" | "y" | "yes" | "1" => Some(true),
+        "f" | "false" | "n" | "no" | "0" => Some(false),
+        _ => None,
     }
+}
 
-    let b = bytes[0];
-    let negative = b == b'-';
-    let mut offset = 0;
+fn null_lists_with_null_items(
+    list: &ListArray,
+    items: &ArrayRef,
+    to_field: FieldRef,
+) -> Result<ArrayRef> {
+    let mut valids = Vec::with_capacity(list.len());
+    let mut items_mask = vec![false; items.len()];
+    let mut offsets = Vec::with_capacity(list.len() + 1);
+    offsets.push(0i32);
 
-    if negative || b == b'+' {
-        offset += 1;
-        if bytes.len() == 1 {
-            return None;
+    for (i, range) in list.value_offsets().windows(2).enumerate() {
+        let range = range[0] as usize..range[1] as usize;
+        let valid = list.is_valid(i) && !range.clone().any(|j| items.is_null(j));
+        if valid {
+            items_mask[range.clone()].fill(true);
         }
+        let len = if valid { range.len() as i32 } else { 0 };
+        offsets.push(offsets.last().unwrap() + len);
+        valids.push(valid);
     }
+    let items = arrow::compute::filter(items, &BooleanArray::from(items_mask))?;
+    Ok(Arc::new(ListArray::try_new(
+        to_field,
+        OffsetBuffer::new(offsets.into()),
+        items,
+        Some(NullBuffer::from(valids)),
+    )?))
+}
 
-    let separator = b'.';
-    let radix = T::from_usize(10).unwrap();
-    let stop_value = T::min_value() / radix;
-    let mut result = T::zero();
+fn timestamp_units_per_second(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    }
+}
 
-    while offset < bytes.len() {
-        let b = bytes[offset];
-        offset += 1;
-        if b == separator {
-            // We allow decimals and will return a truncated integral in that case.
-            // Therefore we won't throw an exception here (checking the fractional
-            // part happens below.)
-            break;
-        }
+/// Unit of epoch numbers casted from/to timestamps. spark uses seconds, while
+/// some legacy jobs treat `cast(timestamp as long)` as milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampEpochUnit {
+    Seconds,
+    Milliseconds,
+}
 
-        let digit = if b.is_ascii_digit() {
-            b - b'0'
-        } else {
-            return None;
+impl TimestampEpochUnit {
+    pub fn parse(unit: &str) -> Result<Self> {
+        Ok(match unit.to_ascii_uppercase().as_str() {
+            "SECONDS" => TimestampEpochUnit::Seconds,
+            "MILLISECONDS" => TimestampEpochUnit::Milliseconds,
+            _ => return df_execution_err!("invalid timestamp epoch unit: {unit}"),
+        })
+    }
+
+    fn units_per_second(&self) -> i64 {
+        match self {
+            TimestampEpochUnit::Seconds => 1,
+            TimestampEpochUnit::Milliseconds => 1_000,
+        }
+    }
+}
+
+fn timestamp_epoch_unit() -> TimestampEpochUnit {
+    static EPOCH_UNIT: OnceCell<TimestampEpochUnit> = OnceCell::new();
+    *EPOCH_UNIT.get_or_init(|| {
+        if !is_jni_bridge_inited() {
+            return TimestampEpochUnit::Seconds; // for testing
+        }
+        let unit = conf::CAST_TIMESTAMP_EPOCH_UNIT
+            .value()
+            .expect("error getting conf CAST_TIMESTAMP_EPOCH_UNIT");
+        TimestampEpochUnit::parse(&unit).expect("error parsing conf CAST_TIMESTAMP_EPOCH_UNIT")
+    })
+}
+
+// casts between timestamps and numbers of their raw values, through i64
+fn cast_raw_timestamp_values(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let values = arrow::compute::cast(array, &DataType::Int64)?;
+    Ok(arrow::compute::cast(&values, cast_type)?)
+}
+
+fn cast_timestamp_to_numeric(
+    array: &dyn Array,
+    unit: &TimeUnit,
+    cast_type: &DataType,
+    epoch_unit: TimestampEpochUnit,
+) -> Result<ArrayRef> {
+    let units_per_second = timestamp_units_per_second(unit);
+    let epoch_units_per_second = epoch_unit.units_per_second();
+    let values = arrow::compute::cast(array, &DataType::Int64)?;
+    let values = values.as_primitive::<Int64Type>();
+
+    // long: floor of epoch seconds (or millis), double: epoch seconds with
+    // fraction
+    Ok(match cast_type {
+        DataType::Float32 | DataType::Float64 => {
+            let seconds: Float64Array = if units_per_second >= epoch_units_per_second {
+                let factor = (units_per_second / epoch_units_per_second) as f64;
+                arrow::compute::unary(values, |v| v as f64 / factor)
+            } else {
+                let factor = (epoch_units_per_second / units_per_second) as f64;
+                arrow::compute::unary(values, |v| v as f64 * factor)
+            };
+            arrow::compute::cast(&seconds, cast_type)?
+        }
+        _ => {
+            let seconds: Int64Array = if units_per_second >= epoch_units_per_second {
+                let factor = units_per_second / epoch_units_per_second;
+                arrow::compute::unary(values, |v| v.div_euclid(factor))
+            } else {
+                let factor = epoch_units_per_second / units_per_second;
+                arrow::compute::unary(values, |v| v.saturating_mul(factor))
+            };
+            match cast_type {
+                DataType::Int8 => Arc::new(cast_primitive_as::<_, Int8Type>(&seconds)),
+                DataType::Int16 => Arc::new(cast_primitive_as::<_, Int16Type>(&seconds)),
+                DataType::Int32 => Arc::new(cast_primitive_as::<_, Int32Type>(&seconds)),
+                _ => Arc::new(seconds),
+            }
+        }
+    })
+}
+
+fn cast_numeric_to_timestamp(
+    array: &dyn Array,
+    cast_type: &DataType,
+    epoch_unit: TimestampEpochUnit,
+) -> Result<ArrayRef> {
+    let units_per_second = match cast_type {
+        DataType::Timestamp(unit, _) => timestamp_units_per_second(unit),
+        _ => unreachable!("cast_type must be DataType::Timestamp"),
+    };
+    let epoch_units_per_second = epoch_unit.units_per_second();
+
+    let values: Int64Array = match array.data_type() {
+        DataType::Float32 | DataType::Float64 => {
+            let values = arrow::compute::cast(array, &DataType::Float64)?;
+            values
+                .as_primitive::<Float64Type>()
+                .iter()
+                .map(|v| {
+                    v.filter(|v| v.is_finite()).map(|v| {
+                        (v * units_per_second as f64 / epoch_units_per_second as f64) as i64
+                    })
+                })
+                .collect()
+        }
+        _ => {
+            let values = arrow::compute::cast(array, &DataType::Int64)?;
+            let values = values.as_primitive::<Int64Type>();
+            if units_per_second >= epoch_units_per_second {
+                let factor = units_per_second / epoch_units_per_second;
+                arrow::compute::unary(values, |v| v.saturating_mul(factor))
+            } else {
+                let factor = epoch_units_per_second / units_per_second;
+                arrow::compute::unary(values, |v| v.div_euclid(factor))
+            }
+        }
+    };
+    Ok(arrow::compute::cast(&values, cast_type)?)
+}
+
+fn cast_timestamp_unit(
+    array: &dyn Array,
+    from_unit: &TimeUnit,
+    cast_type: &DataType,
+) -> Result<ArrayRef> {
+    let from_units_per_second = timestamp_units_per_second(from_unit);
+    let to_units_per_second = match cast_type {
+        DataType::Timestamp(unit, _) => timestamp_units_per_second(unit),
+        _ => unreachable!("cast_type must be DataType::Timestamp"),
+    };
+
+    let values = arrow::compute::cast(array, &DataType::Int64)?;
+    let values = values.as_primitive::<Int64Type>();
+    let values: Int64Array = if to_units_per_second >= from_units_per_second {
+        let factor = to_units_per_second / from_units_per_second;
+        values.unary_opt(|v| v.checked_mul(factor))
+    } else {
+        let factor = from_units_per_second / to_units_per_second;
+        values.unary(|v| v.div_euclid(factor))
+    };
+
+    // timezone of the target type is kept as-is
+    Ok(arrow::compute::cast(&values, cast_type)?)
+}
+
+/// fixed number of fractional second digits of timestamps casted to strings,
+/// or None if trailing zeros are trimmed like spark.
+fn timestamp_string_fraction_digits() -> Option<usize> {
+    static FRACTION_DIGITS: OnceCell<Option<usize>> = OnceCell::new();
+    *FRACTION_DIGITS.get_or_init(|| {
+        if !is_jni_bridge_inited() {
+            return None; // for testing
+        }
+        let fraction_digits = conf::CAST_TIMESTAMP_TO_STRING_FRACTION_DIGITS
+            .value()
+            .expect("error getting conf CAST_TIMESTAMP_TO_STRING_FRACTION_DIGITS");
+        (fraction_digits >= 0).then_some(fraction_digits as usize)
+    })
+}
+
+// formats timestamps as `yyyy-MM-dd HH:mm:ss[.SSSSSS]` in the timezone of the
+// type, like spark. the fractional part is printed to microseconds with
+// trailing zeros trimmed (and omitted if zero), or with a fixed number of
+// digits if specified
+fn cast_timestamp_to_string(array: &dyn Array, fraction_digits: Option<usize>) -> Result<ArrayRef> {
+    let DataType::Timestamp(unit, tz) = array.data_type() else {
+        return df_execution_err!("expect timestamp type, got {}", array.data_type());
+    };
+    let micros = cast_timestamp_unit(
+        array,
+        unit,
+        &DataType::Timestamp(TimeUnit::Microsecond, tz.clone()),
+    )?;
+    let micros = micros.as_primitive::<TimestampMicrosecondType>();
+    let tz = tz.as_ref().map(|tz| tz.parse::<Tz>()).transpose()?;
+
+    let strings: StringArray = micros
+        .iter()
+        .map(|v| {
+            let v = v?;
+            let datetime = match tz {
+                Some(tz) => {
+                    as_datetime_with_timezone::<TimestampMicrosecondType>(v, tz)?.naive_local()
+                }
+                None => as_datetime::<TimestampMicrosecondType>(v)?,
+            };
+            let mut s = datetime.format("%Y-%m-%d %H:%M:%S").to_string();
+            let fraction = format!("{:06}", v.rem_euclid(1_000_000));
+            match fraction_digits {
+                None => {
+                    let trimmed = fraction.trim_end_matches('0');
+                    if !trimmed.is_empty() {
+                        s.push('.');
+                        s.push_str(trimmed);
+                    }
+                }
+                Some(0) => {}
+                Some(n) => {
+                    s.push('.');
+                    s.push_str(&format!("{fraction:0<n$}")[..n]);
+                }
+            }
+            Some(s)
+        })
+        .collect();
+    Ok(Arc::new(strings))
+}
+
+/// Policy of parsing strings to dates and timestamps, like spark's
+/// `spark.sql.legacy.timeParserPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TimeParserPolicy {
+    /// out-of-range fields roll over like the lenient `SimpleDateFormat` of
+    /// spark 2.x, e.g. `2023-02-29` is parsed as 2023-03-01
+    Legacy,
+    /// strings with out-of-range fields are parsed to null
+    Corrected,
+    /// same as [`TimeParserPolicy::Corrected`] for casts, the default of spark
+    #[default]
+    Exception,
+}
+
+impl TimeParserPolicy {
+    pub fn parse(policy: &str) -> Result<Self> {
+        Ok(match policy.to_ascii_uppercase().as_str() {
+            "LEGACY" => TimeParserPolicy::Legacy,
+            "CORRECTED" => TimeParserPolicy::Corrected,
+            "EXCEPTION" => TimeParserPolicy::Exception,
+            _ => return df_execution_err!("invalid time parser policy: {policy}"),
+        })
+    }
+}
+
+// timestamps with a timezone are parsed in the timezone of the type (the
+// session timezone) unless the string has an embedded zone
+fn cast_string_to_timestamp(
+    array: &dyn Array,
+    cast_type: &DataType,
+    policy: TimeParserPolicy,
+) -> Result<ArrayRef> {
+    let lenient = policy == TimeParserPolicy::Legacy;
+    let (units_per_second, tz) = match cast_type {
+        DataType::Timestamp(unit, tz) => (
+            timestamp_units_per_second(unit),
+            tz.as_ref().map(|tz| tz.parse::<Tz>()).transpose()?,
+        ),
+        _ => unreachable!("cast_type must be DataType::Timestamp"),
+    };
+    let nanos_per_unit = 1_000_000_000 / units_per_second;
+
+    let values: Int64Array = array
+        .as_string::<i32>()
+        .iter()
+        .map(|v| {
+            let (seconds, nanos) = match &tz {
+                Some(tz) => to_timestamp(v?, tz, lenient)?,
+                None => to_timestamp_ntz(v?, lenient)?,
+            };
+            seconds
+                .checked_mul(units_per_second)?
+                .checked_add(nanos as i64 / nanos_per_unit)
+        })
+        .collect();
+    Ok(arrow::compute::cast(&values, cast_type)?)
+}
+
+/// parses a timestamp string into seconds and nanoseconds since epoch (UTC).
+/// the wall-clock time is in the embedded zone (like `Z`, `UTC+1`, `+08:00` or
+/// `America/Los_Angeles`) if present, otherwise in the session timezone.
+/// missing time parts default to midnight, like spark.
+///
+/// supported formats are the same as [`to_timestamp_ntz`].
+fn to_timestamp(input: &str, session_tz: &Tz, lenient: bool) -> Option<(i64, u32)> {
+    let (seconds, nanos, zone) = parse_timestamp(input, lenient)?;
+    let tz = match zone {
+        "" => *session_tz,
+        "Z" => "+00:00".parse().ok()?,
+        _ => {
+            // UTC/GMT/UT prefixed offsets like `UTC+08:00`
+            let offset = ["UTC", "GMT", "UT"]
+                .iter()
+                .find_map(|prefix| zone.strip_prefix(prefix))
+                .filter(|offset| offset.starts_with(|c| c == '+' || c == '-'));
+            match offset {
+                Some(offset) => offset.parse().ok()?,
+                None => zone.parse().ok()?,
+            }
+        }
+    };
+
+    // local times in daylight saving gaps are shifted forward by the gap, and
+    // the earlier offset is used for overlaps, like java's ZonedDateTime
+    let local = DateTime::from_timestamp(seconds, 0)?.naive_utc();
+    let offset = match tz.offset_from_local_datetime(&local) {
+        LocalResult::Single(offset) | LocalResult::Ambiguous(offset, _) => offset,
+        LocalResult::None => tz
+            .offset_from_local_datetime(&(local - TimeDelta::days(1)))
+            .earliest()?,
+    };
+    Some((seconds - offset.fix().local_minus_utc() as i64, nanos))
+}
+
+/// parses the wall-clock time of a timestamp string into seconds and
+/// nanoseconds since epoch, without any timezone adjustment. like spark's
+/// cast to timestamp_ntz, an embedded zone (like `Z`, `+08:00` or
+/// `America/Los_Angeles`) is accepted but ignored.
+///
+/// supported formats: `yyyy`, `yyyy-[m]m`, `yyyy-[m]m-[d]d`,
+/// `yyyy-[m]m-[d]d[T| ][h]h:[m]m[:[s]s[.fffffffff]][zone]`
+///
+/// out-of-range fields (like hour 24 or day 32) are rolled over if lenient,
+/// otherwise the string is invalid.
+fn to_timestamp_ntz(input: &str, lenient: bool) -> Option<(i64, u32)> {
+    let (seconds, nanos, _zone) = parse_timestamp(input, lenient)?;
+    Some((seconds, nanos))
+}
+
+fn cast_string_to_date(array: &dyn Array, policy: TimeParserPolicy) -> Result<ArrayRef> {
+    let lenient = policy == TimeParserPolicy::Legacy;
+    let days: Date32Array = array
+        .as_string::<i32>()
+        .iter()
+        .map(|v| to_date(v?, lenient))
+        .collect();
+    Ok(Arc::new(days))
+}
+
+/// parses a date string into days since epoch, like spark's `stringToDate`.
+/// returns None for malformed strings and for invalid dates like month 13 or
+/// day 32.
+///
+/// supported formats: `[+-]yyyy*`, `[+-]yyyy*-[m]m`, `[+-]yyyy*-[m]m-[d]d`,
+/// `[+-]yyyy*-[m]m-[d]d[T| ]*`, where the year has 4 to 7 digits, so two-digit
+/// years are rejected. anything following the date and a `T` or space is
+/// ignored. out-of-range months and days are rolled over if lenient.
+fn to_date(input: &str, lenient: bool) -> Option<i32> {
+    fn parse_digits(s: &str, min_len: usize, max_len: usize) -> Option<u32> {
+        if s.len() < min_len || s.len() > max_len || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    }
+
+    let input = input.trim_matches(|c: char| c.is_whitespace() || c.is_control());
+    let (sign, input) = match input.as_bytes().first() {
+        Some(b'-') => (-1, &input[1..]),
+        Some(b'+') => (1, &input[1..]),
+        _ => (1, input),
+    };
+    let (date, has_suffix) = match input.find(|c| c == 'T' || c == ' ') {
+        Some(pos) => (&input[..pos], true),
+        None => (input, false),
+    };
+
+    let mut date_parts = date.splitn(3, '-');
+    let year = parse_digits(date_parts.next()?, 4, 7)? as i64 * sign;
+    let month = date_parts.next().map(|s| parse_digits(s, 1, 2));
+    let day = date_parts.next().map(|s| parse_digits(s, 1, 2));
+
+    // `yyyy` and `yyyy-[m]m` must not be followed by anything
+    if day.is_none() && has_suffix {
+        return None;
+    }
+    let month = month.unwrap_or(Some(1))?;
+    let day = day.unwrap_or(Some(1))?;
+    if lenient {
+        return lenient_days_from_civil(year, month, day).try_into().ok();
+    }
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    days_from_civil(year, month, day).try_into().ok()
+}
+
+// parses a timestamp string into the wall-clock seconds and nanoseconds since
+// epoch, and the embedded zone (empty if absent)
+fn parse_timestamp(input: &str, lenient: bool) -> Option<(i64, u32, &str)> {
+    fn parse_digits(s: &str, min_len: usize, max_len: usize) -> Option<u32> {
+        if s.len() < min_len || s.len() > max_len || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    }
+
+    let input = input.trim();
+    let (date, time) = match input.find(|c| c == 'T' || c == ' ') {
+        Some(pos) => (&input[..pos], Some(&input[pos + 1..])),
+        None => (input, None),
+    };
+
+    // date part
+    let mut date_parts = date.splitn(3, '-');
+    let year = parse_digits(date_parts.next()?, 4, 6)? as i64;
+    let month = date_parts
+        .next()
+        .map_or(Some(1), |s| parse_digits(s, 1, 2))?;
+    let day = date_parts
+        .next()
+        .map_or(Some(1), |s| parse_digits(s, 1, 2))?;
+    let days = if lenient {
+        lenient_days_from_civil(year, month, day)
+    } else {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+        days_from_civil(year, month, day)
+    };
+    let mut seconds = days * 86400;
+    let mut nanos = 0;
+    let mut zone = "";
+
+    // time part, followed by an optional zone which is ignored
+    if let Some(time) = time.filter(|time| !time.is_empty()) {
+        let time_len = time
+            .find(|c: char| !c.is_ascii_digit() && c != ':' && c != '.')
+            .unwrap_or(time.len());
+        let (time, time_zone) = time.split_at(time_len);
+        zone = time_zone.trim_start();
+        if !zone.is_empty()
+            && !zone.starts_with(|c: char| c == '+' || c == '-' || c.is_alphabetic())
+        {
+            return None;
+        }
+
+        let mut time_parts = time.splitn(3, ':');
+        let hour = parse_digits(time_parts.next()?, 1, 2)?;
+        let minute = parse_digits(time_parts.next()?, 1, 2)?;
+        let (second, fraction) = match time_parts.next() {
+            Some(s) => match s.split_once('.') {
+                Some((second, fraction)) => (parse_digits(second, 1, 2)?, fraction),
+                None => (parse_digits(s, 1, 2)?, ""),
+            },
+            None => (0, ""),
+        };
+        if !lenient && (hour > 23 || minute > 59 || second > 59) {
+            return None;
+        }
+        if !fraction.is_empty() {
+            let fraction = &fraction[..fraction.len().min(9)];
+            nanos = parse_digits(fraction, 1, 9)? * 10u32.pow(9 - fraction.len() as u32);
+        }
+        seconds += (hour * 3600 + minute * 60 + second) as i64;
+    }
+    Some((seconds, nanos, zone))
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since epoch of a date with out-of-range fields rolled over like a
+// lenient java calendar, e.g. 2023-02-29 is 2023-03-01, month 13 is january
+// of the next year and day 0 is the last day of the previous month
+fn lenient_days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let months = year * 12 + month as i64 - 1;
+    let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+    days_from_civil(year, month, 1) + day as i64 - 1
+}
+
+// days since epoch of the given proleptic gregorian date, see
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// fields of spark day-time interval qualifiers, like `DAY TO SECOND`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DayTimeIntervalField {
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl DayTimeIntervalField {
+    const ALL: [Self; 4] = [Self::Day, Self::Hour, Self::Minute, Self::Second];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Hour => "hour",
+            Self::Minute => "minute",
+            Self::Second => "second",
+        }
+    }
+
+    fn micros(&self) -> i64 {
+        match self {
+            Self::Day => 86_400_000_000,
+            Self::Hour => 3_600_000_000,
+            Self::Minute => 60_000_000,
+            Self::Second => 1_000_000,
+        }
+    }
+
+    // max value of the field if it is not the leading field
+    fn max_value(&self) -> i64 {
+        match self {
+            Self::Day => i64::MAX,
+            Self::Hour => 23,
+            Self::Minute | Self::Second => 59,
+        }
+    }
+}
+
+/// casts strings to spark day-time intervals of the given qualifier, stored
+/// as microseconds in `Duration(Microsecond)`. malformed strings become nulls.
+///
+/// both the bare form (like `1 02:03:04.567` for `DAY TO SECOND`) and the
+/// ansi literal form (like `INTERVAL '1 02:03:04.567' DAY TO SECOND`) are
+/// supported.
+pub fn cast_string_to_day_time_interval(
+    array: &dyn Array,
+    start_field: DayTimeIntervalField,
+    end_field: DayTimeIntervalField,
+) -> Result<ArrayRef> {
+    let array = array.as_string::<i32>();
+    let values: DurationMicrosecondArray = array
+        .iter()
+        .map(|v| to_day_time_interval(v?, start_field, end_field))
+        .collect();
+    Ok(Arc::new(values))
+}
+
+fn to_day_time_interval(
+    input: &str,
+    start_field: DayTimeIntervalField,
+    end_field: DayTimeIntervalField,
+) -> Option<i64> {
+    let input = input.trim();
+
+    // ansi literal form: INTERVAL [+|-]'<bare form>' <qualifier>
+    if input.len() >= 8 && input[..8].eq_ignore_ascii_case("interval") {
+        let rest = input[8..].trim_start();
+        let (negative, rest) = strip_sign(rest);
+        let rest = rest.strip_prefix('\'')?;
+        let (value, qualifier) = rest.split_once('\'')?;
+        if parse_day_time_qualifier(qualifier)? != (start_field, end_field) {
+            return None;
+        }
+        let micros = to_day_time_interval(value, start_field, end_field)?;
+        return if negative {
+            micros.checked_neg()
+        } else {
+            Some(micros)
+        };
+    }
+
+    // bare form: [+|-]d h:m:s.n, with fields from start_field to end_field
+    let (negative, mut rest) = strip_sign(input);
+    let mut micros = 0i64;
+    for field in DayTimeIntervalField::ALL {
+        if field < start_field || field > end_field {
+            continue;
+        }
+        if field != start_field {
+            let separator = if start_field == DayTimeIntervalField::Day
+                && field == DayTimeIntervalField::Hour
+            {
+                ' '
+            } else {
+                ':'
+            };
+            rest = rest.strip_prefix(separator)?;
+        }
+
+        let num_digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if num_digits == 0 {
+            return None;
+        }
+        let value: i64 = rest[..num_digits].parse().ok()?;
+        rest = &rest[num_digits..];
+        if field != start_field && value > field.max_value() {
+            return None;
+        }
+        micros = micros.checked_add(value.checked_mul(field.micros())?)?;
+
+        // fractional seconds, truncated to microseconds
+        if field == DayTimeIntervalField::Second {
+            if let Some(fraction) = rest.strip_prefix('.') {
+                let num_digits = fraction
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(fraction.len());
+                if num_digits == 0 || num_digits > 9 {
+                    return None;
+                }
+                let fraction_micros = fraction[..num_digits.min(6)].parse::<i64>().ok()?
+                    * 10i64.pow(6 - num_digits.min(6) as u32);
+                micros = micros.checked_add(fraction_micros)?;
+                rest = &fraction[num_digits..];
+            }
+        }
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+    if negative {
+        micros.checked_neg()
+    } else {
+        Some(micros)
+    }
+}
+
+fn strip_sign(input: &str) -> (bool, &str) {
+    if let Some(rest) = input.strip_prefix('-') {
+        (true, rest)
+    } else {
+        (false, input.strip_prefix('+').unwrap_or(input))
+    }
+}
+
+fn parse_day_time_qualifier(
+    qualifier: &str,
+) -> Option<(DayTimeIntervalField, DayTimeIntervalField)> {
+    let parse_field = |name: &str| {
+        DayTimeIntervalField::ALL
+            .into_iter()
+            .find(|field| field.name().eq_ignore_ascii_case(name))
+    };
+    let words = qualifier.split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        [field] => parse_field(field).map(|field| (field, field)),
+        [start, to, end] if to.eq_ignore_ascii_case("to") => {
+            let (start, end) = (parse_field(start)?, parse_field(end)?);
+            (start < end).then_some((start, end))
+        }
+        _ => None,
+    }
+}
+
+/// casts spark day-time intervals (`DAY TO SECOND`, like differences of
+/// timestamps) in durations of any unit. like spark, integers are the number
+/// of whole seconds truncated towards zero (nulls if overflowed), decimals
+/// are the exact seconds, and strings are ansi interval literals like
+/// `INTERVAL '1 02:03:04.567' DAY TO SECOND`. floats are the seconds with
+/// fractions.
+fn cast_day_time_interval(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let micros: DurationMicrosecondArray = match array.data_type() {
+        DataType::Duration(TimeUnit::Second) => array
+            .as_primitive::<DurationSecondType>()
+            .unary_opt(|v| v.checked_mul(1_000_000)),
+        DataType::Duration(TimeUnit::Millisecond) => array
+            .as_primitive::<DurationMillisecondType>()
+            .unary_opt(|v| v.checked_mul(1_000)),
+        DataType::Duration(TimeUnit::Microsecond) => {
+            array.as_primitive::<DurationMicrosecondType>().clone()
+        }
+        DataType::Duration(TimeUnit::Nanosecond) => array
+            .as_primitive::<DurationNanosecondType>()
+            .unary(|v| v.div_euclid(1_000)),
+        other => return df_execution_err!("expect duration, got {other:?}"),
+    };
+
+    Ok(match cast_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+            let seconds: Int64Array = micros.unary(|v| v / 1_000_000);
+            cast_integer_checked(&seconds, cast_type)?
+        }
+        DataType::Float32 | DataType::Float64 => {
+            let seconds: Float64Array = micros.unary(|v| v as f64 / 1_000_000.0);
+            arrow::compute::kernels::cast::cast(&seconds, cast_type)?
+        }
+        DataType::Decimal128(..) => {
+            let seconds = micros
+                .unary::<_, Decimal128Type>(|v| v as i128)
+                .with_precision_and_scale(19, 6)?;
+            cast_decimal_to_decimal(&seconds, cast_type)?
+        }
+        DataType::Utf8 => {
+            let strings: StringArray = micros
+                .iter()
+                .map(|v| v.map(to_day_time_interval_string))
+                .collect();
+            Arc::new(strings)
+        }
+        other => return df_execution_err!("cannot cast interval day to second to {other:?}"),
+    })
+}
+
+// formats microseconds like spark: INTERVAL '[-]d hh:mm:ss[.ffffff]' DAY TO
+// SECOND, with trailing zeros of the fraction trimmed
+fn to_day_time_interval_string(micros: i64) -> String {
+    let sign = if micros < 0 { "-" } else { "" };
+    let micros = micros.unsigned_abs(); // no overflow for i64::MIN
+    let days = micros / 86_400_000_000;
+    let hours = micros / 3_600_000_000 % 24;
+    let minutes = micros / 60_000_000 % 60;
+    let seconds = micros / 1_000_000 % 60;
+    let fraction = micros % 1_000_000;
+
+    let mut string = format!("INTERVAL '{sign}{days} {hours:02}:{minutes:02}:{seconds:02}");
+    if fraction > 0 {
+        string.push_str(format!(".{fraction:06}").trim_end_matches('0'));
+    }
+    string.push_str("' DAY TO SECOND");
+    string
+}
+
+fn cast_primitive_as<F: ArrowPrimitiveType, T: ArrowPrimitiveType>(
+    array: &PrimitiveArray<F>,
+) -> PrimitiveArray<T>
+where
+    F::Native: AsPrimitive<T::Native>,
+{
+    arrow::compute::unary(array, |v| v.as_())
+}
+
+fn cast_float_to_integer<F: ArrowPrimitiveType, T: ArrowPrimitiveType>(
+    array: &PrimitiveArray<F>,
+) -> PrimitiveArray<T>
+where
+    F::Native: AsPrimitive<T::Native>,
+{
+    arrow::compute::unary(array, |v| v.as_())
+}
+
+// this implementation is original copied from spark UTF8String.scala
+fn to_integer<T: Bounded + FromPrimitive + Integer + Signed + Copy>(input: &str) -> Option<T> {
+    let bytes = input.as_bytes();
+
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let b = bytes[0];
+    let negative = b == b'-';
+    let mut offset = 0;
+
+    if negative || b == b'+' {
+        offset += 1;
+        if bytes.len() == 1 {
+            return None;
+        }
+    }
+
+    let separator = b'.';
+    let radix = T::from_usize(10).unwrap();
+    let stop_value = T::min_value() / radix;
+    let mut result = T::zero();
+
+    while offset < bytes.len() {
+        let b = bytes[offset];
+        offset += 1;
+        if b == separator {
+            // We allow decimals and will return a truncated integral in that case.
+            // Therefore we won't throw an exception here (checking the fractional
+            // part happens below.)
+            break;
+        }
+
+        let digit = if b.is_ascii_digit() {
+            b - b'0'
+        } else {
+            return None;
         };
 
         // We are going to process the new digit and accumulate the result. However,
@@ -375,19 +2109,321 @@ fn to_decimal(input: &str, precision: u8, scale: i8) -> Option<i128> {
         })
 }
 
-#[cfg(test)]
-mod test {
-    use datafusion::common::cast::as_int32_array;
+// parses a decimal with up to 76 digits, rounded HALF_UP to the target scale
+// like spark's Decimal.changePrecision. values overflowing the target
+// precision become null.
+fn to_decimal256(input: &str, precision: u8, scale: i8) -> Option<i256> {
+    let decimal = BigDecimal::from_str(input)
+        .ok()?
+        .with_scale_round(scale as i64, RoundingMode::HalfUp);
+    if decimal.digits() > precision as u64 {
+        return None;
+    }
+    i256::from_string(&decimal.as_bigint_and_exponent().0.to_string())
+}
 
-    use crate::cast::*;
+/// Casts an array (usually of a complex type) to json strings like spark's
+/// `to_json`, unlike the bracket format of spark's string cast. structs and
+/// maps are rendered as objects, lists as arrays. null struct fields are
+/// omitted, while null list items and map values are rendered as `null`.
+///
+/// numbers and booleans are rendered as json literals (non-finite floats as
+/// strings like jackson), other values as json strings of their string casts.
+/// strings are escaped like jackson: `"`, `\` and control characters only.
+pub fn cast_to_json_string(array: &dyn Array) -> Result<ArrayRef> {
+    let encoder = JsonEncoder::try_new(array)?;
+    let mut builder = StringBuilder::with_capacity(array.len(), 0);
+    let mut json = String::new();
+    for i in 0..array.len() {
+        if encoder.is_null(i) {
+            builder.append_null();
+            continue;
+        }
+        json.clear();
+        encoder.encode(i, &mut json);
+        builder.append_value(&json);
+    }
+    Ok(Arc::new(builder.finish()))
+}
 
-    #[test]
-    fn test_float_to_int() {
-        let f64_array: ArrayRef = Arc::new(Float64Array::from_iter(vec![
-            None,
-            Some(123.456),
-            Some(987.654),
-            Some(i32::MAX as f64 + 10000.0),
+struct JsonEncoder {
+    nulls: Option<NullBuffer>,
+    kind: JsonKind,
+}
+
+enum JsonKind {
+    Literal(StringArray),
+    String(StringArray),
+    List(Vec<usize>, Box<JsonEncoder>),
+    Struct(Vec<(String, JsonEncoder)>),
+    Map(Vec<usize>, Box<JsonEncoder>, Box<JsonEncoder>),
+}
+
+impl JsonEncoder {
+    fn try_new(array: &dyn Array) -> Result<Self> {
+        let offsets =
+            |offsets: &[i32]| -> Vec<usize> { offsets.iter().map(|&o| o as usize).collect() };
+        let kind = match array.data_type() {
+            DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Decimal128(..)
+            | DataType::Decimal256(..) => {
+                JsonKind::Literal(cast(array, &DataType::Utf8)?.as_string::<i32>().clone())
+            }
+            DataType::List(_) => {
+                let list = array.as_list::<i32>();
+                JsonKind::List(
+                    offsets(list.value_offsets()),
+                    Box::new(Self::try_new(list.values())?),
+                )
+            }
+            DataType::LargeList(_) => {
+                let list = array.as_list::<i64>();
+                JsonKind::List(
+                    list.value_offsets().iter().map(|&o| o as usize).collect(),
+                    Box::new(Self::try_new(list.values())?),
+                )
+            }
+            DataType::Struct(fields) => {
+                let struct_ = array.as_struct();
+                JsonKind::Struct(
+                    fields
+                        .iter()
+                        .zip(struct_.columns())
+                        .map(|(field, column)| Ok((field.name().clone(), Self::try_new(column)?)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            DataType::Map(..) => {
+                let map = array.as_map();
+                JsonKind::Map(
+                    offsets(map.value_offsets()),
+                    Box::new(Self::try_new(map.keys())?),
+                    Box::new(Self::try_new(map.values())?),
+                )
+            }
+            _ => JsonKind::String(cast(array, &DataType::Utf8)?.as_string::<i32>().clone()),
+        };
+        Ok(Self {
+            nulls: array.logical_nulls(),
+            kind,
+        })
+    }
+
+    fn is_null(&self, i: usize) -> bool {
+        self.nulls.as_ref().is_some_and(|nulls| nulls.is_null(i))
+    }
+
+    fn encode(&self, i: usize, out: &mut String) {
+        if self.is_null(i) {
+            out.push_str("null");
+            return;
+        }
+        match &self.kind {
+            JsonKind::Literal(values) => {
+                let value = values.value(i);
+                if value.ends_with("NaN") || value.ends_with("Infinity") {
+                    write_json_string(value, out);
+                } else {
+                    out.push_str(value);
+                }
+            }
+            JsonKind::String(values) => write_json_string(values.value(i), out),
+            JsonKind::List(offsets, items) => {
+                out.push('[');
+                for j in offsets[i]..offsets[i + 1] {
+                    if j > offsets[i] {
+                        out.push(',');
+                    }
+                    items.encode(j, out);
+                }
+                out.push(']');
+            }
+            JsonKind::Struct(fields) => {
+                out.push('{');
+                let mut first = true;
+                for (name, field) in fields.iter().filter(|(_, field)| !field.is_null(i)) {
+                    if !std::mem::take(&mut first) {
+                        out.push(',');
+                    }
+                    write_json_string(name, out);
+                    out.push(':');
+                    field.encode(i, out);
+                }
+                out.push('}');
+            }
+            JsonKind::Map(offsets, keys, values) => {
+                out.push('{');
+                for j in offsets[i]..offsets[i + 1] {
+                    if j > offsets[i] {
+                        out.push(',');
+                    }
+                    // non-string keys are rendered as strings of their json
+                    let mut key = String::new();
+                    keys.encode(j, &mut key);
+                    match &keys.kind {
+                        JsonKind::String(_) => out.push_str(&key),
+                        _ => write_json_string(&key, out),
+                    }
+                    out.push(':');
+                    values.encode(j, out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_json_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::buffer::Buffer;
+    use datafusion::common::{
+        cast::{as_float64_array, as_int32_array, as_int64_array},
+        DataFusionError,
+    };
+
+    use crate::cast::*;
+
+    #[test]
+    fn test_narrowing_former_unsigned_integers() {
+        let u32_max = u32::MAX as i64;
+
+        // UINT_32 read as long
+        let long_array: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(u32_max),
+            Some(i32::MAX as i64),
+            Some(i32::MAX as i64 + 1),
+            Some(-1),
+            None,
+        ]));
+        let casted = cast(&long_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from(vec![None, Some(i32::MAX), None, Some(-1), None]),
+        );
+        let casted = cast(&long_array, &DataType::Int16).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Int16Type>(),
+            &Int16Array::from(vec![None, None, None, Some(-1), None]),
+        );
+
+        // unsigned arrays
+        let u32_array: ArrayRef = Arc::new(UInt32Array::from(vec![u32::MAX, 1]));
+        let casted = cast(&u32_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from(vec![None, Some(1)]),
+        );
+        let casted = cast(&u32_array, &DataType::Int64).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![u32_max, 1]),
+        );
+        let u64_array: ArrayRef = Arc::new(UInt64Array::from(vec![u64::MAX, i64::MAX as u64]));
+        let casted = cast(&u64_array, &DataType::Int64).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![None, Some(i64::MAX)]),
+        );
+    }
+
+    #[test]
+    fn test_narrowing_former_unsigned_decimals() {
+        // UINT_64 read as decimal(20, 0)
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![
+                Some(u64::MAX as i128),
+                Some(i64::MAX as i128 + 1),
+                Some(i64::MAX as i128),
+                Some(u32::MAX as i128),
+                Some(7),
+                None,
+            ])
+            .with_precision_and_scale(20, 0)
+            .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Int64).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![
+                None,
+                None,
+                Some(i64::MAX),
+                Some(u32::MAX as i64),
+                Some(7),
+                None,
+            ]),
+        );
+        let casted = cast(&decimal_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from(vec![None, None, None, None, Some(7), None]),
+        );
+
+        // fractions are truncated
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![Some(-1299), Some(1299)])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Int8).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Int8Type>(),
+            &Int8Array::from(vec![-12, 12]),
+        );
+
+        // decimal256
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal256Array::from(vec![
+                Some(i256::from_i128(u64::MAX as i128)),
+                Some(i256::from_i128(-42)),
+            ])
+            .with_precision_and_scale(40, 0)
+            .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Int64).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![None, Some(-42)]),
+        );
+    }
+
+    #[test]
+    fn test_float_to_int() {
+        let f64_array: ArrayRef = Arc::new(Float64Array::from_iter(vec![
+            None,
+            Some(123.456),
+            Some(987.654),
+            Some(i32::MAX as f64 + 10000.0),
             Some(i32::MIN as f64 - 10000.0),
             Some(f64::INFINITY),
             Some(f64::NEG_INFINITY),
@@ -410,4 +2446,2294 @@ mod test {
             ])
         );
     }
+
+    #[test]
+    fn test_float16_to_numeric() {
+        let f16_array: ArrayRef = Arc::new(Float16Array::from(vec![
+            None,
+            Some(f16::from_f32(1.5)),
+            Some(f16::from_f32(-2.75)),
+            Some(f16::MAX),
+            Some(f16::MIN),
+            Some(f16::MIN_POSITIVE_SUBNORMAL),
+            Some(f16::INFINITY),
+            Some(f16::NEG_INFINITY),
+            Some(f16::NAN),
+        ]));
+
+        // widened exactly
+        let casted = cast(&f16_array, &DataType::Float64).unwrap();
+        let f64_array = as_float64_array(&casted).unwrap();
+        assert!(f64_array.is_null(0));
+        assert_eq!(
+            f64_array.values()[1..8],
+            [
+                1.5,
+                -2.75,
+                65504.0,
+                -65504.0,
+                2f64.powi(-24),
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ],
+        );
+        assert!(f64_array.value(8).is_nan());
+        let casted = cast(&f16_array, &DataType::Float32).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Float32Type>().value(5),
+            2f32.powi(-24)
+        );
+
+        // truncated like float32, saturated on overflow, nan as zero
+        let casted = cast(&f16_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from(vec![
+                None,
+                Some(1),
+                Some(-2),
+                Some(65504),
+                Some(-65504),
+                Some(0),
+                Some(i32::MAX),
+                Some(i32::MIN),
+                Some(0),
+            ]),
+        );
+        let casted = cast(&f16_array, &DataType::Int16).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Int16Type>(),
+            &Int16Array::from(vec![
+                None,
+                Some(1),
+                Some(-2),
+                Some(i16::MAX),
+                Some(i16::MIN),
+                Some(0),
+                Some(i16::MAX),
+                Some(i16::MIN),
+                Some(0),
+            ]),
+        );
+
+        // decimals, non-finite values are null
+        let casted = cast(&f16_array, &DataType::Decimal128(10, 2)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![
+                None,
+                Some(150),
+                Some(-275),
+                Some(6550400),
+                Some(-6550400),
+                Some(0),
+                None,
+                None,
+                None,
+            ])
+            .with_precision_and_scale(10, 2)
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_numeric_to_float16() {
+        // rounded to nearest, overflowed to infinities, tiny values to
+        // subnormals or zeros
+        let f64_array: ArrayRef = Arc::new(Float64Array::from(vec![
+            None,
+            Some(0.1),
+            Some(65504.0),
+            Some(65520.0),
+            Some(-1e10),
+            Some(1e-7),
+            Some(1e-10),
+            Some(f64::NAN),
+        ]));
+        let casted = cast(&f64_array, &DataType::Float16).unwrap();
+        let f16_array = casted.as_primitive::<Float16Type>();
+        assert!(f16_array.is_null(0));
+        assert_eq!(f16_array.value(1), f16::from_f32(0.1));
+        assert_eq!(f16_array.value(2), f16::MAX);
+        assert_eq!(f16_array.value(3), f16::INFINITY);
+        assert_eq!(f16_array.value(4), f16::NEG_INFINITY);
+        assert!(!f16_array.value(5).is_normal() && f16_array.value(5) > f16::ZERO);
+        assert_eq!(f16_array.value(6), f16::ZERO);
+        assert!(f16_array.value(7).is_nan());
+
+        // integrals and decimals
+        let i64_array: ArrayRef = Arc::new(Int64Array::from(vec![Some(2049), Some(-7), None]));
+        let casted = cast(&i64_array, &DataType::Float16).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Float16Type>(),
+            &Float16Array::from(vec![
+                Some(f16::from_f32(2048.0)), // nearest even
+                Some(f16::from_f32(-7.0)),
+                None,
+            ]),
+        );
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![Some(12345), Some(-50)])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Float16).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Float16Type>(),
+            &Float16Array::from(vec![f16::from_f64(123.45), f16::from_f32(-0.5)]),
+        );
+
+        // round trip from strings
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![Some("1.5"), Some("abc")]));
+        let casted = cast(&string_array, &DataType::Float16).unwrap();
+        let casted = cast(&casted, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![Some("1.5"), None]),
+        );
+    }
+
+    #[test]
+    fn test_timestamp_to_numeric() {
+        let ts_array: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            None,
+            Some(0),
+            Some(1_500_000),
+            Some(-1_500_000),
+            Some(1_700_000_000_123_456),
+        ]));
+
+        let casted = spark_cast(&ts_array, &DataType::Int64, TimeParserPolicy::default()).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![None, Some(0), Some(1), Some(-2), Some(1_700_000_000)])
+        );
+
+        let casted =
+            spark_cast(&ts_array, &DataType::Float64, TimeParserPolicy::default()).unwrap();
+        assert_eq!(
+            as_float64_array(&casted).unwrap(),
+            &Float64Array::from(vec![
+                None,
+                Some(0.0),
+                Some(1.5),
+                Some(-1.5),
+                Some(1_700_000_000.123456),
+            ])
+        );
+
+        let ms_array: ArrayRef = Arc::new(TimestampMillisecondArray::from(vec![Some(-1)]));
+        let casted = spark_cast(&ms_array, &DataType::Int64, TimeParserPolicy::default()).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![Some(-1)])
+        );
+    }
+
+    #[test]
+    fn test_raw_timestamp_values() {
+        // internal casts like inputs of agg.sum() keep raw values
+        let ts_array: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            None,
+            Some(1_500_000),
+            Some(-1),
+        ]));
+        let casted = cast(&ts_array, &DataType::Float64).unwrap();
+        assert_eq!(
+            as_float64_array(&casted).unwrap(),
+            &Float64Array::from(vec![None, Some(1_500_000.0), Some(-1.0)])
+        );
+        let casted = cast(&ts_array, &DataType::Int64).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![None, Some(1_500_000), Some(-1)])
+        );
+
+        let ts_type = DataType::Timestamp(TimeUnit::Microsecond, None);
+        let round_trip = cast(&casted, &ts_type).unwrap();
+        assert_eq!(&round_trip, &ts_array);
+    }
+
+    #[test]
+    fn test_numeric_to_timestamp() {
+        let ts_type = DataType::Timestamp(TimeUnit::Microsecond, None);
+
+        let i64_array: ArrayRef = Arc::new(Int64Array::from(vec![None, Some(1), Some(-2)]));
+        let casted = spark_cast(&i64_array, &ts_type, TimeParserPolicy::default()).unwrap();
+        assert_eq!(
+            casted.as_primitive::<TimestampMicrosecondType>(),
+            &TimestampMicrosecondArray::from(vec![None, Some(1_000_000), Some(-2_000_000)])
+        );
+        let round_trip =
+            spark_cast(&casted, &DataType::Int64, TimeParserPolicy::default()).unwrap();
+        assert_eq!(&round_trip, &i64_array);
+
+        let f64_array: ArrayRef = Arc::new(Float64Array::from(vec![
+            None,
+            Some(1.5),
+            Some(-1.5),
+            Some(f64::NAN),
+        ]));
+        let casted = spark_cast(&f64_array, &ts_type, TimeParserPolicy::default()).unwrap();
+        assert_eq!(
+            casted.as_primitive::<TimestampMicrosecondType>(),
+            &TimestampMicrosecondArray::from(vec![None, Some(1_500_000), Some(-1_500_000), None])
+        );
+        let round_trip =
+            spark_cast(&casted, &DataType::Float64, TimeParserPolicy::default()).unwrap();
+        assert_eq!(
+            as_float64_array(&round_trip).unwrap(),
+            &Float64Array::from(vec![None, Some(1.5), Some(-1.5), None])
+        );
+    }
+
+    #[test]
+    fn test_timestamp_epoch_millis() {
+        let ts_type = DataType::Timestamp(TimeUnit::Microsecond, None);
+        let millis = TimestampEpochUnit::Milliseconds;
+        let ts_array: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            None,
+            Some(1_500_000),
+            Some(-1_500),
+            Some(1_700_000_000_123_456),
+        ]));
+
+        // long: floor of epoch millis, double: epoch millis with fraction
+        let casted =
+            cast_timestamp_to_numeric(&ts_array, &TimeUnit::Microsecond, &DataType::Int64, millis)
+                .unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![None, Some(1_500), Some(-2), Some(1_700_000_000_123)])
+        );
+        let casted = cast_timestamp_to_numeric(
+            &ts_array,
+            &TimeUnit::Microsecond,
+            &DataType::Float64,
+            millis,
+        )
+        .unwrap();
+        assert_eq!(
+            as_float64_array(&casted).unwrap(),
+            &Float64Array::from(vec![
+                None,
+                Some(1_500.0),
+                Some(-1.5),
+                Some(1_700_000_000_123.456)
+            ])
+        );
+
+        // round trips of both units
+        let i64_array: ArrayRef = Arc::new(Int64Array::from(vec![None, Some(1_500), Some(-2)]));
+        for (epoch_unit, expected) in [
+            (
+                TimestampEpochUnit::Seconds,
+                vec![None, Some(1_500_000_000), Some(-2_000_000)],
+            ),
+            (
+                TimestampEpochUnit::Milliseconds,
+                vec![None, Some(1_500_000), Some(-2_000)],
+            ),
+        ] {
+            let casted = cast_numeric_to_timestamp(&i64_array, &ts_type, epoch_unit).unwrap();
+            assert_eq!(
+                casted.as_primitive::<TimestampMicrosecondType>(),
+                &TimestampMicrosecondArray::from(expected)
+            );
+            let round_trip = cast_timestamp_to_numeric(
+                &casted,
+                &TimeUnit::Microsecond,
+                &DataType::Int64,
+                epoch_unit,
+            )
+            .unwrap();
+            assert_eq!(&round_trip, &i64_array);
+        }
+
+        // timestamps coarser than the epoch unit
+        let s_type = DataType::Timestamp(TimeUnit::Second, None);
+        let casted = cast_numeric_to_timestamp(&i64_array, &s_type, millis).unwrap();
+        assert_eq!(
+            casted.as_primitive::<TimestampSecondType>(),
+            &TimestampSecondArray::from(vec![None, Some(1), Some(-1)])
+        );
+        let round_trip =
+            cast_timestamp_to_numeric(&casted, &TimeUnit::Second, &DataType::Int64, millis)
+                .unwrap();
+        assert_eq!(
+            as_int64_array(&round_trip).unwrap(),
+            &Int64Array::from(vec![None, Some(1_000), Some(-1_000)])
+        );
+
+        assert_eq!(
+            TimestampEpochUnit::parse("milliseconds").unwrap(),
+            TimestampEpochUnit::Milliseconds
+        );
+        assert!(TimestampEpochUnit::parse("minutes").is_err());
+    }
+
+    #[test]
+    fn test_string_with_grouping_separator_to_numeric() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some("1,234"),
+            Some("1,234.56"),
+            Some("1234"),
+        ]));
+
+        // spark returns null for strings with grouping separators
+        let casted = cast(&string_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from(vec![None, None, None, Some(1234)])
+        );
+        let casted = cast(&string_array, &DataType::Float64).unwrap();
+        assert_eq!(
+            as_float64_array(&casted).unwrap(),
+            &Float64Array::from(vec![None, None, None, Some(1234.0)])
+        );
+        let casted = cast(&string_array, &DataType::Decimal128(10, 2)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![None, None, None, Some(123400)])
+                .with_precision_and_scale(10, 2)
+                .unwrap()
+        );
+
+        // lenient mode
+        let stripped: ArrayRef = Arc::new(strip_grouping_separator(string_array.as_string(), ','));
+        let casted = cast(&stripped, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from(vec![None, Some(1234), Some(1234), Some(1234)])
+        );
+        let casted = cast(&stripped, &DataType::Float64).unwrap();
+        assert_eq!(
+            as_float64_array(&casted).unwrap(),
+            &Float64Array::from(vec![None, Some(1234.0), Some(1234.56), Some(1234.0)])
+        );
+        let casted = cast(&stripped, &DataType::Decimal128(10, 2)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![None, Some(123400), Some(123456), Some(123400)])
+                .with_precision_and_scale(10, 2)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_string_to_decimal256() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("1234567890123456789012345678901234567890.125"),
+            Some("-1234567890123456789012345678901234567890.125"),
+            Some(" 1234567890123456789012345678901234567890.124 "),
+            Some("9999999999999999999999999999999999999999.995"),
+            Some("1e39"),
+            Some("abc"),
+            None,
+        ]));
+        let casted = cast(&string_array, &DataType::Decimal256(42, 2)).unwrap();
+        let expected = |s: &str| Some(i256::from_string(s).unwrap());
+        assert_eq!(
+            casted.as_primitive::<Decimal256Type>(),
+            &Decimal256Array::from(vec![
+                expected("123456789012345678901234567890123456789013"),
+                expected("-123456789012345678901234567890123456789013"),
+                expected("123456789012345678901234567890123456789012"),
+                None, // overflow after rounding
+                expected("100000000000000000000000000000000000000000"),
+                None,
+                None,
+            ])
+            .with_precision_and_scale(42, 2)
+            .unwrap()
+        );
+
+        // up to 76 digits
+        let digits_76 = "9".repeat(76);
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            digits_76.clone(),
+            format!("{digits_76}9"),
+        ]));
+        let casted = cast(&string_array, &DataType::Decimal256(76, 0)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal256Type>(),
+            &Decimal256Array::from(vec![expected(&digits_76), None])
+                .with_precision_and_scale(76, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decimal256_to_string() {
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal256Array::from(vec![
+                Some(i256::from_string("123456789012345678901234567890123456789012").unwrap()),
+                Some(i256::from_string("-5").unwrap()),
+                Some(i256::from_i128(0)),
+                None,
+            ])
+            .with_precision_and_scale(50, 3)
+            .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some("123456789012345678901234567890123456789.012"),
+                Some("-0.005"),
+                Some("0.000"),
+                None,
+            ])
+        );
+
+        // round trip
+        let casted = cast(&casted, &DataType::Decimal256(50, 3)).unwrap();
+        assert_eq!(&casted, &decimal_array);
+    }
+
+    #[test]
+    fn test_boolean_to_string() {
+        let boolean_array: ArrayRef =
+            Arc::new(BooleanArray::from(vec![Some(true), None, Some(false)]));
+        let casted = cast(&boolean_array, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![Some("true"), None, Some("false")])
+        );
+    }
+
+    #[test]
+    fn test_string_to_boolean() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some("true"),
+            Some("TRUE"),
+            Some(" t "),
+            Some("Yes"),
+            Some("y"),
+            Some("1"),
+            Some("false"),
+            Some("F"),
+            Some("no"),
+            Some("n"),
+            Some("0"),
+            Some(""),
+            Some("2"),
+            Some("truee"),
+        ]));
+        let casted = cast(&string_array, &DataType::Boolean).unwrap();
+        assert_eq!(
+            casted.as_boolean(),
+            &BooleanArray::from(vec![
+                None,
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(false),
+                None,
+                None,
+                None,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_view_types_unsupported() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![Some("a"), None]));
+        let err = cast(&string_array, &DataType::Utf8View).unwrap_err();
+        assert!(matches!(err, DataFusionError::NotImplemented(_)));
+        assert!(err
+            .to_string()
+            .contains("cast from Utf8 to Utf8View is not supported: view arrays are not"));
+
+        let int_array: ArrayRef = Arc::new(Int32Array::from(vec![1]));
+        let err = cast(&int_array, &DataType::BinaryView).unwrap_err();
+        assert!(matches!(err, DataFusionError::NotImplemented(_)));
+    }
+
+    #[test]
+    fn test_numeric_to_boolean() {
+        let expected = BooleanArray::from(vec![
+            None,
+            Some(false),
+            Some(false),
+            Some(true),
+            Some(true),
+            Some(true),
+            Some(true),
+            Some(true),
+        ]);
+        let float64_array: ArrayRef = Arc::new(Float64Array::from(vec![
+            None,
+            Some(0.0),
+            Some(-0.0),
+            Some(f64::NAN),
+            Some(f64::INFINITY),
+            Some(f64::NEG_INFINITY),
+            Some(f64::MIN_POSITIVE),
+            Some(-5e-324),
+        ]));
+        let casted = cast(&float64_array, &DataType::Boolean).unwrap();
+        assert_eq!(casted.as_boolean(), &expected);
+
+        let float32_array: ArrayRef = Arc::new(Float32Array::from(vec![
+            None,
+            Some(0.0),
+            Some(-0.0),
+            Some(f32::NAN),
+            Some(f32::INFINITY),
+            Some(f32::NEG_INFINITY),
+            Some(1e-45),
+            Some(-1e-45),
+        ]));
+        let casted = cast(&float32_array, &DataType::Boolean).unwrap();
+        assert_eq!(casted.as_boolean(), &expected);
+
+        let float16_array: ArrayRef = Arc::new(Float16Array::from(vec![
+            None,
+            Some(f16::ZERO),
+            Some(f16::NEG_ZERO),
+            Some(f16::NAN),
+            Some(f16::INFINITY),
+            Some(f16::NEG_INFINITY),
+            Some(f16::MIN_POSITIVE_SUBNORMAL),
+            Some(-f16::MIN_POSITIVE_SUBNORMAL),
+        ]));
+        let casted = cast(&float16_array, &DataType::Boolean).unwrap();
+        assert_eq!(casted.as_boolean(), &expected);
+
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![
+                None,
+                Some(0),
+                Some(-0),
+                Some(1),
+                Some(-1),
+                Some(i128::MAX),
+            ])
+            .with_precision_and_scale(38, 10)
+            .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Boolean).unwrap();
+        assert_eq!(casted.as_boolean(), &expected.slice(0, 6));
+
+        // integral types
+        let int_arrays: Vec<ArrayRef> = vec![
+            Arc::new(Int8Array::from(vec![
+                None,
+                Some(0),
+                Some(0),
+                Some(1),
+                Some(-1),
+                Some(i8::MIN),
+            ])),
+            Arc::new(Int16Array::from(vec![
+                None,
+                Some(0),
+                Some(0),
+                Some(1),
+                Some(-1),
+                Some(i16::MIN),
+            ])),
+            Arc::new(Int32Array::from(vec![
+                None,
+                Some(0),
+                Some(0),
+                Some(1),
+                Some(-1),
+                Some(i32::MIN),
+            ])),
+            Arc::new(Int64Array::from(vec![
+                None,
+                Some(0),
+                Some(0),
+                Some(1),
+                Some(-1),
+                Some(i64::MIN),
+            ])),
+        ];
+        for int_array in int_arrays {
+            let casted = cast(&int_array, &DataType::Boolean).unwrap();
+            assert_eq!(casted.as_boolean(), &expected.slice(0, 6));
+        }
+        let uint8_array: ArrayRef = Arc::new(UInt8Array::from(vec![None, Some(0), Some(255)]));
+        let casted = cast(&uint8_array, &DataType::Boolean).unwrap();
+        assert_eq!(
+            casted.as_boolean(),
+            &BooleanArray::from(vec![None, Some(false), Some(true)]),
+        );
+    }
+
+    #[test]
+    fn test_string_to_timestamp_ntz() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some("2024-01-01 12:00:00"),
+            Some("2024-01-01T12:00:00"),
+            Some(" 2024-01-01 12:00:00.123456789 "),
+            Some("2024-01-01"),
+            Some("2024-1"),
+            Some("2024"),
+            Some("1969-12-31 23:59:59.5"),
+            Some("2024-02-29 00:00"),
+            Some("2023-02-29"),
+            Some("2024-01-01 24:00:00"),
+            Some("2024-01-01 12:00:00#"),
+            Some("abc"),
+        ]));
+        let casted = cast(
+            &string_array,
+            &DataType::Timestamp(TimeUnit::Microsecond, None),
+        )
+        .unwrap();
+
+        let day_micros = 86400 * 1_000_000i64;
+        let ts_2024_01_01 = 19723 * day_micros;
+        assert_eq!(
+            casted.as_primitive::<TimestampMicrosecondType>(),
+            &TimestampMicrosecondArray::from(vec![
+                None,
+                Some(ts_2024_01_01 + 12 * 3_600_000_000),
+                Some(ts_2024_01_01 + 12 * 3_600_000_000),
+                Some(ts_2024_01_01 + 12 * 3_600_000_000 + 123456),
+                Some(ts_2024_01_01),
+                Some(ts_2024_01_01),
+                Some(ts_2024_01_01),
+                Some(-500_000),
+                Some((19723 + 59) * day_micros),
+                None,
+                None,
+                None,
+                None,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_string_with_offset_to_timestamp_ntz() {
+        // embedded zones are ignored, the literal wall-clock time is kept
+        // regardless of the zone (and session timezone, which is not used)
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("2024-01-01 12:00:00"),
+            Some("2024-01-01 12:00:00Z"),
+            Some("2024-01-01 12:00:00+08:00"),
+            Some("2024-01-01T12:00:00-05:30"),
+            Some("2024-01-01 12:00:00 America/Los_Angeles"),
+        ]));
+        let casted = cast(
+            &string_array,
+            &DataType::Timestamp(TimeUnit::Millisecond, None),
+        )
+        .unwrap();
+
+        let expected = 19723 * 86_400_000 + 12 * 3_600_000;
+        assert_eq!(
+            casted.as_primitive::<TimestampMillisecondType>(),
+            &TimestampMillisecondArray::from(vec![Some(expected); 5])
+        );
+    }
+
+    #[test]
+    fn test_string_to_timestamp_partial_forms() {
+        // parsed in the session timezone (UTC+8), missing parts default to
+        // midnight of the first day
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("2024-06-01"),
+            Some(" 2024-06-01 "),
+            Some("2024-06"),
+            Some("2024-6"),
+            Some("2024"),
+            Some("2024-06-01 08:30"),
+            Some("2024-06-32"),
+            Some("2024-13"),
+            Some("24"),
+        ]));
+        let casted = cast(
+            &string_array,
+            &DataType::Timestamp(TimeUnit::Microsecond, Some("Asia/Shanghai".into())),
+        )
+        .unwrap();
+
+        let day_micros = 86400 * 1_000_000i64;
+        let offset_micros = 8 * 3_600_000_000i64;
+        let ts_2024_01_01 = 19723 * day_micros - offset_micros;
+        let ts_2024_06_01 = 19875 * day_micros - offset_micros;
+        assert_eq!(
+            casted.as_primitive::<TimestampMicrosecondType>(),
+            &TimestampMicrosecondArray::from(vec![
+                Some(ts_2024_06_01),
+                Some(ts_2024_06_01),
+                Some(ts_2024_06_01),
+                Some(ts_2024_06_01),
+                Some(ts_2024_01_01),
+                Some(ts_2024_06_01 + 8 * 3_600_000_000 + 30 * 60_000_000),
+                None,
+                None,
+                None,
+            ])
+            .with_timezone("Asia/Shanghai")
+        );
+    }
+
+    #[test]
+    fn test_string_with_zone_to_timestamp() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("2024-01-01 12:00:00"),
+            Some("2024-01-01 12:00:00Z"),
+            Some("2024-01-01 12:00:00+08:00"),
+            Some("2024-01-01T12:00:00 UTC-05:30"),
+            Some("2024-01-01 12:00:00 Asia/Shanghai"),
+            Some("2024-01-01 12:00:00 Mars/Olympus"),
+            // skipped by daylight saving, shifted forward by an hour
+            Some("2024-03-10 02:30:00"),
+            // repeated by daylight saving, the earlier offset is used
+            Some("2024-11-03 01:30:00"),
+        ]));
+        let casted = cast(
+            &string_array,
+            &DataType::Timestamp(TimeUnit::Second, Some("America/Los_Angeles".into())),
+        )
+        .unwrap();
+
+        let noon = 19723 * 86400 + 12 * 3600;
+        assert_eq!(
+            casted.as_primitive::<TimestampSecondType>(),
+            &TimestampSecondArray::from(vec![
+                Some(noon + 8 * 3600),
+                Some(noon),
+                Some(noon - 8 * 3600),
+                Some(noon + 5 * 3600 + 1800),
+                Some(noon - 8 * 3600),
+                None,
+                Some(19792 * 86400 + 10 * 3600 + 1800),
+                Some(20030 * 86400 + 8 * 3600 + 1800),
+            ])
+            .with_timezone("America/Los_Angeles")
+        );
+    }
+
+    #[test]
+    fn test_list_to_non_nullable_items() {
+        let list_array: ArrayRef =
+            Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+                Some(vec![Some(1), Some(2)]),
+                Some(vec![Some(3), None]),
+                None,
+                Some(vec![]),
+                Some(vec![Some(4)]),
+            ]));
+
+        // nullable items
+        let nullable_type = DataType::List(Arc::new(Field::new("item", DataType::Int64, true)));
+        let casted = cast(&list_array, &nullable_type).unwrap();
+        assert_eq!(casted.data_type(), &nullable_type);
+        assert_eq!(casted.null_count(), 1);
+        assert_eq!(as_list_array(&casted).value(1).null_count(), 1);
+
+        // non-nullable items, lists containing nulls become nulls
+        let non_nullable_type =
+            DataType::List(Arc::new(Field::new("item", DataType::Int64, false)));
+        let casted = cast(&list_array, &non_nullable_type).unwrap();
+        assert_eq!(casted.data_type(), &non_nullable_type);
+        let casted = as_list_array(&casted);
+        assert_eq!(
+            casted.iter().map(|v| v.is_some()).collect::<Vec<_>>(),
+            vec![true, false, false, true, true],
+        );
+        assert_eq!(casted.values().null_count(), 0);
+        assert_eq!(
+            casted.value(0).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![1, 2])
+        );
+        assert_eq!(casted.value(3).len(), 0);
+        assert_eq!(
+            casted.value(4).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![4])
+        );
+
+        // non-nullable items, no nulls present
+        let sliced = list_array.slice(3, 2);
+        let casted = cast(&sliced, &non_nullable_type).unwrap();
+        assert_eq!(casted.null_count(), 0);
+    }
+
+    #[test]
+    fn test_nested_struct_case_insensitive() {
+        let inner_fields = Fields::from(vec![
+            Field::new("A", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let inner: ArrayRef = Arc::new(StructArray::new(
+            inner_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Int32Array::from(vec![3, 4])),
+            ],
+            None,
+        ));
+        let outer_fields =
+            Fields::from(vec![Field::new("S", DataType::Struct(inner_fields), true)]);
+        let outer: ArrayRef = Arc::new(StructArray::new(outer_fields, vec![inner], None));
+
+        // query field cases differ from the file
+        let to_inner_fields = Fields::from(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("B", DataType::Int32, true),
+        ]);
+        let to_type = DataType::Struct(Fields::from(vec![Field::new(
+            "s",
+            DataType::Struct(to_inner_fields),
+            true,
+        )]));
+        let casted = cast_scan_input_array(&outer, &to_type, false).unwrap();
+        let casted_inner = as_struct_array(as_struct_array(&casted).column(0));
+        assert_eq!(
+            casted_inner.column(0).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![1, 2])
+        );
+        assert_eq!(
+            casted_inner.column(1).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![3, 4])
+        );
+
+        // case-sensitive mode
+        let inner = as_struct_array(as_struct_array(&outer).column(0));
+        assert!(find_struct_column(inner, "a", true).unwrap().is_none());
+        assert!(find_struct_column(inner, "A", true).unwrap().is_some());
+
+        // unmatched fields are read as nulls in case-sensitive mode
+        let to_type = DataType::Struct(Fields::from(vec![Field::new(
+            "S",
+            DataType::Struct(Fields::from(vec![
+                Field::new("a", DataType::Int64, true),
+                Field::new("b", DataType::Int32, true),
+            ])),
+            true,
+        )]));
+        let casted = cast_scan_input_array(&outer, &to_type, true).unwrap();
+        let casted_inner = as_struct_array(as_struct_array(&casted).column(0));
+        assert_eq!(casted_inner.column(0).null_count(), 2);
+        assert_eq!(
+            casted_inner.column(1).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![3, 4])
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_struct_fields_case_insensitive() {
+        let struct_ = StructArray::new(
+            Fields::from(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("A", DataType::Int32, true),
+            ]),
+            vec![
+                Arc::new(Int32Array::from(vec![1])),
+                Arc::new(Int32Array::from(vec![2])),
+            ],
+            None,
+        );
+        assert!(find_struct_column(&struct_, "a", false).is_err());
+        assert!(find_struct_column(&struct_, "a", true).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_string_to_day_time_interval() {
+        use DayTimeIntervalField::*;
+
+        const S: i64 = 1_000_000;
+        const M: i64 = 60 * S;
+        const H: i64 = 60 * M;
+        const D: i64 = 24 * H;
+        let cases: Vec<(
+            &str,
+            DayTimeIntervalField,
+            DayTimeIntervalField,
+            Option<i64>,
+        )> = vec![
+            // day to second
+            (
+                "1 02:03:04.567",
+                Day,
+                Second,
+                Some(D + 2 * H + 3 * M + 4 * S + 567000),
+            ),
+            (
+                "-1 02:03:04.1234567",
+                Day,
+                Second,
+                Some(-(D + 2 * H + 3 * M + 4 * S + 123456)),
+            ),
+            ("+0 00:00:00", Day, Second, Some(0)),
+            ("1 24:00:00", Day, Second, None),
+            ("1 02:03", Day, Second, None),
+            ("1 02:03:04.", Day, Second, None),
+            ("1 02:03:04.1234567890", Day, Second, None),
+            // day to hour / minute
+            ("3", Day, Day, Some(3 * D)),
+            ("3 04", Day, Hour, Some(3 * D + 4 * H)),
+            ("3 04:05", Day, Minute, Some(3 * D + 4 * H + 5 * M)),
+            ("3 04:05:06", Day, Minute, None),
+            // hour to second
+            ("25", Hour, Hour, Some(25 * H)),
+            ("25:30", Hour, Minute, Some(25 * H + 30 * M)),
+            (
+                "-25:30:15.5",
+                Hour,
+                Second,
+                Some(-(25 * H + 30 * M + 15 * S + 500000)),
+            ),
+            ("25:60:00", Hour, Second, None),
+            // minute to second
+            ("90", Minute, Minute, Some(90 * M)),
+            (
+                "90:59.999999",
+                Minute,
+                Second,
+                Some(90 * M + 59 * S + 999999),
+            ),
+            ("90:60", Minute, Second, None),
+            // second
+            ("100.5", Second, Second, Some(100 * S + 500000)),
+            ("abc", Second, Second, None),
+            ("", Second, Second, None),
+            // ansi literal form
+            (
+                "INTERVAL '1 02:03:04.567' DAY TO SECOND",
+                Day,
+                Second,
+                Some(D + 2 * H + 3 * M + 4 * S + 567000),
+            ),
+            (
+                "interval -'10:30' hour to minute",
+                Hour,
+                Minute,
+                Some(-(10 * H + 30 * M)),
+            ),
+            ("INTERVAL '10:30' HOUR TO MINUTE", Day, Second, None),
+        ];
+
+        for (input, start_field, end_field, expected) in cases {
+            let string_array: ArrayRef = Arc::new(StringArray::from(vec![Some(input), None]));
+            let casted =
+                cast_string_to_day_time_interval(&string_array, start_field, end_field).unwrap();
+            assert_eq!(
+                casted.as_primitive::<DurationMicrosecondType>(),
+                &DurationMicrosecondArray::from(vec![expected, None]),
+                "input: {input}",
+            );
+        }
+
+        // casting to duration uses day to second
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec!["1 00:00:01"]));
+        let casted = cast(&string_array, &DataType::Duration(TimeUnit::Millisecond)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<DurationMillisecondType>(),
+            &DurationMillisecondArray::from(vec![86_401_000]),
+        );
+    }
+
+    #[test]
+    fn test_day_time_interval_to_numeric_and_string() {
+        // differences of timestamps: 1.5s, -0.5s, 1 day 02:03:04.567,
+        // i64::MIN micros and a span of 10^8 days
+        let micros: ArrayRef = Arc::new(DurationMicrosecondArray::from(vec![
+            Some(1_500_000),
+            Some(-500_000),
+            Some(93_784_567_000),
+            Some(i64::MIN),
+            Some(100_000_000 * 86_400_000_000),
+            None,
+        ]));
+
+        let casted = cast(&micros, &DataType::Int64).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![
+                Some(1),
+                Some(0),
+                Some(93_784),
+                Some(i64::MIN / 1_000_000),
+                Some(100_000_000 * 86_400),
+                None,
+            ]),
+        );
+
+        // overflowed seconds are nulls
+        let casted = cast(&micros, &DataType::Int32).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![Some(1), Some(0), Some(93_784), None, None, None]),
+        );
+
+        // sub-second differences are kept by fractional types
+        let casted = cast(&micros, &DataType::Float64).unwrap();
+        let casted = casted.as_primitive::<Float64Type>();
+        assert_eq!(casted.value(0), 1.5);
+        assert_eq!(casted.value(1), -0.5);
+        assert_eq!(casted.value(2), 93_784.567);
+        assert!(casted.is_null(5));
+
+        let casted = cast(&micros, &DataType::Decimal128(38, 6)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![
+                Some(1_500_000),
+                Some(-500_000),
+                Some(93_784_567_000),
+                Some(i64::MIN as i128),
+                Some(100_000_000 * 86_400_000_000),
+                None,
+            ])
+            .with_precision_and_scale(38, 6)
+            .unwrap(),
+        );
+
+        let casted = cast(&micros, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some("INTERVAL '0 00:00:01.5' DAY TO SECOND"),
+                Some("INTERVAL '-0 00:00:00.5' DAY TO SECOND"),
+                Some("INTERVAL '1 02:03:04.567' DAY TO SECOND"),
+                Some("INTERVAL '-106751991 04:00:54.775808' DAY TO SECOND"),
+                Some("INTERVAL '100000000 00:00:00' DAY TO SECOND"),
+                None,
+            ]),
+        );
+
+        // strings are casted back to the same intervals (except i64::MIN,
+        // whose absolute value overflows when parsed)
+        let round_trip = cast(&casted, &DataType::Duration(TimeUnit::Microsecond)).unwrap();
+        assert_eq!(&round_trip.slice(0, 3), &micros.slice(0, 3));
+        assert_eq!(&round_trip.slice(4, 2), &micros.slice(4, 2));
+
+        // durations of other units are casted by microseconds
+        let seconds: ArrayRef = Arc::new(DurationSecondArray::from(vec![90, i64::MAX]));
+        let casted = cast(&seconds, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![Some("INTERVAL '0 00:01:30' DAY TO SECOND"), None]),
+        );
+    }
+
+    #[test]
+    fn test_timestamp_to_timestamp() {
+        let units = [
+            TimeUnit::Second,
+            TimeUnit::Millisecond,
+            TimeUnit::Microsecond,
+            TimeUnit::Nanosecond,
+        ];
+
+        // 1970-01-01 00:00:01.234567891 and -1.5s, in every unit
+        let values_of = |unit: &TimeUnit| -> Vec<Option<i64>> {
+            match unit {
+                TimeUnit::Second => vec![Some(1), Some(-2), None],
+                TimeUnit::Millisecond => vec![Some(1_234), Some(-1_500), None],
+                TimeUnit::Microsecond => vec![Some(1_234_567), Some(-1_500_000), None],
+                TimeUnit::Nanosecond => vec![Some(1_234_567_891), Some(-1_500_000_000), None],
+            }
+        };
+
+        for from_unit in &units {
+            for to_unit in &units {
+                let from_type = DataType::Timestamp(from_unit.clone(), Some("UTC".into()));
+                let to_type = DataType::Timestamp(to_unit.clone(), Some("UTC".into()));
+                let array =
+                    arrow::compute::cast(&Int64Array::from(values_of(from_unit)), &from_type)
+                        .unwrap();
+                let casted = cast(&array, &to_type).unwrap();
+                assert_eq!(casted.data_type(), &to_type);
+
+                // rescaled values are truncated to the coarser unit
+                let coarse_unit = units
+                    .iter()
+                    .find(|unit| *unit == from_unit || *unit == to_unit)
+                    .unwrap();
+                let expected: Vec<Option<i64>> = values_of(coarse_unit)
+                    .into_iter()
+                    .map(|v| {
+                        v.map(|v| {
+                            v * timestamp_units_per_second(to_unit)
+                                / timestamp_units_per_second(coarse_unit)
+                        })
+                    })
+                    .collect();
+                let casted = arrow::compute::cast(&casted, &DataType::Int64).unwrap();
+                assert_eq!(
+                    casted.as_primitive::<Int64Type>(),
+                    &Int64Array::from(expected),
+                    "{from_unit:?} -> {to_unit:?}",
+                );
+            }
+        }
+
+        // overflow at the nanos boundary
+        let max_nanos_seconds = i64::MAX / 1_000_000_000;
+        let array: ArrayRef = Arc::new(TimestampSecondArray::from(vec![
+            max_nanos_seconds,
+            max_nanos_seconds + 1,
+            -max_nanos_seconds,
+            -max_nanos_seconds - 1,
+        ]));
+        let casted = cast(&array, &DataType::Timestamp(TimeUnit::Nanosecond, None)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<TimestampNanosecondType>(),
+            &TimestampNanosecondArray::from(vec![
+                Some(max_nanos_seconds * 1_000_000_000),
+                None,
+                Some(-max_nanos_seconds * 1_000_000_000),
+                None,
+            ]),
+        );
+        let array: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![i64::MAX]));
+        let casted = cast(&array, &DataType::Timestamp(TimeUnit::Nanosecond, None)).unwrap();
+        assert!(casted.is_null(0));
+    }
+
+    #[test]
+    fn test_timestamp_timezone_change() {
+        // 2024-03-10 10:30:00 UTC, across the los angeles dst change
+        let micros = 1_710_066_600_000_000;
+        let array: ArrayRef = Arc::new(
+            TimestampMicrosecondArray::from(vec![Some(micros), Some(0), None]).with_timezone("UTC"),
+        );
+
+        for tz in ["America/Los_Angeles", "Asia/Shanghai", "+05:30", "UTC"] {
+            let to_type = DataType::Timestamp(TimeUnit::Microsecond, Some(tz.into()));
+            let casted = cast(&array, &to_type).unwrap();
+            assert_eq!(casted.data_type(), &to_type);
+
+            // the instant is kept, only the timezone is changed
+            assert_eq!(
+                casted.as_primitive::<TimestampMicrosecondType>().values(),
+                array.as_primitive::<TimestampMicrosecondType>().values(),
+            );
+            assert_eq!(casted.nulls(), array.nulls());
+
+            // and casting back is lossless
+            let casted_back = cast(&casted, array.data_type()).unwrap();
+            assert_eq!(&casted_back, &array);
+        }
+
+        // the same instant is displayed in the new timezone
+        let to_type =
+            DataType::Timestamp(TimeUnit::Microsecond, Some("America/Los_Angeles".into()));
+        let casted = cast(&cast(&array, &to_type).unwrap(), &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some("2024-03-10 03:30:00"),
+                Some("1969-12-31 16:00:00"),
+                None,
+            ]),
+        );
+
+        // timezone and unit changes keep the instant as well
+        let to_type = DataType::Timestamp(TimeUnit::Millisecond, Some("Asia/Shanghai".into()));
+        let casted = cast(&array, &to_type).unwrap();
+        assert_eq!(casted.data_type(), &to_type);
+        assert_eq!(
+            casted.as_primitive::<TimestampMillisecondType>(),
+            &TimestampMillisecondArray::from(vec![Some(micros / 1000), Some(0), None])
+                .with_timezone("Asia/Shanghai"),
+        );
+    }
+
+    #[test]
+    fn test_decimal_to_string_keeps_scale() {
+        let cases: Vec<(i128, u8, i8, &str)> = vec![
+            (150, 10, 2, "1.50"),
+            (-150, 10, 2, "-1.50"),
+            (5, 10, 2, "0.05"),
+            (-5, 10, 2, "-0.05"),
+            (0, 10, 2, "0.00"),
+            (0, 38, 10, "0.0000000000"),
+            (123, 10, 0, "123"),
+            (-123, 10, 0, "-123"),
+            (0, 10, 0, "0"),
+            (1, 38, 37, "0.0000000000000000000000000000000000001"),
+            (
+                -99999999999999999999999999999999999999,
+                38,
+                18,
+                "-99999999999999999999.999999999999999999",
+            ),
+        ];
+        for (unscaled, precision, scale, expected) in cases {
+            let array: ArrayRef = Arc::new(
+                Decimal128Array::from(vec![Some(unscaled), None])
+                    .with_precision_and_scale(precision, scale)
+                    .unwrap(),
+            );
+            let casted = cast(&array, &DataType::Utf8).unwrap();
+            assert_eq!(
+                casted.as_string::<i32>(),
+                &StringArray::from(vec![Some(expected), None]),
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_to_numeric_trims_whitespaces() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("   "),
+            Some(""),
+            Some(" 5 "),
+            Some("\t-12\n"),
+            Some(" 1 2 "),
+            None,
+        ]));
+
+        let casted = cast(&string_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from(vec![None, None, Some(5), Some(-12), None, None]),
+        );
+
+        let casted = cast(&string_array, &DataType::Int64).unwrap();
+        assert_eq!(
+            as_int64_array(&casted).unwrap(),
+            &Int64Array::from(vec![None, None, Some(5), Some(-12), None, None]),
+        );
+
+        let casted = cast(&string_array, &DataType::Float64).unwrap();
+        assert_eq!(
+            as_float64_array(&casted).unwrap(),
+            &Float64Array::from(vec![None, None, Some(5.0), Some(-12.0), None, None]),
+        );
+
+        let casted = cast(&string_array, &DataType::Decimal128(10, 2)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![None, None, Some(500), Some(-1200), None, None])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_integer_to_string() {
+        let i64_array: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(123),
+            Some(-123),
+            Some(0),
+            Some(1234567),
+            Some(i64::MAX),
+            Some(i64::MIN),
+            None,
+        ]));
+        let casted = cast(&i64_array, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some("123"),
+                Some("-123"),
+                Some("0"),
+                Some("1234567"),
+                Some("9223372036854775807"),
+                Some("-9223372036854775808"),
+                None,
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_float_to_string() {
+        let f64_array: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(1.0),
+            Some(-123.456),
+            Some(100.0),
+            Some(0.001),
+            Some(0.0001),
+            Some(9999999.0),
+            Some(1e7),
+            Some(1e10),
+            Some(-1.5e-10),
+            Some(1.7976931348623157e308),
+            Some(0.0),
+            Some(-0.0),
+            Some(f64::NAN),
+            Some(f64::INFINITY),
+            Some(f64::NEG_INFINITY),
+            None,
+        ]));
+        let casted = cast(&f64_array, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some("1.0"),
+                Some("-123.456"),
+                Some("100.0"),
+                Some("0.001"),
+                Some("1.0E-4"),
+                Some("9999999.0"),
+                Some("1.0E7"),
+                Some("1.0E10"),
+                Some("-1.5E-10"),
+                Some("1.7976931348623157E308"),
+                Some("0.0"),
+                Some("-0.0"),
+                Some("NaN"),
+                Some("Infinity"),
+                Some("-Infinity"),
+                None,
+            ]),
+        );
+
+        // floats are formatted with their own shortest digits
+        let f32_array: ArrayRef = Arc::new(Float32Array::from(vec![
+            Some(1.1f32),
+            Some(3.4028235e38f32),
+            Some(-0.1f32),
+        ]));
+        let casted = cast(&f32_array, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec!["1.1", "3.4028235E38", "-0.1"]),
+        );
+    }
+
+    #[test]
+    fn test_struct_field_casts() {
+        let inner: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("x", DataType::Int32, true)),
+            Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+        )]));
+        let struct_array: ArrayRef = Arc::new(StructArray::from(vec![
+            (
+                Arc::new(Field::new("widen", DataType::Int32, true)),
+                Arc::new(Int32Array::from(vec![1, -2])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("narrow", DataType::Int64, true)),
+                Arc::new(Int64Array::from(vec![3, i64::MAX])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("incompatible", inner.data_type().clone(), true)),
+                inner,
+            ),
+        ]));
+        let to_fields = Fields::from(vec![
+            Field::new("widen", DataType::Int64, false),
+            Field::new("narrow", DataType::Int32, true),
+            Field::new("incompatible", DataType::Int32, false),
+        ]);
+
+        for match_struct_fields in [false, true] {
+            let casted = cast_impl(
+                &struct_array,
+                &DataType::Struct(to_fields.clone()),
+                match_struct_fields,
+            )
+            .unwrap();
+            let casted = casted.as_struct();
+
+            // widening always succeeds
+            assert_eq!(
+                casted.column(0).as_primitive::<Int64Type>(),
+                &Int64Array::from(vec![1, -2]),
+            );
+            // narrowing produces nulls on overflow
+            assert_eq!(
+                casted.column(1).as_primitive::<Int32Type>(),
+                &Int32Array::from(vec![Some(3), None]),
+            );
+            // incompatible field is casted to nulls, without failing other fields
+            assert_eq!(casted.column(2).null_count(), 2);
+            assert!(casted.fields()[2].is_nullable());
+            assert!(!casted.fields()[0].is_nullable());
+        }
+    }
+
+    #[test]
+    fn test_string_to_date() {
+        // cases from spark's DateTimeUtilsSuite stringToDate tests
+        let cases: Vec<(&str, Option<i32>)> = vec![
+            ("2015-01-28", Some(16463)),
+            ("2015", Some(16436)),
+            ("0001", Some(-719162)),
+            ("2015-03", Some(16495)),
+            ("2015-03-18", Some(16512)),
+            ("2015-03-18 ", Some(16512)),
+            (" 2015-03-18", Some(16512)),
+            (" 2015-03-18 ", Some(16512)),
+            ("2015-03-18 123142", Some(16512)),
+            ("2015-03-18T123123", Some(16512)),
+            ("2015-03-18T", Some(16512)),
+            ("2015-3-18", Some(16512)),
+            ("2015-03-8", Some(16502)),
+            ("\t2015-03-18\n", Some(16512)),
+            ("+2015-03-18", Some(16512)),
+            ("-0001-01-01", Some(-719893)),
+            ("1999-02-29", None),
+            ("2000-02-29", Some(11016)),
+            ("1900-02-29", None),
+            ("2015-00-18", None),
+            ("2015-13-18", None),
+            ("2015-03-00", None),
+            ("2015-03-32", None),
+            ("2015-04-31", None),
+            ("2015-03-18X", None),
+            ("2015/03/18", None),
+            ("2015.03.18", None),
+            ("20150318", None),
+            ("2015-031-8", None),
+            ("015-03-18", None),
+            ("015", None),
+            ("02015", Some(16436)),
+            ("15-03-18", None),
+            ("99", None),
+            ("1999 08 01", None),
+            ("1999-08 01", None),
+            ("1999 08", None),
+            ("", None),
+            ("   ", None),
+            ("+", None),
+            ("abc", None),
+        ];
+        let string_array: ArrayRef = Arc::new(StringArray::from(
+            cases.iter().map(|(s, _)| Some(*s)).collect::<Vec<_>>(),
+        ));
+        let casted = cast(&string_array, &DataType::Date32).unwrap();
+        let expected = cases.iter().map(|(_, days)| *days).collect::<Vec<_>>();
+        assert_eq!(
+            casted.as_primitive::<Date32Type>(),
+            &Date32Array::from(expected),
+        );
+    }
+
+    #[test]
+    fn test_time_parser_policy_rollover() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("2023-02-29"),
+            Some("2024-13-01 00:00:00"),
+            Some("2024-01-32"),
+            Some("2024-00-10"),
+            Some("2024-01-00"),
+            Some("2024-01-01 24:00:00"),
+            Some("2024-01-01 23:59:60"),
+            Some("2024-01-01 12:00:00"),
+            Some("24-01-01"),
+            Some("abc"),
+        ]));
+        let day_micros = 86400 * 1_000_000i64;
+        let ts_type = DataType::Timestamp(TimeUnit::Microsecond, None);
+
+        // out-of-range fields are rolled over in legacy policy
+        let legacy =
+            cast_string_to_timestamp(&string_array, &ts_type, TimeParserPolicy::Legacy).unwrap();
+        assert_eq!(
+            legacy.as_primitive::<TimestampMicrosecondType>(),
+            &TimestampMicrosecondArray::from(vec![
+                Some(19417 * day_micros), // 2023-03-01
+                Some(20089 * day_micros), // 2025-01-01
+                Some(19754 * day_micros), // 2024-02-01
+                Some(19701 * day_micros), // 2023-12-10
+                Some(19722 * day_micros), // 2023-12-31
+                Some(19724 * day_micros), // 2024-01-02
+                Some(19724 * day_micros), // 2024-01-02
+                Some(19723 * day_micros + 12 * 3_600_000_000),
+                None,
+                None,
+            ]),
+        );
+        let legacy = cast_string_to_date(&string_array, TimeParserPolicy::Legacy).unwrap();
+        assert_eq!(
+            legacy.as_primitive::<Date32Type>(),
+            &Date32Array::from(vec![
+                Some(19417),
+                Some(20089),
+                Some(19754),
+                Some(19701),
+                Some(19722),
+                Some(19723),
+                Some(19723),
+                Some(19723),
+                None,
+                None,
+            ]),
+        );
+
+        // and are invalid in corrected and exception policies
+        for policy in [TimeParserPolicy::Corrected, TimeParserPolicy::Exception] {
+            let casted = cast_string_to_timestamp(&string_array, &ts_type, policy).unwrap();
+            assert_eq!(
+                casted.as_primitive::<TimestampMicrosecondType>(),
+                &TimestampMicrosecondArray::from(vec![
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(19723 * day_micros + 12 * 3_600_000_000),
+                    None,
+                    None,
+                ]),
+            );
+            let casted = cast_string_to_date(&string_array, policy).unwrap();
+            assert_eq!(
+                casted.as_primitive::<Date32Type>(),
+                &Date32Array::from(vec![
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(19723),
+                    Some(19723),
+                    Some(19723),
+                    None,
+                    None,
+                ]),
+            );
+        }
+
+        // the policy is taken from the session by spark_cast
+        let legacy =
+            spark_cast(&string_array, &DataType::Date32, TimeParserPolicy::Legacy).unwrap();
+        assert_eq!(legacy.null_count(), 2);
+        let casted = spark_cast(
+            &string_array,
+            &DataType::Date32,
+            TimeParserPolicy::default(),
+        )
+        .unwrap();
+        assert_eq!(casted.null_count(), 7);
+
+        assert_eq!(
+            TimeParserPolicy::parse("legacy").unwrap(),
+            TimeParserPolicy::Legacy
+        );
+        assert!(TimeParserPolicy::parse("lenient").is_err());
+    }
+
+    #[test]
+    fn test_cast_to_json_string() {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]));
+        let b: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("x\"y\\z"),
+            Some("line\nbreak\ttab\u{1}"),
+            Some("中文/"),
+        ]));
+        let c: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Float64Type, _, _>(vec![
+            Some(vec![Some(1.5), None, Some(f64::NAN)]),
+            Some(vec![]),
+            None,
+        ]));
+        let mut map_builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        map_builder.keys().append_value("k1");
+        map_builder.values().append_value(1);
+        map_builder.keys().append_value("k2");
+        map_builder.values().append_null();
+        map_builder.append(true).unwrap();
+        map_builder.append(true).unwrap();
+        map_builder.append(false).unwrap();
+        let m: ArrayRef = Arc::new(map_builder.finish());
+
+        let fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", c.data_type().clone(), true),
+            Field::new("m", m.data_type().clone(), true),
+        ]);
+        let struct_array: ArrayRef = Arc::new(
+            StructArray::try_new(
+                fields,
+                vec![a, b, c, m],
+                Some(NullBuffer::from(vec![true, true, false])),
+            )
+            .unwrap(),
+        );
+        let casted = cast_to_json_string(&struct_array).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some(r#"{"a":1,"b":"x\"y\\z","c":[1.5,null,"NaN"],"m":{"k1":1,"k2":null}}"#),
+                Some(r#"{"b":"line\nbreak\ttab\u0001","c":[],"m":{}}"#),
+                None,
+            ]),
+        );
+
+        // non-string map keys are rendered as strings
+        let mut map_builder = MapBuilder::new(None, Int32Builder::new(), BooleanBuilder::new());
+        map_builder.keys().append_value(1);
+        map_builder.values().append_value(true);
+        map_builder.append(true).unwrap();
+        let casted = cast_to_json_string(&map_builder.finish()).unwrap();
+        assert_eq!(casted.as_string::<i32>().value(0), r#"{"1":true}"#);
+
+        // primitives are rendered as json values
+        let casted = cast_to_json_string(&Int32Array::from(vec![Some(1), None])).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![Some("1"), None]),
+        );
+    }
+
+    #[test]
+    fn test_date64_casts() {
+        // 2024-02-29 and 1969-12-31
+        let date32_array: ArrayRef = Arc::new(Date32Array::from(vec![Some(19782), Some(-1), None]));
+        let date64_array: ArrayRef = Arc::new(Date64Array::from(vec![
+            Some(19782 * MILLIS_PER_DAY),
+            Some(-MILLIS_PER_DAY),
+            None,
+        ]));
+
+        // date32 <-> date64
+        assert_eq!(
+            &cast(&date32_array, &DataType::Date64).unwrap(),
+            &date64_array
+        );
+        assert_eq!(
+            &cast(&date64_array, &DataType::Date32).unwrap(),
+            &date32_array
+        );
+
+        // millis not at midnight are truncated to the date
+        let unaligned: ArrayRef = Arc::new(Date64Array::from(vec![
+            Some(19782 * MILLIS_PER_DAY + 1000),
+            Some(-1),
+            None,
+        ]));
+        assert_eq!(&cast(&unaligned, &DataType::Date32).unwrap(), &date32_array);
+
+        // string <-> date64
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("2024-02-29"),
+            Some("1969-12-31"),
+            None,
+        ]));
+        assert_eq!(
+            &cast(&string_array, &DataType::Date64).unwrap(),
+            &date64_array
+        );
+        assert_eq!(
+            &cast(&date64_array, &DataType::Utf8).unwrap(),
+            &string_array
+        );
+
+        // timestamp <-> date64, in the timestamp's timezone
+        let ts_type = DataType::Timestamp(TimeUnit::Microsecond, Some("Asia/Shanghai".into()));
+        let ts_array = cast(&date64_array, &ts_type).unwrap();
+        assert_eq!(&cast(&ts_array, &DataType::Date64).unwrap(), &date64_array);
+        assert_eq!(
+            &cast(&ts_array, &DataType::Date64).unwrap(),
+            &cast(
+                &cast(&ts_array, &DataType::Date32).unwrap(),
+                &DataType::Date64
+            )
+            .unwrap(),
+        );
+
+        // 2024-02-29 23:00:00 UTC is 2024-03-01 in Asia/Shanghai
+        let ts_array: ArrayRef = Arc::new(
+            TimestampMicrosecondArray::from(vec![(19782 * MILLIS_PER_DAY + 23 * 3_600_000) * 1000])
+                .with_timezone("Asia/Shanghai"),
+        );
+        assert_eq!(
+            cast(&ts_array, &DataType::Date64)
+                .unwrap()
+                .as_primitive::<Date64Type>()
+                .value(0),
+            19783 * MILLIS_PER_DAY,
+        );
+    }
+
+    #[test]
+    fn test_cast_offset_width() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("hello"),
+            None,
+            Some(""),
+            Some("world"),
+        ]));
+        let large_string_array: ArrayRef = Arc::new(LargeStringArray::from(vec![
+            Some("hello"),
+            None,
+            Some(""),
+            Some("world"),
+        ]));
+        assert_eq!(
+            &cast(&string_array, &DataType::LargeUtf8).unwrap(),
+            &large_string_array
+        );
+        assert_eq!(
+            &cast(&large_string_array, &DataType::Utf8).unwrap(),
+            &string_array
+        );
+
+        // sliced arrays are rebased
+        let sliced = large_string_array.slice(2, 2);
+        assert_eq!(
+            &cast(&sliced, &DataType::Utf8).unwrap(),
+            &(Arc::new(StringArray::from(vec![Some(""), Some("world")])) as ArrayRef),
+        );
+
+        let binary_array: ArrayRef = Arc::new(BinaryArray::from(vec![Some(&b"ab"[..]), None]));
+        let large_binary_array: ArrayRef =
+            Arc::new(LargeBinaryArray::from(vec![Some(&b"ab"[..]), None]));
+        assert_eq!(
+            &cast(&binary_array, &DataType::LargeBinary).unwrap(),
+            &large_binary_array
+        );
+        assert_eq!(
+            &cast(&large_binary_array, &DataType::Binary).unwrap(),
+            &binary_array
+        );
+    }
+
+    #[test]
+    fn test_cast_offset_width_overflow() {
+        // zeroed vecs are lazily allocated by calloc, values are never touched
+        let limit = i32::MAX as usize;
+        let values = Buffer::from_vec(vec![0u8; limit + 2]);
+        let large_binary_array = |offsets: Vec<i64>| -> ArrayRef {
+            Arc::new(LargeBinaryArray::new(
+                OffsetBuffer::new(ScalarBuffer::from(offsets)),
+                values.clone(),
+                None,
+            ))
+        };
+
+        // values of exactly i32::MAX bytes can be narrowed
+        let array = large_binary_array(vec![0, 1, limit as i64]);
+        let casted = cast(&array, &DataType::Binary).unwrap();
+        assert_eq!(casted.as_binary::<i32>().value_offsets(), &[0, 1, i32::MAX]);
+
+        // one more byte overflows
+        let array = large_binary_array(vec![0, 1, limit as i64 + 1]);
+        assert!(cast(&array, &DataType::Binary).is_err());
+
+        // sliced arrays are checked with the sliced values only
+        let array = large_binary_array(vec![0, 2, limit as i64 + 2]).slice(1, 1);
+        let casted = cast(&array, &DataType::Binary).unwrap();
+        assert_eq!(casted.as_binary::<i32>().value_offsets(), &[0, i32::MAX]);
+    }
+
+    #[test]
+    fn test_cast_time_unit() {
+        // 12:34:56.789 and 00:00:00.001
+        let millis: ArrayRef = Arc::new(Time32MillisecondArray::from(vec![
+            Some(45_296_789),
+            None,
+            Some(1),
+        ]));
+        let micros: ArrayRef = Arc::new(Time64MicrosecondArray::from(vec![
+            Some(45_296_789_000),
+            None,
+            Some(1_000),
+        ]));
+        let seconds: ArrayRef =
+            Arc::new(Time32SecondArray::from(vec![Some(45_296), None, Some(0)]));
+
+        assert_eq!(
+            &cast(&millis, &DataType::Time64(TimeUnit::Microsecond)).unwrap(),
+            &micros,
+        );
+        assert_eq!(
+            &cast(&micros, &DataType::Time32(TimeUnit::Millisecond)).unwrap(),
+            &millis,
+        );
+        assert_eq!(
+            &cast(&micros, &DataType::Time32(TimeUnit::Second)).unwrap(),
+            &seconds,
+        );
+        assert_eq!(
+            cast(&millis, &DataType::Time64(TimeUnit::Nanosecond))
+                .unwrap()
+                .as_primitive::<Time64NanosecondType>()
+                .value(0),
+            45_296_789_000_000,
+        );
+    }
+
+    #[test]
+    fn test_cast_decimal_to_decimal() {
+        let cases: Vec<(DataType, i128, DataType, Option<i128>)> = vec![
+            // widen precision and scale
+            (
+                DataType::Decimal128(10, 2),
+                12345,
+                DataType::Decimal128(20, 4),
+                Some(1234500),
+            ),
+            (
+                DataType::Decimal128(10, 2),
+                -12345,
+                DataType::Decimal128(20, 4),
+                Some(-1234500),
+            ),
+            // same scale
+            (
+                DataType::Decimal128(10, 2),
+                12345,
+                DataType::Decimal128(20, 2),
+                Some(12345),
+            ),
+            (
+                DataType::Decimal128(10, 2),
+                123456,
+                DataType::Decimal128(5, 2),
+                None,
+            ),
+            // narrow scale with HALF_UP rounding
+            (
+                DataType::Decimal128(20, 4),
+                1234567,
+                DataType::Decimal128(10, 2),
+                Some(12346),
+            ),
+            (
+                DataType::Decimal128(20, 4),
+                1234549,
+                DataType::Decimal128(10, 2),
+                Some(12345),
+            ),
+            (
+                DataType::Decimal128(20, 4),
+                1234550,
+                DataType::Decimal128(10, 2),
+                Some(12346),
+            ),
+            (
+                DataType::Decimal128(20, 4),
+                -1234550,
+                DataType::Decimal128(10, 2),
+                Some(-12346),
+            ),
+            (
+                DataType::Decimal128(20, 4),
+                -1234549,
+                DataType::Decimal128(10, 2),
+                Some(-12345),
+            ),
+            (
+                DataType::Decimal128(20, 4),
+                5,
+                DataType::Decimal128(10, 0),
+                Some(0),
+            ),
+            (
+                DataType::Decimal128(20, 4),
+                5000,
+                DataType::Decimal128(10, 0),
+                Some(1),
+            ),
+            // overflow of target precision
+            (
+                DataType::Decimal128(20, 4),
+                999999999999999,
+                DataType::Decimal128(10, 2),
+                None,
+            ),
+            (
+                DataType::Decimal128(5, 2),
+                12345,
+                DataType::Decimal128(5, 4),
+                None,
+            ),
+            (
+                DataType::Decimal128(5, 2),
+                12345,
+                DataType::Decimal128(7, 4),
+                Some(1234500),
+            ),
+            // rounding up to overflow
+            (
+                DataType::Decimal128(10, 3),
+                99995,
+                DataType::Decimal128(4, 2),
+                None,
+            ),
+            (
+                DataType::Decimal128(10, 3),
+                99994,
+                DataType::Decimal128(4, 2),
+                Some(9999),
+            ),
+            // large scale deltas
+            (
+                DataType::Decimal128(38, 38),
+                5,
+                DataType::Decimal128(38, 0),
+                Some(0),
+            ),
+            (
+                DataType::Decimal128(38, 0),
+                1,
+                DataType::Decimal128(38, 38),
+                None,
+            ),
+            (
+                DataType::Decimal128(38, 0),
+                0,
+                DataType::Decimal128(38, 37),
+                Some(0),
+            ),
+        ];
+        for (from_type, value, to_type, expected) in cases {
+            let array: ArrayRef = Arc::new(
+                Decimal128Array::from(vec![Some(value), None]).with_data_type(from_type.clone()),
+            );
+            let casted = cast(&array, &to_type).unwrap();
+            assert_eq!(casted.data_type(), &to_type);
+            assert_eq!(
+                casted.as_primitive::<Decimal128Type>(),
+                &Decimal128Array::from(vec![expected, None]).with_data_type(to_type.clone()),
+                "{from_type} {value} => {to_type}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_cast_decimal128_decimal256() {
+        let array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![Some(12345), Some(-5), None])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+        let casted = cast(&array, &DataType::Decimal256(50, 4)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal256Type>(),
+            &Decimal256Array::from(vec![
+                Some(i256::from_i128(1234500)),
+                Some(i256::from_i128(-500)),
+                None,
+            ])
+            .with_precision_and_scale(50, 4)
+            .unwrap(),
+        );
+
+        // back to decimal128, values not fitting become null
+        let large = i256::from_i128(i128::MAX).wrapping_mul(i256::from_i128(10));
+        let array: ArrayRef = Arc::new(
+            Decimal256Array::from(vec![Some(i256::from_i128(1234567)), Some(large), None])
+                .with_precision_and_scale(50, 4)
+                .unwrap(),
+        );
+        let casted = cast(&array, &DataType::Decimal128(38, 2)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![Some(12346), None, None])
+                .with_precision_and_scale(38, 2)
+                .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_cast_binary_byte_list() {
+        let binary: ArrayRef = Arc::new(BinaryArray::from(vec![
+            Some(&[0x01u8, 0x7f, 0x80, 0xff][..]),
+            None,
+            Some(&[][..]),
+            Some(&[0xfe][..]),
+        ]));
+        let byte_list_type = DataType::List(Arc::new(Field::new("item", DataType::Int8, true)));
+        let byte_list = cast(&binary, &byte_list_type).unwrap();
+        assert_eq!(
+            as_list_array(&byte_list),
+            &ListArray::from_iter_primitive::<Int8Type, _, _>(vec![
+                Some(vec![Some(1), Some(127), Some(-128), Some(-1)]),
+                None,
+                Some(vec![]),
+                Some(vec![Some(-2)]),
+            ]),
+        );
+
+        // round trip, including sliced arrays
+        let casted = cast(&byte_list, &DataType::Binary).unwrap();
+        assert_eq!(casted.as_binary::<i32>(), binary.as_binary::<i32>());
+        let casted = cast(&byte_list.slice(1, 3), &DataType::Binary).unwrap();
+        assert_eq!(
+            casted.as_binary::<i32>(),
+            binary.slice(1, 3).as_binary::<i32>()
+        );
+
+        // lists containing null items become nulls
+        let byte_list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int8Type, _, _>(vec![
+            Some(vec![Some(1), None]),
+            Some(vec![Some(2), Some(3)]),
+            None,
+        ]));
+        let casted = cast(&byte_list, &DataType::Binary).unwrap();
+        assert_eq!(
+            casted.as_binary::<i32>(),
+            &BinaryArray::from(vec![None, Some(&[2u8, 3][..]), None]),
+        );
+    }
+
+    #[test]
+    fn test_string_to_float_special_literals() {
+        let strings: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("Infinity"),
+            Some("infinity"),
+            Some("INFINITY"),
+            Some("+Infinity"),
+            Some("Inf"),
+            Some("inf"),
+            Some("+INF"),
+            Some(" Infinity "),
+            Some("-Infinity"),
+            Some("-infinity"),
+            Some("-Inf"),
+            Some("-INF"),
+            Some("NaN"),
+            Some("nan"),
+            Some("NAN"),
+            Some("+NaN"),
+            Some("-NaN"),
+            Some("-nan"),
+            Some("+nan"),
+            Some("infinit"),
+            Some("Infinityy"),
+            Some("+-inf"),
+            Some("1.5"),
+            Some("-2e3"),
+            None,
+        ]));
+        let inf = f64::INFINITY;
+        let expected = vec![
+            Some(inf),
+            Some(inf),
+            Some(inf),
+            Some(inf),
+            Some(inf),
+            Some(inf),
+            Some(inf),
+            Some(inf),
+            Some(-inf),
+            Some(-inf),
+            Some(-inf),
+            Some(-inf),
+            Some(f64::NAN),
+            Some(f64::NAN),
+            Some(f64::NAN),
+            Some(f64::NAN),
+            Some(f64::NAN),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(1.5),
+            Some(-2000.0),
+            None,
+        ];
+
+        let casted = cast(&strings, &DataType::Float64).unwrap();
+        let casted = as_float64_array(&casted).unwrap();
+        assert_eq!(casted.len(), expected.len());
+        for (i, (v, expected)) in casted.iter().zip(&expected).enumerate() {
+            match (v, expected) {
+                (Some(v), Some(expected)) if expected.is_nan() => assert!(v.is_nan(), "#{i}"),
+                (v, expected) => assert_eq!(v, *expected, "#{i}"),
+            }
+        }
+
+        let casted = cast(&strings, &DataType::Float32).unwrap();
+        let casted = casted.as_primitive::<Float32Type>();
+        for (i, (v, expected)) in casted.iter().zip(&expected).enumerate() {
+            match (v, expected) {
+                (Some(v), Some(expected)) if expected.is_nan() => assert!(v.is_nan(), "#{i}"),
+                (v, expected) => assert_eq!(v, expected.map(|v| v as f32), "#{i}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cast_timestamp_to_string() {
+        let array: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            Some(1_700_000_000_000_000), // zero fraction
+            Some(1_700_000_000_120_000), // millis only
+            Some(1_700_000_000_123_456), // micros
+            Some(1_700_000_000_000_010),
+            Some(-1),
+            None,
+        ]));
+
+        // spark compatible
+        let casted = cast(&array, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some("2023-11-14 22:13:20"),
+                Some("2023-11-14 22:13:20.12"),
+                Some("2023-11-14 22:13:20.123456"),
+                Some("2023-11-14 22:13:20.00001"),
+                Some("1969-12-31 23:59:59.999999"),
+                None,
+            ]),
+        );
+
+        // fixed precision
+        let casted = cast_timestamp_to_string(&array, Some(3)).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec![
+                Some("2023-11-14 22:13:20.000"),
+                Some("2023-11-14 22:13:20.120"),
+                Some("2023-11-14 22:13:20.123"),
+                Some("2023-11-14 22:13:20.000"),
+                Some("1969-12-31 23:59:59.999"),
+                None,
+            ]),
+        );
+        let casted = cast_timestamp_to_string(&array.slice(2, 1), Some(0)).unwrap();
+        assert_eq!(casted.as_string::<i32>().value(0), "2023-11-14 22:13:20");
+        let casted = cast_timestamp_to_string(&array.slice(2, 1), Some(9)).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>().value(0),
+            "2023-11-14 22:13:20.123456000"
+        );
+
+        // other units and timezones
+        let array: ArrayRef = Arc::new(
+            TimestampMillisecondArray::from(vec![Some(1_700_000_000_120)]).with_timezone("+08:00"),
+        );
+        let casted = cast(&array, &DataType::Utf8).unwrap();
+        assert_eq!(casted.as_string::<i32>().value(0), "2023-11-15 06:13:20.12");
+        let array: ArrayRef = Arc::new(TimestampNanosecondArray::from(vec![Some(
+            1_700_000_000_123_456_789,
+        )]));
+        let casted = cast(&array, &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>().value(0),
+            "2023-11-14 22:13:20.123456"
+        );
+    }
+
+    #[test]
+    fn test_cast_float_to_decimal() {
+        let f64_array: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(0.1),
+            Some(0.3),
+            Some(1.005),
+            Some(-1.005),
+            Some(0.125),
+            Some(123456789.0),
+            Some(1e20),
+            Some(f64::NAN),
+            Some(f64::INFINITY),
+            None,
+        ]));
+        let casted = cast(&f64_array, &DataType::Decimal128(10, 2)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![
+                Some(10),
+                Some(30),
+                Some(101),
+                Some(-101),
+                Some(13),
+                None, // overflow
+                None,
+                None,
+                None,
+                None,
+            ])
+            .with_precision_and_scale(10, 2)
+            .unwrap(),
+        );
+
+        // floats are converted through doubles like spark
+        let f32_array: ArrayRef = Arc::new(Float32Array::from(vec![Some(0.1), Some(0.3)]));
+        let casted = cast(&f32_array, &DataType::Decimal128(10, 2)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![Some(10), Some(30)])
+                .with_precision_and_scale(10, 2)
+                .unwrap(),
+        );
+        let casted = cast(&f32_array, &DataType::Decimal128(20, 10)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![Some(1000000015), Some(3000000119)])
+                .with_precision_and_scale(20, 10)
+                .unwrap(),
+        );
+
+        // large magnitudes
+        let f64_array: ArrayRef = Arc::new(Float64Array::from(vec![Some(1e20), Some(-1.5e30)]));
+        let casted = cast(&f64_array, &DataType::Decimal128(38, 0)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal128Type>(),
+            &Decimal128Array::from(vec![Some(10i128.pow(20)), Some(-15 * 10i128.pow(29))])
+                .with_precision_and_scale(38, 0)
+                .unwrap(),
+        );
+        let casted = cast(&f64_array, &DataType::Decimal256(50, 10)).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Decimal256Type>(),
+            &Decimal256Array::from(vec![
+                Some(i256::from_i128(10i128.pow(30))),
+                Some(
+                    i256::from_i128(-15 * 10i128.pow(29))
+                        .wrapping_mul(i256::from_i128(10i128.pow(10)))
+                ),
+            ])
+            .with_precision_and_scale(50, 10)
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_cast_decimal_to_float() {
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![
+                Some(1),
+                Some(3),
+                Some(-7),
+                Some(12345678901234567890123),
+                None,
+            ])
+            .with_precision_and_scale(38, 1)
+            .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Float64).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Float64Type>(),
+            &Float64Array::from(vec![
+                Some(0.1),
+                Some(0.3),
+                Some(-0.7),
+                Some("1234567890123456789012.3".parse::<f64>().unwrap()),
+                None,
+            ]),
+        );
+        let casted = cast(&decimal_array, &DataType::Float32).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Float32Type>(),
+            &Float32Array::from(vec![
+                Some(0.1),
+                Some(0.3),
+                Some(-0.7),
+                Some("1234567890123456789012.3".parse::<f32>().unwrap()),
+                None,
+            ]),
+        );
+
+        // round trip of doubles through decimals with enough digits
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal256Array::from(vec![Some(i256::from_i128(123456789012345678))])
+                .with_precision_and_scale(40, 20)
+                .unwrap(),
+        );
+        let casted = cast(&decimal_array, &DataType::Float64).unwrap();
+        assert_eq!(
+            casted.as_primitive::<Float64Type>().value(0),
+            "0.00123456789012345678".parse::<f64>().unwrap(),
+        );
+    }
 }
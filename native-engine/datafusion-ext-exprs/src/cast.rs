@@ -14,15 +14,29 @@
 
 use std::{
     any::Any,
+    collections::HashSet,
     fmt::{Display, Formatter},
     hash::{Hash, Hasher},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        Arc,
+    },
 };
 
-use arrow::{datatypes::*, record_batch::RecordBatch};
+use arrow::{
+    array::{new_null_array, Array, ArrayRef, BooleanArray, UInt32Array},
+    compute::{and, is_not_null, is_null, take, FilterBuilder},
+    datatypes::*,
+    record_batch::{RecordBatch, RecordBatchOptions},
+    util::display::array_value_to_string,
+};
 use datafusion::{
-    common::Result, logical_expr::ColumnarValue, physical_expr::PhysicalExpr, scalar::ScalarValue,
+    common::Result,
+    logical_expr::{interval_arithmetic::Interval, ColumnarValue},
+    physical_expr::{utils::collect_columns, PhysicalExpr},
+    scalar::ScalarValue,
 };
+use datafusion_ext_commons::cast::TimeParserPolicy;
 
 use crate::down_cast_any_ref;
 
@@ -31,20 +45,122 @@ use crate::down_cast_any_ref;
 pub struct TryCastExpr {
     pub expr: Arc<dyn PhysicalExpr>,
     pub cast_type: DataType,
+    time_parser_policy: TimeParserPolicy,
+    diagnostics: Option<Arc<CastDiagnostics>>,
 }
 
 impl PartialEq<dyn Any> for TryCastExpr {
     fn eq(&self, other: &dyn Any) -> bool {
         down_cast_any_ref(other)
             .downcast_ref::<Self>()
-            .map(|x| self.expr.eq(&x.expr) && self.cast_type == x.cast_type)
+            .map(|x| {
+                self.expr.eq(&x.expr)
+                    && self.cast_type == x.cast_type
+                    && self.time_parser_policy == x.time_parser_policy
+            })
             .unwrap_or(false)
     }
 }
 
 impl TryCastExpr {
     pub fn new(expr: Arc<dyn PhysicalExpr>, cast_type: DataType) -> Self {
-        Self { expr, cast_type }
+        Self {
+            expr,
+            cast_type,
+            time_parser_policy: TimeParserPolicy::default(),
+            diagnostics: None,
+        }
+    }
+
+    /// Sets the policy of parsing strings to dates/timestamps, which is
+    /// spark.sql.legacy.timeParserPolicy of the session submitting the plan.
+    pub fn with_time_parser_policy(self, time_parser_policy: TimeParserPolicy) -> Self {
+        Self {
+            time_parser_policy,
+            ..self
+        }
+    }
+
+    /// Enables logging samples of the input values failed to cast (producing
+    /// nulls), at most `max_logged_values` values are logged.
+    pub fn with_diagnostics(self, max_logged_values: usize) -> Self {
+        Self {
+            diagnostics: Some(Arc::new(CastDiagnostics::new(max_logged_values))),
+            ..self
+        }
+    }
+
+    /// Number of non-null input values failed to cast, only counted when
+    /// diagnostics are enabled.
+    pub fn num_failed_values(&self) -> usize {
+        self.diagnostics
+            .as_ref()
+            .map(|diagnostics| diagnostics.num_failed_values.load(Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// Evaluates the cast along with a mask of rows failed to cast, that is,
+    /// rows with non-null input values casted to nulls. rows of null inputs
+    /// are not failed, so that bad rows can be told apart from missing
+    /// values (like routing them to a quarantine table).
+    pub fn evaluate_with_failure_mask(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<(ArrayRef, BooleanArray)> {
+        let array = self.expr.evaluate(batch)?.into_array(batch.num_rows())?;
+        let casted = self.cast(&array)?;
+        let cast_failed = and(&is_not_null(&array)?, &is_null(&casted)?)?;
+        Ok((casted, cast_failed))
+    }
+
+    fn cast(&self, array: &ArrayRef) -> Result<ArrayRef> {
+        let casted = datafusion_ext_commons::cast::spark_cast(
+            array,
+            &self.cast_type,
+            self.time_parser_policy,
+        )?;
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.check(self, array, &casted);
+        }
+        Ok(casted)
+    }
+}
+
+#[derive(Debug)]
+struct CastDiagnostics {
+    max_logged_values: usize,
+    num_failed_values: AtomicUsize,
+}
+
+impl Hash for CastDiagnostics {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.max_logged_values.hash(state);
+    }
+}
+
+impl CastDiagnostics {
+    fn new(max_logged_values: usize) -> Self {
+        Self {
+            max_logged_values,
+            num_failed_values: AtomicUsize::new(0),
+        }
+    }
+
+    fn check(&self, expr: &TryCastExpr, array: &ArrayRef, casted: &ArrayRef) {
+        if casted.null_count() == array.null_count() {
+            return; // fast path: no failed values
+        }
+        for i in 0..array.len() {
+            if array.is_valid(i) && casted.is_null(i) {
+                let num_failed = self.num_failed_values.fetch_add(1, Relaxed);
+                if num_failed < self.max_logged_values {
+                    log::warn!(
+                        "{expr}: failed to cast value: {}",
+                        array_value_to_string(array, i).unwrap_or_else(|e| e.to_string()),
+                    );
+                }
+            }
+        }
     }
 }
 
@@ -54,6 +170,38 @@ impl Display for TryCastExpr {
     }
 }
 
+/// Returns true if the cast never fails and preserves the order of values,
+/// like widening integers and dates to timestamps.
+fn is_monotonic_cast(from_type: &DataType, to_type: &DataType) -> bool {
+    match (from_type, to_type) {
+        (DataType::Int8, DataType::Int16 | DataType::Int32 | DataType::Int64)
+        | (DataType::Int16, DataType::Int32 | DataType::Int64)
+        | (DataType::Int32, DataType::Int64)
+        | (
+            DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64,
+            DataType::Float32 | DataType::Float64,
+        )
+        | (DataType::Float32, DataType::Float64)
+        | (DataType::Date32, DataType::Timestamp(..)) => true,
+        (from_type, to_type) => from_type == to_type,
+    }
+}
+
+/// Returns true if the cast is monotonic and never maps distinct values to
+/// the same value, so that comparing casted values with a literal is the same
+/// as comparing input values with the literal cast back to the input type (if
+/// it can be cast back exactly).
+pub fn is_injective_monotonic_cast(from_type: &DataType, to_type: &DataType) -> bool {
+    match (from_type, to_type) {
+        // integers wider than the float mantissa lose precision
+        (DataType::Int32 | DataType::Int64, DataType::Float32)
+        | (DataType::Int64, DataType::Float64) => false,
+        // casting back depends on the session timezone
+        (DataType::Date32, DataType::Timestamp(..)) => false,
+        (from_type, to_type) => is_monotonic_cast(from_type, to_type),
+    }
+}
+
 impl PhysicalExpr for TryCastExpr {
     fn as_any(&self) -> &dyn Any {
         self
@@ -69,31 +217,70 @@ impl PhysicalExpr for TryCastExpr {
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         Ok(match self.expr.evaluate(batch)? {
-            ColumnarValue::Array(array) => {
-                ColumnarValue::Array(datafusion_ext_commons::cast::cast(&array, &self.cast_type)?)
-            }
+            ColumnarValue::Array(array) => ColumnarValue::Array(self.cast(&array)?),
             ColumnarValue::Scalar(scalar) => {
                 let array = scalar.to_array()?;
-                ColumnarValue::Scalar(ScalarValue::try_from_array(
-                    &datafusion_ext_commons::cast::cast(&array, &self.cast_type)?,
-                    0,
-                )?)
+                ColumnarValue::Scalar(ScalarValue::try_from_array(&self.cast(&array)?, 0)?)
             }
         })
     }
 
+    fn evaluate_selection(
+        &self,
+        batch: &RecordBatch,
+        selection: &BooleanArray,
+    ) -> Result<ColumnarValue> {
+        let num_selected = selection.true_count();
+        if num_selected == batch.num_rows() {
+            return self.evaluate(batch);
+        }
+        if num_selected == 0 {
+            let nulls = new_null_array(&self.cast_type, batch.num_rows());
+            return Ok(ColumnarValue::Array(nulls));
+        }
+
+        // only selected rows are evaluated and casted. unlike the default
+        // implementation, columns not used by the child are not filtered
+        let selected_batch = filter_used_columns(batch, selection, num_selected, &self.expr)?;
+        Ok(match self.evaluate(&selected_batch)? {
+            ColumnarValue::Array(casted) => {
+                ColumnarValue::Array(scatter_selected(selection, &casted)?)
+            }
+            scalar @ ColumnarValue::Scalar(_) => scalar,
+        })
+    }
+
     fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
         vec![self.expr.clone()]
     }
 
+    fn evaluate_bounds(&self, children: &[&Interval]) -> Result<Interval> {
+        // only monotonic casts preserve the order of bounds
+        let input = children[0];
+        if !is_monotonic_cast(&input.data_type(), &self.cast_type) {
+            return Interval::make_unbounded(&self.cast_type);
+        }
+        let cast_bound = |bound: &ScalarValue| -> Result<ScalarValue> {
+            let casted = datafusion_ext_commons::cast::spark_cast(
+                &bound.to_array()?,
+                &self.cast_type,
+                self.time_parser_policy,
+            )?;
+            ScalarValue::try_from_array(&casted, 0)
+        };
+        Interval::try_new(cast_bound(input.lower())?, cast_bound(input.upper())?)
+    }
+
     fn with_new_children(
         self: Arc<Self>,
         children: Vec<Arc<dyn PhysicalExpr>>,
     ) -> Result<Arc<dyn PhysicalExpr>> {
-        Ok(Arc::new(Self::new(
-            children[0].clone(),
-            self.cast_type.clone(),
-        )))
+        Ok(Arc::new(Self {
+            expr: children[0].clone(),
+            cast_type: self.cast_type.clone(),
+            time_parser_policy: self.time_parser_policy,
+            diagnostics: self.diagnostics.clone(),
+        }))
     }
 
     fn dyn_hash(&self, state: &mut dyn Hasher) {
@@ -101,16 +288,70 @@ impl PhysicalExpr for TryCastExpr {
         self.hash(&mut s);
     }
 }
+
+// filters columns used by the expression with the selection, other columns
+// are replaced with zero-copy slices of the same length
+fn filter_used_columns(
+    batch: &RecordBatch,
+    selection: &BooleanArray,
+    num_selected: usize,
+    expr: &Arc<dyn PhysicalExpr>,
+) -> Result<RecordBatch> {
+    let used_columns = collect_columns(expr)
+        .into_iter()
+        .map(|col| col.index())
+        .collect::<HashSet<_>>();
+    let filter = FilterBuilder::new(selection).optimize().build();
+    let columns = batch
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            if used_columns.contains(&i) {
+                filter.filter(column)
+            } else {
+                Ok(column.slice(0, num_selected))
+            }
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new_with_options(
+        batch.schema(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_selected)),
+    )?)
+}
+
+// scatters values of selected rows back to their positions, unselected rows
+// are null
+fn scatter_selected(selection: &BooleanArray, selected: &ArrayRef) -> Result<ArrayRef> {
+    let mut next_selected = 0u32;
+    let indices: UInt32Array = (0..selection.len())
+        .map(|i| {
+            let is_selected = selection.is_valid(i) && selection.value(i);
+            is_selected.then(|| {
+                next_selected += 1;
+                next_selected - 1
+            })
+        })
+        .collect();
+    Ok(take(selected, &indices, None)?)
+}
+
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
+    use std::{sync::Arc, time::Instant};
 
     use arrow::{
-        array::{ArrayRef, Float32Array, Int32Array, StringArray},
+        array::{ArrayRef, BooleanArray, Date32Array, Float32Array, Int32Array, StringArray},
         datatypes::{DataType, Field, Schema},
         record_batch::RecordBatch,
     };
-    use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use datafusion::{
+        common::ScalarValue,
+        logical_expr::interval_arithmetic::Interval,
+        physical_expr::{expressions as phys_expr, PhysicalExpr},
+    };
+    use datafusion_ext_commons::cast::TimeParserPolicy;
 
     use crate::cast::TryCastExpr;
 
@@ -236,4 +477,228 @@ mod test {
         ]));
         assert_eq!(&ret, &expected);
     }
+
+    #[test]
+    fn test_diagnostics() {
+        let string_arr: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("123"),
+            Some("abc"),
+            None,
+            Some("1.2.3"),
+        ]));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Utf8, true)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![string_arr]).expect("Error creating RecordBatch");
+
+        // disabled by default
+        let expr = TryCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Int32,
+        );
+        expr.evaluate(&batch).expect("Error evaluating expr");
+        assert_eq!(expr.num_failed_values(), 0);
+
+        // null inputs are not counted as failures
+        let expr = expr.with_diagnostics(1);
+        let ret = expr
+            .evaluate(&batch)
+            .expect("Error evaluating expr")
+            .into_array(batch.num_rows())
+            .unwrap();
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(123), None, None, None]));
+        assert_eq!(&ret, &expected);
+        assert_eq!(expr.num_failed_values(), 2);
+    }
+
+    #[test]
+    fn test_evaluate_with_failure_mask() {
+        let string_arr: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("123"),
+            Some("abc"),
+            None,
+            Some("1.2.3"),
+        ]));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Utf8, true)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![string_arr]).expect("Error creating RecordBatch");
+
+        // both failed values and null inputs are null in the result, only
+        // failed values are masked
+        let expr = TryCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Int32,
+        );
+        let (ret, cast_failed) = expr.evaluate_with_failure_mask(&batch).unwrap();
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![Some(123), None, None, None]));
+        assert_eq!(&ret, &expected);
+        assert_eq!(
+            cast_failed,
+            BooleanArray::from(vec![false, true, false, true]),
+        );
+
+        // scalar inputs are expanded to all rows
+        let expr = TryCastExpr::new(phys_expr::lit("abc"), DataType::Int32);
+        let (ret, cast_failed) = expr.evaluate_with_failure_mask(&batch).unwrap();
+        assert_eq!(ret.null_count(), 4);
+        assert_eq!(cast_failed, BooleanArray::from(vec![true; 4]));
+
+        let expr = TryCastExpr::new(phys_expr::lit(ScalarValue::Utf8(None)), DataType::Int32);
+        let (ret, cast_failed) = expr.evaluate_with_failure_mask(&batch).unwrap();
+        assert_eq!(ret.null_count(), 4);
+        assert_eq!(cast_failed, BooleanArray::from(vec![false; 4]));
+    }
+
+    #[test]
+    fn test_evaluate_bounds() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let col = phys_expr::col("a", &schema).unwrap();
+
+        // bounds propagate through monotonic int to long cast
+        let expr = TryCastExpr::new(col.clone(), DataType::Int64);
+        let input =
+            Interval::try_new(ScalarValue::Int32(Some(1)), ScalarValue::Int32(Some(10))).unwrap();
+        assert_eq!(
+            expr.evaluate_bounds(&[&input]).unwrap(),
+            Interval::try_new(ScalarValue::Int64(Some(1)), ScalarValue::Int64(Some(10))).unwrap(),
+        );
+
+        // unbounded side is kept unbounded
+        let input =
+            Interval::try_new(ScalarValue::Int32(None), ScalarValue::Int32(Some(10))).unwrap();
+        assert_eq!(
+            expr.evaluate_bounds(&[&input]).unwrap(),
+            Interval::try_new(ScalarValue::Int64(None), ScalarValue::Int64(Some(10))).unwrap(),
+        );
+
+        // non-monotonic cast has unknown bounds
+        let expr = TryCastExpr::new(col, DataType::Utf8);
+        let input =
+            Interval::try_new(ScalarValue::Int32(Some(1)), ScalarValue::Int32(Some(10))).unwrap();
+        assert_eq!(
+            expr.evaluate_bounds(&[&input]).unwrap(),
+            Interval::make_unbounded(&DataType::Utf8).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_evaluate_selection() {
+        let string_arr: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("1"),
+            Some("abc"),
+            Some("3"),
+            None,
+            Some("5"),
+        ]));
+        let other_arr: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5]));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("other", DataType::Int32, false),
+            Field::new("col", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(schema, vec![other_arr, string_arr]).unwrap();
+        let expr = TryCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Int32,
+        )
+        .with_diagnostics(0);
+
+        // unselected and null-selected rows are null
+        let selection =
+            BooleanArray::from(vec![Some(true), Some(false), None, Some(true), Some(true)]);
+        let ret = expr
+            .evaluate_selection(&batch, &selection)
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap();
+        let expected: ArrayRef =
+            Arc::new(Int32Array::from(vec![Some(1), None, None, None, Some(5)]));
+        assert_eq!(&ret, &expected);
+
+        // unselected values are never casted
+        assert_eq!(expr.num_failed_values(), 0);
+        let ret = expr
+            .evaluate_selection(&batch, &BooleanArray::from(vec![false; 5]))
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap();
+        assert_eq!(ret.null_count(), 5);
+        assert_eq!(expr.num_failed_values(), 0);
+
+        // all selected
+        let ret = expr
+            .evaluate_selection(&batch, &BooleanArray::from(vec![true; 5]))
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap();
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![
+            Some(1),
+            None,
+            Some(3),
+            None,
+            Some(5),
+        ]));
+        assert_eq!(&ret, &expected);
+        assert_eq!(expr.num_failed_values(), 1);
+    }
+
+    #[test]
+    fn test_time_parser_policy() {
+        let string_arr: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("2023-02-28"),
+            Some("2023-02-29"),
+        ]));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Utf8, true)]));
+        let batch = RecordBatch::try_new(schema, vec![string_arr]).unwrap();
+        let expr = TryCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Date32,
+        );
+
+        let ret = expr.evaluate(&batch).unwrap().into_array(2).unwrap();
+        let expected: ArrayRef = Arc::new(Date32Array::from(vec![Some(19416), None]));
+        assert_eq!(&ret, &expected);
+
+        // out-of-range fields roll over in the legacy policy
+        let expr = expr.with_time_parser_policy(TimeParserPolicy::Legacy);
+        let ret = expr.evaluate(&batch).unwrap().into_array(2).unwrap();
+        let expected: ArrayRef = Arc::new(Date32Array::from(vec![Some(19416), Some(19417)]));
+        assert_eq!(&ret, &expected);
+    }
+
+    // benchmark of casting under a 1% selectivity filter, run with:
+    // cargo test --release -p datafusion-ext-exprs bench_evaluate_selection --
+    // --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_evaluate_selection() {
+        let num_rows = 1 << 20;
+        let string_arr: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..num_rows).map(|i| format!("{}.{}", i, i % 100)),
+        ));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Utf8, true)]));
+        let batch = RecordBatch::try_new(schema, vec![string_arr]).unwrap();
+        let selection = BooleanArray::from_iter((0..num_rows).map(|i| Some(i % 100 == 0)));
+        let expr = TryCastExpr::new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            DataType::Float64,
+        )
+        .with_diagnostics(0);
+
+        let start = Instant::now();
+        let full = expr.evaluate(&batch).unwrap().into_array(num_rows).unwrap();
+        let full_elapsed = start.elapsed();
+
+        let start = Instant::now();
+        let selected = expr
+            .evaluate_selection(&batch, &selection)
+            .unwrap()
+            .into_array(num_rows)
+            .unwrap();
+        let selected_elapsed = start.elapsed();
+
+        assert_eq!(full.null_count(), 0);
+        assert_eq!(selected.null_count(), num_rows - num_rows / 100);
+        println!(
+            "cast {num_rows} rows: full evaluation {full_elapsed:?}, 1% selection {selected_elapsed:?}"
+        );
+    }
 }
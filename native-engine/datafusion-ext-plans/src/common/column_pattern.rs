@@ -0,0 +1,180 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::datatypes::Schema;
+use datafusion::common::Result;
+use datafusion_ext_commons::df_execution_err;
+use regex::{Regex, RegexBuilder};
+
+const REGEX_PATTERN_PREFIX: &str = "regex:";
+
+/// A pattern selecting top-level columns of a file schema by name.
+///
+/// patterns are globs matching the whole column name by default, where `*`
+/// matches any characters and `?` matches a single character. patterns
+/// prefixed with `regex:` are regular expressions matching any part of the
+/// column name unless anchored with `^` or `$`. names are matched
+/// case-insensitively unless `case_sensitive`, like spark.sql.caseSensitive.
+#[derive(Debug, Clone)]
+pub struct ColumnPattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl ColumnPattern {
+    pub fn new(pattern: &str, case_sensitive: bool) -> Result<Self> {
+        let regex_str = match pattern.strip_prefix(REGEX_PATTERN_PREFIX) {
+            Some(regex_str) => regex_str.to_string(),
+            None => glob_to_regex(pattern),
+        };
+        let regex = match RegexBuilder::new(&regex_str)
+            .case_insensitive(!case_sensitive)
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(err) => return df_execution_err!("invalid column pattern {pattern}: {err}"),
+        };
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn matches(&self, column_name: &str) -> bool {
+        self.regex.is_match(column_name)
+    }
+}
+
+/// Expands column patterns against the file schema into a projection of
+/// column indices, to be used before constructing a parquet scan. columns are
+/// projected in the file schema order, each at most once even if matched by
+/// multiple patterns.
+pub fn expand_column_patterns(
+    file_schema: &Schema,
+    patterns: &[&str],
+    case_sensitive: bool,
+) -> Result<Vec<usize>> {
+    let patterns = patterns
+        .iter()
+        .map(|pattern| ColumnPattern::new(pattern, case_sensitive))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(file_schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| patterns.iter().any(|p| p.matches(field.name())))
+        .map(|(idx, _)| idx)
+        .collect())
+}
+
+// translates a glob into an anchored regex, other characters are matched
+// literally
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex_str = String::from("^");
+    let mut literal = [0u8; 4];
+    for c in glob.chars() {
+        match c {
+            '*' => regex_str.push_str(".*"),
+            '?' => regex_str.push('.'),
+            c => regex_str.push_str(&regex::escape(c.encode_utf8(&mut literal))),
+        }
+    }
+    regex_str.push('$');
+    regex_str
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    use crate::common::column_pattern::{expand_column_patterns, ColumnPattern};
+
+    fn file_schema() -> Schema {
+        Schema::new(
+            [
+                "id",
+                "metric_a",
+                "Metric_B",
+                "metric_10",
+                "error_count",
+                "metric.x",
+            ]
+            .into_iter()
+            .map(|name| Field::new(name, DataType::Int64, true))
+            .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_glob_patterns() {
+        let schema = file_schema();
+        assert_eq!(
+            expand_column_patterns(&schema, &["metric_*"], false).unwrap(),
+            vec![1, 2, 3],
+        );
+        assert_eq!(
+            expand_column_patterns(&schema, &["metric_*"], true).unwrap(),
+            vec![1, 3],
+        );
+        assert_eq!(
+            expand_column_patterns(&schema, &["metric_?"], false).unwrap(),
+            vec![1, 2],
+        );
+
+        // globs match whole names and other characters literally
+        assert!(expand_column_patterns(&schema, &["count"], false)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            expand_column_patterns(&schema, &["metric.*"], false).unwrap(),
+            vec![5],
+        );
+
+        // projected in file schema order without duplicates
+        assert_eq!(
+            expand_column_patterns(&schema, &["*_count", "id", "metric_a*"], false).unwrap(),
+            vec![0, 1, 4],
+        );
+    }
+
+    #[test]
+    fn test_regex_patterns() {
+        let schema = file_schema();
+        assert_eq!(
+            expand_column_patterns(&schema, &["regex:count"], false).unwrap(),
+            vec![4],
+        );
+        assert_eq!(
+            expand_column_patterns(&schema, &["regex:^metric_\\d+$"], false).unwrap(),
+            vec![3],
+        );
+        assert_eq!(
+            expand_column_patterns(&schema, &["regex:^m"], false).unwrap(),
+            vec![1, 2, 3, 5],
+        );
+        assert!(expand_column_patterns(&schema, &["regex:_b$"], true)
+            .unwrap()
+            .is_empty());
+
+        assert!(ColumnPattern::new("regex:metric_(", false).is_err());
+        assert!(ColumnPattern::new("metric_(", false)
+            .unwrap()
+            .matches("metric_("));
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use datafusion::common::Result;
+use datafusion_ext_commons::df_execution_err;
+use futures::{Stream, StreamExt};
+
+/// A set of cpus, parsed from a linux cpu list like `0-15,32-47`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuSet(Vec<usize>);
+
+impl CpuSet {
+    pub fn parse(cpu_list: &str) -> Result<Self> {
+        let mut cpus = vec![];
+        for part in cpu_list.split(',').map(str::trim) {
+            let parse_cpu = |s: &str| {
+                s.trim()
+                    .parse::<usize>()
+                    .or_else(|_| df_execution_err!("invalid cpu list: {cpu_list}"))
+            };
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (parse_cpu(first)?, parse_cpu(last)?);
+                    if first > last {
+                        return df_execution_err!("invalid cpu list: {cpu_list}");
+                    }
+                    cpus.extend(first..=last);
+                }
+                None => cpus.push(parse_cpu(part)?),
+            }
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(Self(cpus))
+    }
+
+    /// parses cpu sets separated by `;`, like one set for each numa node
+    pub fn parse_sets(cpu_sets: &str) -> Result<Vec<Self>> {
+        cpu_sets
+            .split(';')
+            .map(str::trim)
+            .filter(|cpu_list| !cpu_list.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    pub fn cpus(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+/// Pins the current thread to the cpu set until the returned guard is dropped,
+/// which restores the previous affinity of the thread. returns None if
+/// affinity is not supported on the platform or not permitted.
+pub fn pin_current_thread(cpu_set: &CpuSet) -> Option<PinnedThread> {
+    let previous = current_thread_affinity()?;
+    if previous == *cpu_set {
+        return Some(PinnedThread { previous: None });
+    }
+    if !set_current_thread_affinity(cpu_set.cpus()) {
+        log::warn!("cannot pin thread to cpus: {:?}", cpu_set.cpus());
+        return None;
+    }
+    Some(PinnedThread {
+        previous: Some(previous),
+    })
+}
+
+/// Guard of a thread pinned by [`pin_current_thread`].
+pub struct PinnedThread {
+    previous: Option<CpuSet>,
+}
+
+impl Drop for PinnedThread {
+    fn drop(&mut self) {
+        if let Some(previous) = &self.previous {
+            set_current_thread_affinity(previous.cpus());
+        }
+    }
+}
+
+/// Wraps a stream so that threads polling it are pinned to the cpu set, like
+/// worker threads of the task runtime decoding a parquet scan partition.
+///
+/// worker threads are shared by all tasks of the runtime, so a thread is only
+/// pinned while polling the stream and its affinity is restored after each
+/// poll, which costs a few syscalls per decoded batch.
+pub fn pin_polling_threads<S: Stream + Unpin>(stream: S, cpu_set: Arc<CpuSet>) -> PinnedStream<S> {
+    PinnedStream {
+        inner: stream,
+        cpu_set,
+        supported: true,
+    }
+}
+
+pub struct PinnedStream<S> {
+    inner: S,
+    cpu_set: Arc<CpuSet>,
+    supported: bool,
+}
+
+impl<S: Stream + Unpin> Stream for PinnedStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let _pinned = if self.supported {
+            // stop trying once affinity is found unsupported
+            let pinned = pin_current_thread(&self.cpu_set);
+            self.supported = pinned.is_some();
+            pinned
+        } else {
+            None
+        };
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_current_thread_affinity(cpus: &[usize]) -> bool {
+    let max_cpus = libc::CPU_SETSIZE as usize;
+    if cpus.is_empty() || cpus.iter().any(|&cpu| cpu >= max_cpus) {
+        return false;
+    }
+
+    // safety: cpu_set_t is a plain bitmask, cpus are checked within its size
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_current_thread_affinity(_cpus: &[usize]) -> bool {
+    false
+}
+
+/// Returns the cpus the current thread can run on, or None if affinity is
+/// not supported on the platform.
+#[cfg(target_os = "linux")]
+pub fn current_thread_affinity() -> Option<CpuSet> {
+    // safety: cpu_set_t is a plain bitmask filled by the kernel
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+        let cpus = (0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+            .collect();
+        Some(CpuSet(cpus))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn current_thread_affinity() -> Option<CpuSet> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Instant};
+
+    use futures::{StreamExt, TryStreamExt};
+
+    use crate::common::cpu_affinity::{
+        current_thread_affinity, pin_current_thread, pin_polling_threads, CpuSet,
+    };
+
+    #[test]
+    fn test_parse_cpu_set() {
+        assert_eq!(
+            CpuSet::parse("0-3, 8,10-11,2").unwrap().cpus(),
+            &[0, 1, 2, 3, 8, 10, 11]
+        );
+        assert!(CpuSet::parse("3-1").is_err());
+        assert!(CpuSet::parse("a").is_err());
+        assert!(CpuSet::parse("").is_err());
+
+        let cpu_sets = CpuSet::parse_sets("0-1;2-3;").unwrap();
+        assert_eq!(cpu_sets, vec![CpuSet(vec![0, 1]), CpuSet(vec![2, 3])]);
+        assert!(CpuSet::parse_sets("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pin_polling_threads() {
+        // pinned in a separate thread, not to affect other tests
+        std::thread::spawn(|| {
+            let Some(allowed) = current_thread_affinity() else {
+                return; // affinity not supported
+            };
+            let cpu_set = Arc::new(CpuSet(vec![allowed.cpus()[0]]));
+
+            // results are not affected by pinning, the thread is pinned only
+            // while polling
+            let stream =
+                futures::stream::iter(0..100).map(|i| Ok::<_, ()>((i, current_thread_affinity())));
+            let values = futures::executor::block_on(
+                pin_polling_threads(stream, cpu_set.clone()).try_collect::<Vec<_>>(),
+            )
+            .unwrap();
+            assert_eq!(
+                values.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+                (0..100).collect::<Vec<_>>(),
+            );
+            assert!(values
+                .iter()
+                .all(|(_, affinity)| affinity.as_ref() == Some(cpu_set.as_ref())));
+            assert_eq!(current_thread_affinity(), Some(allowed.clone()));
+
+            // cpus out of the cpu set size cannot be pinned
+            assert!(pin_current_thread(&CpuSet(vec![1 << 20])).is_none());
+            assert_eq!(current_thread_affinity(), Some(allowed.clone()));
+
+            // the previous affinity is restored by the guard
+            let pinned = pin_current_thread(&cpu_set);
+            assert!(pinned.is_some());
+            assert_eq!(current_thread_affinity(), Some((*cpu_set).clone()));
+            drop(pinned);
+            assert_eq!(current_thread_affinity(), Some(allowed));
+        })
+        .join()
+        .unwrap();
+    }
+
+    // benchmark of decoding-like work pinned to one numa node versus
+    // scattered on all cpus, run on a multi-socket machine with:
+    // BLAZE_BENCH_CPU_SET=0-15 cargo test --release -p datafusion-ext-plans
+    // bench_pinned_decode -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_pinned_decode() {
+        let cpu_set = std::env::var("BLAZE_BENCH_CPU_SET").unwrap_or_else(|_| "0".to_string());
+        let cpu_set = Arc::new(CpuSet::parse(&cpu_set).unwrap());
+        let num_threads = cpu_set.cpus().len();
+
+        // each thread repeatedly scans a buffer larger than the cpu caches,
+        // which misses the local memory if scheduled on other sockets
+        let run = |pinned: bool| {
+            let cpu_set = cpu_set.clone();
+            let start = Instant::now();
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(num_threads)
+                .build()
+                .unwrap();
+            let sums = runtime.block_on(async {
+                let tasks = (0..num_threads).map(|i| {
+                    let cpu_set = cpu_set.clone();
+                    tokio::spawn(async move {
+                        let data = vec![i as u64; 64 << 20];
+                        let passes = futures::stream::iter(0..16);
+                        let passes = if pinned {
+                            pin_polling_threads(passes, cpu_set).boxed()
+                        } else {
+                            passes.boxed()
+                        };
+                        passes
+                            .fold(0u64, |sum, _| {
+                                let pass_sum = data.iter().fold(0u64, |s, v| s.wrapping_add(*v));
+                                async move { sum.wrapping_add(pass_sum) }
+                            })
+                            .await
+                    })
+                });
+                futures::future::try_join_all(tasks).await.unwrap()
+            });
+            (sums, start.elapsed())
+        };
+        let (scattered_sums, scattered_elapsed) = run(false);
+        let (pinned_sums, pinned_elapsed) = run(true);
+        assert_eq!(scattered_sums, pinned_sums);
+        let cpus = cpu_set.cpus();
+        println!(
+            "{num_threads} threads: scattered {scattered_elapsed:?}, pinned {pinned_elapsed:?}"
+        );
+        println!("pinned to cpus: {cpus:?}");
+    }
+}
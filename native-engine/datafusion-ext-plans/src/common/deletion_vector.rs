@@ -0,0 +1,272 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{Cursor, Read};
+
+use arrow::array::{BooleanArray, BooleanBufferBuilder};
+use byteorder::{ReadBytesExt, LE};
+use datafusion::common::Result;
+use datafusion_ext_commons::df_execution_err;
+
+const DELTA_DELETION_VECTOR_MAGIC: u32 = 1681511377;
+const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
+const SERIAL_COOKIE: u32 = 12347;
+const NO_OFFSET_THRESHOLD: usize = 4;
+const MAX_ARRAY_CONTAINER_CARDINALITY: usize = 4096;
+const BITMAP_CONTAINER_WORDS: usize = 1024;
+
+/// Deleted row indices of a parquet file, decoded from a roaring bitmap like
+/// delta lake's deletion vectors.
+#[derive(Debug, Clone, Default)]
+pub struct DeletionVector {
+    deleted: Vec<u64>, // sorted
+}
+
+impl DeletionVector {
+    /// Decodes a delta lake deletion vector: a magic number followed by a
+    /// 64-bit roaring bitmap in the portable format.
+    pub fn from_delta_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        let magic = cursor.read_u32::<LE>()?;
+        if magic != DELTA_DELETION_VECTOR_MAGIC {
+            return df_execution_err!("invalid deletion vector magic number: {magic}");
+        }
+        let num_bitmaps = cursor.read_u64::<LE>()?;
+        let mut deleted = vec![];
+        for _ in 0..num_bitmaps {
+            let high = (cursor.read_u32::<LE>()? as u64) << 32;
+            let low_values = read_portable_roaring(&mut cursor)?;
+            deleted.extend(low_values.into_iter().map(|low| high | low as u64));
+        }
+        Ok(Self::from_unsorted(deleted))
+    }
+
+    /// Decodes a 32-bit roaring bitmap in the portable format.
+    pub fn from_portable_roaring(bytes: &[u8]) -> Result<Self> {
+        let values = read_portable_roaring(&mut Cursor::new(bytes))?;
+        Ok(Self::from_unsorted(
+            values.into_iter().map(|v| v as u64).collect(),
+        ))
+    }
+
+    fn from_unsorted(mut deleted: Vec<u64>) -> Self {
+        deleted.sort_unstable();
+        deleted.dedup();
+        Self { deleted }
+    }
+
+    pub fn num_deleted(&self) -> usize {
+        self.deleted.len()
+    }
+
+    pub fn is_deleted(&self, row_index: u64) -> bool {
+        self.deleted.binary_search(&row_index).is_ok()
+    }
+
+    /// Returns the selection of rows `[start, start + num_rows)` of the file,
+    /// deleted rows are unselected.
+    pub fn selection(&self, start: u64, num_rows: usize) -> BooleanArray {
+        let mut builder = BooleanBufferBuilder::new(num_rows);
+        self.append_selection(start, num_rows, &mut builder);
+        BooleanArray::new(builder.finish(), None)
+    }
+
+    /// Appends the selection of rows `[start, start + num_rows)` of the file
+    /// to the builder, like [`Self::selection`].
+    pub fn append_selection(
+        &self,
+        start: u64,
+        num_rows: usize,
+        builder: &mut BooleanBufferBuilder,
+    ) {
+        let end = start + num_rows as u64;
+        let first = self.deleted.partition_point(|&i| i < start);
+        let mut pos = start;
+        for &i in self.deleted[first..].iter().take_while(|&&i| i < end) {
+            builder.append_n((i - pos) as usize, true);
+            builder.append(false);
+            pos = i + 1;
+        }
+        builder.append_n((end - pos) as usize, true);
+    }
+}
+
+/// reads a 32-bit roaring bitmap in the portable serialization format, see
+/// https://github.com/RoaringBitmap/RoaringFormatSpec
+fn read_portable_roaring(cursor: &mut Cursor<&[u8]>) -> Result<Vec<u32>> {
+    let cookie = cursor.read_u32::<LE>()?;
+    let (num_containers, run_flags) = if cookie & 0xffff == SERIAL_COOKIE {
+        let num_containers = (cookie >> 16) as usize + 1;
+        let mut run_flags = vec![0u8; (num_containers + 7) / 8];
+        cursor.read_exact(&mut run_flags)?;
+        (num_containers, Some(run_flags))
+    } else if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+        (cursor.read_u32::<LE>()? as usize, None)
+    } else {
+        return df_execution_err!("invalid roaring bitmap cookie: {cookie}");
+    };
+    let is_run = |i: usize| {
+        run_flags
+            .as_ref()
+            .map(|flags| flags[i / 8] & (1 << (i % 8)) != 0)
+            .unwrap_or(false)
+    };
+
+    // descriptive header: keys and cardinalities
+    let mut headers = Vec::with_capacity(num_containers);
+    for _ in 0..num_containers {
+        let key = cursor.read_u16::<LE>()?;
+        let cardinality = cursor.read_u16::<LE>()? as usize + 1;
+        headers.push((key, cardinality));
+    }
+
+    // offset header is not needed for sequential reading
+    if run_flags.is_none() || num_containers >= NO_OFFSET_THRESHOLD {
+        cursor.set_position(cursor.position() + 4 * num_containers as u64);
+    }
+
+    let mut values = vec![];
+    for (i, &(key, cardinality)) in headers.iter().enumerate() {
+        let high = (key as u32) << 16;
+        if is_run(i) {
+            let num_runs = cursor.read_u16::<LE>()?;
+            for _ in 0..num_runs {
+                let start = cursor.read_u16::<LE>()? as u32;
+                let len = cursor.read_u16::<LE>()? as u32;
+                values.extend((start..=start + len).map(|low| high | low));
+            }
+        } else if cardinality <= MAX_ARRAY_CONTAINER_CARDINALITY {
+            for _ in 0..cardinality {
+                values.push(high | cursor.read_u16::<LE>()? as u32);
+            }
+        } else {
+            for word_idx in 0..BITMAP_CONTAINER_WORDS {
+                let mut word = cursor.read_u64::<LE>()?;
+                while word != 0 {
+                    let bit = word.trailing_zeros();
+                    values.push(high | (word_idx as u32 * 64 + bit));
+                    word &= word - 1;
+                }
+            }
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::array::BooleanArray;
+    use byteorder::{WriteBytesExt, LE};
+
+    use crate::common::deletion_vector::DeletionVector;
+
+    fn write_u16s(buf: &mut Vec<u8>, values: &[u16]) {
+        for &v in values {
+            buf.write_u16::<LE>(v).unwrap();
+        }
+    }
+
+    // array containers: {1, 5, 7, 65536}
+    fn scattered_bitmap() -> Vec<u8> {
+        let mut buf = vec![];
+        buf.write_u32::<LE>(12346).unwrap();
+        buf.write_u32::<LE>(2).unwrap();
+        write_u16s(&mut buf, &[0, 2, 1, 0]); // keys and cardinalities
+        buf.write_u32::<LE>(0).unwrap(); // offsets
+        buf.write_u32::<LE>(0).unwrap();
+        write_u16s(&mut buf, &[1, 5, 7]);
+        write_u16s(&mut buf, &[0]);
+        buf
+    }
+
+    // run container: [100, 200)
+    fn contiguous_bitmap() -> Vec<u8> {
+        let mut buf = vec![];
+        buf.write_u32::<LE>(12347).unwrap(); // one container
+        buf.push(0x01); // run flags
+        write_u16s(&mut buf, &[0, 99]);
+        write_u16s(&mut buf, &[1, 100, 99]);
+        buf
+    }
+
+    #[test]
+    fn test_scattered_deletions() {
+        let dv = DeletionVector::from_portable_roaring(&scattered_bitmap()).unwrap();
+        assert_eq!(dv.num_deleted(), 4);
+        assert!(dv.is_deleted(65536));
+        assert!(!dv.is_deleted(2));
+
+        assert_eq!(
+            dv.selection(0, 8),
+            BooleanArray::from(vec![true, false, true, true, true, false, true, false]),
+        );
+        assert_eq!(
+            dv.selection(65534, 4),
+            BooleanArray::from(vec![true, true, false, true]),
+        );
+    }
+
+    #[test]
+    fn test_contiguous_deletions() {
+        let dv = DeletionVector::from_portable_roaring(&contiguous_bitmap()).unwrap();
+        assert_eq!(dv.num_deleted(), 100);
+
+        let selection = dv.selection(90, 120);
+        assert_eq!(selection.true_count(), 20);
+        assert!(selection.value(9));
+        assert!(!selection.value(10));
+        assert!(!selection.value(109));
+        assert!(selection.value(110));
+    }
+
+    #[test]
+    fn test_bitmap_container() {
+        // even numbers in [0, 16384)
+        let mut buf = vec![];
+        buf.write_u32::<LE>(12346).unwrap();
+        buf.write_u32::<LE>(1).unwrap();
+        write_u16s(&mut buf, &[0, 8191]);
+        buf.write_u32::<LE>(0).unwrap();
+        for i in 0..1024 {
+            let word = if i < 256 { 0x5555_5555_5555_5555 } else { 0 };
+            buf.write_u64::<LE>(word).unwrap();
+        }
+
+        let dv = DeletionVector::from_portable_roaring(&buf).unwrap();
+        assert_eq!(dv.num_deleted(), 8192);
+        assert!(dv.is_deleted(16382));
+        assert!(!dv.is_deleted(16383));
+        assert!(!dv.is_deleted(16384));
+    }
+
+    #[test]
+    fn test_delta_deletion_vector() {
+        let mut buf = vec![];
+        buf.write_u32::<LE>(1681511377).unwrap();
+        buf.write_u64::<LE>(2).unwrap();
+        buf.write_u32::<LE>(0).unwrap();
+        buf.extend(contiguous_bitmap());
+        buf.write_u32::<LE>(1).unwrap();
+        buf.extend(scattered_bitmap());
+
+        let dv = DeletionVector::from_delta_bytes(&buf).unwrap();
+        assert_eq!(dv.num_deleted(), 104);
+        assert!(dv.is_deleted(150));
+        assert!(dv.is_deleted((1 << 32) + 5));
+        assert!(dv.is_deleted((1 << 32) + 65536));
+        assert!(!dv.is_deleted(5));
+
+        assert!(DeletionVector::from_delta_bytes(&scattered_bitmap()).is_err());
+    }
+}
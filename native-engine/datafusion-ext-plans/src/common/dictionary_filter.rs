@@ -0,0 +1,692 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pruning row groups by dictionaries of their column chunks, like the
+//! dictionary filter of parquet-mr. statistics cannot prune row groups whose
+//! min/max bounds cover the predicate values (like `c IN ('a', 'z')` on a
+//! string column), while the dictionary of a fully dictionary-encoded column
+//! chunk lists all of its values exactly.
+//!
+//! only conjuncts of the predicate comparing a top-level column to literals
+//! (`c = v` and `c IN (v1, v2, ...)`) are evaluated, and a row group is pruned
+//! if the dictionary of any such column contains none of the values. columns
+//! are matched by names like the scan does, and columns matching more than one
+//! file column are not evaluated.
+
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    ops::Range,
+    sync::Arc,
+};
+
+use arrow::datatypes::{DataType, Schema};
+use bytes::Bytes;
+use datafusion::{
+    common::{DataFusionError, Result, ScalarValue},
+    logical_expr::Operator,
+    parquet::{
+        basic::{Encoding, PageType, Type as PhysicalType},
+        column::page::{Page, PageReader},
+        errors::{ParquetError, Result as ParquetResult},
+        file::{
+            metadata::{ColumnChunkMetaData, ParquetMetaData},
+            reader::{ChunkReader, Length},
+            serialized_reader::SerializedPageReader,
+        },
+    },
+    physical_expr::{
+        expressions::{BinaryExpr, Column, InListExpr, Literal},
+        split_conjunction, PhysicalExpr,
+    },
+    physical_plan::metrics::Count,
+};
+use object_store::path::Path;
+use parking_lot::Mutex;
+
+/// A cache slot holding indices of pruned row groups of one file.
+type PrunedRowGroupsSlot = Arc<tokio::sync::OnceCell<Arc<Vec<usize>>>>;
+
+/// Prunes row groups by dictionaries of the columns compared to literals in
+/// the predicate.
+pub struct DictionaryFilter {
+    conjuncts: Vec<ValuesConjunct>,
+    case_sensitive: bool,
+    pruned_row_groups: Mutex<HashMap<Path, PrunedRowGroupsSlot>>,
+}
+
+/// A conjunct of the predicate requiring a column to equal one of the values,
+/// which are kept plain-encoded like the values in dictionary pages.
+#[derive(Debug)]
+struct ValuesConjunct {
+    column_name: String,
+    data_type: DataType,
+    values: HashSet<Vec<u8>>,
+}
+
+/// The dictionary page of a column chunk to read.
+#[derive(Debug, PartialEq)]
+pub struct DictionaryPage {
+    pub row_group_idx: usize,
+    pub column_idx: usize,
+    pub conjunct_idx: usize,
+    pub range: Range<usize>,
+}
+
+impl DictionaryFilter {
+    /// returns None if no conjunct of the predicate can be evaluated with
+    /// dictionaries
+    pub fn try_new(predicate: &Arc<dyn PhysicalExpr>, case_sensitive: bool) -> Option<Self> {
+        let conjuncts = split_conjunction(predicate)
+            .into_iter()
+            .filter_map(ValuesConjunct::try_from_expr)
+            .collect::<Vec<_>>();
+        if conjuncts.is_empty() {
+            return None;
+        }
+        Some(Self {
+            conjuncts,
+            case_sensitive,
+            pruned_row_groups: Mutex::default(),
+        })
+    }
+
+    /// returns indices of row groups pruned by dictionaries, reading the
+    /// dictionary pages with `read_ranges`. a file is usually opened by
+    /// multiple readers, so pruned row groups are cached by file and counted
+    /// once.
+    pub async fn prune<F, Fut>(
+        &self,
+        location: &Path,
+        metadata: &ParquetMetaData,
+        file_schema: &Schema,
+        pruned_by_dictionary: &Count,
+        read_ranges: F,
+    ) -> Result<Arc<Vec<usize>>>
+    where
+        F: FnOnce(Vec<Range<usize>>) -> Fut,
+        Fut: Future<Output = Result<Vec<Bytes>>>,
+    {
+        let slot = self
+            .pruned_row_groups
+            .lock()
+            .entry(location.clone())
+            .or_default()
+            .clone();
+        let pruned = slot
+            .get_or_try_init(|| async move {
+                let pages = self.dictionary_pages(metadata, file_schema);
+                if pages.is_empty() {
+                    return Ok(Arc::new(vec![]));
+                }
+                let ranges = pages.iter().map(|page| page.range.clone()).collect();
+                let page_bytes = read_ranges(ranges).await?;
+                let pruned = self.pruned_row_groups(metadata, &pages, page_bytes)?;
+                pruned_by_dictionary.add(pruned.len());
+                Ok::<_, DataFusionError>(Arc::new(pruned))
+            })
+            .await?;
+        Ok(pruned.clone())
+    }
+
+    /// returns dictionary pages of column chunks which can be evaluated by
+    /// the conjuncts
+    pub fn dictionary_pages(
+        &self,
+        metadata: &ParquetMetaData,
+        file_schema: &Schema,
+    ) -> Vec<DictionaryPage> {
+        let schema_descr = metadata.file_metadata().schema_descr();
+        let columns = self
+            .conjuncts
+            .iter()
+            .enumerate()
+            .filter_map(|(conjunct_idx, conjunct)| {
+                // the file column must have the same type as the literals
+                let field = find_unique(file_schema.fields().iter(), |field| {
+                    self.name_matches(field.name(), &conjunct.column_name)
+                })?;
+                if field.data_type() != &conjunct.data_type {
+                    return None;
+                }
+                let column_idx = find_unique(0..schema_descr.num_columns(), |&i| {
+                    let parts = schema_descr.column(i).path().parts();
+                    parts.len() == 1 && self.name_matches(&parts[0], &conjunct.column_name)
+                })?;
+                Some((conjunct_idx, column_idx))
+            })
+            .collect::<Vec<_>>();
+
+        let mut pages = vec![];
+        for (row_group_idx, rg) in metadata.row_groups().iter().enumerate() {
+            for &(conjunct_idx, column_idx) in &columns {
+                let column = rg.column(column_idx);
+                if let Some(range) = dictionary_page_range(column) {
+                    pages.push(DictionaryPage {
+                        row_group_idx,
+                        column_idx,
+                        conjunct_idx,
+                        range,
+                    });
+                }
+            }
+        }
+        pages
+    }
+
+    // whether a file column name matches the column of a conjunct, the same
+    // way as the scan matches table columns
+    fn name_matches(&self, file_name: &str, column_name: &str) -> bool {
+        match self.case_sensitive {
+            true => file_name == column_name,
+            false => file_name.to_lowercase() == column_name.to_lowercase(),
+        }
+    }
+
+    /// returns indices of row groups with any dictionary containing none of
+    /// the values of its conjunct
+    pub fn pruned_row_groups(
+        &self,
+        metadata: &ParquetMetaData,
+        pages: &[DictionaryPage],
+        page_bytes: Vec<Bytes>,
+    ) -> Result<Vec<usize>> {
+        let mut pruned = vec![];
+        for (page, bytes) in pages.iter().zip(page_bytes) {
+            if pruned.last() == Some(&page.row_group_idx) {
+                continue;
+            }
+            let column = metadata
+                .row_group(page.row_group_idx)
+                .column(page.column_idx);
+            let Some(dictionary) = read_dictionary(column, page.range.start, bytes)? else {
+                continue;
+            };
+            let conjunct = &self.conjuncts[page.conjunct_idx];
+            if !conjunct
+                .values
+                .iter()
+                .any(|value| dictionary.contains(value))
+            {
+                pruned.push(page.row_group_idx);
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+impl ValuesConjunct {
+    fn try_from_expr(expr: &Arc<dyn PhysicalExpr>) -> Option<Self> {
+        let as_column = |expr: &Arc<dyn PhysicalExpr>| expr.as_any().downcast_ref::<Column>();
+        let as_literal = |expr: &Arc<dyn PhysicalExpr>| {
+            let literal = expr.as_any().downcast_ref::<Literal>()?;
+            Some(literal.value().clone())
+        };
+
+        if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+            if *binary.op() != Operator::Eq {
+                return None;
+            }
+            let (column, literal) = match (as_column(binary.left()), as_literal(binary.right())) {
+                (Some(column), Some(literal)) => (column, literal),
+                _ => (as_column(binary.right())?, as_literal(binary.left())?),
+            };
+            return Self::try_new(column, vec![literal]);
+        }
+        if let Some(in_list) = expr.as_any().downcast_ref::<InListExpr>() {
+            if in_list.negated() {
+                return None;
+            }
+            let column = as_column(in_list.expr())?;
+            let literals = in_list
+                .list()
+                .iter()
+                .map(as_literal)
+                .collect::<Option<Vec<_>>>()?;
+            return Self::try_new(column, literals);
+        }
+        None
+    }
+
+    fn try_new(column: &Column, literals: Vec<ScalarValue>) -> Option<Self> {
+        let data_type = literals.first()?.data_type();
+        let mut values = HashSet::new();
+        for literal in &literals {
+            if literal.data_type() != data_type {
+                return None;
+            }
+            if !literal.is_null() {
+                // nulls never equal any value
+                values.insert(plain_encoded(literal)?);
+            }
+        }
+        if values.is_empty() {
+            return None;
+        }
+        Some(Self {
+            column_name: column.name().to_string(),
+            data_type,
+            values,
+        })
+    }
+}
+
+// returns the only item matching the predicate, or None if none or more than
+// one items match
+fn find_unique<T>(items: impl IntoIterator<Item = T>, predicate: impl Fn(&T) -> bool) -> Option<T> {
+    let mut matched = items.into_iter().filter(predicate);
+    let item = matched.next()?;
+    matched.next().is_none().then_some(item)
+}
+
+/// Removes the given row groups from the metadata.
+pub fn remove_row_groups(
+    metadata: Arc<ParquetMetaData>,
+    row_groups: &[usize],
+) -> Arc<ParquetMetaData> {
+    if row_groups.is_empty() {
+        return metadata;
+    }
+    let row_groups = row_groups.iter().collect::<HashSet<_>>();
+    let remaining = metadata
+        .row_groups()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !row_groups.contains(i))
+        .map(|(_, rg)| rg.clone())
+        .collect();
+    Arc::new(ParquetMetaData::new(
+        metadata.file_metadata().clone(),
+        remaining,
+    ))
+}
+
+/// returns the range of the dictionary page of a column chunk, if all of its
+/// data pages are dictionary-encoded
+fn dictionary_page_range(column: &ColumnChunkMetaData) -> Option<Range<usize>> {
+    let start = column
+        .dictionary_page_offset()
+        .filter(|&offset| offset > 0)?;
+    let end = column.data_page_offset();
+    if start >= end || end - start > column.compressed_size() {
+        return None;
+    }
+    is_fully_dictionary_encoded(column).then_some(start as usize..end as usize)
+}
+
+// whether all data pages of a column chunk are dictionary-encoded, told by
+// the page encoding stats. without the stats, like parquet-mr, a chunk is
+// only known to be fully encoded if plain encoding is not used at all, since
+// dictionary pages encoded plain cannot be told apart from fallback pages.
+fn is_fully_dictionary_encoded(column: &ColumnChunkMetaData) -> bool {
+    let is_dictionary = |encoding: &Encoding| {
+        matches!(
+            encoding,
+            Encoding::PLAIN_DICTIONARY | Encoding::RLE_DICTIONARY
+        )
+    };
+    match column.page_encoding_stats() {
+        Some(stats) => stats
+            .iter()
+            .filter(|stats| stats.page_type != PageType::DICTIONARY_PAGE && stats.count > 0)
+            .all(|stats| is_dictionary(&stats.encoding)),
+        None => column.encodings().iter().all(|encoding| {
+            is_dictionary(encoding) || matches!(encoding, Encoding::RLE | Encoding::BIT_PACKED)
+        }),
+    }
+}
+
+// decodes the dictionary page of a column chunk into plain-encoded values,
+// or returns None if the page is not a supported dictionary page
+fn read_dictionary(
+    column: &ColumnChunkMetaData,
+    offset: usize,
+    bytes: Bytes,
+) -> Result<Option<HashSet<Vec<u8>>>> {
+    let file_slice = Arc::new(FileSlice {
+        offset: offset as u64,
+        bytes,
+    });
+    let mut page_reader = SerializedPageReader::new(file_slice, column, 0, None)?;
+    Ok(match page_reader.get_next_page()? {
+        Some(Page::DictionaryPage {
+            buf,
+            num_values,
+            encoding: Encoding::PLAIN | Encoding::PLAIN_DICTIONARY,
+            ..
+        }) => split_plain_values(column.column_type(), &buf, num_values as usize),
+        _ => None,
+    })
+}
+
+// splits plain-encoded values, None for unsupported physical types
+fn split_plain_values(
+    physical_type: PhysicalType,
+    buf: &[u8],
+    num_values: usize,
+) -> Option<HashSet<Vec<u8>>> {
+    let mut values = HashSet::with_capacity(num_values);
+    match physical_type {
+        PhysicalType::INT32 | PhysicalType::INT64 => {
+            let width = if physical_type == PhysicalType::INT32 {
+                4
+            } else {
+                8
+            };
+            if buf.len() < width * num_values {
+                return None;
+            }
+            values.extend(buf.chunks_exact(width).take(num_values).map(<[u8]>::to_vec));
+        }
+        PhysicalType::BYTE_ARRAY => {
+            let mut rest = buf;
+            for _ in 0..num_values {
+                let len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+                values.insert(rest.get(4..4 + len)?.to_vec());
+                rest = &rest[4 + len..];
+            }
+        }
+        _ => return None,
+    }
+    Some(values)
+}
+
+// plain encoding of a literal, the same as its values in dictionary pages
+fn plain_encoded(value: &ScalarValue) -> Option<Vec<u8>> {
+    Some(match value {
+        ScalarValue::Int8(Some(v)) => (*v as i32).to_le_bytes().to_vec(),
+        ScalarValue::Int16(Some(v)) => (*v as i32).to_le_bytes().to_vec(),
+        ScalarValue::Int32(Some(v)) | ScalarValue::Date32(Some(v)) => v.to_le_bytes().to_vec(),
+        ScalarValue::Int64(Some(v)) => v.to_le_bytes().to_vec(),
+        ScalarValue::Utf8(Some(v)) => v.as_bytes().to_vec(),
+        ScalarValue::Binary(Some(v)) => v.clone(),
+        _ => return None,
+    })
+}
+
+/// Bytes of a part of a file, read by offsets in the whole file.
+struct FileSlice {
+    offset: u64,
+    bytes: Bytes,
+}
+
+impl FileSlice {
+    fn relative_range(&self, start: u64, len: usize) -> ParquetResult<u64> {
+        match start.checked_sub(self.offset) {
+            Some(relative) if relative as usize + len <= self.bytes.len() => Ok(relative),
+            _ => Err(ParquetError::EOF(format!(
+                "reading {len} bytes at {start} out of the file slice at {}",
+                self.offset,
+            ))),
+        }
+    }
+}
+
+impl Length for FileSlice {
+    fn len(&self) -> u64 {
+        self.offset + self.bytes.len() as u64
+    }
+}
+
+impl ChunkReader for FileSlice {
+    type T = <Bytes as ChunkReader>::T;
+
+    fn get_read(&self, start: u64) -> ParquetResult<Self::T> {
+        self.bytes.get_read(self.relative_range(start, 0)?)
+    }
+
+    fn get_bytes(&self, start: u64, length: usize) -> ParquetResult<Bytes> {
+        self.bytes
+            .get_bytes(self.relative_range(start, length)?, length)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ops::Range, sync::Arc};
+
+    use arrow::{
+        array::{ArrayRef, Int32Array, StringArray},
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use bytes::Bytes;
+    use datafusion::{
+        common::{DataFusionError, Result, ScalarValue},
+        logical_expr::Operator,
+        parquet::{
+            arrow::ArrowWriter,
+            file::{footer::parse_metadata, properties::WriterProperties},
+            schema::types::ColumnPath,
+        },
+        physical_expr::{expressions as phys_expr, PhysicalExpr},
+        physical_plan::metrics::Count,
+    };
+    use object_store::path::Path;
+
+    use crate::common::dictionary_filter::{remove_row_groups, DictionaryFilter};
+
+    // 8 row groups of 100 rows, with values v{rg}_{i % 3} in s and
+    // rg * 10 + i % 3 in n and p. p is written without dictionary encoding.
+    fn dictionary_fixture() -> (Bytes, Arc<Schema>) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("s", DataType::Utf8, true),
+            Field::new("n", DataType::Int32, true),
+            Field::new("p", DataType::Int32, true),
+        ]));
+        let (rgs, idxs): (Vec<i32>, Vec<i32>) = (0..800).map(|i| (i / 100, i % 3)).unzip();
+        let s: ArrayRef = Arc::new(StringArray::from_iter_values(
+            rgs.iter().zip(&idxs).map(|(rg, i)| format!("v{rg}_{i}")),
+        ));
+        let n: ArrayRef = Arc::new(Int32Array::from_iter_values(
+            rgs.iter().zip(&idxs).map(|(rg, i)| rg * 10 + i),
+        ));
+        let batch = RecordBatch::try_new(schema.clone(), vec![s, n.clone(), n]).unwrap();
+
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(100)
+            .set_column_dictionary_enabled(ColumnPath::from("p"), false)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema.clone(), Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        (Bytes::from(buf), schema)
+    }
+
+    fn in_list(schema: &Schema, column: &str, values: Vec<ScalarValue>) -> Arc<dyn PhysicalExpr> {
+        let list = values.into_iter().map(phys_expr::lit).collect();
+        phys_expr::in_list(
+            phys_expr::col(column, schema).unwrap(),
+            list,
+            &false,
+            schema,
+        )
+        .unwrap()
+    }
+
+    fn eq(schema: &Schema, column: &str, value: ScalarValue) -> Arc<dyn PhysicalExpr> {
+        phys_expr::binary(
+            phys_expr::lit(value),
+            Operator::Eq,
+            phys_expr::col(column, schema).unwrap(),
+            schema,
+        )
+        .unwrap()
+    }
+
+    async fn prune(predicate: &Arc<dyn PhysicalExpr>, data: &Bytes, schema: &Schema) -> Vec<usize> {
+        let Some(filter) = DictionaryFilter::try_new(predicate, false) else {
+            return vec![];
+        };
+        let metadata = parse_metadata(data).unwrap();
+        let read_ranges = |ranges: Vec<Range<usize>>| async move {
+            Ok(ranges.into_iter().map(|range| data.slice(range)).collect())
+        };
+        let pruned = filter
+            .prune(
+                &Path::from("a.parquet"),
+                &metadata,
+                schema,
+                &Count::new(),
+                read_ranges,
+            )
+            .await
+            .unwrap();
+        pruned.as_ref().clone()
+    }
+
+    #[tokio::test]
+    async fn test_prune_by_dictionary() {
+        let (data, schema) = dictionary_fixture();
+        let utf8 = |v: &str| ScalarValue::Utf8(Some(v.to_string()));
+        let int32 = |v: i32| ScalarValue::Int32(Some(v));
+
+        // statistics cannot prune these row groups, as min/max of every row
+        // group of s cover the values
+        let predicate = in_list(
+            &schema,
+            "s",
+            vec![
+                utf8("v3_0"),
+                utf8("v5_1"),
+                utf8("v9"),
+                ScalarValue::Utf8(None),
+            ],
+        );
+        assert_eq!(
+            prune(&predicate, &data, &schema).await,
+            vec![0, 1, 2, 4, 6, 7]
+        );
+        let predicate = eq(&schema, "n", int32(42));
+        assert_eq!(
+            prune(&predicate, &data, &schema).await,
+            vec![0, 1, 2, 3, 5, 6, 7]
+        );
+
+        // any conjunct prunes row groups
+        let predicate = phys_expr::binary(
+            in_list(&schema, "s", vec![utf8("v3_0")]),
+            Operator::And,
+            eq(&schema, "n", int32(42)),
+            &schema,
+        )
+        .unwrap();
+        assert_eq!(
+            prune(&predicate, &data, &schema).await,
+            (0..8).collect::<Vec<_>>()
+        );
+
+        // columns without dictionaries and other predicates are not evaluated
+        let predicate = eq(&schema, "p", int32(42));
+        assert_eq!(prune(&predicate, &data, &schema).await, Vec::<usize>::new());
+        let predicate = phys_expr::binary(
+            phys_expr::col("n", &schema).unwrap(),
+            Operator::Gt,
+            phys_expr::lit(int32(42)),
+            &schema,
+        )
+        .unwrap();
+        assert!(DictionaryFilter::try_new(&predicate, false).is_none());
+        let predicate = in_list(&schema, "n", vec![ScalarValue::Int32(None)]);
+        assert!(DictionaryFilter::try_new(&predicate, false).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prune_by_dictionary_case_sensitive() -> Result<()> {
+        // two row groups with id = 0 and ID = 5 in the first one, and id = 1
+        // and ID = 6 in the second one
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("ID", DataType::Int32, true),
+        ]));
+        let id: ArrayRef = Arc::new(Int32Array::from_iter_values((0..200).map(|i| i / 100)));
+        let upper_id: ArrayRef =
+            Arc::new(Int32Array::from_iter_values((0..200).map(|i| 5 + i / 100)));
+        let batch = RecordBatch::try_new(schema.clone(), vec![id, upper_id]).unwrap();
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(100)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema.clone(), Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let data = Bytes::from(buf);
+        let metadata = parse_metadata(&data)?;
+
+        let predicate = eq(&schema, "ID", ScalarValue::Int32(Some(5)));
+        let prune = |case_sensitive: bool| {
+            let filter = DictionaryFilter::try_new(&predicate, case_sensitive).unwrap();
+            let data = &data;
+            let metadata = &metadata;
+            let schema = &schema;
+            async move {
+                let pruned = filter
+                    .prune(
+                        &Path::from("a.parquet"),
+                        metadata,
+                        schema,
+                        &Count::new(),
+                        |ranges| async move {
+                            Ok(ranges.into_iter().map(|range| data.slice(range)).collect())
+                        },
+                    )
+                    .await?;
+                Ok::<_, DataFusionError>(pruned.as_ref().clone())
+            }
+        };
+
+        // ID is checked against its own dictionaries, not the ones of id
+        assert_eq!(prune(true).await?, vec![1]);
+
+        // ID matches both columns case-insensitively, and is not evaluated
+        assert_eq!(prune(false).await?, Vec::<usize>::new());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pruned_row_groups_cached() -> Result<()> {
+        let (data, schema) = dictionary_fixture();
+        let predicate = eq(&schema, "n", ScalarValue::Int32(Some(42)));
+        let filter = DictionaryFilter::try_new(&predicate, false).unwrap();
+        let metadata = parse_metadata(&data)?;
+        assert_eq!(filter.dictionary_pages(&metadata, &schema).len(), 8);
+
+        // dictionaries are only read and counted once per file
+        let pruned_by_dictionary = Count::new();
+        let location = Path::from("a.parquet");
+        let data = &data;
+        for _ in 0..2 {
+            let pruned = filter
+                .prune(
+                    &location,
+                    &metadata,
+                    &schema,
+                    &pruned_by_dictionary,
+                    |ranges| async move {
+                        Ok(ranges.into_iter().map(|range| data.slice(range)).collect())
+                    },
+                )
+                .await?;
+            assert_eq!(pruned.len(), 7);
+        }
+        assert_eq!(pruned_by_dictionary.value(), 7);
+
+        let metadata = remove_row_groups(Arc::new(metadata), &[0, 1, 2, 3, 5, 6, 7]);
+        assert_eq!(metadata.num_row_groups(), 1);
+        assert_eq!(metadata.file_metadata().num_rows(), 800);
+        assert_eq!(metadata.row_group(0).num_rows(), 100);
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, Gauge, MetricBuilder};
+use parking_lot::Mutex;
+
+const NUM_LATENCY_BUCKETS: usize = 32;
+
+/// Latency metrics of reads in a scan partition, all in nanoseconds.
+#[derive(Clone)]
+pub struct IoLatencyMetrics {
+    pub read_latency_p50: Gauge,
+    pub read_latency_p99: Gauge,
+    pub read_latency_first_window: Gauge,
+    pub read_latency_last_window: Gauge,
+    pub read_latency_max_window: Gauge,
+}
+
+impl IoLatencyMetrics {
+    pub fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        let gauge = |name: &'static str| MetricBuilder::new(metrics).gauge(name, partition);
+        Self {
+            read_latency_p50: gauge("read_latency_p50"),
+            read_latency_p99: gauge("read_latency_p99"),
+            read_latency_first_window: gauge("read_latency_first_window"),
+            read_latency_last_window: gauge("read_latency_last_window"),
+            read_latency_max_window: gauge("read_latency_max_window"),
+        }
+    }
+}
+
+/// Samples latencies of reads during a scan, so that a storage slowdown in
+/// the middle of the scan (like a degrading datanode) is visible, which is
+/// not with the aggregated io_time.
+///
+/// latencies are counted in power-of-two buckets of microseconds, so
+/// percentiles are approximated within a factor of 2. latencies are also
+/// averaged in fixed time windows since the scan starts, and the averages of
+/// the first, last and slowest windows are reported. recording is a few
+/// additions under a lock, which is cheap compared to the reads.
+pub struct IoLatencySampler {
+    start: Instant,
+    window: Duration,
+    state: Mutex<IoLatencyState>,
+    metrics: IoLatencyMetrics,
+}
+
+#[derive(Default)]
+struct IoLatencyState {
+    buckets: [usize; NUM_LATENCY_BUCKETS],
+    windows: Vec<(Duration, usize)>, // sum and count of latencies
+}
+
+impl IoLatencySampler {
+    pub fn new(window: Duration, metrics: IoLatencyMetrics) -> Self {
+        Self {
+            start: Instant::now(),
+            window,
+            state: Mutex::default(),
+            metrics,
+        }
+    }
+
+    /// runs and records the latency of a read
+    pub fn time_read<T>(&self, read: impl FnOnce() -> T) -> T {
+        let read_start = Instant::now();
+        let result = read();
+        self.record_at(read_start - self.start, read_start.elapsed());
+        result
+    }
+
+    /// awaits and records the latency of an async read
+    pub async fn time_async_read<T>(&self, read: impl Future<Output = T>) -> T {
+        let read_start = Instant::now();
+        let result = read.await;
+        self.record_at(read_start - self.start, read_start.elapsed());
+        result
+    }
+
+    /// records the latency of a read started at the given time since the
+    /// scan starts
+    pub fn record_at(&self, started: Duration, latency: Duration) {
+        let bucket = latency_bucket(latency);
+        let window_idx = (started.as_nanos() / self.window.as_nanos().max(1)) as usize;
+
+        let mut state = self.state.lock();
+        state.buckets[bucket] += 1;
+        if state.windows.len() <= window_idx {
+            state.windows.resize(window_idx + 1, (Duration::ZERO, 0));
+        }
+        let window = &mut state.windows[window_idx];
+        window.0 += latency;
+        window.1 += 1;
+    }
+
+    /// returns the approximated latency at the given percentile (0 to 100),
+    /// that is, the upper bound of its bucket
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let state = self.state.lock();
+        let total = state.buckets.iter().sum::<usize>();
+        if total == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((total as f64 * percentile / 100.0).ceil() as usize).clamp(1, total);
+        let mut counted = 0;
+        for (bucket, &count) in state.buckets.iter().enumerate() {
+            counted += count;
+            if counted >= rank {
+                return Duration::from_micros(1 << (bucket + 1));
+            }
+        }
+        unreachable!()
+    }
+
+    /// returns average latencies of time windows, or None for windows without
+    /// reads
+    pub fn window_latencies(&self) -> Vec<Option<Duration>> {
+        self.state
+            .lock()
+            .windows
+            .iter()
+            .map(|&(sum, count)| (count > 0).then(|| sum / count as u32))
+            .collect()
+    }
+
+    /// updates the latency metrics with the reads recorded so far
+    pub fn update_metrics(&self) {
+        let window_latencies = self.window_latencies().into_iter().flatten();
+        let first = window_latencies.clone().next().unwrap_or_default();
+        let last = window_latencies.clone().last().unwrap_or_default();
+        let max = window_latencies.max().unwrap_or_default();
+
+        let set = |gauge: &Gauge, latency: Duration| gauge.set(latency.as_nanos() as usize);
+        set(&self.metrics.read_latency_p50, self.percentile(50.0));
+        set(&self.metrics.read_latency_p99, self.percentile(99.0));
+        set(&self.metrics.read_latency_first_window, first);
+        set(&self.metrics.read_latency_last_window, last);
+        set(&self.metrics.read_latency_max_window, max);
+    }
+}
+
+// bucket i counts latencies in [2^i, 2^(i+1)) microseconds, bucket 0 also
+// counts latencies under 1us
+fn latency_bucket(latency: Duration) -> usize {
+    let micros = latency.as_micros().max(1) as u64;
+    (63 - micros.leading_zeros() as usize).min(NUM_LATENCY_BUCKETS - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+
+    use crate::common::io_latency::{IoLatencyMetrics, IoLatencySampler};
+
+    fn sampler(window: Duration) -> IoLatencySampler {
+        let metrics = ExecutionPlanMetricsSet::new();
+        IoLatencySampler::new(window, IoLatencyMetrics::new(&metrics, 0))
+    }
+
+    #[test]
+    fn test_rising_latency() {
+        let sampler = sampler(Duration::from_secs(10));
+
+        // a mock reader slowing down by 1ms every 10 seconds, with a silent
+        // window in between
+        let ms = Duration::from_millis;
+        for window in [0, 1, 3] {
+            for i in 0..10 {
+                let started = Duration::from_secs(window * 10 + i);
+                sampler.record_at(started, ms(window + 1));
+            }
+        }
+        assert_eq!(
+            sampler.window_latencies(),
+            vec![Some(ms(1)), Some(ms(2)), None, Some(ms(4))],
+        );
+
+        // latencies are approximated by power-of-two buckets
+        assert_eq!(sampler.percentile(50.0), Duration::from_micros(2048));
+        assert_eq!(sampler.percentile(99.0), Duration::from_micros(4096));
+
+        sampler.update_metrics();
+        assert_eq!(sampler.metrics.read_latency_first_window.value(), 1_000_000);
+        assert_eq!(sampler.metrics.read_latency_last_window.value(), 4_000_000);
+        assert_eq!(sampler.metrics.read_latency_max_window.value(), 4_000_000);
+        assert_eq!(sampler.metrics.read_latency_p99.value(), 4_096_000);
+    }
+
+    #[test]
+    fn test_time_read() {
+        let sampler = sampler(Duration::from_secs(3600));
+        assert_eq!(sampler.percentile(50.0), Duration::ZERO);
+
+        let result = sampler.time_read(|| {
+            std::thread::sleep(Duration::from_millis(2));
+            42
+        });
+        assert_eq!(result, 42);
+        assert!(sampler.percentile(100.0) >= Duration::from_millis(2));
+        assert_eq!(sampler.window_latencies().len(), 1);
+    }
+}
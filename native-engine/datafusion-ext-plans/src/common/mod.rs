@@ -15,6 +15,15 @@
 pub mod batch_selection;
 pub mod batch_statisitcs;
 pub mod cached_exprs_evaluator;
+pub mod column_pattern;
 pub mod column_pruning;
+pub mod cpu_affinity;
+pub mod deletion_vector;
+pub mod dictionary_filter;
+pub mod io_latency;
 pub mod ipc_compression;
 pub mod output;
+pub mod parquet_footer;
+pub mod parquet_metadata_cache;
+pub mod prefetch_budget;
+pub mod scratch_buffers;
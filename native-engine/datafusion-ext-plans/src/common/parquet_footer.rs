@@ -0,0 +1,2068 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Projected decoding of parquet footers. for very wide schemas, decoding
+//! statistics and other optional metadata of all column chunks dominates the
+//! cost of opening a file, even if only a few columns are read. the raw footer
+//! is scanned with a minimal thrift compact protocol reader, and optional
+//! metadata of unneeded column chunks is dropped before decoding.
+//!
+//! top-level columns can also be renamed in the raw footer, resolving them to
+//! table columns by parquet field ids or positions, see [`FieldIdResolver`],
+//! or disambiguating duplicate column names, see
+//! [`DuplicateColumnResolver`]. and map columns can be rewritten to lists of
+//! their keys or values, so that only the needed map children are read, see
+//! [`MapChildrenProjection`].
+//!
+//! logical types unknown to the bundled parquet crate, like GEOMETRY, are
+//! dropped before decoding, see [`drop_unknown_logical_types`].
+//!
+//! the same reader also reads sizes and checksums of page headers, see
+//! [`read_page_header`], and the plaintext crypto metadata of encrypted
+//! footers, see [`read_file_encryption`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::Arc,
+};
+
+use arrow::datatypes::Schema;
+use bytes::Bytes;
+use datafusion::{
+    common::Result,
+    parquet::{
+        arrow::PARQUET_FIELD_ID_META_KEY,
+        errors::Result as ParquetResult,
+        file::{footer::decode_metadata, metadata::ParquetMetaData},
+    },
+};
+use datafusion_ext_commons::df_execution_err;
+use object_store::ObjectMeta;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+
+use crate::common::parquet_metadata_cache::{metadata_fetch_semaphore, with_fetch_permit};
+
+// thrift compact protocol types
+const CT_STOP: u8 = 0;
+const CT_BOOLEAN_TRUE: u8 = 1;
+const CT_BOOLEAN_FALSE: u8 = 2;
+const CT_BYTE: u8 = 3;
+const CT_I16: u8 = 4;
+const CT_I32: u8 = 5;
+const CT_I64: u8 = 6;
+const CT_DOUBLE: u8 = 7;
+const CT_BINARY: u8 = 8;
+const CT_LIST: u8 = 9;
+const CT_SET: u8 = 10;
+const CT_MAP: u8 = 11;
+const CT_STRUCT: u8 = 12;
+
+// field ids of parquet.thrift
+const FILE_METADATA_SCHEMA: i16 = 2;
+const FILE_METADATA_ROW_GROUPS: i16 = 4;
+const FILE_METADATA_KEY_VALUE_METADATA: i16 = 5;
+const FILE_METADATA_COLUMN_ORDERS: i16 = 7;
+const SCHEMA_ELEMENT_NAME: i16 = 4;
+const SCHEMA_ELEMENT_NUM_CHILDREN: i16 = 5;
+const SCHEMA_ELEMENT_CONVERTED_TYPE: i16 = 6;
+const SCHEMA_ELEMENT_FIELD_ID: i16 = 9;
+const SCHEMA_ELEMENT_LOGICAL_TYPE: i16 = 10;
+const ROW_GROUP_COLUMNS: i16 = 1;
+const COLUMN_CHUNK_META_DATA: i16 = 3;
+const KEY_VALUE_KEY: i16 = 1;
+const PAGE_HEADER_COMPRESSED_PAGE_SIZE: i16 = 3;
+const PAGE_HEADER_CRC: i16 = 4;
+const FILE_CRYPTO_METADATA_ENCRYPTION_ALGORITHM: i16 = 1;
+const ENCRYPTION_ALGORITHM_AES_GCM_V1: i16 = 1;
+const ENCRYPTION_ALGORITHM_AES_GCM_CTR_V1: i16 = 2;
+const AES_GCM_AAD_PREFIX: i16 = 1;
+const AES_GCM_SUPPLY_AAD_PREFIX: i16 = 3;
+
+// enum values of parquet.thrift
+const CONVERTED_TYPE_MAP: i64 = 1;
+const CONVERTED_TYPE_LIST: i32 = 3;
+const LOGICAL_TYPE_LIST: i16 = 3;
+const LOGICAL_TYPE_FLOAT16: i16 = 15; // the last one known to the bundled parquet crate
+const LOGICAL_TYPE_GEOMETRY: i16 = 17;
+const LOGICAL_TYPE_GEOGRAPHY: i16 = 18;
+
+/// key of the arrow schema embedded in footers by arrow writers
+const ARROW_SCHEMA_META_KEY: &str = "ARROW:schema";
+
+/// optional fields of ColumnMetaData dropped for unneeded columns:
+/// key_value_metadata, statistics, encoding_stats, bloom_filter_offset,
+/// bloom_filter_length and size_statistics. offsets and sizes are kept, so
+/// the column chunks are still readable.
+const STRIPPED_COLUMN_META_DATA_FIELDS: [i16; 6] = [8, 12, 13, 14, 15, 16];
+
+/// Decodes parquet footers with full metadata of the needed columns only.
+pub struct FooterProjection {
+    min_columns: usize,
+    column_names: HashSet<String>,
+}
+
+impl FooterProjection {
+    /// footers with fewer leaf columns than `min_columns` are fully decoded.
+    /// column names are top-level field names matched case-insensitively.
+    pub fn new(min_columns: usize, column_names: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            min_columns,
+            column_names: column_names
+                .into_iter()
+                .map(|name| name.to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// decodes the footer metadata (without the trailing length and magic)
+    pub fn decode(&self, metadata: &[u8]) -> Result<ParquetMetaData> {
+        let leaf_roots = schema_leaf_roots(metadata)?;
+        if leaf_roots.len() < self.min_columns {
+            return Ok(decode_metadata(metadata)?);
+        }
+        let needed = leaf_roots
+            .iter()
+            .map(|root| self.column_names.contains(&root.to_lowercase()))
+            .collect::<Vec<_>>();
+        let projected = project_footer(metadata, |leaf_idx| {
+            needed.get(leaf_idx).copied().unwrap_or(false)
+        })?;
+        Ok(decode_metadata(&projected)?)
+    }
+}
+
+/// Returns the top-level field name of each leaf column in the footer.
+pub fn schema_leaf_roots(metadata: &[u8]) -> Result<Vec<String>> {
+    let elements = read_schema_elements(metadata)?;
+    let roots = schema_roots(&elements);
+    Ok(elements
+        .iter()
+        .zip(roots)
+        .skip(1)
+        .filter(|(element, _)| !element.num_children.is_some_and(|n| n > 0))
+        .map(|(_, root)| elements[root].name.clone())
+        .collect())
+}
+
+struct SchemaElementInfo {
+    name: String,
+    num_children: Option<i64>,
+    converted_type: Option<i64>,
+    field_id: Option<i32>,
+    logical_type: Option<i16>,
+}
+
+/// reads the flattened schema tree of the footer
+fn read_schema_elements(metadata: &[u8]) -> Result<Vec<SchemaElementInfo>> {
+    let mut reader = CompactReader::new(metadata);
+    let mut elements = vec![];
+
+    let mut last_id = 0;
+    while let Some((id, ty)) = reader.read_field_header(last_id)? {
+        last_id = id;
+        if id != FILE_METADATA_SCHEMA || ty != CT_LIST {
+            reader.skip(ty)?;
+            continue;
+        }
+        let (size, _) = reader.read_list_header()?;
+        for _ in 0..size {
+            let mut element = SchemaElementInfo {
+                name: String::new(),
+                num_children: None,
+                converted_type: None,
+                field_id: None,
+                logical_type: None,
+            };
+            let mut last_id = 0;
+            while let Some((id, ty)) = reader.read_field_header(last_id)? {
+                last_id = id;
+                match (id, ty) {
+                    (SCHEMA_ELEMENT_NAME, CT_BINARY) => {
+                        element.name = String::from_utf8_lossy(reader.read_binary()?).into_owned();
+                    }
+                    (SCHEMA_ELEMENT_NUM_CHILDREN, CT_I32) => {
+                        element.num_children = Some(reader.read_zigzag()?);
+                    }
+                    (SCHEMA_ELEMENT_CONVERTED_TYPE, CT_I32) => {
+                        element.converted_type = Some(reader.read_zigzag()?);
+                    }
+                    (SCHEMA_ELEMENT_FIELD_ID, CT_I32) => {
+                        element.field_id = Some(reader.read_zigzag()? as i32);
+                    }
+                    (SCHEMA_ELEMENT_LOGICAL_TYPE, CT_STRUCT) => {
+                        // LogicalType is a union identified by its only field
+                        let mut last_id = 0;
+                        while let Some((id, ty)) = reader.read_field_header(last_id)? {
+                            last_id = id;
+                            element.logical_type.get_or_insert(id);
+                            reader.skip(ty)?;
+                        }
+                    }
+                    _ => reader.skip(ty)?,
+                }
+            }
+            elements.push(element);
+        }
+        break; // other fields are not needed
+    }
+    if elements.is_empty() {
+        return df_execution_err!("parquet footer has no schema");
+    }
+    Ok(elements)
+}
+
+/// returns the index of the top-level element containing each element,
+/// elements with no children are leaves. the root element maps to itself.
+fn schema_roots(elements: &[SchemaElementInfo]) -> Vec<usize> {
+    let mut roots = vec![0];
+    let mut remaining_children: Vec<i64> = vec![];
+    let mut root = 0;
+    for (i, element) in elements.iter().enumerate().skip(1) {
+        match remaining_children.last_mut() {
+            Some(remaining) => *remaining -= 1,
+            None => root = i,
+        }
+        roots.push(root);
+        if let Some(n) = element.num_children.filter(|&n| n > 0) {
+            remaining_children.push(n);
+        }
+        while remaining_children.last() == Some(&0) {
+            remaining_children.pop();
+        }
+    }
+    roots
+}
+
+/// returns the end (exclusive) of the subtree rooted at the given element
+fn schema_subtree_end(elements: &[SchemaElementInfo], idx: usize) -> usize {
+    let mut end = idx + 1;
+    for _ in 0..elements[idx].num_children.unwrap_or(0) {
+        end = schema_subtree_end(elements, end);
+    }
+    end
+}
+
+/// Order of resolving table columns to top-level file columns by parquet
+/// field ids and names, see spark.blaze.parquet.fieldIdResolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldIdResolution {
+    /// matches by field id, then by name for table columns not matched by id
+    IdThenName,
+    /// matches by name, then by field id for table columns not matched by name
+    NameThenId,
+    /// matches the i-th table column to the i-th file column, ignoring names
+    /// and field ids of the file schema. for files with unreliable schemas
+    /// but stable column order
+    Position,
+}
+
+impl FieldIdResolution {
+    /// parses the conf value, `name` means field ids are not used
+    pub fn parse(value: &str) -> Result<Option<Self>> {
+        match value.to_lowercase().as_str() {
+            "name" => Ok(None),
+            "id_then_name" => Ok(Some(Self::IdThenName)),
+            "name_then_id" => Ok(Some(Self::NameThenId)),
+            "position" => Ok(Some(Self::Position)),
+            other => df_execution_err!("invalid parquet field id resolution: {other}"),
+        }
+    }
+}
+
+/// Resolves top-level columns of parquet files to table columns by field ids
+/// and names, like iceberg tables with renamed columns.
+///
+/// resolved file columns are renamed to their table columns in the footer
+/// before decoding, so they are matched by name when mapped to the table
+/// schema. a table column resolved to no file column is read as nulls, and
+/// an unresolved file column is never matched by its name. nested fields are
+/// matched by name.
+pub struct FieldIdResolver {
+    order: FieldIdResolution,
+    table_columns: Vec<(String, Option<i32>)>,
+}
+
+impl FieldIdResolver {
+    /// field ids of table columns are read from `PARQUET:field_id` metadata
+    pub fn new(order: FieldIdResolution, table_schema: &Schema) -> Self {
+        let table_columns = table_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let field_id = field
+                    .metadata()
+                    .get(PARQUET_FIELD_ID_META_KEY)
+                    .and_then(|id| id.parse().ok());
+                (field.name().clone(), field_id)
+            })
+            .collect();
+        Self {
+            order,
+            table_columns,
+        }
+    }
+
+    /// rewrites the footer metadata (without the trailing length and magic),
+    /// renaming file columns to their resolved table columns
+    pub fn resolve(&self, metadata: &Bytes) -> Result<Bytes> {
+        let elements = read_schema_elements(metadata)?;
+        let top_level = top_level_elements(&elements);
+        let file_columns = top_level
+            .iter()
+            .map(|&i| (elements[i].name.as_str(), elements[i].field_id))
+            .collect::<Vec<_>>();
+
+        let renames = top_level
+            .iter()
+            .zip(self.resolve_columns(&file_columns))
+            .filter_map(|(&i, new_name)| {
+                new_name
+                    .filter(|new_name| new_name != &elements[i].name)
+                    .map(|new_name| (i, new_name))
+            })
+            .collect::<HashMap<_, _>>();
+        if renames.is_empty() {
+            return Ok(metadata.clone());
+        }
+        Ok(Bytes::from(rename_schema_elements(metadata, &renames)?))
+    }
+
+    /// returns the new name of each file column: its resolved table column,
+    /// a reserved name if it is unresolved but named as a table column, or
+    /// None if kept as is
+    fn resolve_columns(&self, file_columns: &[(&str, Option<i32>)]) -> Vec<Option<String>> {
+        let match_by_id = |table_idx: usize, file_idx: usize| {
+            let table_field_id = self.table_columns[table_idx].1;
+            table_field_id.is_some() && file_columns[file_idx].1 == table_field_id
+        };
+        let match_by_name = |table_idx: usize, file_idx: usize| {
+            file_columns[file_idx].0 == self.table_columns[table_idx].0
+        };
+        let match_by_position = |table_idx: usize, file_idx: usize| table_idx == file_idx;
+        let passes: Vec<&dyn Fn(usize, usize) -> bool> = match self.order {
+            FieldIdResolution::IdThenName => vec![&match_by_id, &match_by_name],
+            FieldIdResolution::NameThenId => vec![&match_by_name, &match_by_id],
+            FieldIdResolution::Position => vec![&match_by_position],
+        };
+
+        // each table column is resolved to at most one file column
+        let mut resolved: Vec<Option<usize>> = vec![None; file_columns.len()];
+        let mut table_resolved = vec![false; self.table_columns.len()];
+        for matches in passes {
+            for table_idx in 0..self.table_columns.len() {
+                if table_resolved[table_idx] {
+                    continue;
+                }
+                let file_idx = (0..file_columns.len())
+                    .find(|&file_idx| resolved[file_idx].is_none() && matches(table_idx, file_idx));
+                if let Some(file_idx) = file_idx {
+                    resolved[file_idx] = Some(table_idx);
+                    table_resolved[table_idx] = true;
+                }
+            }
+        }
+
+        file_columns
+            .iter()
+            .zip(resolved)
+            .enumerate()
+            .map(|(file_idx, (&(name, _), table_idx))| match table_idx {
+                Some(table_idx) => Some(self.table_columns[table_idx].0.clone()),
+                None if self
+                    .table_columns
+                    .iter()
+                    .any(|(table_name, _)| table_name == name) =>
+                {
+                    Some(format!("__unresolved_{file_idx}_{name}"))
+                }
+                None => None,
+            })
+            .collect()
+    }
+}
+
+/// Detects top-level file columns with the same name (or names differing only
+/// by case when columns are resolved case-insensitively), which are written
+/// by some buggy writers.
+///
+/// a table column matching more than one file column is ambiguous and fails
+/// the scan with the conflicting columns, instead of silently reading any of
+/// them. if picking the first column is enabled, the other matching columns
+/// are renamed in the footer before decoding, so that they are never matched.
+pub struct DuplicateColumnResolver {
+    table_columns: Vec<String>,
+    case_sensitive: bool,
+    pick_first: bool,
+}
+
+impl DuplicateColumnResolver {
+    pub fn new(table_schema: &Schema, case_sensitive: bool, pick_first: bool) -> Self {
+        Self {
+            table_columns: table_schema
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .collect(),
+            case_sensitive,
+            pick_first,
+        }
+    }
+
+    /// returns true if the footer is rewritten to pick the first column of
+    /// duplicate columns, see [`Self::resolve`]
+    pub fn pick_first(&self) -> bool {
+        self.pick_first
+    }
+
+    /// rewrites the footer metadata (without the trailing length and magic),
+    /// renaming all but the first file column matching each table column
+    pub fn resolve(&self, metadata: &Bytes) -> Result<Bytes> {
+        let elements = read_schema_elements(metadata)?;
+        let top_level = top_level_elements(&elements);
+        let file_columns = top_level
+            .iter()
+            .map(|&i| elements[i].name.as_str())
+            .collect::<Vec<_>>();
+
+        let renames = self
+            .duplicates(&file_columns)
+            .into_iter()
+            .flat_map(|(_, file_idxs)| file_idxs.into_iter().skip(1))
+            .map(|file_idx| {
+                let new_name = format!("__duplicate_{file_idx}_{}", file_columns[file_idx]);
+                (top_level[file_idx], new_name)
+            })
+            .collect::<HashMap<_, _>>();
+        if renames.is_empty() {
+            return Ok(metadata.clone());
+        }
+        Ok(Bytes::from(rename_schema_elements(metadata, &renames)?))
+    }
+
+    /// fails if any table column matches more than one top-level column of
+    /// the file
+    pub fn check(&self, metadata: &ParquetMetaData, path: &str) -> Result<()> {
+        let root_schema = metadata.file_metadata().schema_descr().root_schema();
+        let file_columns = root_schema
+            .get_fields()
+            .iter()
+            .map(|field| field.name())
+            .collect::<Vec<_>>();
+
+        if let Some((table_column, file_idxs)) = self.duplicates(&file_columns).first() {
+            let conflicts = file_idxs
+                .iter()
+                .map(|&file_idx| format!("{} (#{file_idx})", file_columns[file_idx]))
+                .collect::<Vec<_>>();
+            return df_execution_err!(
+                "ambiguous parquet column {table_column}: matched by file columns [{}] (case sensitive: {}), file: {path}",
+                conflicts.join(", "),
+                self.case_sensitive,
+            );
+        }
+        Ok(())
+    }
+
+    // returns table columns matching more than one file column, with indices
+    // of the matching file columns
+    fn duplicates(&self, file_columns: &[&str]) -> Vec<(&str, Vec<usize>)> {
+        let normalize = |name: &str| match self.case_sensitive {
+            true => name.to_string(),
+            false => name.to_lowercase(),
+        };
+        let mut file_idxs: HashMap<String, Vec<usize>> = HashMap::new();
+        for (file_idx, &name) in file_columns.iter().enumerate() {
+            file_idxs.entry(normalize(name)).or_default().push(file_idx);
+        }
+        self.table_columns
+            .iter()
+            .filter_map(|table_column| {
+                let file_idxs = file_idxs.get(&normalize(table_column))?;
+                (file_idxs.len() > 1).then(|| (table_column.as_str(), file_idxs.clone()))
+            })
+            .collect()
+    }
+}
+
+/// returns indices of the top-level schema elements
+fn top_level_elements(elements: &[SchemaElementInfo]) -> Vec<usize> {
+    schema_roots(elements)
+        .into_iter()
+        .enumerate()
+        .skip(1)
+        .filter(|&(i, root)| i == root)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Rewrites the footer, renaming schema elements of the given indices. other
+/// bytes are copied without decoding.
+fn rename_schema_elements(metadata: &[u8], renames: &HashMap<usize, String>) -> Result<Vec<u8>> {
+    let mut reader = CompactReader::new(metadata);
+    let mut out = Vec::with_capacity(metadata.len());
+
+    rewrite_struct(
+        &mut reader,
+        &mut out,
+        |id| match id {
+            FILE_METADATA_SCHEMA => FieldAction::Rewrite,
+            _ => FieldAction::Copy,
+        },
+        |_, reader, out| {
+            rewrite_list(reader, out, |elem_idx, reader, out| {
+                // schema element
+                let new_name = renames.get(&elem_idx);
+                rewrite_struct(
+                    reader,
+                    out,
+                    |id| match id {
+                        SCHEMA_ELEMENT_NAME if new_name.is_some() => FieldAction::Rewrite,
+                        _ => FieldAction::Copy,
+                    },
+                    |_, reader, out| {
+                        reader.skip(CT_BINARY)?;
+                        write_binary(out, new_name.expect("missing new name").as_bytes());
+                        Ok(())
+                    },
+                )
+            })
+        },
+    )?;
+    Ok(out)
+}
+
+/// Drops logical types unknown to the bundled parquet crate from the footer
+/// metadata (without the trailing length and magic), which otherwise fails to
+/// decode it.
+///
+/// GEOMETRY and GEOGRAPHY columns (WKB-encoded binary) are read as plain
+/// binary if `geometry_as_binary` is enabled (see
+/// spark.blaze.parquet.geometryAsBinary), otherwise the file fails with the
+/// column. columns with other unknown logical types always fail.
+pub fn drop_unknown_logical_types(metadata: &Bytes, geometry_as_binary: bool) -> Result<Bytes> {
+    let elements = read_schema_elements(metadata)?;
+    let mut dropped = HashSet::new();
+    for (elem_idx, element) in elements.iter().enumerate() {
+        match element.logical_type {
+            Some(LOGICAL_TYPE_GEOMETRY | LOGICAL_TYPE_GEOGRAPHY) if geometry_as_binary => {
+                dropped.insert(elem_idx);
+            }
+            Some(id @ (LOGICAL_TYPE_GEOMETRY | LOGICAL_TYPE_GEOGRAPHY)) => {
+                let logical_type = match id {
+                    LOGICAL_TYPE_GEOMETRY => "GEOMETRY",
+                    _ => "GEOGRAPHY",
+                };
+                return df_execution_err!(
+                    "parquet column {} has unsupported logical type {logical_type}, enable spark.blaze.parquet.geometryAsBinary to read it as WKB binary",
+                    element.name,
+                );
+            }
+            Some(id) if id > LOGICAL_TYPE_FLOAT16 => {
+                return df_execution_err!(
+                    "parquet column {} has unsupported logical type (LogicalType field {id})",
+                    element.name,
+                );
+            }
+            _ => {}
+        }
+    }
+    if dropped.is_empty() {
+        return Ok(metadata.clone());
+    }
+
+    let mut reader = CompactReader::new(metadata);
+    let mut out = Vec::with_capacity(metadata.len());
+    rewrite_struct(
+        &mut reader,
+        &mut out,
+        |id| match id {
+            FILE_METADATA_SCHEMA => FieldAction::Rewrite,
+            _ => FieldAction::Copy,
+        },
+        |_, reader, out| {
+            rewrite_list(reader, out, |elem_idx, reader, out| {
+                // schema element
+                let drop_logical_type = dropped.contains(&elem_idx);
+                rewrite_struct(
+                    reader,
+                    out,
+                    |id| match id {
+                        SCHEMA_ELEMENT_LOGICAL_TYPE if drop_logical_type => FieldAction::Drop,
+                        _ => FieldAction::Copy,
+                    },
+                    |_, _, _| unreachable!(),
+                )
+            })
+        },
+    )?;
+    Ok(Bytes::from(out))
+}
+
+/// Child of a map column read by itself, see [`MapChildrenProjection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapChild {
+    Keys,
+    Values,
+}
+
+/// Metadata key of table fields reading only the keys or values (`keys` or
+/// `values`) of a map column, like `map_keys(m)` or `map_values(m)`. the table
+/// field is declared as a list of the map keys or values.
+pub const MAP_CHILD_META_KEY: &str = "blaze.map_child";
+
+/// Reads map columns as lists of their keys or values, so that column chunks
+/// of the other child are never fetched.
+///
+/// the map group is rewritten to a list group in the raw footer, whose
+/// repeated key-value group has the needed child only. schema elements and
+/// column chunks of the other child are dropped, as is the embedded arrow
+/// schema (which still declares maps). columns not being maps in the file are
+/// kept as is.
+pub struct MapChildrenProjection {
+    map_children: HashMap<String, MapChild>,
+}
+
+impl MapChildrenProjection {
+    /// column names are top-level field names matched case-insensitively.
+    pub fn new(map_children: impl IntoIterator<Item = (String, MapChild)>) -> Self {
+        Self {
+            map_children: map_children
+                .into_iter()
+                .map(|(name, child)| (name.to_lowercase(), child))
+                .collect(),
+        }
+    }
+
+    /// reads map children from `blaze.map_child` metadata of table fields,
+    /// returns None if no map children are projected
+    pub fn try_from_schema(table_schema: &Schema) -> Result<Option<Self>> {
+        let mut map_children = vec![];
+        for field in table_schema.fields() {
+            let child = match field.metadata().get(MAP_CHILD_META_KEY).map(|s| s.as_str()) {
+                Some("keys") => MapChild::Keys,
+                Some("values") => MapChild::Values,
+                Some(other) => return df_execution_err!("invalid map child: {other}"),
+                None => continue,
+            };
+            map_children.push((field.name().clone(), child));
+        }
+        Ok((!map_children.is_empty()).then(|| Self::new(map_children)))
+    }
+
+    /// rewrites the footer metadata (without the trailing length and magic)
+    pub fn project(&self, metadata: &Bytes) -> Result<Bytes> {
+        let elements = read_schema_elements(metadata)?;
+        let mut leaf_indices = vec![None; elements.len()];
+        let mut num_leaves = 0;
+        for (i, element) in elements.iter().enumerate().skip(1) {
+            if !element.num_children.is_some_and(|n| n > 0) {
+                leaf_indices[i] = Some(num_leaves);
+                num_leaves += 1;
+            }
+        }
+
+        // map group -> (key-value group, dropped child subtree)
+        let mut maps = HashMap::new();
+        let mut dropped_elements = HashSet::new();
+        let mut dropped_leaves = HashSet::new();
+        for (i, root) in schema_roots(&elements).into_iter().enumerate().skip(1) {
+            if i != root {
+                continue;
+            }
+            let Some(&child) = self.map_children.get(&elements[i].name.to_lowercase()) else {
+                continue;
+            };
+            let is_map = elements[i].converted_type == Some(CONVERTED_TYPE_MAP)
+                && elements[i].num_children == Some(1)
+                && elements
+                    .get(i + 1)
+                    .is_some_and(|kv| kv.num_children == Some(2));
+            if !is_map {
+                continue;
+            }
+            let key = i + 2;
+            let value = schema_subtree_end(&elements, key);
+            let dropped = match child {
+                MapChild::Keys => value..schema_subtree_end(&elements, value),
+                MapChild::Values => key..value,
+            };
+            dropped_leaves.extend(dropped.clone().filter_map(|i| leaf_indices[i]));
+            dropped_elements.extend(dropped);
+            maps.insert(i, i + 1);
+        }
+        if maps.is_empty() {
+            return Ok(metadata.clone());
+        }
+        let key_values = maps.values().copied().collect::<HashSet<_>>();
+
+        let mut reader = CompactReader::new(metadata);
+        let mut out = Vec::with_capacity(metadata.len());
+        rewrite_struct(
+            &mut reader,
+            &mut out,
+            |id| match id {
+                FILE_METADATA_SCHEMA
+                | FILE_METADATA_ROW_GROUPS
+                | FILE_METADATA_KEY_VALUE_METADATA
+                | FILE_METADATA_COLUMN_ORDERS => FieldAction::Rewrite,
+                _ => FieldAction::Copy,
+            },
+            |id, reader, out| match id {
+                FILE_METADATA_SCHEMA => rewrite_list_filtered(reader, out, |i, reader, out| {
+                    if dropped_elements.contains(&i) {
+                        reader.skip(CT_STRUCT)?;
+                        return Ok(false);
+                    }
+                    if maps.contains_key(&i) {
+                        rewrite_map_as_list(reader, out)?;
+                    } else if key_values.contains(&i) {
+                        rewrite_key_value_as_repeated(reader, out)?;
+                    } else {
+                        copy_struct(reader, out)?;
+                    }
+                    Ok(true)
+                }),
+                FILE_METADATA_ROW_GROUPS => rewrite_list(reader, out, |_, reader, out| {
+                    // row group
+                    rewrite_struct(
+                        reader,
+                        out,
+                        |id| match id {
+                            ROW_GROUP_COLUMNS => FieldAction::Rewrite,
+                            _ => FieldAction::Copy,
+                        },
+                        |_, reader, out| {
+                            rewrite_list_filtered(reader, out, |leaf_idx, reader, out| {
+                                // column chunk
+                                if dropped_leaves.contains(&leaf_idx) {
+                                    reader.skip(CT_STRUCT)?;
+                                    return Ok(false);
+                                }
+                                copy_struct(reader, out)?;
+                                Ok(true)
+                            })
+                        },
+                    )
+                }),
+                FILE_METADATA_KEY_VALUE_METADATA => {
+                    rewrite_list_filtered(reader, out, |_, reader, out| {
+                        let key_value = copy_struct(reader, out)?;
+                        let mut key_value_reader = CompactReader::new(key_value);
+                        match key_value_reader.read_field_header(0)? {
+                            Some((KEY_VALUE_KEY, CT_BINARY)) => {
+                                let key = key_value_reader.read_binary()?;
+                                Ok(key != ARROW_SCHEMA_META_KEY.as_bytes())
+                            }
+                            _ => Ok(true),
+                        }
+                    })
+                }
+                FILE_METADATA_COLUMN_ORDERS => {
+                    rewrite_list_filtered(reader, out, |leaf_idx, reader, out| {
+                        if dropped_leaves.contains(&leaf_idx) {
+                            reader.skip(CT_STRUCT)?;
+                            return Ok(false);
+                        }
+                        copy_struct(reader, out)?;
+                        Ok(true)
+                    })
+                }
+                _ => unreachable!(),
+            },
+        )?;
+        Ok(Bytes::from(out))
+    }
+}
+
+// rewrites the schema element of a map group to a list group
+fn rewrite_map_as_list(reader: &mut CompactReader, out: &mut Vec<u8>) -> Result<()> {
+    rewrite_struct(
+        reader,
+        out,
+        |id| match id {
+            SCHEMA_ELEMENT_CONVERTED_TYPE | SCHEMA_ELEMENT_LOGICAL_TYPE => FieldAction::Rewrite,
+            _ => FieldAction::Copy,
+        },
+        |id, reader, out| {
+            if id == SCHEMA_ELEMENT_CONVERTED_TYPE {
+                reader.skip(CT_I32)?;
+                write_i32(out, CONVERTED_TYPE_LIST);
+            } else {
+                // LogicalType union of an empty ListType
+                reader.skip(CT_STRUCT)?;
+                write_field_header(out, 0, LOGICAL_TYPE_LIST, CT_STRUCT);
+                out.push(CT_STOP);
+                out.push(CT_STOP);
+            }
+            Ok(())
+        },
+    )
+}
+
+// rewrites the schema element of a key-value group to a repeated group of the
+// remaining child
+fn rewrite_key_value_as_repeated(reader: &mut CompactReader, out: &mut Vec<u8>) -> Result<()> {
+    rewrite_struct(
+        reader,
+        out,
+        |id| match id {
+            SCHEMA_ELEMENT_NUM_CHILDREN => FieldAction::Rewrite,
+            SCHEMA_ELEMENT_CONVERTED_TYPE | SCHEMA_ELEMENT_LOGICAL_TYPE => FieldAction::Drop,
+            _ => FieldAction::Copy,
+        },
+        |_, reader, out| {
+            reader.skip(CT_I32)?;
+            write_i32(out, 1);
+            Ok(())
+        },
+    )
+}
+
+/// Rewrites the footer, dropping optional metadata of column chunks whose
+/// leaf index is not needed. other bytes are copied without decoding.
+pub fn project_footer(metadata: &[u8], is_needed: impl Fn(usize) -> bool) -> Result<Vec<u8>> {
+    let mut reader = CompactReader::new(metadata);
+    let mut out = Vec::with_capacity(metadata.len());
+
+    rewrite_struct(
+        &mut reader,
+        &mut out,
+        |id| match id {
+            FILE_METADATA_ROW_GROUPS => FieldAction::Rewrite,
+            _ => FieldAction::Copy,
+        },
+        |_, reader, out| {
+            rewrite_list(reader, out, |_, reader, out| {
+                // row group
+                rewrite_struct(
+                    reader,
+                    out,
+                    |id| match id {
+                        ROW_GROUP_COLUMNS => FieldAction::Rewrite,
+                        _ => FieldAction::Copy,
+                    },
+                    |_, reader, out| {
+                        rewrite_list(reader, out, |leaf_idx, reader, out| {
+                            // column chunk
+                            let needed = is_needed(leaf_idx);
+                            rewrite_struct(
+                                reader,
+                                out,
+                                |id| match id {
+                                    COLUMN_CHUNK_META_DATA if !needed => FieldAction::Rewrite,
+                                    _ => FieldAction::Copy,
+                                },
+                                |_, reader, out| {
+                                    rewrite_struct(
+                                        reader,
+                                        out,
+                                        |id| match id {
+                                            id if STRIPPED_COLUMN_META_DATA_FIELDS
+                                                .contains(&id) =>
+                                            {
+                                                FieldAction::Drop
+                                            }
+                                            _ => FieldAction::Copy,
+                                        },
+                                        |_, _, _| unreachable!(),
+                                    )
+                                },
+                            )
+                        })
+                    },
+                )
+            })
+        },
+    )?;
+    Ok(out)
+}
+
+enum FieldAction {
+    Copy,
+    Drop,
+    Rewrite,
+}
+
+fn rewrite_struct(
+    reader: &mut CompactReader,
+    out: &mut Vec<u8>,
+    action: impl Fn(i16) -> FieldAction,
+    mut rewrite: impl FnMut(i16, &mut CompactReader, &mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    let mut last_read_id = 0;
+    let mut last_written_id = 0;
+    while let Some((id, ty)) = reader.read_field_header(last_read_id)? {
+        last_read_id = id;
+        let action = action(id);
+        if matches!(action, FieldAction::Drop) {
+            reader.skip(ty)?;
+            continue;
+        }
+
+        // field ids are delta-encoded, so headers are re-encoded after
+        // dropping fields
+        write_field_header(out, last_written_id, id, ty);
+        last_written_id = id;
+        match action {
+            FieldAction::Copy => {
+                let start = reader.pos;
+                reader.skip(ty)?;
+                out.extend_from_slice(&reader.buf[start..reader.pos]);
+            }
+            FieldAction::Rewrite => rewrite(id, reader, out)?,
+            FieldAction::Drop => unreachable!(),
+        }
+    }
+    out.push(CT_STOP);
+    Ok(())
+}
+
+fn rewrite_list(
+    reader: &mut CompactReader,
+    out: &mut Vec<u8>,
+    mut rewrite_element: impl FnMut(usize, &mut CompactReader, &mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    let (size, elem_type) = reader.read_list_header()?;
+    if elem_type != CT_STRUCT {
+        return df_execution_err!("invalid parquet footer: expect list of structs");
+    }
+    write_list_header(out, size, elem_type);
+    for i in 0..size {
+        rewrite_element(i, reader, out)?;
+    }
+    Ok(())
+}
+
+/// like [`rewrite_list`], but only elements for which `rewrite_element`
+/// returns true are kept
+fn rewrite_list_filtered(
+    reader: &mut CompactReader,
+    out: &mut Vec<u8>,
+    mut rewrite_element: impl FnMut(usize, &mut CompactReader, &mut Vec<u8>) -> Result<bool>,
+) -> Result<()> {
+    let (size, elem_type) = reader.read_list_header()?;
+    if elem_type != CT_STRUCT {
+        return df_execution_err!("invalid parquet footer: expect list of structs");
+    }
+    let mut elements = vec![];
+    for i in 0..size {
+        let mut element = vec![];
+        if rewrite_element(i, reader, &mut element)? {
+            elements.push(element);
+        }
+    }
+    write_list_header(out, elements.len(), elem_type);
+    for element in elements {
+        out.extend_from_slice(&element);
+    }
+    Ok(())
+}
+
+/// copies a struct element, returns its bytes
+fn copy_struct<'a>(reader: &mut CompactReader<'a>, out: &mut Vec<u8>) -> Result<&'a [u8]> {
+    let buf = reader.buf;
+    let start = reader.pos;
+    reader.skip(CT_STRUCT)?;
+    let bytes = &buf[start..reader.pos];
+    out.extend_from_slice(bytes);
+    Ok(bytes)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_i32(out: &mut Vec<u8>, value: i32) {
+    write_varint(out, ((value << 1) ^ (value >> 31)) as u32 as u64);
+}
+
+fn write_binary(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_field_header(out: &mut Vec<u8>, last_id: i16, id: i16, ty: u8) {
+    let delta = id as i32 - last_id as i32;
+    if (1..=15).contains(&delta) {
+        out.push(((delta as u8) << 4) | ty);
+    } else {
+        out.push(ty);
+        write_varint(out, ((id << 1) ^ (id >> 15)) as u16 as u64);
+    }
+}
+
+fn write_list_header(out: &mut Vec<u8>, size: usize, elem_type: u8) {
+    if size < 15 {
+        out.push(((size as u8) << 4) | elem_type);
+    } else {
+        out.push(0xf0 | elem_type);
+        write_varint(out, size as u64);
+    }
+}
+
+/// Sizes and checksum of a page, read from its raw header.
+#[derive(Debug, PartialEq)]
+pub struct RawPageHeader {
+    pub header_len: usize,
+    pub compressed_page_size: usize,
+    pub crc: Option<u32>,
+}
+
+/// reads the page header at the start of the bytes, other fields of the
+/// header are skipped
+pub fn read_page_header(buf: &[u8]) -> Result<RawPageHeader> {
+    let mut reader = CompactReader::new(buf);
+    let mut compressed_page_size = None;
+    let mut crc = None;
+    let mut last_id = 0;
+    while let Some((id, ty)) = reader.read_field_header(last_id)? {
+        last_id = id;
+        match (id, ty) {
+            (PAGE_HEADER_COMPRESSED_PAGE_SIZE, CT_I32) => {
+                compressed_page_size = Some(reader.read_zigzag()?);
+            }
+            (PAGE_HEADER_CRC, CT_I32) => crc = Some(reader.read_zigzag()? as i32 as u32),
+            _ => reader.skip(ty)?,
+        }
+    }
+    match compressed_page_size {
+        Some(size) if size >= 0 => Ok(RawPageHeader {
+            header_len: reader.pos,
+            compressed_page_size: size as usize,
+            crc,
+        }),
+        _ => df_execution_err!("invalid parquet page header: missing compressed_page_size"),
+    }
+}
+
+/// Encryption of a file with an encrypted footer.
+#[derive(Debug, PartialEq)]
+pub struct FileEncryption {
+    pub algorithm: &'static str,
+    /// whether an aad prefix is stored in the file or must be supplied by
+    /// readers, and is verified in decryption
+    pub aad_prefix: bool,
+}
+
+/// reads the encryption algorithm from the plaintext crypto metadata at the
+/// start of an encrypted footer, other fields are skipped
+pub fn read_file_encryption(crypto_metadata: &[u8]) -> Result<FileEncryption> {
+    let mut reader = CompactReader::new(crypto_metadata);
+    let mut last_id = 0;
+    while let Some((id, ty)) = reader.read_field_header(last_id)? {
+        last_id = id;
+        if id != FILE_CRYPTO_METADATA_ENCRYPTION_ALGORITHM || ty != CT_STRUCT {
+            reader.skip(ty)?;
+            continue;
+        }
+
+        // EncryptionAlgorithm is a union identified by its only field
+        let Some((algorithm_id, CT_STRUCT)) = reader.read_field_header(0)? else {
+            break;
+        };
+        let algorithm = match algorithm_id {
+            ENCRYPTION_ALGORITHM_AES_GCM_V1 => "AES_GCM_V1",
+            ENCRYPTION_ALGORITHM_AES_GCM_CTR_V1 => "AES_GCM_CTR_V1",
+            _ => "unknown",
+        };
+        let mut aad_prefix = false;
+        let mut last_id = 0;
+        while let Some((id, ty)) = reader.read_field_header(last_id)? {
+            last_id = id;
+            match (id, ty) {
+                (AES_GCM_AAD_PREFIX, CT_BINARY) => {
+                    reader.skip(ty)?;
+                    aad_prefix = true;
+                }
+                (AES_GCM_SUPPLY_AAD_PREFIX, CT_BOOLEAN_TRUE) => aad_prefix = true,
+                _ => reader.skip(ty)?,
+            }
+        }
+        return Ok(FileEncryption {
+            algorithm,
+            aad_prefix,
+        });
+    }
+    df_execution_err!("invalid parquet crypto metadata: missing encryption_algorithm")
+}
+
+/// Minimal reader of thrift compact protocol, only supporting what is needed
+/// to walk through a parquet footer.
+struct CompactReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> CompactReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn advance(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.buf.len() - self.pos < len {
+            return df_execution_err!("invalid parquet footer: unexpected end of metadata");
+        }
+        let bytes = &self.buf[self.pos..][..len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        Ok(self.advance(1)?[0])
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        df_execution_err!("invalid parquet footer: varint overflow")
+    }
+
+    fn read_zigzag(&mut self) -> Result<i64> {
+        let value = self.read_varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn read_binary(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_varint()? as usize;
+        self.advance(len)
+    }
+
+    /// returns field id and type, or None if reaching the end of the struct
+    fn read_field_header(&mut self, last_id: i16) -> Result<Option<(i16, u8)>> {
+        let byte = self.read_byte()?;
+        let ty = byte & 0x0f;
+        if ty == CT_STOP {
+            return Ok(None);
+        }
+        let id = match byte >> 4 {
+            0 => self.read_zigzag()? as i16,
+            delta => last_id + delta as i16,
+        };
+        Ok(Some((id, ty)))
+    }
+
+    fn read_list_header(&mut self) -> Result<(usize, u8)> {
+        let byte = self.read_byte()?;
+        let size = match byte >> 4 {
+            15 => self.read_varint()? as usize,
+            size => size as usize,
+        };
+        Ok((size, byte & 0x0f))
+    }
+
+    fn skip(&mut self, ty: u8) -> Result<()> {
+        match ty {
+            CT_BOOLEAN_TRUE | CT_BOOLEAN_FALSE => {} // value is in the field header
+            CT_BYTE => {
+                self.advance(1)?;
+            }
+            CT_I16 | CT_I32 | CT_I64 => {
+                self.read_varint()?;
+            }
+            CT_DOUBLE => {
+                self.advance(8)?;
+            }
+            CT_BINARY => {
+                self.read_binary()?;
+            }
+            CT_LIST | CT_SET => {
+                let (size, elem_type) = self.read_list_header()?;
+                for _ in 0..size {
+                    self.skip_element(elem_type)?;
+                }
+            }
+            CT_MAP => {
+                let size = self.read_varint()?;
+                if size > 0 {
+                    let kv_types = self.read_byte()?;
+                    for _ in 0..size {
+                        self.skip_element(kv_types >> 4)?;
+                        self.skip_element(kv_types & 0x0f)?;
+                    }
+                }
+            }
+            CT_STRUCT => {
+                let mut last_id = 0;
+                while let Some((id, ty)) = self.read_field_header(last_id)? {
+                    last_id = id;
+                    self.skip(ty)?;
+                }
+            }
+            _ => return df_execution_err!("invalid parquet footer: unknown thrift type {ty}"),
+        }
+        Ok(())
+    }
+
+    fn skip_element(&mut self, ty: u8) -> Result<()> {
+        match ty {
+            // booleans in collections are encoded as one byte
+            CT_BOOLEAN_TRUE | CT_BOOLEAN_FALSE => self.advance(1).map(|_| ()),
+            _ => self.skip(ty),
+        }
+    }
+}
+
+/// A cache slot holding the raw footer bytes of one parquet file.
+pub type FooterBytesSlot = Arc<tokio::sync::OnceCell<Bytes>>;
+
+/// Caches raw footers of the most recently used files. raw footers are much
+/// smaller than decoded metadata, and can be decoded with different
+/// projections.
+pub struct FooterBytesCache {
+    capacity: usize,
+    slots: Mutex<Vec<(ObjectMeta, FooterBytesSlot)>>,
+}
+
+impl FooterBytesCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            slots: Mutex::default(),
+        }
+    }
+
+    fn slot(&self, meta: &ObjectMeta) -> FooterBytesSlot {
+        let mut slots = self.slots.lock();
+
+        // find existed cache slot
+        for (cache_meta, cache_slot) in slots.iter() {
+            if cache_meta.location == meta.location {
+                return cache_slot.clone();
+            }
+        }
+
+        // reserve a new cache slot
+        if slots.len() >= self.capacity {
+            slots.remove(0); // remove eldest
+        }
+        let cache_slot = FooterBytesSlot::default();
+        slots.push((meta.clone(), cache_slot.clone()));
+        cache_slot
+    }
+}
+
+/// Returns the process-wide raw footer cache.
+pub fn footer_bytes_cache() -> &'static FooterBytesCache {
+    const FOOTER_BYTES_CACHE_SIZE: usize = 5;
+    static FOOTER_BYTES_CACHE: OnceCell<FooterBytesCache> = OnceCell::new();
+    FOOTER_BYTES_CACHE.get_or_init(|| FooterBytesCache::new(FOOTER_BYTES_CACHE_SIZE))
+}
+
+/// Returns the cached raw footer of the given file, fetching and caching it if
+/// missing. fetches are limited by the metadata fetch semaphore.
+pub async fn get_or_fetch_footer_bytes<F, Fut>(
+    cache: &FooterBytesCache,
+    meta: &ObjectMeta,
+    fetch: F,
+) -> ParquetResult<Bytes>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = ParquetResult<Bytes>>,
+{
+    let cache_slot = cache.slot(meta);
+    cache_slot
+        .get_or_try_init(move || with_fetch_permit(metadata_fetch_semaphore(), fetch()))
+        .await
+        .cloned()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering::SeqCst},
+            Arc,
+        },
+    };
+
+    use arrow::{
+        array::{
+            Array, ArrayRef, AsArray, Int32Array, Int32Builder, MapBuilder, StringArray,
+            StringBuilder, StructArray,
+        },
+        datatypes::{DataType, Field, Fields, Int32Type, Schema},
+        record_batch::RecordBatch,
+    };
+    use bytes::Bytes;
+    use datafusion::parquet::{
+        arrow::{
+            arrow_reader::{ArrowReaderMetadata, ParquetRecordBatchReaderBuilder},
+            parquet_to_arrow_schema, ArrowWriter, ProjectionMask,
+        },
+        data_type::{ByteArray, ByteArrayType, Int32Type as ParquetInt32Type},
+        errors::Result as ParquetResult,
+        file::{
+            footer::decode_metadata,
+            properties::WriterProperties,
+            reader::{ChunkReader, Length},
+            writer::SerializedFileWriter,
+            FOOTER_SIZE,
+        },
+        schema::parser::parse_message_type,
+    };
+    use object_store::{path::Path, ObjectMeta};
+    use parking_lot::Mutex;
+
+    use crate::common::parquet_footer::{
+        drop_unknown_logical_types, get_or_fetch_footer_bytes, project_footer,
+        read_file_encryption, rewrite_list, rewrite_struct, schema_leaf_roots, write_field_header,
+        CompactReader, DuplicateColumnResolver, FieldAction, FieldIdResolution, FieldIdResolver,
+        FileEncryption, FooterBytesCache, FooterProjection, MapChild, MapChildrenProjection,
+        CT_STOP, CT_STRUCT, FILE_METADATA_SCHEMA, LOGICAL_TYPE_GEOGRAPHY, LOGICAL_TYPE_GEOMETRY,
+        SCHEMA_ELEMENT_LOGICAL_TYPE,
+    };
+
+    fn write_parquet(batch: &RecordBatch) -> Bytes {
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, batch.schema(), Some(props)).unwrap();
+        writer.write(batch).unwrap();
+        writer.close().unwrap();
+        Bytes::from(buf)
+    }
+
+    fn raw_footer(data: &Bytes) -> Bytes {
+        let footer_start = data.len() - FOOTER_SIZE;
+        let metadata_len =
+            u32::from_le_bytes(data[footer_start..][..4].try_into().unwrap()) as usize;
+        data.slice(footer_start - metadata_len..footer_start)
+    }
+
+    fn wide_batch(num_columns: usize) -> RecordBatch {
+        let schema = Arc::new(Schema::new(
+            (0..num_columns)
+                .map(|i| Field::new(format!("c{i}"), DataType::Int32, true))
+                .collect::<Vec<_>>(),
+        ));
+        let columns = (0..num_columns)
+            .map(|i| Arc::new(Int32Array::from(vec![i as i32, 1, 2, 3])) as ArrayRef)
+            .collect();
+        RecordBatch::try_new(schema, columns).unwrap()
+    }
+
+    #[test]
+    fn test_schema_leaf_roots() {
+        let struct_fields = Fields::from(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Int32, true),
+        ]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("s", DataType::Struct(struct_fields.clone()), true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let int_array: ArrayRef = Arc::new(Int32Array::from(vec![1, 2]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                int_array.clone(),
+                Arc::new(StructArray::new(
+                    struct_fields,
+                    vec![int_array.clone(), int_array.clone()],
+                    None,
+                )),
+                int_array,
+            ],
+        )
+        .unwrap();
+        let footer = raw_footer(&write_parquet(&batch));
+        assert_eq!(
+            schema_leaf_roots(&footer).unwrap(),
+            vec!["a", "s", "s", "b"]
+        );
+    }
+
+    #[test]
+    fn test_projected_footer_on_wide_schema() {
+        const NUM_COLUMNS: usize = 10000;
+        let data = write_parquet(&wide_batch(NUM_COLUMNS));
+        let footer = raw_footer(&data);
+        let full_metadata = decode_metadata(&footer).unwrap();
+
+        // only statistics of needed columns are retained
+        let projection = FooterProjection::new(1000, ["C1".to_string(), "c9999".to_string()]);
+        let metadata = projection.decode(&footer).unwrap();
+        assert_eq!(metadata.num_row_groups(), 2);
+        for (rg, full_rg) in metadata.row_groups().iter().zip(full_metadata.row_groups()) {
+            assert_eq!(rg.num_columns(), NUM_COLUMNS);
+            assert_eq!(rg.num_rows(), full_rg.num_rows());
+            assert!(rg.column(1).statistics().is_some());
+            assert!(rg.column(9999).statistics().is_some());
+            assert!(rg.column(2).statistics().is_none());
+            assert_eq!(rg.column(2).byte_range(), full_rg.column(2).byte_range());
+            assert_eq!(rg.column(1).statistics(), full_rg.column(1).statistics());
+        }
+        let projected = project_footer(&footer, |i| i == 1 || i == 9999).unwrap();
+        assert!(projected.len() < footer.len());
+
+        // unneeded columns are still readable
+        let reader_metadata =
+            ArrowReaderMetadata::try_new(Arc::new(metadata), Default::default()).unwrap();
+        let mask = ProjectionMask::roots(
+            reader_metadata.metadata().file_metadata().schema_descr(),
+            [5],
+        );
+        let batch = ParquetRecordBatchReaderBuilder::new_with_metadata(data, reader_metadata)
+            .with_projection(mask)
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            batch.column(0).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![5, 1]),
+        );
+
+        // narrow footers are fully decoded
+        let projection = FooterProjection::new(NUM_COLUMNS + 1, ["c1".to_string()]);
+        let metadata = projection.decode(&footer).unwrap();
+        assert!(metadata.row_group(0).column(2).statistics().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_footer_bytes() {
+        let cache = FooterBytesCache::new(2);
+        let num_fetches = AtomicUsize::new(0);
+        let meta = ObjectMeta {
+            location: Path::from("a"),
+            last_modified: Default::default(),
+            size: 0,
+            e_tag: None,
+            version: None,
+        };
+
+        for _ in 0..3 {
+            let footer = get_or_fetch_footer_bytes(&cache, &meta, || async {
+                num_fetches.fetch_add(1, SeqCst);
+                Ok(Bytes::from_static(b"footer"))
+            })
+            .await
+            .unwrap();
+            assert_eq!(footer, Bytes::from_static(b"footer"));
+        }
+        assert_eq!(num_fetches.load(SeqCst), 1);
+    }
+
+    fn field_id_resolver(
+        order: FieldIdResolution,
+        table_columns: &[(&str, Option<i32>)],
+    ) -> FieldIdResolver {
+        let fields = table_columns
+            .iter()
+            .map(|&(name, field_id)| {
+                let metadata = field_id
+                    .map(|id| HashMap::from([("PARQUET:field_id".to_string(), id.to_string())]))
+                    .unwrap_or_default();
+                Field::new(name, DataType::Int32, true).with_metadata(metadata)
+            })
+            .collect::<Vec<_>>();
+        FieldIdResolver::new(order, &Schema::new(fields))
+    }
+
+    #[test]
+    fn test_resolve_field_ids() {
+        use FieldIdResolution::{IdThenName, NameThenId};
+        let a_b = [("a", Some(1)), ("b", Some(2))];
+
+        for order in [IdThenName, NameThenId] {
+            // id-only match
+            let resolver = field_id_resolver(order, &[("x", Some(1))]);
+            assert_eq!(resolver.resolve_columns(&a_b), vec![Some("x".into()), None]);
+
+            // name-only match
+            let resolver = field_id_resolver(order, &[("b", None)]);
+            assert_eq!(resolver.resolve_columns(&a_b), vec![None, Some("b".into())]);
+            let resolver = field_id_resolver(order, &[("a", Some(5))]);
+            assert_eq!(
+                resolver.resolve_columns(&[("a", None)]),
+                vec![Some("a".into())],
+            );
+
+            // neither matches, the table column is read as nulls
+            let resolver = field_id_resolver(order, &[("c", Some(9))]);
+            assert_eq!(resolver.resolve_columns(&a_b), vec![None, None]);
+        }
+
+        // conflicting: file column `a` has the id of table column `b`
+        let table_columns = [("a", Some(1)), ("b", Some(2))];
+        let file_columns = [("a", Some(2))];
+        let resolver = field_id_resolver(IdThenName, &table_columns);
+        assert_eq!(
+            resolver.resolve_columns(&file_columns),
+            vec![Some("b".into())],
+        );
+        let resolver = field_id_resolver(NameThenId, &table_columns);
+        assert_eq!(
+            resolver.resolve_columns(&file_columns),
+            vec![Some("a".into())],
+        );
+
+        // conflicting: columns renamed and ids swapped
+        let table_columns = [("a", Some(2)), ("b", Some(3))];
+        let resolver = field_id_resolver(IdThenName, &table_columns);
+        assert_eq!(
+            resolver.resolve_columns(&a_b),
+            vec![Some("__unresolved_0_a".into()), Some("a".into())],
+        );
+        let resolver = field_id_resolver(NameThenId, &table_columns);
+        assert_eq!(
+            resolver.resolve_columns(&a_b),
+            vec![Some("a".into()), Some("b".into())],
+        );
+    }
+
+    #[test]
+    fn test_resolve_positions() {
+        let resolver = field_id_resolver(FieldIdResolution::Position, &[("a", None), ("b", None)]);
+
+        // names and field ids of file columns are ignored
+        assert_eq!(
+            resolver.resolve_columns(&[("b", Some(2)), ("a", Some(1))]),
+            vec![Some("a".into()), Some("b".into())],
+        );
+
+        // extra file columns are never matched, missing ones are read as nulls
+        assert_eq!(
+            resolver.resolve_columns(&[("x", None), ("y", None), ("a", None), ("z", None)]),
+            vec![
+                Some("a".into()),
+                Some("b".into()),
+                Some("__unresolved_2_a".into()),
+                None,
+            ],
+        );
+        assert_eq!(
+            resolver.resolve_columns(&[("x", None)]),
+            vec![Some("a".into())],
+        );
+        assert_eq!(
+            FieldIdResolution::parse("POSITION").unwrap(),
+            Some(FieldIdResolution::Position),
+        );
+    }
+
+    #[test]
+    fn test_position_resolver_renames_footer() {
+        // file schema names are unreliable, like columns written as `_col0`
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("_col0", DataType::Int32, true),
+            Field::new("_col1", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Int32Array::from(vec![3, 4])),
+            ],
+        )
+        .unwrap();
+        let data = write_parquet(&batch);
+        let footer = raw_footer(&data);
+        let read_column = |footer: &Bytes, name: &str| {
+            let metadata = decode_metadata(footer).unwrap();
+            let reader_metadata =
+                ArrowReaderMetadata::try_new(Arc::new(metadata), Default::default()).unwrap();
+            let batch =
+                ParquetRecordBatchReaderBuilder::new_with_metadata(data.clone(), reader_metadata)
+                    .build()
+                    .unwrap()
+                    .next()
+                    .unwrap()
+                    .unwrap();
+            batch.column_by_name(name).cloned()
+        };
+
+        // name-based resolution finds no table columns
+        assert!(read_column(&footer, "id").is_none());
+        assert!(read_column(&footer, "value").is_none());
+
+        // positional resolution
+        let resolver = field_id_resolver(
+            FieldIdResolution::Position,
+            &[("id", None), ("value", None)],
+        );
+        let resolved = resolver.resolve(&footer).unwrap();
+        assert_eq!(schema_leaf_roots(&resolved).unwrap(), vec!["id", "value"]);
+        assert_eq!(
+            read_column(&resolved, "id")
+                .unwrap()
+                .as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![1, 2]),
+        );
+        assert_eq!(
+            read_column(&resolved, "value")
+                .unwrap()
+                .as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![3, 4]),
+        );
+    }
+
+    #[test]
+    fn test_field_id_resolver_renames_footer() {
+        let with_field_id = |name: &str, id: i32| {
+            Field::new(name, DataType::Int32, true).with_metadata(HashMap::from([(
+                "PARQUET:field_id".to_string(),
+                id.to_string(),
+            )]))
+        };
+        let schema = Arc::new(Schema::new(vec![
+            with_field_id("old_a", 1),
+            with_field_id("b", 2),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Int32Array::from(vec![3, 4])),
+            ],
+        )
+        .unwrap();
+        let data = write_parquet(&batch);
+        let footer = raw_footer(&data);
+
+        let resolver = field_id_resolver(
+            FieldIdResolution::IdThenName,
+            &[("a", Some(1)), ("b", None)],
+        );
+        let resolved = resolver.resolve(&footer).unwrap();
+        let metadata = decode_metadata(&resolved).unwrap();
+        let names = metadata
+            .file_metadata()
+            .schema()
+            .get_fields()
+            .iter()
+            .map(|field| field.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(schema_leaf_roots(&resolved).unwrap(), vec!["a", "b"]);
+
+        // renamed columns are still readable
+        let reader_metadata =
+            ArrowReaderMetadata::try_new(Arc::new(metadata), Default::default()).unwrap();
+        let batch = ParquetRecordBatchReaderBuilder::new_with_metadata(data, reader_metadata)
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.schema().field(0).name(), "a");
+        assert_eq!(
+            batch.column(0).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![1, 2]),
+        );
+
+        // footers needing no rename are not rewritten
+        let resolver = field_id_resolver(
+            FieldIdResolution::NameThenId,
+            &[("old_a", None), ("b", None)],
+        );
+        assert_eq!(resolver.resolve(&footer).unwrap(), footer);
+    }
+
+    /// records start offsets of all reads
+    struct RecordingChunkReader {
+        data: Bytes,
+        read_offsets: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl Length for RecordingChunkReader {
+        fn len(&self) -> u64 {
+            self.data.len() as u64
+        }
+    }
+
+    impl ChunkReader for RecordingChunkReader {
+        type T = <Bytes as ChunkReader>::T;
+
+        fn get_read(&self, start: u64) -> ParquetResult<Self::T> {
+            self.read_offsets.lock().push(start);
+            self.data.get_read(start)
+        }
+
+        fn get_bytes(&self, start: u64, length: usize) -> ParquetResult<Bytes> {
+            self.read_offsets.lock().push(start);
+            self.data.get_bytes(start, length)
+        }
+    }
+
+    fn map_batch() -> RecordBatch {
+        // {a: 1, b: 2}, null, {c: 3}
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        builder.keys().append_value("a");
+        builder.values().append_value(1);
+        builder.keys().append_value("b");
+        builder.values().append_value(2);
+        builder.append(true).unwrap();
+        builder.append(false).unwrap();
+        builder.keys().append_value("c");
+        builder.values().append_value(3);
+        builder.append(true).unwrap();
+        let map_array = builder.finish();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("m", map_array.data_type().clone(), true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(map_array),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_map_children_projection() {
+        let data = write_parquet(&map_batch());
+        let footer = raw_footer(&data);
+        let full_metadata = decode_metadata(&footer).unwrap();
+        assert_eq!(schema_leaf_roots(&footer).unwrap(), vec!["id", "m", "m"]);
+
+        // map values are read as lists, without the key column chunks
+        let projection = MapChildrenProjection::new([("M".to_string(), MapChild::Values)]);
+        let projected = projection.project(&footer).unwrap();
+        assert_eq!(schema_leaf_roots(&projected).unwrap(), vec!["id", "m"]);
+        let metadata = decode_metadata(&projected).unwrap();
+        assert_eq!(metadata.num_row_groups(), 2);
+        for (rg, full_rg) in metadata.row_groups().iter().zip(full_metadata.row_groups()) {
+            assert_eq!(rg.num_columns(), 2);
+            assert_eq!(rg.column(1).byte_range(), full_rg.column(2).byte_range());
+        }
+
+        let read_offsets = Arc::new(Mutex::new(vec![]));
+        let reader = RecordingChunkReader {
+            data: data.clone(),
+            read_offsets: read_offsets.clone(),
+        };
+        let reader_metadata =
+            ArrowReaderMetadata::try_new(Arc::new(metadata), Default::default()).unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::new_with_metadata(reader, reader_metadata)
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+        let values = batch.column(1).as_list::<i32>();
+        assert_eq!(
+            values.value(0).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![1, 2]),
+        );
+        assert!(values.is_null(1));
+        assert_eq!(
+            values.value(2).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![3]),
+        );
+
+        // only the value child is fetched
+        let key_ranges = full_metadata
+            .row_groups()
+            .iter()
+            .map(|rg| {
+                let (start, len) = rg.column(1).byte_range();
+                start..start + len
+            })
+            .collect::<Vec<_>>();
+        let read_offsets = read_offsets.lock();
+        assert!(!read_offsets.is_empty());
+        assert!(!read_offsets
+            .iter()
+            .any(|offset| key_ranges.iter().any(|range| range.contains(offset))));
+
+        // map keys
+        let projection = MapChildrenProjection::new([("m".to_string(), MapChild::Keys)]);
+        let projected = projection.project(&footer).unwrap();
+        let metadata = decode_metadata(&projected).unwrap();
+        assert_eq!(
+            metadata.row_group(0).column(1).byte_range(),
+            full_metadata.row_group(0).column(1).byte_range(),
+        );
+        let reader_metadata =
+            ArrowReaderMetadata::try_new(Arc::new(metadata), Default::default()).unwrap();
+        let batch = ParquetRecordBatchReaderBuilder::new_with_metadata(data, reader_metadata)
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let keys = batch.column(1).as_list::<i32>();
+        assert_eq!(
+            keys.value(0).as_string::<i32>(),
+            &StringArray::from(vec!["a", "b"]),
+        );
+
+        // non-map columns are kept as is
+        let projection = MapChildrenProjection::new([("id".to_string(), MapChild::Keys)]);
+        assert_eq!(projection.project(&footer).unwrap(), footer);
+    }
+
+    #[test]
+    fn test_map_children_projection_from_schema() {
+        let map_child_field = |name: &str, child: &str| {
+            Field::new(name, DataType::Int32, true).with_metadata(HashMap::from([(
+                "blaze.map_child".to_string(),
+                child.to_string(),
+            )]))
+        };
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        assert!(MapChildrenProjection::try_from_schema(&schema)
+            .unwrap()
+            .is_none());
+
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            map_child_field("b", "values"),
+            map_child_field("c", "keys"),
+        ]);
+        let projection = MapChildrenProjection::try_from_schema(&schema)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            projection.map_children,
+            HashMap::from([
+                ("b".to_string(), MapChild::Values),
+                ("c".to_string(), MapChild::Keys),
+            ]),
+        );
+
+        let schema = Schema::new(vec![map_child_field("b", "entries")]);
+        assert!(MapChildrenProjection::try_from_schema(&schema).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_columns() {
+        // fixture written by a buggy writer, with columns named `id`, `ID` and
+        // `id` again
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("ID", DataType::Int32, true),
+            Field::new("value", DataType::Int32, true),
+            Field::new("id", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Int32Array::from(vec![3, 4])),
+                Arc::new(Int32Array::from(vec![5, 6])),
+                Arc::new(Int32Array::from(vec![7, 8])),
+            ],
+        )
+        .unwrap();
+        let footer = raw_footer(&write_parquet(&batch));
+        let metadata = decode_metadata(&footer).unwrap();
+        let table_schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("value", DataType::Int32, true),
+        ]);
+
+        // ambiguous columns are reported with their positions
+        let resolver = DuplicateColumnResolver::new(&table_schema, false, false);
+        let err = resolver
+            .check(&metadata, "/tmp/a.parquet")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("ambiguous parquet column id"));
+        assert!(err.contains("[id (#0), ID (#1), id (#3)]"));
+        assert!(err.contains("/tmp/a.parquet"));
+
+        // case-sensitive resolution only conflicts on exact names
+        let resolver = DuplicateColumnResolver::new(&table_schema, true, false);
+        let err = resolver
+            .check(&metadata, "/tmp/a.parquet")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("[id (#0), id (#3)]"));
+
+        // picking the first column renames the others
+        let resolver = DuplicateColumnResolver::new(&table_schema, false, true);
+        let resolved = resolver.resolve(&footer).unwrap();
+        assert_eq!(
+            schema_leaf_roots(&resolved).unwrap(),
+            vec!["id", "__duplicate_1_ID", "value", "__duplicate_3_id"],
+        );
+        let resolved_metadata = decode_metadata(&resolved).unwrap();
+        assert!(resolver.check(&resolved_metadata, "/tmp/a.parquet").is_ok());
+
+        // no duplicates
+        let table_schema = Schema::new(vec![Field::new("value", DataType::Int32, true)]);
+        let resolver = DuplicateColumnResolver::new(&table_schema, false, true);
+        assert!(resolver.check(&metadata, "/tmp/a.parquet").is_ok());
+        assert_eq!(resolver.resolve(&footer).unwrap(), footer);
+    }
+
+    // writes a file with a binary column annotated as BSON
+    fn write_bson_parquet() -> Bytes {
+        let parquet_schema = Arc::new(
+            parse_message_type("message test { optional binary geom (BSON); required int32 id; }")
+                .unwrap(),
+        );
+        let mut buf = vec![];
+        let mut writer =
+            SerializedFileWriter::new(&mut buf, parquet_schema, Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+        col_writer
+            .typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from(vec![1u8, 2, 3])], Some(&[1, 0]), None)
+            .unwrap();
+        col_writer.close().unwrap();
+        let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+        col_writer
+            .typed::<ParquetInt32Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        col_writer.close().unwrap();
+        row_group_writer.close().unwrap();
+        writer.close().unwrap();
+        Bytes::from(buf)
+    }
+
+    // replaces the logical type of a schema element with the given (empty)
+    // LogicalType union field, like GEOMETRY written by newer writers
+    fn with_logical_type(metadata: &[u8], elem_idx: usize, logical_type: i16) -> Bytes {
+        let mut reader = CompactReader::new(metadata);
+        let mut out = vec![];
+        rewrite_struct(
+            &mut reader,
+            &mut out,
+            |id| match id {
+                FILE_METADATA_SCHEMA => FieldAction::Rewrite,
+                _ => FieldAction::Copy,
+            },
+            |_, reader, out| {
+                rewrite_list(reader, out, |idx, reader, out| {
+                    rewrite_struct(
+                        reader,
+                        out,
+                        |id| match id {
+                            SCHEMA_ELEMENT_LOGICAL_TYPE if idx == elem_idx => FieldAction::Rewrite,
+                            _ => FieldAction::Copy,
+                        },
+                        |_, reader, out| {
+                            reader.skip(CT_STRUCT)?;
+                            write_field_header(out, 0, logical_type, CT_STRUCT);
+                            out.push(CT_STOP);
+                            out.push(CT_STOP);
+                            Ok(())
+                        },
+                    )
+                })
+            },
+        )
+        .unwrap();
+        Bytes::from(out)
+    }
+
+    #[test]
+    fn test_drop_unknown_logical_types() {
+        let footer = raw_footer(&write_bson_parquet());
+        assert_eq!(drop_unknown_logical_types(&footer, false).unwrap(), footer);
+
+        for logical_type in [LOGICAL_TYPE_GEOMETRY, LOGICAL_TYPE_GEOGRAPHY] {
+            let geometry_footer = with_logical_type(&footer, 1, logical_type);
+
+            // fails with the column unless read as binary
+            let err = drop_unknown_logical_types(&geometry_footer, false)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("parquet column geom has unsupported logical type"));
+            assert!(err.contains("spark.blaze.parquet.geometryAsBinary"));
+
+            let dropped = drop_unknown_logical_types(&geometry_footer, true).unwrap();
+            let metadata = decode_metadata(&dropped).unwrap();
+            let schema_descr = metadata.file_metadata().schema_descr();
+            assert!(schema_descr.column(0).logical_type().is_none());
+            let arrow_schema = parquet_to_arrow_schema(schema_descr, None).unwrap();
+            assert_eq!(arrow_schema.field(0).data_type(), &DataType::Binary);
+            assert_eq!(arrow_schema.field(1).data_type(), &DataType::Int32);
+        }
+
+        // other unknown logical types, like VARIANT
+        let variant_footer = with_logical_type(&footer, 1, 16);
+        let err = drop_unknown_logical_types(&variant_footer, true)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("parquet column geom has unsupported logical type (LogicalType field 16)")
+        );
+    }
+
+    /// FileCryptoMetaData of the given algorithm (union field id), with the
+    /// given AesGcmV1/AesGcmCtrV1 fields and key metadata
+    fn file_crypto_metadata(algorithm_id: u8, algorithm_fields: &[u8]) -> Vec<u8> {
+        let mut crypto_metadata = vec![0x1c, (algorithm_id << 4) | CT_STRUCT];
+        crypto_metadata.extend(algorithm_fields);
+        crypto_metadata.extend([CT_STOP, CT_STOP]);
+        crypto_metadata.extend([0x18, 3]); // key_metadata
+        crypto_metadata.extend(b"key");
+        crypto_metadata.push(CT_STOP);
+        crypto_metadata
+    }
+
+    #[test]
+    fn test_read_file_encryption() {
+        let aad_file_unique = [0x28, 4, 1, 2, 3, 4];
+        let crypto_metadata = file_crypto_metadata(1, &aad_file_unique);
+        assert_eq!(
+            read_file_encryption(&crypto_metadata).unwrap(),
+            FileEncryption {
+                algorithm: "AES_GCM_V1",
+                aad_prefix: false,
+            }
+        );
+
+        // aad prefix stored in the file
+        let mut fields = vec![0x18, 3];
+        fields.extend(b"tbl");
+        fields.extend([0x18, 4, 1, 2, 3, 4]);
+        let crypto_metadata = file_crypto_metadata(2, &fields);
+        assert_eq!(
+            read_file_encryption(&crypto_metadata).unwrap(),
+            FileEncryption {
+                algorithm: "AES_GCM_CTR_V1",
+                aad_prefix: true,
+            }
+        );
+
+        // aad prefix to be supplied by readers
+        let mut fields = aad_file_unique.to_vec();
+        fields.push(0x11); // supply_aad_prefix = true
+        let crypto_metadata = file_crypto_metadata(1, &fields);
+        assert!(read_file_encryption(&crypto_metadata).unwrap().aad_prefix);
+        let mut fields = aad_file_unique.to_vec();
+        fields.push(0x12); // supply_aad_prefix = false
+        let crypto_metadata = file_crypto_metadata(1, &fields);
+        assert!(!read_file_encryption(&crypto_metadata).unwrap().aad_prefix);
+
+        assert!(read_file_encryption(&[CT_STOP]).is_err());
+    }
+}
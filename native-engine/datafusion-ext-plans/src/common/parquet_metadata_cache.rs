@@ -0,0 +1,314 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{future::Future, sync::Arc};
+
+use blaze_jni_bridge::{conf, conf::IntConf, is_jni_bridge_inited};
+use datafusion::{
+    common::Result,
+    parquet::{errors::Result as ParquetResult, file::metadata::ParquetMetaData},
+};
+use datafusion_ext_commons::df_execution_err;
+use object_store::ObjectMeta;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use tokio::sync::Semaphore;
+
+/// A cache slot holding the metadata of one parquet file. concurrent readers
+/// of the same file share one slot, so the footer is fetched only once.
+pub type ParquetMetadataSlot = Arc<tokio::sync::OnceCell<Arc<ParquetMetaData>>>;
+
+/// Cache of parquet footers shared by all parquet scans in the process.
+pub trait ParquetMetadataCache: Send + Sync {
+    /// Returns the slot caching metadata of the given file. an uninitialized
+    /// slot is filled by the caller after fetching the footer.
+    fn slot(&self, meta: &ObjectMeta) -> ParquetMetadataSlot;
+}
+
+/// Default cache keeping metadata of the most recently used files in memory.
+pub struct InMemoryParquetMetadataCache {
+    capacity: usize,
+    slots: Mutex<Vec<(ObjectMeta, ParquetMetadataSlot)>>,
+}
+
+impl InMemoryParquetMetadataCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            slots: Mutex::default(),
+        }
+    }
+}
+
+impl ParquetMetadataCache for InMemoryParquetMetadataCache {
+    fn slot(&self, meta: &ObjectMeta) -> ParquetMetadataSlot {
+        let mut slots = self.slots.lock();
+
+        // find existed cache slot
+        for (cache_meta, cache_slot) in slots.iter() {
+            if cache_meta.location == meta.location {
+                return cache_slot.clone();
+            }
+        }
+
+        // reserve a new cache slot
+        if slots.len() >= self.capacity {
+            slots.remove(0); // remove eldest
+        }
+        let cache_slot = ParquetMetadataSlot::default();
+        slots.push((meta.clone(), cache_slot.clone()));
+        cache_slot
+    }
+}
+
+static METADATA_CACHE: OnceCell<Arc<dyn ParquetMetadataCache>> = OnceCell::new();
+
+/// Registers the process-wide metadata cache, must be called before any
+/// parquet file is read.
+pub fn register_parquet_metadata_cache(cache: Arc<dyn ParquetMetadataCache>) -> Result<()> {
+    if METADATA_CACHE.set(cache).is_err() {
+        return df_execution_err!("parquet metadata cache is already initialized");
+    }
+    Ok(())
+}
+
+/// Returns the registered metadata cache, or the default in-memory cache if
+/// none was registered.
+pub fn parquet_metadata_cache() -> &'static Arc<dyn ParquetMetadataCache> {
+    const METADATA_CACHE_SIZE: usize = 5; // TODO: make it configurable
+    METADATA_CACHE.get_or_init(|| Arc::new(InMemoryParquetMetadataCache::new(METADATA_CACHE_SIZE)))
+}
+
+/// Returns the max number of concurrent footer fetches in the process.
+pub fn metadata_fetch_concurrency() -> usize {
+    const DEFAULT_METADATA_FETCH_CONCURRENCY: usize = 16;
+    static CONCURRENCY: OnceCell<usize> = OnceCell::new();
+    *CONCURRENCY.get_or_init(|| {
+        if !is_jni_bridge_inited() {
+            return DEFAULT_METADATA_FETCH_CONCURRENCY; // for testing
+        }
+        let concurrency = conf::PARQUET_METADATA_FETCH_CONCURRENCY
+            .value()
+            .expect("error getting conf PARQUET_METADATA_FETCH_CONCURRENCY");
+        concurrency.max(1) as usize
+    })
+}
+
+/// Returns the semaphore limiting concurrent footer fetches in the process.
+/// it is independent of data reads, so a burst of footer reads on many small
+/// files cannot overwhelm the namenode.
+pub fn metadata_fetch_semaphore() -> &'static Semaphore {
+    static SEMAPHORE: OnceCell<Semaphore> = OnceCell::new();
+    SEMAPHORE.get_or_init(|| Semaphore::new(metadata_fetch_concurrency()))
+}
+
+/// Runs the fetch future while holding a permit of the given semaphore.
+pub async fn with_fetch_permit<T>(semaphore: &Semaphore, fetch: impl Future<Output = T>) -> T {
+    let _permit = semaphore
+        .acquire()
+        .await
+        .expect("metadata fetch semaphore closed");
+    fetch.await
+}
+
+/// Returns the cached metadata of the given file, fetching and caching it if
+/// missing. fetches are limited by [`metadata_fetch_semaphore`].
+pub async fn get_or_fetch_metadata<F, Fut>(
+    cache: &dyn ParquetMetadataCache,
+    meta: &ObjectMeta,
+    fetch: F,
+) -> ParquetResult<Arc<ParquetMetaData>>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = ParquetResult<ParquetMetaData>>,
+{
+    let cache_slot = cache.slot(meta);
+    cache_slot
+        .get_or_try_init(move || async move {
+            with_fetch_permit(metadata_fetch_semaphore(), fetch())
+                .await
+                .map(Arc::new)
+        })
+        .await
+        .cloned()
+}
+
+/// Fetches metadata of the given files concurrently into the cache, files
+/// already cached are not fetched again. fetches are limited by
+/// [`metadata_fetch_semaphore`].
+pub async fn warm_metadata<'a, F, Fut>(
+    cache: &dyn ParquetMetadataCache,
+    files: impl IntoIterator<Item = (&'a ObjectMeta, F)>,
+) -> ParquetResult<()>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = ParquetResult<ParquetMetaData>>,
+{
+    let fetches = files
+        .into_iter()
+        .map(|(meta, fetch)| get_or_fetch_metadata(cache, meta, fetch));
+    futures::future::try_join_all(fetches).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    };
+
+    use arrow::{
+        array::{ArrayRef, Int32Array},
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use bytes::Bytes;
+    use datafusion::parquet::{
+        arrow::ArrowWriter,
+        file::{footer::parse_metadata, metadata::ParquetMetaData},
+    };
+    use object_store::{path::Path, ObjectMeta};
+    use tokio::sync::Semaphore;
+
+    use crate::common::parquet_metadata_cache::{
+        get_or_fetch_metadata, warm_metadata, with_fetch_permit, InMemoryParquetMetadataCache,
+        ParquetMetadataCache, ParquetMetadataSlot,
+    };
+
+    /// test double counting slot lookups
+    struct CountingMetadataCache {
+        inner: InMemoryParquetMetadataCache,
+        num_lookups: AtomicUsize,
+    }
+
+    impl ParquetMetadataCache for CountingMetadataCache {
+        fn slot(&self, meta: &ObjectMeta) -> ParquetMetadataSlot {
+            self.num_lookups.fetch_add(1, SeqCst);
+            self.inner.slot(meta)
+        }
+    }
+
+    fn object_meta(location: &str) -> ObjectMeta {
+        ObjectMeta {
+            location: Path::from(location),
+            last_modified: Default::default(),
+            size: 0,
+            e_tag: None,
+            version: None,
+        }
+    }
+
+    fn test_metadata() -> ParquetMetaData {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        parse_metadata(&Bytes::from(buf)).unwrap()
+    }
+
+    #[test]
+    fn test_in_memory_cache() {
+        let cache = InMemoryParquetMetadataCache::new(2);
+        let slot_a = cache.slot(&object_meta("a"));
+        slot_a.set(Arc::new(test_metadata())).unwrap();
+
+        // slots are shared, not copied
+        assert!(cache.slot(&object_meta("a")).initialized());
+
+        // eldest slot is evicted
+        let _ = cache.slot(&object_meta("b"));
+        let _ = cache.slot(&object_meta("c"));
+        assert!(!cache.slot(&object_meta("a")).initialized());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_metadata() {
+        let cache = CountingMetadataCache {
+            inner: InMemoryParquetMetadataCache::new(5),
+            num_lookups: AtomicUsize::new(0),
+        };
+        let num_fetches = AtomicUsize::new(0);
+        let meta = object_meta("a");
+
+        for _ in 0..3 {
+            let metadata = get_or_fetch_metadata(&cache, &meta, || async {
+                num_fetches.fetch_add(1, SeqCst);
+                Ok(test_metadata())
+            })
+            .await
+            .unwrap();
+            assert_eq!(metadata.file_metadata().num_rows(), 3);
+        }
+        assert_eq!(cache.num_lookups.load(SeqCst), 3);
+        assert_eq!(num_fetches.load(SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_warm_metadata() {
+        let cache = InMemoryParquetMetadataCache::new(5);
+        let num_fetches = &AtomicUsize::new(0);
+        let metas = (0..3)
+            .map(|i| object_meta(&format!("file-{i}")))
+            .collect::<Vec<_>>();
+
+        let fetch = || async {
+            num_fetches.fetch_add(1, SeqCst);
+            Ok(test_metadata())
+        };
+        warm_metadata(&cache, metas.iter().map(|meta| (meta, fetch)))
+            .await
+            .unwrap();
+        assert_eq!(num_fetches.load(SeqCst), 3);
+
+        // subsequent reads are cache hits
+        for meta in &metas {
+            let metadata = get_or_fetch_metadata(&cache, meta, fetch).await.unwrap();
+            assert_eq!(metadata.file_metadata().num_rows(), 3);
+        }
+        assert_eq!(num_fetches.load(SeqCst), 3);
+
+        // warming again does not refetch
+        warm_metadata(&cache, metas.iter().map(|meta| (meta, fetch)))
+            .await
+            .unwrap();
+        assert_eq!(num_fetches.load(SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_concurrency() {
+        let semaphore = Semaphore::new(2);
+        let num_running = &AtomicUsize::new(0);
+        let max_running = &AtomicUsize::new(0);
+
+        // burst of fetches on distinct files
+        let fetches = (0..10).map(|i| {
+            with_fetch_permit(&semaphore, async move {
+                let running = num_running.fetch_add(1, SeqCst) + 1;
+                max_running.fetch_max(running, SeqCst);
+                for _ in 0..3 {
+                    tokio::task::yield_now().await;
+                }
+                num_running.fetch_sub(1, SeqCst);
+                object_meta(&format!("file-{i}"))
+            })
+        });
+        let metas = futures::future::join_all(fetches).await;
+        assert_eq!(metas.len(), 10);
+        assert_eq!(max_running.load(SeqCst), 2);
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering::SeqCst},
+    Arc,
+};
+
+use blaze_jni_bridge::{conf, conf::LongConf, is_jni_bridge_inited};
+use once_cell::sync::OnceCell;
+use tokio::sync::Notify;
+
+/// Budget of bytes being read ahead of consumption, shared by all scans in
+/// the process. readers wait for buffered bytes to be released once the
+/// budget is exhausted, instead of buffering unbounded memory.
+pub struct PrefetchBudget {
+    max_bytes: usize,
+    used_bytes: AtomicUsize,
+    released: Notify,
+}
+
+impl PrefetchBudget {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            used_bytes: AtomicUsize::new(0),
+            released: Notify::new(),
+        }
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes.load(SeqCst)
+    }
+
+    /// waits until the given bytes fit in the budget. a request larger than
+    /// the whole budget is granted once nothing else is buffered, so it
+    /// cannot wait forever.
+    pub async fn acquire(self: &Arc<Self>, num_bytes: usize) -> PrefetchPermit {
+        loop {
+            // register before checking, so releases in between are not missed
+            let released = self.released.notified();
+            if self.try_acquire(num_bytes) {
+                return PrefetchPermit {
+                    budget: self.clone(),
+                    num_bytes,
+                };
+            }
+            released.await;
+        }
+    }
+
+    fn try_acquire(&self, num_bytes: usize) -> bool {
+        self.used_bytes
+            .fetch_update(SeqCst, SeqCst, |used| {
+                let fits = used == 0 || used + num_bytes <= self.max_bytes;
+                fits.then_some(used + num_bytes)
+            })
+            .is_ok()
+    }
+
+    fn release(&self, num_bytes: usize) {
+        self.used_bytes.fetch_sub(num_bytes, SeqCst);
+        self.released.notify_waiters();
+    }
+}
+
+/// Bytes granted by [`PrefetchBudget`], released on drop.
+pub struct PrefetchPermit {
+    budget: Arc<PrefetchBudget>,
+    num_bytes: usize,
+}
+
+impl Drop for PrefetchPermit {
+    fn drop(&mut self) {
+        self.budget.release(self.num_bytes);
+    }
+}
+
+/// Returns the process-wide prefetch budget, or None if unlimited. see
+/// spark.blaze.parquet.prefetchMaxBytes
+pub fn prefetch_budget() -> Option<&'static Arc<PrefetchBudget>> {
+    static PREFETCH_BUDGET: OnceCell<Option<Arc<PrefetchBudget>>> = OnceCell::new();
+    PREFETCH_BUDGET
+        .get_or_init(|| {
+            if !is_jni_bridge_inited() {
+                return None; // for testing
+            }
+            let max_bytes = conf::PARQUET_PREFETCH_MAX_BYTES
+                .value()
+                .expect("error getting conf PARQUET_PREFETCH_MAX_BYTES");
+            (max_bytes > 0).then(|| Arc::new(PrefetchBudget::new(max_bytes as usize)))
+        })
+        .as_ref()
+}
+
+/// Acquires the given bytes from the process-wide prefetch budget if enabled.
+pub async fn acquire_prefetch_budget(num_bytes: usize) -> Option<PrefetchPermit> {
+    match prefetch_budget() {
+        Some(budget) => Some(budget.acquire(num_bytes).await),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    };
+
+    use futures::FutureExt;
+
+    use crate::common::prefetch_budget::PrefetchBudget;
+
+    #[tokio::test]
+    async fn test_prefetch_budget() {
+        let budget = Arc::new(PrefetchBudget::new(100));
+        let permit1 = budget.acquire(60).await;
+        let permit2 = budget.acquire(40).await;
+        assert_eq!(budget.used_bytes(), 100);
+
+        // stalls once exhausted
+        let mut pending = Box::pin(budget.acquire(50));
+        assert!((&mut pending).now_or_never().is_none());
+
+        // still stalls if released bytes are not enough
+        drop(permit2);
+        assert!((&mut pending).now_or_never().is_none());
+
+        // resumes as buffers drain
+        drop(permit1);
+        let permit3 = pending.await;
+        assert_eq!(budget.used_bytes(), 50);
+        drop(permit3);
+        assert_eq!(budget.used_bytes(), 0);
+
+        // oversized requests are granted if nothing is buffered
+        let permit = budget.acquire(1000).await;
+        assert_eq!(budget.used_bytes(), 1000);
+        assert!(budget.acquire(1).now_or_never().is_none());
+        drop(permit);
+        assert!(budget.acquire(1).now_or_never().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_budget_concurrent() {
+        let budget = Arc::new(PrefetchBudget::new(10));
+        let max_used = Arc::new(AtomicUsize::new(0));
+
+        let tasks = (0..20)
+            .map(|_| {
+                let budget = budget.clone();
+                let max_used = max_used.clone();
+                tokio::spawn(async move {
+                    let _permit = budget.acquire(4).await;
+                    max_used.fetch_max(budget.used_bytes(), SeqCst);
+                    tokio::task::yield_now().await;
+                })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert!(max_used.load(SeqCst) <= 8);
+        assert_eq!(budget.used_bytes(), 0);
+    }
+}
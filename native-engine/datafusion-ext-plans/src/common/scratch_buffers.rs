@@ -0,0 +1,178 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::{
+    array::{Array, BooleanArray, BooleanBufferBuilder},
+    buffer::MutableBuffer,
+    util::bit_util,
+};
+
+// recycled buffers kept at most, a scan usually needs one mask at a time
+const MAX_FREE_BUFFERS: usize = 4;
+
+/// Pool of bitmap buffers of scratch selection masks, which are built for a
+/// batch and dropped once the batch is filtered (like masks of deleted rows).
+///
+/// arrow buffers are immutable once shared, but a mask not referenced by any
+/// output batch is uniquely owned after filtering, so its buffer can be
+/// reclaimed and reused by the mask of the next batch instead of allocating
+/// a new one for each batch.
+#[derive(Debug, Default)]
+pub struct ScratchBitmaps {
+    free: Vec<MutableBuffer>,
+    num_allocated: usize,
+    num_reused: usize,
+}
+
+impl ScratchBitmaps {
+    /// Returns an empty builder of a mask of `len` bits, backed by a recycled
+    /// buffer if there is one large enough.
+    pub fn builder(&mut self, len: usize) -> BooleanBufferBuilder {
+        let num_bytes = bit_util::ceil(len, 8);
+        match self.free.iter().position(|buf| buf.capacity() >= num_bytes) {
+            Some(i) => {
+                let mut buffer = self.free.swap_remove(i);
+                buffer.clear();
+                self.num_reused += 1;
+                BooleanBufferBuilder::new_from_buffer(buffer, 0)
+            }
+            None => {
+                self.num_allocated += 1;
+                BooleanBufferBuilder::new(len)
+            }
+        }
+    }
+
+    /// Reclaims the buffer of a mask no longer used. the buffer is dropped
+    /// if it is still referenced elsewhere.
+    pub fn recycle(&mut self, mask: BooleanArray) {
+        if self.free.len() >= MAX_FREE_BUFFERS || mask.offset() != 0 {
+            return;
+        }
+        let (values, _) = mask.into_parts();
+        if let Ok(buffer) = values.into_inner().into_mutable() {
+            self.free.push(buffer);
+        }
+    }
+
+    /// number of masks built with newly allocated buffers
+    pub fn num_allocated(&self) -> usize {
+        self.num_allocated
+    }
+
+    /// number of masks built with recycled buffers
+    pub fn num_reused(&self) -> usize {
+        self.num_reused
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Instant};
+
+    use arrow::{
+        array::{ArrayRef, BooleanArray, Int64Array},
+        compute::filter_record_batch,
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+
+    use crate::common::{deletion_vector::DeletionVector, scratch_buffers::ScratchBitmaps};
+
+    fn build_mask(scratch: &mut ScratchBitmaps, values: &[bool]) -> BooleanArray {
+        let mut builder = scratch.builder(values.len());
+        builder.append_slice(values);
+        BooleanArray::new(builder.finish(), None)
+    }
+
+    #[test]
+    fn test_recycle_masks() {
+        let mut scratch = ScratchBitmaps::default();
+        let mask = build_mask(&mut scratch, &[true, false, true]);
+        assert_eq!(mask, BooleanArray::from(vec![true, false, true]));
+        scratch.recycle(mask);
+
+        // smaller and equal masks reuse the buffer, stale bits are cleared
+        let mask = build_mask(&mut scratch, &[false, true]);
+        assert_eq!(mask, BooleanArray::from(vec![false, true]));
+        scratch.recycle(mask);
+        assert_eq!((scratch.num_allocated(), scratch.num_reused()), (1, 1));
+
+        // masks still referenced are not recycled
+        let mask = build_mask(&mut scratch, &[true; 8]);
+        let shared = mask.clone();
+        scratch.recycle(mask);
+        let mask = build_mask(&mut scratch, &[true; 8]);
+        assert_eq!((scratch.num_allocated(), scratch.num_reused()), (2, 2));
+        assert_eq!(shared, mask);
+
+        // sliced masks are not recycled
+        scratch.recycle(mask.slice(1, 7));
+        build_mask(&mut scratch, &[true; 8]);
+        assert_eq!((scratch.num_allocated(), scratch.num_reused()), (3, 2));
+    }
+
+    // benchmark of filtering batches by deletion vector masks with and without
+    // recycled buffers, run with:
+    // cargo test --release -p datafusion-ext-plans bench_scratch_bitmaps --
+    // --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_scratch_bitmaps() {
+        let batch_size = 8192;
+        let num_batches = 10000;
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let array: ArrayRef = Arc::new(Int64Array::from_iter_values(0..batch_size as i64));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+
+        // every 16th row is deleted
+        let mut dv = vec![];
+        dv.extend(12346u32.to_le_bytes());
+        dv.extend(1u32.to_le_bytes());
+        dv.extend([0u16, 4095].iter().flat_map(|v| v.to_le_bytes()));
+        dv.extend(0u32.to_le_bytes());
+        dv.extend((0..4096u16).flat_map(|i| (i * 16).to_le_bytes()));
+        let dv = DeletionVector::from_portable_roaring(&dv).unwrap();
+
+        let run = |mut scratch: Option<ScratchBitmaps>| {
+            let start = Instant::now();
+            let mut num_rows = 0;
+            for i in 0..num_batches {
+                let position = (i * batch_size % 65536) as u64;
+                let selection = match &mut scratch {
+                    Some(scratch) => {
+                        let mut builder = scratch.builder(batch_size);
+                        dv.append_selection(position, batch_size, &mut builder);
+                        BooleanArray::new(builder.finish(), None)
+                    }
+                    None => dv.selection(position, batch_size),
+                };
+                num_rows += filter_record_batch(&batch, &selection).unwrap().num_rows();
+                if let Some(scratch) = &mut scratch {
+                    scratch.recycle(selection);
+                }
+            }
+            let num_allocated = scratch.map(|s| s.num_allocated()).unwrap_or(num_batches);
+            (num_rows, num_allocated, start.elapsed())
+        };
+        let (num_rows, allocated, elapsed) = run(None);
+        let (reused_num_rows, reused_allocated, reused_elapsed) =
+            run(Some(ScratchBitmaps::default()));
+        assert_eq!(num_rows, reused_num_rows);
+        println!(
+            "{num_batches} batches: {allocated} masks allocated in {elapsed:?} without reuse, \
+            {reused_allocated} masks allocated in {reused_elapsed:?} with reuse"
+        );
+    }
+}
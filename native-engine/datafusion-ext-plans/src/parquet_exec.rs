@@ -17,36 +17,81 @@
 
 //! Execution plan for reading Parquet files
 
-use std::{any::Any, fmt, fmt::Formatter, ops::Range, sync::Arc};
+use std::{
+    any::Any,
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    fmt,
+    fmt::Formatter,
+    future::Future,
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use arrow::{
-    array::{Array, ArrayRef, AsArray, ListArray},
-    datatypes::{DataType, SchemaRef},
+    array::{
+        make_array, new_null_array, Array, ArrayRef, AsArray, BooleanArray, Int32Array, Int64Array,
+        ListArray, Scalar, StringArray, StructArray,
+    },
+    buffer::OffsetBuffer,
+    compute::{concat_batches, filter_record_batch, kernels::cmp::eq, nullif},
+    datatypes::{DataType, Field, FieldRef, Fields, Schema, SchemaRef, TimeUnit},
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+    row::{OwnedRow, RowConverter, SortField},
 };
 use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
 use blaze_jni_bridge::{
-    conf, conf::BooleanConf, jni_call_static, jni_new_global_ref, jni_new_string,
+    conf,
+    conf::{BooleanConf, IntConf, LongConf, StringConf},
+    is_jni_bridge_inited, jni_call, jni_call_static, jni_convert_byte_array, jni_new_global_ref,
+    jni_new_string,
 };
 use bytes::Bytes;
 use datafusion::{
-    common::DataFusionError,
-    datasource::physical_plan::{
-        parquet::{page_filter::PagePruningPredicate, ParquetOpener},
-        FileMeta, FileScanConfig, FileStream, OnError, ParquetFileMetrics,
-        ParquetFileReaderFactory,
+    common::{DataFusionError, ScalarValue},
+    datasource::{
+        listing::{FileRange, PartitionedFile},
+        physical_plan::{
+            parquet::{page_filter::PagePruningPredicate, ParquetOpener},
+            wrap_partition_type_in_dict, FileMeta, FileOpenFuture, FileOpener, FileScanConfig,
+            FileStream, OnError, ParquetFileMetrics, ParquetFileReaderFactory,
+        },
     },
     error::Result,
-    execution::context::TaskContext,
+    execution::{
+        context::TaskContext,
+        memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation},
+    },
+    logical_expr::Operator,
     parquet::{
-        arrow::async_reader::{fetch_parquet_metadata, AsyncFileReader},
+        arrow::{async_reader::AsyncFileReader, parquet_to_arrow_schema},
+        basic::{ConvertedType, LogicalType, Type as PhysicalType},
         errors::ParquetError,
-        file::metadata::ParquetMetaData,
+        file::{
+            footer::{decode_footer, decode_metadata},
+            metadata::{FileMetaData, ParquetMetaData, RowGroupMetaData},
+            statistics::Statistics as ParquetStatistics,
+            FOOTER_SIZE,
+        },
+        format::KeyValue,
+        schema::types::ColumnPath,
+    },
+    physical_expr::{
+        expressions::{BinaryExpr, Column, Literal},
+        utils::{collect_columns, reassign_predicate_columns},
+        ScalarFunctionExpr,
     },
     physical_optimizer::pruning::PruningPredicate,
     physical_plan::{
         expressions::PhysicalSortExpr,
         metrics::{
-            BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricValue, MetricsSet, Time,
+            BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricValue,
+            MetricsSet, Time,
         },
         stream::RecordBatchStreamAdapter,
         DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, PhysicalExpr,
@@ -54,16 +99,53 @@ use datafusion::{
     },
 };
 use datafusion_ext_commons::{
-    batch_size, df_execution_err,
+    array_size::ArraySize,
+    batch_size,
+    cast::cast,
+    df_execution_err,
     hadoop_fs::{FsDataInputStream, FsProvider},
 };
+use datafusion_ext_exprs::{
+    cast::{is_injective_monotonic_cast, TryCastExpr},
+    string_starts_with::StringStartsWithExpr,
+};
 use fmt::Debug;
-use futures::{future::BoxFuture, stream::once, FutureExt, StreamExt, TryStreamExt};
-use object_store::ObjectMeta;
+use futures::{
+    future::BoxFuture,
+    stream::{once, BoxStream},
+    FutureExt, Stream, StreamExt, TryStreamExt,
+};
+use jni::objects::JObject;
+use object_store::{
+    path::{Path, PathPart},
+    ObjectMeta, ObjectStore,
+};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
+use tokio::{
+    sync::{oneshot, Semaphore},
+    task::JoinHandle,
+};
+use tracing::{debug_span, Instrument};
+use url::Url;
 
-use crate::common::output::TaskOutputter;
+use crate::common::{
+    cpu_affinity::{pin_polling_threads, CpuSet},
+    deletion_vector::DeletionVector,
+    dictionary_filter::{remove_row_groups, DictionaryFilter},
+    io_latency::{IoLatencyMetrics, IoLatencySampler},
+    output::TaskOutputter,
+    parquet_footer::{
+        drop_unknown_logical_types, footer_bytes_cache, get_or_fetch_footer_bytes,
+        read_file_encryption, read_page_header, DuplicateColumnResolver, FieldIdResolution,
+        FieldIdResolver, FooterProjection, MapChildrenProjection,
+    },
+    parquet_metadata_cache::{
+        get_or_fetch_metadata, metadata_fetch_concurrency, parquet_metadata_cache, warm_metadata,
+    },
+    prefetch_budget::acquire_prefetch_budget,
+    scratch_buffers::ScratchBitmaps,
+};
 
 #[no_mangle]
 fn schema_adapter_cast_column(
@@ -106,10 +188,21 @@ fn schema_adapter_cast_column(
             ),
         },
         DataType::List(to_field) => match col.data_type() {
-            DataType::List(_from_field) => {
+            DataType::List(from_field) => {
                 let col = col.as_list::<i32>();
                 let from_inner = col.values();
-                let to_inner = schema_adapter_cast_column(from_inner, to_field.data_type())?;
+                let to_inner = match legacy_list_struct_element(from_field, to_field) {
+                    Some(struct_fields) => {
+                        let child =
+                            schema_adapter_cast_column(from_inner, struct_fields[0].data_type())?;
+                        Arc::new(StructArray::try_new(
+                            struct_fields.clone(),
+                            vec![child],
+                            None,
+                        )?)
+                    }
+                    None => schema_adapter_cast_column(from_inner, to_field.data_type())?,
+                };
                 Ok(Arc::new(ListArray::try_new(
                     to_field.clone(),
                     col.offsets().clone(),
@@ -123,8 +216,182 @@ fn schema_adapter_cast_column(
                 data_type,
             ),
         },
-        _ => datafusion_ext_commons::cast::cast_scan_input_array(col.as_ref(), data_type),
+        DataType::Utf8 if col.data_type() == &DataType::Binary => {
+            // binary columns annotated with ENUM are read as strings like spark
+            Ok(arrow::compute::cast(col, data_type)?)
+        }
+        _ => datafusion_ext_commons::cast::cast_scan_input_array(
+            col.as_ref(),
+            data_type,
+            SCAN_CASE_SENSITIVE.with(Cell::get),
+        ),
+    }
+}
+
+thread_local! {
+    // case sensitivity of the scan being polled by the current thread, for
+    // schema_adapter_cast_column, which is called by datafusion without any
+    // context of the scan
+    static SCAN_CASE_SENSITIVE: Cell<bool> = Cell::new(false);
+}
+
+/// Sets the case sensitivity of matching nested fields in
+/// [`schema_adapter_cast_column`] while polling the scan stream.
+fn with_scan_case_sensitive(
+    mut stream: BoxStream<'static, Result<RecordBatch>>,
+    case_sensitive: bool,
+) -> BoxStream<'static, Result<RecordBatch>> {
+    futures::stream::poll_fn(move |cx| {
+        let previous = SCAN_CASE_SENSITIVE.with(|cell| cell.replace(case_sensitive));
+        let polled = stream.poll_next_unpin(cx);
+        SCAN_CASE_SENSITIVE.with(|cell| cell.set(previous));
+        polled
+    })
+    .boxed()
+}
+
+/// Detects a legacy 2-level list whose repeated group has a single field,
+/// like `repeated group element { required int32 x; }`.
+///
+/// arrow-rs already follows parquet-mr's backward-compatibility rules,
+/// taking a repeated primitive, a repeated group with multiple fields, or a
+/// repeated group named `array` or `<list>_tuple` as the list element, and
+/// otherwise (like `repeated group bag { optional int32 array_element; }`)
+/// taking the only field of the repeated group as the element. spark also
+/// takes the repeated group as the element if the table expects a struct
+/// with only that field, in which case the decoded elements are wrapped into
+/// structs. returns fields of the expected struct in this case.
+fn legacy_list_struct_element<'a>(from_field: &Field, to_field: &'a Field) -> Option<&'a Fields> {
+    match to_field.data_type() {
+        DataType::Struct(to_fields) if to_fields.len() == 1 => {
+            let already_struct = matches!(
+                from_field.data_type(),
+                DataType::Struct(from_fields)
+                    if from_fields.len() == 1 && from_fields[0].name() == to_fields[0].name()
+            );
+            (!already_struct && from_field.name() == to_fields[0].name()).then_some(to_fields)
+        }
+        _ => None,
+    }
+}
+
+/// Converts the parquet schema of a file to arrow schema. unlike arrow-rs,
+/// ENUM-annotated binary columns are converted to strings like spark.
+fn parquet_file_arrow_schema(file_metadata: &FileMetaData) -> Result<Schema> {
+    parquet_file_arrow_schema_impl(file_metadata, geometry_as_binary())
+}
+
+fn parquet_file_arrow_schema_impl(
+    file_metadata: &FileMetaData,
+    geometry_as_binary: bool,
+) -> Result<Schema> {
+    let schema = parquet_to_arrow_schema(
+        file_metadata.schema_descr(),
+        file_metadata.key_value_metadata(),
+    )?;
+    let geometry_columns = if geometry_as_binary {
+        wkb_geometry_columns(&schema, file_metadata.key_value_metadata())
+    } else {
+        HashSet::new()
+    };
+    let fields = schema
+        .fields()
+        .iter()
+        .zip(file_metadata.schema_descr().root_schema().get_fields())
+        .map(|(field, parquet_field)| {
+            let is_enum = parquet_field.is_primitive()
+                && (parquet_field.get_basic_info().logical_type() == Some(LogicalType::Enum)
+                    || parquet_field.get_basic_info().converted_type() == ConvertedType::ENUM);
+            let is_wkb = parquet_field.is_primitive()
+                && parquet_field.get_physical_type() == PhysicalType::BYTE_ARRAY
+                && geometry_columns.contains(field.name());
+            if is_enum && field.data_type() == &DataType::Binary {
+                Arc::new(field.as_ref().clone().with_data_type(DataType::Utf8))
+            } else if is_wkb {
+                // plain WKB bytes without extension type metadata
+                Arc::new(Field::new(
+                    field.name(),
+                    DataType::Binary,
+                    field.is_nullable(),
+                ))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+/// whether geometry/geography columns are read as plain WKB binary, see
+/// spark.blaze.parquet.geometryAsBinary
+fn geometry_as_binary() -> bool {
+    static GEOMETRY_AS_BINARY: OnceCell<bool> = OnceCell::new();
+    *GEOMETRY_AS_BINARY.get_or_init(|| {
+        if !is_jni_bridge_inited() {
+            return false; // for testing
+        }
+        conf::PARQUET_GEOMETRY_AS_BINARY
+            .value()
+            .expect("error getting conf PARQUET_GEOMETRY_AS_BINARY")
+    })
+}
+
+/// Finds WKB-encoded geometry/geography columns of a file, annotated by
+/// geoparquet metadata or by geoarrow extension types in the arrow schema.
+fn wkb_geometry_columns(
+    schema: &Schema,
+    key_value_metadata: Option<&Vec<KeyValue>>,
+) -> HashSet<String> {
+    const WKB_EXTENSION_NAMES: &[&str] = &["geoarrow.wkb", "ogc.wkb"];
+
+    let mut columns = schema
+        .fields()
+        .iter()
+        .filter(|field| {
+            field
+                .metadata()
+                .get("ARROW:extension:name")
+                .is_some_and(|name| WKB_EXTENSION_NAMES.contains(&name.as_str()))
+        })
+        .map(|field| field.name().clone())
+        .collect::<HashSet<_>>();
+
+    let geo_metadata = key_value_metadata
+        .into_iter()
+        .flatten()
+        .find(|kv| kv.key == "geo")
+        .and_then(|kv| kv.value.as_ref());
+    if let Some(geo_metadata) = geo_metadata {
+        match serde_json::from_str::<serde_json::Value>(geo_metadata) {
+            Ok(geo_metadata) => {
+                let geo_columns = geo_metadata.get("columns").and_then(|c| c.as_object());
+                for (name, column) in geo_columns.into_iter().flatten() {
+                    // encoding defaults to WKB in geoparquet 1.0
+                    let encoding = column.get("encoding").and_then(|e| e.as_str());
+                    if encoding.unwrap_or("WKB").eq_ignore_ascii_case("WKB") {
+                        columns.insert(name.clone());
+                    }
+                }
+            }
+            Err(e) => log::warn!("Could not parse geoparquet metadata: {e}"),
+        }
     }
+    columns
+}
+
+/// Rewrites a predicate (with the file schema) into a form which can be
+/// translated to pruning predicates, returning the predicate as-is if there
+/// is nothing to rewrite.
+pub type PruningPredicateRewrite =
+    Arc<dyn Fn(Arc<dyn PhysicalExpr>, &Schema) -> Result<Arc<dyn PhysicalExpr>> + Send + Sync>;
+
+/// Rewrites for blaze-specific expressions which cannot be translated by
+/// [`PruningPredicate`] itself.
+pub fn default_pruning_predicate_rewrites() -> Vec<PruningPredicateRewrite> {
+    vec![
+        Arc::new(|expr, _schema: &Schema| rewrite_starts_with_as_bounds(expr)),
+        Arc::new(rewrite_casts_as_column_bounds),
+    ]
 }
 
 /// Execution plan for scanning one or more Parquet partitions
@@ -139,6 +406,15 @@ pub struct ParquetExec {
     predicate: Option<Arc<dyn PhysicalExpr>>,
     pruning_predicate: Option<Arc<PruningPredicate>>,
     page_pruning_predicate: Option<Arc<PagePruningPredicate>>,
+    output_mapping: Option<Arc<OutputSchemaMapping>>,
+    null_sentinels: Option<Arc<NullSentinels>>,
+    row_dedup: Option<Arc<RowDedup>>,
+    external_columns: Option<Arc<ExternalColumns>>,
+    deletion_vectors_resource_id: Option<String>,
+    byte_limit: Option<usize>,
+    case_sensitive: bool,
+    record_level_filter: bool,
+    predicate_creation_errors: Vec<String>,
 }
 
 impl ParquetExec {
@@ -149,38 +425,119 @@ impl ParquetExec {
         fs_resource_id: String,
         predicate: Option<Arc<dyn PhysicalExpr>>,
     ) -> Self {
-        let metrics = ExecutionPlanMetricsSet::new();
-        let predicate_creation_errors =
-            MetricBuilder::new(&metrics).global_counter("num_predicate_creation_errors");
+        Self::new_with_pruning_predicate_rewrites(
+            base_config,
+            fs_resource_id,
+            predicate,
+            &default_pruning_predicate_rewrites(),
+        )
+    }
+
+    /// Create a new Parquet reader execution plan, rewriting the predicate
+    /// with the given rewrites (in order) before creating pruning predicates,
+    /// so that custom expressions can be translated to statistics pruning.
+    pub fn new_with_pruning_predicate_rewrites(
+        base_config: FileScanConfig,
+        fs_resource_id: String,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+        rewrites: &[PruningPredicateRewrite],
+    ) -> Self {
+        let predicate_creation_errors = Count::new();
+
+        // reasons of failed rewrites and predicate creations, with the predicate
+        let mut creation_errors = vec![];
 
         let file_schema = &base_config.file_schema;
-        let pruning_predicate = predicate
+        let pruning_predicate_expr = predicate.clone().map(|predicate_expr| {
+            rewrites.iter().fold(predicate_expr, |expr, rewrite| {
+                rewrite(expr.clone(), file_schema.as_ref()).unwrap_or_else(|e| {
+                    log::warn!("Could not rewrite pruning predicate: {e}");
+                    creation_errors.push(format!("cannot rewrite pruning predicate {expr}: {e}"));
+                    expr
+                })
+            })
+        });
+        let pruning_predicate = pruning_predicate_expr
             .clone()
             .and_then(|predicate_expr| {
-                match PruningPredicate::try_new(predicate_expr, file_schema.clone()) {
+                match PruningPredicate::try_new(predicate_expr.clone(), file_schema.clone()) {
                     Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
                     Err(e) => {
                         log::warn!("Could not create pruning predicate: {e}");
                         predicate_creation_errors.add(1);
+                        creation_errors.push(format!(
+                            "cannot create pruning predicate from {predicate_expr}: {e}"
+                        ));
                         None
                     }
                 }
             })
             .filter(|p| !p.allways_true());
 
-        let page_pruning_predicate = predicate.as_ref().and_then(|predicate_expr| {
+        let page_pruning_predicate = pruning_predicate_expr.as_ref().and_then(|predicate_expr| {
             match PagePruningPredicate::try_new(predicate_expr, file_schema.clone()) {
                 Ok(pruning_predicate) => Some(Arc::new(pruning_predicate)),
                 Err(e) => {
                     log::warn!("Could not create page pruning predicate: {}", e);
                     predicate_creation_errors.add(1);
+                    creation_errors.push(format!(
+                        "cannot create page pruning predicate from {predicate_expr}: {e}"
+                    ));
                     None
                 }
             }
         });
 
+        Self {
+            predicate_creation_errors: creation_errors,
+            ..Self::new_with_predicate_creation_errors(
+                base_config,
+                fs_resource_id,
+                predicate,
+                pruning_predicate,
+                page_pruning_predicate,
+                predicate_creation_errors,
+            )
+        }
+    }
+
+    /// Create a new Parquet reader execution plan with already built pruning
+    /// predicates, e.g. taken from another plan scanning the same table with
+    /// the same predicate, avoiding rebuilding them for every partition.
+    pub fn new_with_pruning_predicates(
+        base_config: FileScanConfig,
+        fs_resource_id: String,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+        pruning_predicate: Option<Arc<PruningPredicate>>,
+        page_pruning_predicate: Option<Arc<PagePruningPredicate>>,
+    ) -> Self {
+        Self::new_with_predicate_creation_errors(
+            base_config,
+            fs_resource_id,
+            predicate,
+            pruning_predicate,
+            page_pruning_predicate,
+            Count::new(),
+        )
+    }
+
+    /// registers the number of failed predicate creations as a metric, so
+    /// that plans have the same metrics however they are created
+    fn new_with_predicate_creation_errors(
+        base_config: FileScanConfig,
+        fs_resource_id: String,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+        pruning_predicate: Option<Arc<PruningPredicate>>,
+        page_pruning_predicate: Option<Arc<PagePruningPredicate>>,
+        predicate_creation_errors: Count,
+    ) -> Self {
         let (projected_schema, projected_statistics, projected_output_ordering) =
             base_config.project();
+        let metrics = ExecutionPlanMetricsSet::new();
+        MetricBuilder::new(&metrics).build(MetricValue::Count {
+            name: "num_predicate_creation_errors".into(),
+            count: predicate_creation_errors,
+        });
 
         Self {
             fs_resource_id,
@@ -192,8 +549,459 @@ impl ParquetExec {
             predicate,
             pruning_predicate,
             page_pruning_predicate,
+            output_mapping: None,
+            null_sentinels: None,
+            row_dedup: None,
+            external_columns: None,
+            deletion_vectors_resource_id: None,
+            byte_limit: None,
+            case_sensitive: false,
+            record_level_filter: false,
+            predicate_creation_errors: vec![],
+        }
+    }
+
+    /// Maps the scanned columns onto the given output schema, so that columns
+    /// can be reordered/renamed without a separate projection. the i-th output
+    /// column is taken from the `indices[i]`-th projected column and cast to
+    /// the output field type if necessary.
+    ///
+    /// this is a native-only api for embedders building the exec directly,
+    /// the plan proto of spark scans does not carry an output schema.
+    pub fn with_output_schema(self, output_schema: SchemaRef, indices: Vec<usize>) -> Result<Self> {
+        if output_schema.fields().len() != indices.len() {
+            return df_execution_err!(
+                "output schema has {} fields, but {} column indices are given",
+                output_schema.fields().len(),
+                indices.len(),
+            );
+        }
+        let num_projected_columns = self.projected_schema.fields().len();
+        if let Some(&i) = indices.iter().find(|&&i| i >= num_projected_columns) {
+            return df_execution_err!(
+                "output column index {i} out of range, number of projected columns: {}",
+                num_projected_columns,
+            );
+        }
+
+        let mut projected_statistics = self.projected_statistics.clone();
+        projected_statistics.column_statistics = indices
+            .iter()
+            .map(|&i| self.projected_statistics.column_statistics[i].clone())
+            .collect();
+
+        Ok(Self {
+            projected_schema: output_schema.clone(),
+            projected_statistics,
+            projected_output_ordering: vec![],
+            output_mapping: Some(Arc::new(OutputSchemaMapping {
+                schema: output_schema,
+                indices,
+            })),
+            ..self
+        })
+    }
+
+    /// Maps sentinel values (like `"\N"` or `-999` in legacy exports) of the
+    /// given scanned columns to nulls after decoding. columns are named and
+    /// typed as decoded, before being mapped by [`Self::with_output_schema`],
+    /// so the builders can be called in any order. sentinels are cast to the
+    /// column types. like the output schema, sentinels are not carried by
+    /// the plan proto.
+    ///
+    /// statistics and row filters see the sentinels as values, not nulls, so
+    /// the columns must not be used by the predicate. the columns become
+    /// nullable in the scanned schema, unless mapped to an output schema.
+    pub fn with_null_sentinels(self, null_sentinels: HashMap<String, ScalarValue>) -> Result<Self> {
+        let predicates = self.predicate.iter().cloned().chain(
+            self.pruning_predicate
+                .iter()
+                .map(|pruning_predicate| pruning_predicate.orig_expr().clone()),
+        );
+        for predicate in predicates {
+            for column in collect_columns(&predicate) {
+                if null_sentinels.contains_key(column.name()) {
+                    return df_execution_err!(
+                        "null sentinel column {} cannot be used by the predicate",
+                        column.name(),
+                    );
+                }
+            }
+        }
+
+        let (decoded_schema, ..) = self.base_config.project();
+        let null_sentinels = null_sentinels
+            .into_iter()
+            .map(|(name, sentinel)| {
+                let field = decoded_schema.field_with_name(&name)?;
+                let sentinel = Scalar::new(sentinel.cast_to(field.data_type())?.to_array()?);
+                Ok((name, sentinel))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let null_sentinels = NullSentinels(null_sentinels);
+        let projected_schema = match &self.output_mapping {
+            Some(_) => self.projected_schema.clone(),
+            None => null_sentinels.nullable_schema(&self.projected_schema),
+        };
+        Ok(Self {
+            projected_schema,
+            null_sentinels: Some(Arc::new(null_sentinels)),
+            ..self
+        })
+    }
+
+    /// Drops duplicate rows after decoding with the given dedup predicates,
+    /// like rows of row groups duplicated in files overlapped by compactions.
+    /// a predicate is created for each partition and sees the decoded batches
+    /// (file columns followed by partition columns) in scan order, see
+    /// [`dedup_by_key_columns`].
+    ///
+    /// deduplication is expensive: states like seen keys are usually kept for
+    /// the whole partition without being tracked by the memory pool, and
+    /// duplicates read by different partitions are not detected. it also
+    /// happens after the scan limit, so fewer rows than the limit may be
+    /// produced.
+    ///
+    /// the dedup predicates are native closures, so this is only available to
+    /// embedders building the exec directly, not to plans from the proto.
+    pub fn with_row_dedup(self, factory: RowDedupFactory) -> Self {
+        Self {
+            row_dedup: Some(Arc::new(RowDedup(factory))),
+            ..self
+        }
+    }
+
+    /// Hands raw (still compressed) column chunks of the given top-level
+    /// columns to the callback for each row group read, instead of decoding
+    /// them, for external decoders of custom encodings. the columns must be
+    /// declared as binary in the file schema and are read as opaque nulls,
+    /// and must not be used by the predicate.
+    ///
+    /// the callback runs in-process, there is no way to register it from the
+    /// jvm side, so external columns are not part of the plan proto.
+    pub fn with_external_columns(
+        self,
+        column_names: Vec<String>,
+        callback: RawColumnChunkCallback,
+    ) -> Result<Self> {
+        for name in &column_names {
+            let field = self.base_config.file_schema.field_with_name(name)?;
+            if field.data_type() != &DataType::Binary {
+                return df_execution_err!(
+                    "external column {name} must be declared as binary, found {}",
+                    field.data_type(),
+                );
+            }
+        }
+        if let Some(predicate) = &self.predicate {
+            for column in collect_columns(predicate) {
+                if column_names.contains(&column.name().to_string()) {
+                    return df_execution_err!(
+                        "external column {} cannot be used by the predicate",
+                        column.name(),
+                    );
+                }
+            }
+        }
+        Ok(Self {
+            external_columns: Some(Arc::new(ExternalColumns {
+                column_names: column_names.into_iter().collect(),
+                callback,
+            })),
+            ..self
+        })
+    }
+
+    /// Drops rows deleted by deletion vectors (like delta lake's) of the
+    /// scanned files. the jvm resource of the given id is a function from file
+    /// paths to their serialized deletion vectors (see
+    /// [`DeletionVector::from_delta_bytes`]), or null for files without
+    /// deleted rows.
+    ///
+    /// rows are located by their positions in files, so files with deleted
+    /// rows are read row group by row group, without page pruning, row-level
+    /// filtering and limits inside the reader, see [`delete_rows`].
+    pub fn with_deletion_vectors(self, resource_id: String) -> Self {
+        Self {
+            deletion_vectors_resource_id: Some(resource_id),
+            ..self
+        }
+    }
+
+    /// Stops each partition successfully once it has read approximately the
+    /// given bytes (as counted by `bytes_scanned`, including footers), for
+    /// sampling jobs. batches decoded so far are all produced.
+    ///
+    /// the limit is approximate: column chunks of a row group are read at
+    /// once before being decoded, so a partition may read up to a row group
+    /// (and the footer of the next file) more than the limit, while rows of
+    /// the row group not decoded yet when the limit is reached are dropped.
+    ///
+    /// native-only: spark plans have no byte limit, so it is not set by
+    /// plans from the proto.
+    pub fn with_byte_limit(self, max_bytes: usize) -> Self {
+        Self {
+            byte_limit: Some(max_bytes),
+            ..self
+        }
+    }
+
+    /// Resolves columns of files case-sensitively, like spark.sql.caseSensitive
+    /// of the session submitting the plan. this applies to nested struct
+    /// fields, duplicate columns and strict schema checks, which are
+    /// case-insensitive by default.
+    pub fn with_case_sensitive(self, case_sensitive: bool) -> Self {
+        Self {
+            case_sensitive,
+            ..self
+        }
+    }
+
+    /// Filters out rows not matching the predicate inside the scan, like
+    /// spark.sql.parquet.recordLevelFilter.enabled of the session submitting
+    /// the plan, see [`row_filtering_modes`]. disabled by default, leaving rows
+    /// not eliminated by pruning to the downstream filter.
+    pub fn with_record_level_filter(self, record_level_filter: bool) -> Self {
+        Self {
+            record_level_filter,
+            ..self
+        }
+    }
+
+    /// Produces partition columns as dictionary arrays of their single value
+    /// per file, instead of repeating the value in every row, which saves
+    /// memory for long partition values like paths. keys buffers are shared
+    /// by batches of the same size.
+    ///
+    /// types of the projected partition columns become `Dictionary(UInt16,
+    /// _)`, so downstream operators must accept dictionary arrays, unless the
+    /// columns are cast back by [`Self::with_output_schema`], which must then
+    /// be called after this. run-end encoding is not supported by the
+    /// partition column projector of datafusion.
+    ///
+    /// spark operators expect dense partition columns, so this is for native
+    /// consumers only and is never enabled by plans from the proto.
+    pub fn with_dictionary_partition_columns(self) -> Self {
+        let table_partition_cols = self
+            .base_config
+            .table_partition_cols
+            .iter()
+            .map(|field| match field.data_type() {
+                DataType::Dictionary(..) => field.clone(),
+                data_type => field
+                    .clone()
+                    .with_data_type(wrap_partition_type_in_dict(data_type.clone())),
+            })
+            .collect();
+        let base_config = FileScanConfig {
+            table_partition_cols,
+            ..self.base_config
+        };
+        if self.output_mapping.is_some() {
+            // columns are cast back to the output schema
+            return Self {
+                base_config,
+                ..self
+            };
+        }
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+        let projected_schema = match &self.null_sentinels {
+            Some(null_sentinels) => null_sentinels.nullable_schema(&projected_schema),
+            None => projected_schema,
+        };
+        Self {
+            base_config,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            ..self
+        }
+    }
+
+    /// Ref to the base configs
+    pub fn base_config(&self) -> &FileScanConfig {
+        &self.base_config
+    }
+
+    /// Ref to the schema after projection
+    pub fn projected_schema(&self) -> &SchemaRef {
+        &self.projected_schema
+    }
+
+    /// Optional predicate passed to the scan
+    pub fn predicate(&self) -> Option<&Arc<dyn PhysicalExpr>> {
+        self.predicate.as_ref()
+    }
+
+    /// Optional row group pruning predicate, None if it is not provided or
+    /// cannot be created from the predicate
+    pub fn pruning_predicate(&self) -> Option<&Arc<PruningPredicate>> {
+        self.pruning_predicate.as_ref()
+    }
+
+    /// Optional page pruning predicate, None if it is not provided or cannot
+    /// be created from the predicate
+    pub fn page_pruning_predicate(&self) -> Option<&Arc<PagePruningPredicate>> {
+        self.page_pruning_predicate.as_ref()
+    }
+
+    /// Reasons why the predicate could not be (fully) pushed down, one for
+    /// each failed rewrite or pruning predicate creation, including the
+    /// predicate. failures are also counted by `num_predicate_creation_errors`
+    /// (except rewrites, which fall back to the predicate before rewriting).
+    pub fn predicate_creation_errors(&self) -> &[String] {
+        &self.predicate_creation_errors
+    }
+}
+
+/// Rewrites `starts_with(expr, 'abc')` into `expr >= 'abc' AND expr < 'abd'`,
+/// so that it can be used for pruning with min/max statistics.
+fn rewrite_starts_with_as_bounds(expr: Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExpr>> {
+    let starts_with =
+        if let Some(e) = expr.as_any().downcast_ref::<StringStartsWithExpr>() {
+            Some((e.expr().clone(), e.prefix().to_string()))
+        } else if let Some(e) = expr.as_any().downcast_ref::<ScalarFunctionExpr>() {
+            match (e.name(), e.args()) {
+                ("starts_with", [child, prefix]) => prefix
+                    .as_any()
+                    .downcast_ref::<Literal>()
+                    .and_then(|prefix| match prefix.value() {
+                        ScalarValue::Utf8(Some(prefix)) => Some((child.clone(), prefix.clone())),
+                        _ => None,
+                    }),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+    if let Some((child, prefix)) = starts_with.filter(|(_, prefix)| !prefix.is_empty()) {
+        let lower: Arc<dyn PhysicalExpr> = Arc::new(BinaryExpr::new(
+            child.clone(),
+            Operator::GtEq,
+            Arc::new(Literal::new(ScalarValue::Utf8(Some(prefix.clone())))),
+        ));
+        return Ok(match prefix_upper_bound(&prefix) {
+            Some(upper_bound) => Arc::new(BinaryExpr::new(
+                lower,
+                Operator::And,
+                Arc::new(BinaryExpr::new(
+                    child,
+                    Operator::Lt,
+                    Arc::new(Literal::new(ScalarValue::Utf8(Some(upper_bound)))),
+                )),
+            )),
+            None => lower,
+        });
+    }
+
+    let children = expr.children();
+    if children.is_empty() {
+        return Ok(expr);
+    }
+    let new_children = children
+        .into_iter()
+        .map(|child| rewrite_starts_with_as_bounds(child))
+        .collect::<Result<Vec<_>>>()?;
+    expr.with_new_children(new_children)
+}
+
+/// Rewrites comparisons of injective monotonic casts of columns with literals,
+/// like `cast(col as long) > 5`, as comparisons of the columns with the
+/// literals cast back to the column types (`col > 5`), so that predicates on
+/// casted columns can be translated to pruning predicates. comparisons are
+/// kept if the literals cannot be cast back exactly.
+fn rewrite_casts_as_column_bounds(
+    expr: Arc<dyn PhysicalExpr>,
+    schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+        let (left, op, right) = (binary.left(), *binary.op(), binary.right());
+        if let Some(rewritten) = uncast_comparison(left, op, right, schema)? {
+            return Ok(rewritten);
+        }
+        if let Some(swapped_op) = op.swap() {
+            if let Some(rewritten) = uncast_comparison(right, swapped_op, left, schema)? {
+                return Ok(rewritten);
+            }
+        }
+    }
+
+    let children = expr.children();
+    if children.is_empty() {
+        return Ok(expr);
+    }
+    let new_children = children
+        .into_iter()
+        .map(|child| rewrite_casts_as_column_bounds(child, schema))
+        .collect::<Result<Vec<_>>>()?;
+    expr.with_new_children(new_children)
+}
+
+// rewrites `cast(col) op literal` as `col op uncasted_literal`
+fn uncast_comparison(
+    left: &Arc<dyn PhysicalExpr>,
+    op: Operator,
+    right: &Arc<dyn PhysicalExpr>,
+    schema: &Schema,
+) -> Result<Option<Arc<dyn PhysicalExpr>>> {
+    if !matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+    ) {
+        return Ok(None);
+    }
+    let Some(cast_expr) = left.as_any().downcast_ref::<TryCastExpr>() else {
+        return Ok(None);
+    };
+    let Some(column) = cast_expr.expr.as_any().downcast_ref::<Column>() else {
+        return Ok(None);
+    };
+    let Some(literal) = right.as_any().downcast_ref::<Literal>() else {
+        return Ok(None);
+    };
+    let column_type = column.data_type(schema)?;
+    if !is_injective_monotonic_cast(&column_type, &cast_expr.cast_type)
+        || literal.value().data_type() != cast_expr.cast_type
+    {
+        return Ok(None);
+    }
+
+    let cast_scalar = |value: &ScalarValue, data_type: &DataType| -> Result<ScalarValue> {
+        ScalarValue::try_from_array(&cast(&value.to_array()?, data_type)?, 0)
+    };
+    let uncasted = cast_scalar(literal.value(), &column_type)?;
+    if uncasted.is_null() || cast_scalar(&uncasted, &cast_expr.cast_type)? != *literal.value() {
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(BinaryExpr::new(
+        cast_expr.expr.clone(),
+        op,
+        Arc::new(Literal::new(uncasted)),
+    ))))
+}
+
+/// Returns the smallest string greater than all strings with the given
+/// prefix, None if there is no such string.
+fn prefix_upper_bound(prefix: &str) -> Option<String> {
+    let mut chars = prefix.chars().collect::<Vec<_>>();
+    while let Some(last) = chars.pop() {
+        let next = match last as u32 + 1 {
+            0xD800 => Some('\u{E000}'), // skip surrogates
+            next => char::from_u32(next),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
         }
     }
+    None
 }
 
 impl DisplayAs for ParquetExec {
@@ -260,6 +1068,7 @@ impl ExecutionPlan for ParquetExec {
         partition_index: usize,
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
+        let _span = debug_span!("ParquetExec::execute", partition = partition_index).entered();
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
         let elapsed_compute = baseline_metrics.elapsed_compute();
         let _timer = elapsed_compute.timer();
@@ -274,18 +1083,186 @@ impl ExecutionPlan for ParquetExec {
         ));
         self.metrics.register(io_time_metric);
 
-        // get fs object from jni bridge resource
-        let resource_id = jni_new_string!(&self.fs_resource_id)?;
-        let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
-        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+        // get fs object from jni bridge resource, files may be read with their own
+        // fs resources. the jvm fs is not touched at all with native object stores
+        let mut reader_factory = if conf::PARQUET_NATIVE_OBJECT_STORE.value()? {
+            FsReaderFactory::new_with_object_stores(native_object_stores())
+        } else {
+            let fs_provider = get_fs_provider(&self.fs_resource_id, &io_time)?;
+            let fs_providers = FsProviderResolver::new(fs_provider)
+                .with_resolve(move |resource_id| get_fs_provider(resource_id, &io_time));
+            FsReaderFactory::new_with_resolver(Arc::new(fs_providers))
+        };
 
         let projection = match self.base_config.file_column_projection_indices() {
             Some(proj) => proj,
             None => (0..self.base_config.file_schema.fields().len()).collect(),
         };
 
+        // row counts of files are not reduced by deletion vectors
+        let count_only = projection.is_empty()
+            && self.predicate.is_none()
+            && self.deletion_vectors_resource_id.is_none();
+
+        // external columns are not decoded, and are inserted back as nulls
+        let external_columns = self.external_columns.as_ref().map(|external_columns| {
+            let file_schema = &self.base_config.file_schema;
+            let placeholders = projection
+                .iter()
+                .enumerate()
+                .filter(|(_, &idx)| external_columns.is_external(file_schema.field(idx).name()))
+                .map(|(pos, &idx)| {
+                    (
+                        pos,
+                        Arc::new(file_schema.field(idx).clone().with_nullable(true)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            ExternalColumnsScan {
+                columns: external_columns.clone(),
+                placeholders: Arc::new(placeholders),
+            }
+        });
+        let projection = match &self.external_columns {
+            Some(external_columns) => projection
+                .into_iter()
+                .filter(|&idx| {
+                    !external_columns.is_external(self.base_config.file_schema.field(idx).name())
+                })
+                .collect(),
+            None => projection,
+        };
         let page_filtering_enabled = conf::PARQUET_ENABLE_PAGE_FILTERING.value()?;
         let bloom_filter_enabled = conf::PARQUET_ENABLE_BLOOM_FILTER.value()?;
+        let (pushdown_filters, post_decode_filter) =
+            row_filtering_modes(page_filtering_enabled, self.record_level_filter);
+        let post_decode_predicate = match &self.predicate {
+            Some(predicate) if post_decode_filter => {
+                // decoded batches are not mapped to the output schema yet
+                let (decoded_schema, ..) = self.base_config.project();
+                post_decode_predicate(predicate, &decoded_schema)
+            }
+            _ => None,
+        };
+        let rows_filtered =
+            MetricBuilder::new(&self.metrics).counter("rows_filtered", partition_index);
+        let mut row_dedup = self.row_dedup.as_ref().map(|row_dedup| {
+            let rows_deduplicated =
+                MetricBuilder::new(&self.metrics).counter("rows_deduplicated", partition_index);
+            ((row_dedup.0)(), rows_deduplicated)
+        });
+
+        let strict_schema = conf::PARQUET_STRICT_SCHEMA.value()?;
+        let max_metadata_bytes = conf::PARQUET_MAX_METADATA_BYTES.value()?;
+        let max_open_files = conf::PARQUET_MAX_OPEN_FILES_PER_PARTITION.value()?;
+        let inline_read_threshold = conf::PARQUET_INLINE_READ_THRESHOLD_BYTES.value()?;
+        let max_bytes_per_partition = conf::PARQUET_MAX_BYTES_PER_PARTITION.value()?;
+        let projected_footer_min_columns = conf::PARQUET_PROJECTED_FOOTER_MIN_COLUMNS.value()?;
+        let output_batch_bytes = conf::PARQUET_OUTPUT_BATCH_BYTES.value()?;
+        let max_row_groups_per_batch = conf::PARQUET_MAX_ROW_GROUPS_PER_BATCH.value()?;
+        let skip_non_nullable_validity = conf::PARQUET_SKIP_NON_NULLABLE_VALIDITY.value()?;
+        let field_id_resolution =
+            FieldIdResolution::parse(&conf::PARQUET_FIELD_ID_RESOLUTION.value()?)?;
+        if max_metadata_bytes > 0 {
+            reader_factory = reader_factory.with_max_metadata_bytes(max_metadata_bytes as usize);
+        }
+        if inline_read_threshold > 0 {
+            reader_factory =
+                reader_factory.with_inline_read_threshold(inline_read_threshold as usize);
+        }
+        if max_bytes_per_partition > 0 {
+            reader_factory =
+                reader_factory.with_max_bytes_per_partition(max_bytes_per_partition as usize);
+        }
+        let bytes_limit = self
+            .byte_limit
+            .map(|max_bytes| Arc::new(ScanBytesLimit::new(max_bytes)));
+        if let Some(bytes_limit) = &bytes_limit {
+            reader_factory = reader_factory.with_bytes_limit(bytes_limit.clone());
+        }
+        if conf::PARQUET_VERIFY_PAGE_CHECKSUMS.value()? {
+            reader_factory = reader_factory.with_page_checksum_verification();
+        }
+        if conf::PARQUET_GEOMETRY_AS_BINARY.value()? {
+            reader_factory = reader_factory.with_geometry_as_binary();
+        }
+        if projected_footer_min_columns > 0 {
+            // columns read or used by the predicate
+            let file_schema = &self.base_config.file_schema;
+            let mut column_names = projection
+                .iter()
+                .map(|&idx| file_schema.field(idx).name().clone())
+                .collect::<HashSet<_>>();
+            if let Some(predicate) = &self.predicate {
+                column_names.extend(
+                    collect_columns(predicate)
+                        .into_iter()
+                        .map(|col| col.name().to_string()),
+                );
+            }
+            reader_factory = reader_factory
+                .with_projected_footer(projected_footer_min_columns as usize, column_names);
+        }
+        if let Some(field_id_resolution) = field_id_resolution {
+            reader_factory = reader_factory
+                .with_field_id_resolution(field_id_resolution, &self.base_config.file_schema);
+        }
+        if let Some(map_children_projection) =
+            MapChildrenProjection::try_from_schema(&self.base_config.file_schema)?
+        {
+            reader_factory = reader_factory.with_map_children_projection(map_children_projection);
+        }
+        reader_factory =
+            reader_factory.with_duplicate_column_resolver(DuplicateColumnResolver::new(
+                &self.base_config.file_schema,
+                self.case_sensitive,
+                conf::PARQUET_PICK_FIRST_DUPLICATE_COLUMN.value()?,
+            ));
+        if strict_schema {
+            reader_factory = reader_factory.with_strict_schema(
+                self.base_config.file_schema.clone(),
+                projection.clone(),
+                self.case_sensitive,
+            );
+        }
+        if conf::PARQUET_WARN_EXTRA_COLUMNS.value()? {
+            reader_factory = reader_factory
+                .with_extra_columns_warning(&self.base_config.file_schema, self.case_sensitive);
+        }
+        if let Some(predicate) = &self.predicate {
+            if conf::PARQUET_DICTIONARY_FILTER.value()? {
+                if let Some(dictionary_filter) =
+                    DictionaryFilter::try_new(predicate, self.case_sensitive)
+                {
+                    reader_factory = reader_factory.with_dictionary_filter(dictionary_filter);
+                }
+            }
+        }
+        reader_factory = reader_factory.with_io_latency_sampler(IoLatencySampler::new(
+            IO_LATENCY_WINDOW,
+            IoLatencyMetrics::new(&self.metrics, partition_index),
+        ));
+
+        // row positions of deleted rows are counted in footers with all row
+        // groups, read without the dictionary filter
+        let deletion_vectors = self
+            .deletion_vectors_resource_id
+            .as_ref()
+            .map(|resource_id| {
+                Ok::<_, DataFusionError>(DeletionVectorScan {
+                    fetch: get_deletion_vector_fetch(resource_id)?,
+                    unpruned_reader_factory: Arc::new(
+                        reader_factory.clone().without_dictionary_filter(),
+                    ),
+                    rows_deleted: MetricBuilder::new(&self.metrics)
+                        .counter("rows_deleted", partition_index),
+                    scratch_bitmaps: conf::PARQUET_REUSE_SCRATCH_BUFFERS
+                        .value()?
+                        .then(|| Arc::new(Mutex::new(ScratchBitmaps::default()))),
+                })
+            })
+            .transpose()?;
+        let reader_factory = Arc::new(reader_factory);
 
         let opener = ParquetOpener {
             partition_index,
@@ -298,35 +1275,169 @@ impl ExecutionPlan for ParquetExec {
             table_schema: self.base_config.file_schema.clone(),
             metadata_size_hint: None,
             metrics: self.metrics.clone(),
-            parquet_file_reader_factory: Arc::new(FsReaderFactory::new(fs_provider)),
-            pushdown_filters: page_filtering_enabled,
-            reorder_filters: page_filtering_enabled,
+            parquet_file_reader_factory: reader_factory.clone(),
+            pushdown_filters,
+            reorder_filters: pushdown_filters,
             enable_page_index: page_filtering_enabled,
             enable_bloom_filter: bloom_filter_enabled,
         };
 
+        // partition values must match the declared types of partition columns
+        let mut file_groups = self.base_config.file_groups.clone();
+        file_groups[partition_index] = cast_partition_values(
+            &file_groups[partition_index],
+            &self.base_config.table_partition_cols,
+            strict_schema,
+        )?;
+        MetricBuilder::new(&self.metrics)
+            .counter("files_assigned", partition_index)
+            .add(file_groups[partition_index].len());
+
+        let ignore_corrupted_files = conf::IGNORE_CORRUPTED_FILES.value()?;
+        let quarantine_corrupted_row_groups =
+            conf::PARQUET_QUARANTINE_CORRUPTED_ROW_GROUPS.value()?;
+        let opener = ParquetFileOpener {
+            inner: Arc::new(opener),
+            partition_index,
+            reader_factory,
+            metrics: self.metrics.clone(),
+            empty_files: MetricBuilder::new(&self.metrics).counter("empty_files", partition_index),
+            open_files: (max_open_files > 0)
+                .then(|| Arc::new(Semaphore::new(max_open_files as usize))),
+            skipped_corrupted_files: ignore_corrupted_files.then(|| {
+                MetricBuilder::new(&self.metrics)
+                    .counter("skipped_corrupted_files", partition_index)
+            }),
+            quarantined_row_groups: quarantine_corrupted_row_groups.then(|| {
+                MetricBuilder::new(&self.metrics).counter("quarantined_row_groups", partition_index)
+            }),
+            memory_pool: context.memory_pool().clone(),
+            estimated_dictionary_decode_time: MetricBuilder::new(&self.metrics)
+                .subset_time("estimated_dictionary_decode_time", partition_index),
+            count_only: count_only.then(|| CountOnlyScan {
+                files: file_groups[partition_index].clone(),
+                row_counts: OnceCell::new(),
+            }),
+            external_columns,
+            deletion_vectors,
+            row_group_batching: (max_row_groups_per_batch > 0).then(|| RowGroupBatching {
+                max_row_groups: max_row_groups_per_batch as usize,
+                target_bytes: (output_batch_bytes > 0).then_some(output_batch_bytes as usize),
+            }),
+        };
+        let base_config = FileScanConfig {
+            file_groups,
+            ..self.base_config.clone()
+        };
+
         let baseline_metrics_cloned = baseline_metrics.clone();
         let mut file_stream =
-            FileStream::new(&self.base_config, partition_index, opener, &self.metrics)?;
-        if conf::IGNORE_CORRUPTED_FILES.value()? {
+            FileStream::new(&base_config, partition_index, opener, &self.metrics)?;
+        if ignore_corrupted_files {
             file_stream = file_stream.with_on_error(OnError::Skip);
         }
-        let mut stream = Box::pin(file_stream);
+        let mut stream = match bytes_limit {
+            Some(bytes_limit) => stop_at_bytes_limit(file_stream, bytes_limit),
+            None => file_stream.boxed(),
+        };
+        stream = with_scan_case_sensitive(stream, self.case_sensitive);
+        let cpu_sets = CpuSet::parse_sets(&conf::PARQUET_DECODE_CPU_SETS.value()?)?;
+        if !cpu_sets.is_empty() {
+            let cpu_set = cpu_sets[partition_index % cpu_sets.len()].clone();
+            stream = pin_polling_threads(stream, Arc::new(cpu_set)).boxed();
+        }
+        let output_schema = self.schema();
+        let output_mapping = self.output_mapping.clone();
+        let null_sentinels = self.null_sentinels.clone();
+        let non_nullable_columns = if skip_non_nullable_validity {
+            // decoded batches are not mapped to the output schema yet
+            let (decoded_schema, ..) = self.base_config.project();
+            NonNullableColumns::try_new(&decoded_schema)
+        } else {
+            None
+        };
+        let mut byte_sized_batches = (output_batch_bytes > 0).then(|| {
+            let staging_reservation =
+                MemoryConsumer::new(format!("ParquetScan[{partition_index}] staging"))
+                    .register(context.memory_pool());
+            ByteSizedBatches::new(
+                output_batch_bytes as usize,
+                self.base_config.limit,
+                staging_reservation,
+            )
+        });
+        let scan_span = debug_span!("parquet_scan", partition = partition_index);
+        let mut reservation = MemoryConsumer::new(format!("ParquetScan[{partition_index}]"))
+            .register(context.memory_pool());
         let context_cloned = context.clone();
         let timed_stream = Box::pin(RecordBatchStreamAdapter::new(
             self.schema(),
             once(async move {
-                context_cloned.output_with_sender(
-                    "ParquetScan",
-                    stream.schema(),
-                    move |sender| async move {
+                context_cloned.output_with_sender("ParquetScan", output_schema, move |sender| {
+                    async move {
                         let mut timer = baseline_metrics_cloned.elapsed_compute().timer();
-                        while let Some(batch) = stream.next().await.transpose()? {
-                            sender.send(Ok(batch), Some(&mut timer)).await;
+                        let mut input_finished = false;
+                        while !input_finished {
+                            let batches = match stream.next().await.transpose()? {
+                                Some(batch) => {
+                                    let batch = match &post_decode_predicate {
+                                        Some(predicate) => {
+                                            filter_decoded_batch(predicate, &batch, &rows_filtered)?
+                                        }
+                                        None => batch,
+                                    };
+                                    let batch = match &mut row_dedup {
+                                        Some((predicate, rows_deduplicated)) => {
+                                            dedup_decoded_batch(
+                                                predicate,
+                                                &batch,
+                                                rows_deduplicated,
+                                            )?
+                                        }
+                                        None => batch,
+                                    };
+                                    if batch.num_rows() == 0 {
+                                        continue;
+                                    }
+                                    let batch = match &non_nullable_columns {
+                                        Some(non_nullable_columns) => {
+                                            non_nullable_columns.apply(&batch)?
+                                        }
+                                        None => batch,
+                                    };
+                                    let batch = match &null_sentinels {
+                                        Some(null_sentinels) => null_sentinels.apply(&batch)?,
+                                        None => batch,
+                                    };
+                                    let batch = match &output_mapping {
+                                        Some(output_mapping) => output_mapping.map_batch(&batch)?,
+                                        None => batch,
+                                    };
+                                    match &mut byte_sized_batches {
+                                        Some(byte_sized_batches) => {
+                                            byte_sized_batches.push(batch)?
+                                        }
+                                        None => vec![batch],
+                                    }
+                                }
+                                None => {
+                                    input_finished = true;
+                                    match &mut byte_sized_batches {
+                                        Some(byte_sized_batches) => byte_sized_batches.finish()?,
+                                        None => vec![],
+                                    }
+                                }
+                            };
+                            for batch in batches {
+                                reserve_output_batch(&mut reservation, &batch);
+                                sender.send(Ok(batch), Some(&mut timer)).await;
+                                reservation.free();
+                            }
                         }
                         Ok(())
-                    },
-                )
+                    }
+                    .instrument(scan_span)
+                })
             })
             .try_flatten(),
         ));
@@ -342,163 +1453,6766 @@ impl ExecutionPlan for ParquetExec {
     }
 }
 
-#[derive(Clone)]
-pub struct FsReaderFactory {
-    fs_provider: Arc<FsProvider>,
+/// Maps batches of the projected schema onto a target output schema.
+#[derive(Debug)]
+struct OutputSchemaMapping {
+    schema: SchemaRef,
+    indices: Vec<usize>,
 }
 
-impl FsReaderFactory {
-    pub fn new(fs_provider: Arc<FsProvider>) -> Self {
-        Self { fs_provider }
+impl OutputSchemaMapping {
+    fn map_batch(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let columns = self
+            .indices
+            .iter()
+            .zip(self.schema.fields())
+            .map(|(&i, field)| {
+                // columns of matched types are shared without being rebuilt
+                let column = batch.column(i);
+                if column.data_type() == field.data_type() {
+                    return Ok(column.clone());
+                }
+                cast(column, field.data_type())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new_with_options(
+            self.schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+        )?)
     }
 }
 
-impl Debug for FsReaderFactory {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "FsReaderFactory")
+/// Per-column sentinel values replaced with nulls, applied on decoded batches
+/// before the output mapping.
+#[derive(Debug)]
+struct NullSentinels(Vec<(String, Scalar<ArrayRef>)>);
+
+impl NullSentinels {
+    /// returns the schema with the sentinel columns nullable
+    fn nullable_schema(&self, schema: &Schema) -> SchemaRef {
+        let fields = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                if !field.is_nullable() && self.0.iter().any(|(name, _)| name == field.name()) {
+                    return Arc::new(field.as_ref().clone().with_nullable(true));
+                }
+                field.clone()
+            })
+            .collect::<Vec<_>>();
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+    }
+
+    /// replaces sentinel values with nulls. batches are decoded with the file
+    /// schema, whose sentinel columns may be non-nullable
+    fn apply(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let mut columns = batch.columns().to_vec();
+        for (name, sentinel) in &self.0 {
+            let i = batch.schema().index_of(name)?;
+            let is_sentinel = eq(&columns[i], sentinel)?;
+
+            // columns without sentinel values are shared without being rebuilt
+            if is_sentinel.true_count() > 0 {
+                columns[i] = nullif(&columns[i], &is_sentinel)?;
+            }
+        }
+        Ok(RecordBatch::try_new_with_options(
+            self.nullable_schema(&batch.schema()),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+        )?)
     }
 }
 
-impl ParquetFileReaderFactory for FsReaderFactory {
-    fn create_reader(
-        &self,
-        partition_index: usize,
-        file_meta: FileMeta,
-        _metadata_size_hint: Option<usize>,
-        metrics: &ExecutionPlanMetricsSet,
-    ) -> Result<Box<dyn AsyncFileReader + Send>> {
-        let reader = ParquetFileReaderRef(Arc::new(ParquetFileReader {
-            fs_provider: self.fs_provider.clone(),
-            input: OnceCell::new(),
-            metrics: ParquetFileMetrics::new(
-                partition_index,
-                file_meta
-                    .object_meta
-                    .location
-                    .filename()
-                    .unwrap_or("__default_filename__"),
-                metrics,
-            ),
-            meta: file_meta.object_meta,
-        }));
-        Ok(Box::new(reader))
+/// Selects rows of a decoded batch to keep, dropping rows duplicated with
+/// previous rows of the partition.
+pub type RowDedupPredicate = Box<dyn FnMut(&RecordBatch) -> Result<BooleanArray> + Send>;
+
+/// Creates a [`RowDedupPredicate`] for each scanned partition.
+pub type RowDedupFactory = Arc<dyn Fn() -> RowDedupPredicate + Send + Sync>;
+
+struct RowDedup(RowDedupFactory);
+
+/// Raw column chunk of an external column, see
+/// [`ParquetExec::with_external_columns`].
+#[derive(Debug, Clone)]
+pub struct RawColumnChunk {
+    pub file_path: String,
+    /// index of the row group in the file, not counting empty row groups
+    pub row_group_idx: usize,
+    pub column_path: ColumnPath,
+    pub num_rows: i64,
+    /// compressed bytes of the column chunk, including all its pages
+    pub bytes: Bytes,
+}
+
+/// Receives raw column chunks of external columns. chunks of a file are
+/// handed in row group order before any row of the file is decoded.
+pub type RawColumnChunkCallback = Arc<dyn Fn(RawColumnChunk) -> Result<()> + Send + Sync>;
+
+struct ExternalColumns {
+    column_names: HashSet<String>,
+    callback: RawColumnChunkCallback,
+}
+
+impl Debug for ExternalColumns {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ExternalColumns({:?})", self.column_names)
     }
 }
 
-struct ParquetFileReader {
-    fs_provider: Arc<FsProvider>,
-    input: OnceCell<Arc<FsDataInputStream>>,
-    meta: ObjectMeta,
-    metrics: ParquetFileMetrics,
+impl ExternalColumns {
+    fn is_external(&self, column_name: &str) -> bool {
+        self.column_names.contains(column_name)
+    }
+
+    /// reads raw column chunks of external columns in the given row groups
+    /// and hands them to the callback
+    async fn read_raw_chunks(
+        &self,
+        reader: &mut (dyn AsyncFileReader + Send),
+        metadata: &ParquetMetaData,
+        row_groups: &[usize],
+        file_path: &str,
+    ) -> Result<()> {
+        for &row_group_idx in row_groups {
+            let rg = metadata.row_group(row_group_idx);
+            for column in rg.columns() {
+                if !self.is_external(&column.column_path().parts()[0]) {
+                    continue;
+                }
+                let (start, len) = column.byte_range();
+                let bytes = reader
+                    .get_bytes(start as usize..(start + len) as usize)
+                    .await?;
+                (self.callback)(RawColumnChunk {
+                    file_path: file_path.to_string(),
+                    row_group_idx,
+                    column_path: column.column_path().clone(),
+                    num_rows: rg.num_rows(),
+                    bytes,
+                })?;
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Clone)]
-struct ParquetFileReaderRef(Arc<ParquetFileReader>);
+/// External columns of a scan partition, with positions and fields of their
+/// null placeholders in decoded batches.
+struct ExternalColumnsScan {
+    columns: Arc<ExternalColumns>,
+    placeholders: Arc<Vec<(usize, FieldRef)>>,
+}
 
-impl ParquetFileReader {
-    fn get_input(&self) -> datafusion::parquet::errors::Result<Arc<FsDataInputStream>> {
-        let input = self
-            .input
-            .get_or_try_init(|| {
-                let path = BASE64_URL_SAFE_NO_PAD
-                    .decode(self.meta.location.filename().expect("missing filename"))
-                    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
-                    .or_else(|_| {
-                        let filename = self.meta.location.filename();
-                        df_execution_err!("cannot decode filename: {filename:?}")
-                    })?;
-                let fs = self.fs_provider.provide(&path)?;
-                Ok(Arc::new(fs.open(&path)?))
+/// Inserts null placeholders of external columns (in ascending positions)
+/// into a decoded batch.
+fn insert_external_placeholders(
+    batch: RecordBatch,
+    placeholders: &[(usize, FieldRef)],
+) -> Result<RecordBatch> {
+    let mut fields = batch.schema().fields().to_vec();
+    let mut columns = batch.columns().to_vec();
+    for (pos, field) in placeholders {
+        fields.insert(*pos, field.clone());
+        columns.insert(*pos, new_null_array(field.data_type(), batch.num_rows()));
+    }
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(Schema::new(fields)),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+    )?)
+}
+
+fn with_external_placeholders(
+    open_future: FileOpenFuture,
+    placeholders: Arc<Vec<(usize, FieldRef)>>,
+) -> FileOpenFuture {
+    Box::pin(async move {
+        let stream = open_future.await?;
+        Ok(stream
+            .map(move |batch| {
+                batch.and_then(|batch| {
+                    insert_external_placeholders(batch, &placeholders)
+                        .map_err(|e| ArrowError::ExternalError(Box::new(e)))
+                })
             })
-            .map_err(|e| ParquetError::External(e))?;
-        Ok(input.clone())
+            .boxed())
+    })
+}
+
+impl Debug for RowDedup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "RowDedup")
     }
+}
 
-    fn read_fully(&self, range: Range<usize>) -> Result<Bytes> {
-        let mut bytes = vec![0u8; range.len()];
-        self.get_input()?
-            .read_fully(range.start as u64, &mut bytes)?;
-        Ok(Bytes::from(bytes))
+/// Deduplicates rows by the given key columns (like a primary key), only the
+/// first row of each key is kept. keys of all kept rows are held in memory.
+pub fn dedup_by_key_columns(key_columns: Vec<String>) -> RowDedupFactory {
+    Arc::new(move || {
+        let key_columns = key_columns.clone();
+        let mut converter: Option<RowConverter> = None;
+        let mut seen_keys: HashSet<OwnedRow> = HashSet::new();
+        Box::new(move |batch: &RecordBatch| {
+            let keys = key_columns
+                .iter()
+                .map(|name| Ok(batch.column(batch.schema().index_of(name)?).clone()))
+                .collect::<Result<Vec<_>>>()?;
+            if converter.is_none() {
+                converter = Some(RowConverter::new(
+                    keys.iter()
+                        .map(|key| SortField::new(key.data_type().clone()))
+                        .collect(),
+                )?);
+            }
+            let rows = converter.as_mut().unwrap().convert_columns(&keys)?;
+            Ok(rows
+                .iter()
+                .map(|row| Some(seen_keys.insert(row.owned())))
+                .collect())
+        })
+    })
+}
+
+fn dedup_decoded_batch(
+    predicate: &mut RowDedupPredicate,
+    batch: &RecordBatch,
+    rows_deduplicated: &Count,
+) -> Result<RecordBatch> {
+    let selected = predicate(batch)?;
+    if selected.len() != batch.num_rows() {
+        return df_execution_err!(
+            "row dedup predicate returned {} rows, expect {}",
+            selected.len(),
+            batch.num_rows(),
+        );
     }
+    let deduplicated = filter_record_batch(batch, &selected)?;
+    rows_deduplicated.add(batch.num_rows() - deduplicated.num_rows());
+    Ok(deduplicated)
 }
 
-impl AsyncFileReader for ParquetFileReaderRef {
-    fn get_bytes(
-        &mut self,
-        range: Range<usize>,
-    ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Bytes>> {
-        let inner = self.0.clone();
-        inner.metrics.bytes_scanned.add(range.end - range.start);
-        async move {
-            tokio::task::spawn_blocking(move || {
-                inner
-                    .read_fully(range)
-                    .map_err(|e| ParquetError::External(Box::new(e)))
+/// Columns declared non-nullable in the table schema, whose validity buffers
+/// are dropped when the decoded data has no nulls. parquet columns are usually
+/// written as optional even if they never contain nulls, so the reader builds
+/// all-set validity buffers for them.
+#[derive(Debug)]
+struct NonNullableColumns(Vec<usize>);
+
+impl NonNullableColumns {
+    fn try_new(schema: &Schema) -> Option<Self> {
+        let indices = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| !field.is_nullable())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        (!indices.is_empty()).then_some(Self(indices))
+    }
+
+    fn apply(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let mut columns = batch.columns().to_vec();
+        for &i in &self.0 {
+            if columns[i].nulls().is_some() && columns[i].null_count() == 0 {
+                let data = columns[i].to_data().into_builder().nulls(None).build()?;
+                columns[i] = make_array(data);
+            }
+        }
+        Ok(RecordBatch::try_new_with_options(
+            batch.schema(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+        )?)
+    }
+}
+
+/// Wraps [`ParquetOpener`] to handle the differences between spark and
+/// datafusion parquet reading.
+///
+/// row groups of a split file are selected the same way as spark
+/// (parquet-mr): a row group is read by the split containing its midpoint.
+/// datafusion selects row groups by the offset of their first page instead,
+/// so the file range is rewritten to cover exactly the start offsets of the
+/// row groups selected by midpoint before it is passed to the inner opener.
+///
+/// empty files (zero bytes or zero rows, usually produced by failed/empty
+/// writes) are not treated as corrupted and produce no rows.
+///
+/// files without ranges (spark always passes ranges) are opened directly by
+/// the inner opener, without being counted as empty or timed, unless a
+/// feature below needs their footers first.
+///
+/// the number of concurrently open files can be limited, a file is counted
+/// as open from the start of opening until its stream is dropped.
+///
+/// when no file columns are projected and there is no predicate (like
+/// `count(*)`), only row counts in the footers are read. footers of all files
+/// of the partition are fetched concurrently, see [`RowCountPrefetch`].
+///
+/// corrupted row groups can be quarantined instead of failing (or skipping)
+/// the whole file, see [`quarantine_corrupted_row_groups`].
+///
+/// time of decoding dictionary-encoded data is not exposed by the decoder,
+/// it is estimated from the decoding time of each file instead, see
+/// [`estimate_dictionary_decode_time`].
+///
+/// row groups can be decoded in bounded units, see [`RowGroupBatching`].
+///
+/// raw column chunks of external columns are handed to their callback after
+/// the footer is read, see [`ParquetExec::with_external_columns`].
+///
+/// rows deleted by deletion vectors of files are dropped by their positions,
+/// see [`delete_rows`].
+struct ParquetFileOpener {
+    inner: Arc<ParquetOpener>,
+    partition_index: usize,
+    reader_factory: Arc<dyn ParquetFileReaderFactory>,
+    metrics: ExecutionPlanMetricsSet,
+    empty_files: Count,
+    open_files: Option<Arc<Semaphore>>,
+    skipped_corrupted_files: Option<Count>,
+    quarantined_row_groups: Option<Count>,
+    memory_pool: Arc<dyn MemoryPool>,
+    estimated_dictionary_decode_time: Time,
+    count_only: Option<CountOnlyScan>,
+    external_columns: Option<ExternalColumnsScan>,
+    deletion_vectors: Option<DeletionVectorScan>,
+    row_group_batching: Option<RowGroupBatching>,
+}
+
+/// Fetches the deletion vector of a file by its path, None if the file has no
+/// deleted rows.
+type FetchDeletionVector = Arc<dyn Fn(&str) -> Result<Option<DeletionVector>> + Send + Sync>;
+
+/// Deletion vectors of a scan partition, see
+/// [`ParquetExec::with_deletion_vectors`].
+#[derive(Clone)]
+struct DeletionVectorScan {
+    fetch: FetchDeletionVector,
+    // reads footers with row groups not pruned by dictionaries, for counting
+    // row positions
+    unpruned_reader_factory: Arc<dyn ParquetFileReaderFactory>,
+    rows_deleted: Count,
+    // buffers of selection masks reused across batches, if enabled
+    scratch_bitmaps: Option<Arc<Mutex<ScratchBitmaps>>>,
+}
+
+/// Files of a count-only scan partition, row counts are prefetched once the
+/// first file is opened.
+struct CountOnlyScan {
+    files: Vec<PartitionedFile>,
+    row_counts: OnceCell<Arc<RowCountPrefetch>>,
+}
+
+impl FileOpener for ParquetFileOpener {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let path = decode_file_path(&file_meta.object_meta)
+            .unwrap_or_else(|_| file_meta.object_meta.location.to_string());
+        let file_context = format!(
+            "error reading parquet file {path} (partition {})",
+            self.partition_index
+        );
+        let open_future: FileOpenFuture = match self.open_file(file_meta) {
+            Ok(open_future) => open_future,
+            Err(e) => {
+                // handled as a failure of opening the file, which may be skipped
+                Box::pin(futures::future::ready(Err(e)))
+            }
+        };
+        let open_future = match &self.external_columns {
+            Some(external_columns) => {
+                with_external_placeholders(open_future, external_columns.placeholders.clone())
+            }
+            None => open_future,
+        };
+        let open_future = with_file_context(open_future, file_context);
+        let open_future = match &self.skipped_corrupted_files {
+            Some(skipped_corrupted_files) => {
+                skip_corrupted_file(open_future, skipped_corrupted_files.clone())
+            }
+            None => open_future,
+        };
+        Ok(match &self.open_files {
+            Some(open_files) => limit_open_files(open_future, open_files.clone()),
+            None => open_future,
+        })
+    }
+}
+
+impl ParquetFileOpener {
+    fn open_file(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        if file_meta.object_meta.size == 0 {
+            self.empty_files.add(1);
+            return Ok(Box::pin(async { Ok(futures::stream::empty().boxed()) }));
+        }
+
+        if let Some(count_only) = &self.count_only {
+            return self.open_count_only(count_only, file_meta);
+        }
+        if file_meta.range.is_none()
+            && self.quarantined_row_groups.is_none()
+            && self.row_group_batching.is_none()
+            && self.external_columns.is_none()
+            && self.deletion_vectors.is_none()
+        {
+            return self.inner.open(file_meta);
+        }
+        let range = file_meta.range.clone();
+        let quarantined_row_groups = self.quarantined_row_groups.clone();
+        let memory_pool = self.memory_pool.clone();
+        let path = decode_file_path(&file_meta.object_meta)
+            .unwrap_or_else(|_| file_meta.object_meta.location.to_string());
+        let inner = self.inner.clone();
+        let object_meta = file_meta.object_meta.clone();
+        let extensions = file_meta.extensions.clone();
+        let empty_files = self.empty_files.clone();
+        let estimated_dictionary_decode_time = self.estimated_dictionary_decode_time.clone();
+        let row_group_batching = self.row_group_batching;
+        let deletion_vectors = self.deletion_vectors.clone();
+        let partition_index = self.partition_index;
+        let metrics = self.metrics.clone();
+        let external_columns = self
+            .external_columns
+            .as_ref()
+            .map(|external_columns| external_columns.columns.clone());
+        let projected_columns = self
+            .inner
+            .projection
+            .iter()
+            .map(|&idx| self.inner.table_schema.field(idx).name().to_lowercase())
+            .collect::<HashSet<_>>();
+        let mut reader = self.reader_factory.create_reader(
+            self.partition_index,
+            file_meta,
+            None,
+            &self.metrics,
+        )?;
+
+        Ok(Box::pin(async move {
+            // metadata is cached and will be reused by the inner opener
+            let metadata = reader.get_metadata().await?;
+            if metadata.file_metadata().num_rows() == 0 {
+                empty_files.add(1);
+                return Ok(futures::stream::empty().boxed());
+            }
+            let dictionary_share = dictionary_encoded_share(&metadata, &projected_columns);
+            if let Some(external_columns) = &external_columns {
+                let row_groups = selected_row_groups(&metadata, range.as_ref());
+                external_columns
+                    .read_raw_chunks(reader.as_mut(), &metadata, &row_groups, &path)
+                    .await?;
+            }
+
+            // open row groups one by one with all their rows, dropping deleted
+            // rows by their positions. quarantining and batching of row groups
+            // are not applied to files with deleted rows
+            if let Some(deletion_vectors) = &deletion_vectors {
+                let deletion_vector = (deletion_vectors.fetch)(&path)?;
+                if let Some(deletion_vector) = deletion_vector.filter(|dv| dv.num_deleted() > 0) {
+                    let unpruned_file_meta = FileMeta {
+                        object_meta: object_meta.clone(),
+                        range: None,
+                        extensions: extensions.clone(),
+                    };
+                    let unpruned_metadata = deletion_vectors
+                        .unpruned_reader_factory
+                        .create_reader(partition_index, unpruned_file_meta, None, &metrics)?
+                        .get_metadata()
+                        .await?;
+                    let first_rows = first_row_positions(&unpruned_metadata);
+                    let row_groups = selected_row_groups(&metadata, range.as_ref());
+                    let opener = opener_with_row_positions(&inner);
+                    let row_group_metadata = metadata.clone();
+                    let open_row_group = move |row_group_idx: usize| {
+                        let offset = row_group_offset(row_group_metadata.row_group(row_group_idx));
+                        opener.open(FileMeta {
+                            object_meta: object_meta.clone(),
+                            range: Some(FileRange {
+                                start: offset,
+                                end: offset + 1,
+                            }),
+                            extensions: extensions.clone(),
+                        })
+                    };
+                    let stream = delete_rows(
+                        &metadata,
+                        first_rows,
+                        row_groups,
+                        open_row_group,
+                        deletion_vector,
+                        deletion_vectors.rows_deleted.clone(),
+                        deletion_vectors.scratch_bitmaps.clone(),
+                    )?;
+                    return Ok(estimate_dictionary_decode_time(
+                        stream,
+                        dictionary_share,
+                        estimated_dictionary_decode_time,
+                    ));
+                }
+            }
+
+            // open row groups one by one, each with a range covering only its
+            // first page offset
+            if let Some(quarantined_row_groups) = quarantined_row_groups {
+                let row_groups = selected_row_groups(&metadata, range.as_ref());
+                let open_row_group = move |row_group_idx: usize| {
+                    let offset = row_group_offset(metadata.row_group(row_group_idx));
+                    inner.open(FileMeta {
+                        object_meta: object_meta.clone(),
+                        range: Some(FileRange {
+                            start: offset,
+                            end: offset + 1,
+                        }),
+                        extensions: extensions.clone(),
+                    })
+                };
+                let stream = quarantine_corrupted_row_groups(
+                    row_groups,
+                    open_row_group,
+                    path,
+                    quarantined_row_groups,
+                    memory_pool,
+                );
+                return Ok(estimate_dictionary_decode_time(
+                    stream,
+                    dictionary_share,
+                    estimated_dictionary_decode_time,
+                ));
+            }
+
+            // open decode units of row groups one by one, each with a range
+            // covering the first page offsets of its row groups
+            if let Some(row_group_batching) = row_group_batching {
+                let row_groups = selected_row_groups(&metadata, range.as_ref());
+                let units = row_group_batching.decode_units(
+                    &metadata,
+                    &row_groups,
+                    &projected_columns,
+                    inner.batch_size,
+                );
+                let stream = futures::stream::iter(units)
+                    .then(move |unit| {
+                        let start = row_group_offset(metadata.row_group(unit.row_groups.start));
+                        let end = row_group_offset(metadata.row_group(unit.row_groups.end - 1));
+                        let file_meta = FileMeta {
+                            object_meta: object_meta.clone(),
+                            range: Some(FileRange {
+                                start,
+                                end: end + 1,
+                            }),
+                            extensions: extensions.clone(),
+                        };
+                        let opener = opener_with_batch_size(&inner, unit.batch_size);
+                        async move { opener.open(file_meta)?.await }
+                    })
+                    .map_err(|e| ArrowError::ExternalError(Box::new(e)))
+                    .try_flatten()
+                    .boxed();
+                return Ok(estimate_dictionary_decode_time(
+                    stream,
+                    dictionary_share,
+                    estimated_dictionary_decode_time,
+                ));
+            }
+
+            let file_meta = FileMeta {
+                object_meta,
+                range: range.map(|range| midpoint_file_range(&metadata, &range)),
+                extensions,
+            };
+            let stream = inner.open(file_meta)?.await?;
+            Ok(estimate_dictionary_decode_time(
+                stream,
+                dictionary_share,
+                estimated_dictionary_decode_time,
+            ))
+        }))
+    }
+
+    /// only row counts are needed, skip reading any data
+    fn open_count_only(
+        &self,
+        count_only: &CountOnlyScan,
+        file_meta: FileMeta,
+    ) -> Result<FileOpenFuture> {
+        let row_counts = count_only
+            .row_counts
+            .get_or_init(|| {
+                let reader_factory = self.reader_factory.clone();
+                let partition_index = self.partition_index;
+                let metrics = self.metrics.clone();
+                let fetch: FetchRowCount = Arc::new(move |file_meta: FileMeta| {
+                    // zero-length files are not read at all
+                    if file_meta.object_meta.size == 0 {
+                        return futures::future::ready(Ok(0)).boxed();
+                    }
+                    let range = file_meta.range.clone();
+                    let reader =
+                        reader_factory.create_reader(partition_index, file_meta, None, &metrics);
+                    async move {
+                        let metadata = reader?.get_metadata().await?;
+                        Ok(count_only_num_rows(&metadata, range.as_ref()))
+                    }
+                    .boxed()
+                });
+                Arc::new(RowCountPrefetch::start(
+                    &count_only.files,
+                    metadata_fetch_concurrency(),
+                    fetch,
+                ))
             })
-            .await
-            .expect("tokio spawn_blocking error")
+            .clone();
+        let empty_files = self.empty_files.clone();
+
+        Ok(Box::pin(async move {
+            let num_rows = row_counts.row_count(file_meta).await?;
+            if num_rows == 0 {
+                empty_files.add(1);
+                return Ok(futures::stream::empty().boxed());
+            }
+            let batches = count_only_batches(num_rows, batch_size())?;
+            Ok(futures::stream::iter(batches.into_iter().map(Ok)).boxed())
+        }))
+    }
+}
+
+type RowCountKey = (Path, Option<(i64, i64)>);
+type FetchRowCount = Arc<dyn Fn(FileMeta) -> BoxFuture<'static, Result<usize>> + Send + Sync>;
+
+/// Row counts of the files of a count-only scan, fetched concurrently ahead of
+/// the file stream, which opens files one by one. outstanding fetches are
+/// stopped once the scan is dropped.
+struct RowCountPrefetch {
+    fetch: FetchRowCount,
+    receivers: Mutex<HashMap<RowCountKey, oneshot::Receiver<Result<usize>>>>,
+    task: JoinHandle<()>,
+}
+
+impl RowCountPrefetch {
+    fn start(files: &[PartitionedFile], concurrency: usize, fetch: FetchRowCount) -> Self {
+        let mut receivers = HashMap::new();
+        let mut senders = vec![];
+        for file in files {
+            let (sender, receiver) = oneshot::channel();
+            receivers.insert(
+                row_count_key(&file.object_meta, file.range.as_ref()),
+                receiver,
+            );
+            let file_meta = FileMeta {
+                object_meta: file.object_meta.clone(),
+                range: file.range.clone(),
+                extensions: file.extensions.clone(),
+            };
+            senders.push((file_meta, sender));
+        }
+
+        let fetch_cloned = fetch.clone();
+        let task = tokio::spawn(futures::stream::iter(senders).for_each_concurrent(
+            concurrency,
+            move |(file_meta, sender)| {
+                let row_count = fetch_cloned(file_meta);
+                async move {
+                    let _ = sender.send(row_count.await);
+                }
+            },
+        ));
+        Self {
+            fetch,
+            receivers: Mutex::new(receivers),
+            task,
         }
-        .boxed()
     }
 
-    fn get_metadata(
-        &mut self,
-    ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Arc<ParquetMetaData>>> {
-        const METADATA_CACHE_SIZE: usize = 5; // TODO: make it configurable
+    /// returns the prefetched row count of the file, or fetches it directly if
+    /// the file is not prefetched
+    async fn row_count(&self, file_meta: FileMeta) -> Result<usize> {
+        let key = row_count_key(&file_meta.object_meta, file_meta.range.as_ref());
+        let receiver = self.receivers.lock().remove(&key);
+        match receiver {
+            Some(receiver) => match receiver.await {
+                Ok(row_count) => row_count,
+                Err(_) => df_execution_err!("row count prefetching is cancelled"),
+            },
+            None => (self.fetch)(file_meta).await,
+        }
+    }
+}
 
-        type ParquetMetaDataSlot = tokio::sync::OnceCell<Arc<ParquetMetaData>>;
-        type ParquetMetaDataCacheTable = Vec<(ObjectMeta, ParquetMetaDataSlot)>;
-        static METADATA_CACHE: OnceCell<Mutex<ParquetMetaDataCacheTable>> = OnceCell::new();
+impl Drop for RowCountPrefetch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
 
-        let inner = self.0.clone();
-        let meta_size = inner.meta.size;
-        let size_hint = Some(1048576);
-        let cache_slot = (move || {
-            let mut metadata_cache = METADATA_CACHE.get_or_init(|| Mutex::new(Vec::new())).lock();
-
-            // find existed cache slot
-            for (cache_meta, cache_slot) in metadata_cache.iter() {
-                if cache_meta.location == self.0.meta.location {
-                    return cache_slot.clone();
+fn row_count_key(object_meta: &ObjectMeta, range: Option<&FileRange>) -> RowCountKey {
+    let range = range.map(|range| (range.start, range.end));
+    (object_meta.location.clone(), range)
+}
+
+/// Returns the number of rows of a file, or of the row groups selected by
+/// midpoint if the file is split.
+fn count_only_num_rows(metadata: &ParquetMetaData, range: Option<&FileRange>) -> usize {
+    match range {
+        Some(range) => midpoint_row_groups(metadata, range)
+            .into_iter()
+            .map(|i| metadata.row_group(i).num_rows() as usize)
+            .sum(),
+        None => metadata.file_metadata().num_rows() as usize,
+    }
+}
+
+/// Returns whether rows are filtered by the scan predicate while decoding
+/// (`pushdown_filters` of the parquet opener) and whether they are filtered on
+/// decoded batches, mirroring spark's `parquet.recordLevelFilter.enabled`:
+/// - pushdown filtering is coupled with page filtering.
+/// - record-level filter applies the predicate on decoded batches, unless rows
+///   are already filtered while decoding.
+/// - if both are disabled, all decoded rows are returned and filtered by the
+///   downstream filter.
+fn row_filtering_modes(
+    page_filtering_enabled: bool,
+    record_level_filter_enabled: bool,
+) -> (bool, bool) {
+    let pushdown_filters = page_filtering_enabled;
+    let post_decode_filter = record_level_filter_enabled && !pushdown_filters;
+    (pushdown_filters, post_decode_filter)
+}
+
+/// Rebinds the scan predicate to the projected schema, so that it can be
+/// evaluated on decoded batches. returns None if the predicate references
+/// columns not in the projection.
+fn post_decode_predicate(
+    predicate: &Arc<dyn PhysicalExpr>,
+    projected_schema: &SchemaRef,
+) -> Option<Arc<dyn PhysicalExpr>> {
+    reassign_predicate_columns(predicate.clone(), projected_schema, false).ok()
+}
+
+/// Removes rows of a decoded batch not matching the predicate. rows are
+/// filtered after decoding, so positions of rows in the file are unaffected.
+fn filter_decoded_batch(
+    predicate: &Arc<dyn PhysicalExpr>,
+    batch: &RecordBatch,
+    rows_filtered: &Count,
+) -> Result<RecordBatch> {
+    let selected = predicate.evaluate(batch)?.into_array(batch.num_rows())?;
+    let filtered = filter_record_batch(batch, selected.as_boolean())?;
+    rows_filtered.add(batch.num_rows() - filtered.num_rows());
+    Ok(filtered)
+}
+
+/// Reserves memory of an output batch in the task memory pool while it is
+/// being sent, so that memory of in-flight scan output is accounted. a decoded
+/// batch cannot be held back, so the reservation is forced even if it exceeds
+/// the pool, making other consumers of the pool spill instead.
+fn reserve_output_batch(reservation: &mut MemoryReservation, batch: &RecordBatch) {
+    reservation.resize(batch.get_array_memory_size());
+}
+
+/// Accumulates output batches of a scan until reaching a target memory size,
+/// batches exceeding the target are split. sizes of rows are estimated by the
+/// average row size of each input batch. staged rows are flushed as soon as
+/// the scan limit is reached, instead of waiting for the end of input.
+///
+/// staged rows are reserved in the memory pool. when the pool is exhausted,
+/// staged rows are flushed early in smaller batches, and rows which still do
+/// not fit are passed through without being staged.
+struct ByteSizedBatches {
+    target_bytes: usize,
+    limit: Option<usize>,
+    staging_batches: Vec<RecordBatch>,
+    staging_rows: usize,
+    staging_bytes: usize,
+    staging_reservation: MemoryReservation,
+    num_output_rows: usize,
+}
+
+impl ByteSizedBatches {
+    fn new(
+        target_bytes: usize,
+        limit: Option<usize>,
+        staging_reservation: MemoryReservation,
+    ) -> Self {
+        Self {
+            target_bytes,
+            limit,
+            staging_batches: vec![],
+            staging_rows: 0,
+            staging_bytes: 0,
+            staging_reservation,
+            num_output_rows: 0,
+        }
+    }
+
+    /// stages a batch, returns the batches reaching the target size
+    fn push(&mut self, batch: RecordBatch) -> Result<Vec<RecordBatch>> {
+        let num_rows = batch.num_rows();
+        let row_bytes = (batch.get_array_mem_size() / num_rows.max(1)).max(1);
+        let mut output_batches = vec![];
+        let mut start = 0;
+        while start < num_rows {
+            let mut num_fitted_rows =
+                self.target_bytes.saturating_sub(self.staging_bytes) / row_bytes;
+            if num_fitted_rows == 0 {
+                if self.staging_rows > 0 {
+                    output_batches.push(self.flush()?);
+                    continue;
+                }
+                num_fitted_rows = 1; // single row exceeding the target
+            }
+            let len = num_fitted_rows.min(num_rows - start);
+            if self.staging_reservation.try_grow(len * row_bytes).is_err() {
+                if self.staging_rows > 0 {
+                    output_batches.push(self.flush()?);
+                    continue;
                 }
+                self.num_output_rows += len;
+                output_batches.push(batch.slice(start, len));
+                start += len;
+                continue;
             }
+            self.staging_batches.push(batch.slice(start, len));
+            self.staging_rows += len;
+            self.staging_bytes += len * row_bytes;
+            start += len;
 
-            // reserve a new cache slot
-            if metadata_cache.len() >= METADATA_CACHE_SIZE {
-                metadata_cache.remove(0); // remove eldest
+            let limit_reached = self
+                .limit
+                .is_some_and(|limit| self.num_output_rows + self.staging_rows >= limit);
+            if self.staging_bytes >= self.target_bytes || limit_reached {
+                output_batches.push(self.flush()?);
             }
-            let cache_slot = ParquetMetaDataSlot::default();
-            metadata_cache.push((self.0.meta.clone(), cache_slot.clone()));
-            cache_slot
-        })();
+        }
+        Ok(output_batches)
+    }
 
-        // fetch metadata from file and update to cache
-        async move {
-            cache_slot
-                .get_or_try_init(move || async move {
-                    fetch_parquet_metadata(
-                        move |range| {
-                            let inner = inner.clone();
-                            inner.metrics.bytes_scanned.add(range.end - range.start);
-                            async move {
-                                tokio::task::spawn_blocking(move || {
-                                    inner
-                                        .read_fully(range)
-                                        .map_err(|e| ParquetError::External(Box::new(e)))
-                                })
-                                .await
-                                .expect("tokio spawn_blocking error")
-                            }
-                        },
-                        meta_size,
-                        size_hint,
-                    )
-                    .await
-                    .map(|parquet_metadata| Arc::new(parquet_metadata))
-                })
-                .map(|parquet_metadata| parquet_metadata.cloned())
-                .await
+    /// returns the remaining staged rows at the end of input
+    fn finish(&mut self) -> Result<Vec<RecordBatch>> {
+        if self.staging_rows == 0 {
+            return Ok(vec![]);
         }
+        Ok(vec![self.flush()?])
+    }
+
+    fn flush(&mut self) -> Result<RecordBatch> {
+        let staging_batches = std::mem::take(&mut self.staging_batches);
+        let batch = concat_batches(&staging_batches[0].schema(), &staging_batches)?;
+        self.num_output_rows += self.staging_rows;
+        self.staging_rows = 0;
+        self.staging_bytes = 0;
+        self.staging_reservation.free();
+        Ok(batch)
+    }
+}
+
+/// Builds column-less batches carrying only the given number of rows.
+fn count_only_batches(num_rows: usize, batch_size: usize) -> Result<Vec<RecordBatch>> {
+    let schema = Arc::new(Schema::empty());
+    let batch_size = batch_size.max(1);
+    (0..num_rows)
+        .step_by(batch_size)
+        .map(|start| {
+            let options =
+                RecordBatchOptions::new().with_row_count(Some(batch_size.min(num_rows - start)));
+            Ok(RecordBatch::try_new_with_options(
+                schema.clone(),
+                vec![],
+                &options,
+            )?)
+        })
+        .collect()
+}
+
+/// Attaches the file path and partition index to errors of opening and
+/// decoding the file, so that failures mid-file are traceable.
+fn with_file_context(open_future: FileOpenFuture, file_context: String) -> FileOpenFuture {
+    Box::pin(async move {
+        let stream = open_future
+            .await
+            .map_err(|e| e.context(file_context.clone()))?;
+        Ok(stream
+            .map(move |batch| {
+                batch.map_err(|e| {
+                    let err = DataFusionError::External(Box::new(e)).context(file_context.clone());
+                    ArrowError::ExternalError(Box::new(err))
+                })
+            })
+            .boxed())
+    })
+}
+
+/// Reads the given row groups one by one. a row group failed to open or decode
+/// is quarantined: none of its rows are emitted, and the scan continues with
+/// the next row group. batches of a row group are buffered until it is fully
+/// decoded, so rows of corrupted row groups are never partially emitted.
+///
+/// buffered batches are reserved from the memory pool until they are emitted.
+/// failing to reserve them fails the scan, it is not a corruption.
+fn quarantine_corrupted_row_groups(
+    row_groups: Vec<usize>,
+    open_row_group: impl Fn(usize) -> Result<FileOpenFuture> + Send + Sync + 'static,
+    path: String,
+    quarantined_row_groups: Count,
+    memory_pool: Arc<dyn MemoryPool>,
+) -> BoxStream<'static, Result<RecordBatch, ArrowError>> {
+    let open_row_group = Arc::new(open_row_group);
+    futures::stream::iter(row_groups)
+        .then(move |row_group_idx| {
+            let open_row_group = open_row_group.clone();
+            let path = path.clone();
+            let quarantined_row_groups = quarantined_row_groups.clone();
+            let mut reservation =
+                MemoryConsumer::new(format!("ParquetRowGroupQuarantine[{path}]"))
+                    .register(&memory_pool);
+            async move {
+                // outer errors are decoding errors, inner errors are reservation
+                // errors
+                let mut batches = vec![];
+                let decoded: Result<Result<()>> = async {
+                    let mut stream = open_row_group(row_group_idx)?.await?;
+                    while let Some(batch) = stream.next().await.transpose()? {
+                        if let Err(e) = reservation.try_grow(batch.get_array_memory_size()) {
+                            return Ok(Err(e));
+                        }
+                        batches.push(batch);
+                    }
+                    Ok(Ok(()))
+                }
+                .await;
+                match decoded {
+                    Ok(reserved) => reserved?,
+                    Err(e) => {
+                        log::warn!(
+                            "quarantined corrupted row group {row_group_idx} of parquet file {path}: {e}"
+                        );
+                        quarantined_row_groups.add(1);
+                        batches.clear();
+                        reservation.free();
+                    }
+                }
+                Ok::<_, DataFusionError>((batches, reservation))
+            }
+        })
+        .map_ok(|(batches, mut reservation)| {
+            futures::stream::iter(batches.into_iter().map(move |batch| {
+                reservation.shrink(batch.get_array_memory_size());
+                Ok::<_, ArrowError>(batch)
+            }))
+        })
+        .map_err(|e| ArrowError::ExternalError(Box::new(e)))
+        .try_flatten()
         .boxed()
+}
+
+/// Drops rows deleted by the deletion vector from row groups of a file opened
+/// one by one, by their positions in the file. each opened row group must
+/// produce all its rows in order, or nothing if it is pruned as a whole, see
+/// [`opener_with_row_positions`].
+///
+/// positions of the first rows of row groups are looked up by their first
+/// page offsets in `first_rows`, see [`first_row_positions`], since row groups
+/// may have been pruned from `metadata` by dictionaries.
+///
+/// selection masks are built in recycled buffers if scratch bitmaps are
+/// given, see spark.blaze.parquet.reuseScratchBuffers.
+fn delete_rows(
+    metadata: &ParquetMetaData,
+    first_rows: HashMap<i64, u64>,
+    row_groups: Vec<usize>,
+    open_row_group: impl Fn(usize) -> Result<FileOpenFuture> + Send + Sync + 'static,
+    deletion_vector: DeletionVector,
+    rows_deleted: Count,
+    scratch_bitmaps: Option<Arc<Mutex<ScratchBitmaps>>>,
+) -> Result<BoxStream<'static, Result<RecordBatch, ArrowError>>> {
+    let row_groups = row_groups
+        .into_iter()
+        .map(|row_group_idx| {
+            let offset = row_group_offset(metadata.row_group(row_group_idx));
+            let first_row = first_rows.get(&offset).copied().ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "cannot find row group at offset {offset} in the footer"
+                ))
+            })?;
+            Ok((row_group_idx, first_row))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let open_row_group = Arc::new(open_row_group);
+    let deletion_vector = Arc::new(deletion_vector);
+    let stream =
+        futures::stream::iter(row_groups)
+            .then(move |(row_group_idx, first_row)| {
+                let open_row_group = open_row_group.clone();
+                async move {
+                    Ok::<_, DataFusionError>((first_row, open_row_group(row_group_idx)?.await?))
+                }
+            })
+            .map_ok(move |(first_row, stream)| {
+                let deletion_vector = deletion_vector.clone();
+                let rows_deleted = rows_deleted.clone();
+                let scratch_bitmaps = scratch_bitmaps.clone();
+                let mut position = first_row;
+                stream.map(move |batch| {
+                    let batch = batch?;
+                    let num_rows = batch.num_rows();
+                    let selection = match &scratch_bitmaps {
+                        Some(scratch_bitmaps) => {
+                            let mut builder = scratch_bitmaps.lock().builder(num_rows);
+                            deletion_vector.append_selection(position, num_rows, &mut builder);
+                            BooleanArray::new(builder.finish(), None)
+                        }
+                        None => deletion_vector.selection(position, num_rows),
+                    };
+                    position += num_rows as u64;
+                    let num_deleted = num_rows - selection.true_count();
+                    let batch = if num_deleted > 0 {
+                        rows_deleted.add(num_deleted);
+                        filter_record_batch(&batch, &selection)?
+                    } else {
+                        batch
+                    };
+                    if let Some(scratch_bitmaps) = &scratch_bitmaps {
+                        scratch_bitmaps.lock().recycle(selection);
+                    }
+                    Ok(batch)
+                })
+            })
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)))
+            .try_flatten()
+            .boxed();
+    Ok(stream)
+}
+
+/// Returns positions of the first rows of row groups in a file by their first
+/// page offsets, counted in the footer with all row groups.
+fn first_row_positions(metadata: &ParquetMetaData) -> HashMap<i64, u64> {
+    let mut first_rows = HashMap::new();
+    let mut num_rows = 0;
+    for rg in metadata.row_groups() {
+        if rg.num_columns() > 0 {
+            first_rows.insert(row_group_offset(rg), num_rows);
+        }
+        num_rows += rg.num_rows() as u64;
+    }
+    first_rows
+}
+
+/// Returns the share of dictionary-encoded column chunks in the projected
+/// columns of a file, weighted by uncompressed size. column names are
+/// lowercased top-level names.
+fn dictionary_encoded_share(metadata: &ParquetMetaData, column_names: &HashSet<String>) -> f64 {
+    let mut dictionary_bytes = 0;
+    let mut total_bytes = 0;
+    for rg in metadata.row_groups() {
+        for column in rg.columns() {
+            let root = &column.column_path().parts()[0];
+            if !column_names.contains(&root.to_lowercase()) {
+                continue;
+            }
+            total_bytes += column.uncompressed_size();
+            if column.dictionary_page_offset().is_some() {
+                dictionary_bytes += column.uncompressed_size();
+            }
+        }
+    }
+    if total_bytes == 0 {
+        return 0.0;
+    }
+    dictionary_bytes as f64 / total_bytes as f64
+}
+
+/// Accumulates the estimated time of decoding dictionary-encoded data of a
+/// file stream, reported as `estimated_dictionary_decode_time`: decoding
+/// happens while polling the stream (reads are awaited, not blocking), and
+/// the time of each poll is attributed to dictionary decoding by the share of
+/// dictionary-encoded column chunks. this is not a measurement, only cheap
+/// and coarse enough to tell whether dictionary expansion is the bottleneck
+/// of a scan.
+fn estimate_dictionary_decode_time(
+    mut stream: BoxStream<'static, Result<RecordBatch, ArrowError>>,
+    dictionary_share: f64,
+    estimated_time: Time,
+) -> BoxStream<'static, Result<RecordBatch, ArrowError>> {
+    if dictionary_share <= 0.0 {
+        return stream;
+    }
+    futures::stream::poll_fn(move |cx| {
+        let start = Instant::now();
+        let poll = stream.poll_next_unpin(cx);
+        estimated_time.add_duration(start.elapsed().mul_f64(dictionary_share));
+        poll
+    })
+    .boxed()
+}
+
+/// Skips files failed to open, including failures of reading footers which
+/// happen before decoding. errors while decoding are skipped by `FileStream`
+/// with `OnError::Skip`.
+fn skip_corrupted_file(
+    open_future: FileOpenFuture,
+    skipped_corrupted_files: Count,
+) -> FileOpenFuture {
+    Box::pin(async move {
+        match open_future.await {
+            Ok(stream) => Ok(stream),
+            Err(e) => {
+                log::warn!("skipped corrupted parquet file: {e}");
+                skipped_corrupted_files.add(1);
+                Ok(futures::stream::empty().boxed())
+            }
+        }
+    })
+}
+
+/// Delays opening the file until a permit of the given semaphore is acquired,
+/// the permit is released when the opened stream is dropped.
+fn limit_open_files(open_future: FileOpenFuture, open_files: Arc<Semaphore>) -> FileOpenFuture {
+    Box::pin(async move {
+        let permit = open_files
+            .acquire_owned()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        let stream = open_future.await?;
+        Ok(stream
+            .map(move |batch| {
+                let _permit = &permit;
+                batch
+            })
+            .boxed())
+    })
+}
+
+/// Casts partition values of the given files to the declared types of the
+/// partition columns, e.g. `dt=2024` declared as int or date. unparseable
+/// values become null, or fail the scan in strict mode.
+fn cast_partition_values(
+    files: &[PartitionedFile],
+    partition_cols: &[Field],
+    strict: bool,
+) -> Result<Vec<PartitionedFile>> {
+    files
+        .iter()
+        .map(|file| {
+            let partition_values = file
+                .partition_values
+                .iter()
+                .zip(partition_cols)
+                .map(|(value, field)| cast_partition_value(value, field, strict))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(PartitionedFile {
+                partition_values,
+                ..file.clone()
+            })
+        })
+        .collect()
+}
+
+fn cast_partition_value(value: &ScalarValue, field: &Field, strict: bool) -> Result<ScalarValue> {
+    if &value.data_type() == field.data_type() {
+        return Ok(value.clone());
+    }
+    if let DataType::Dictionary(key_type, value_type) = field.data_type() {
+        // values are cast to the value type first, see
+        // ParquetExec::with_dictionary_partition_columns
+        let value = match value {
+            ScalarValue::Dictionary(_, value) => value.as_ref(),
+            value => value,
+        };
+        let value_field = field.clone().with_data_type(value_type.as_ref().clone());
+        let casted = cast_partition_value(value, &value_field, strict)?;
+        return Ok(ScalarValue::Dictionary(key_type.clone(), Box::new(casted)));
+    }
+    let casted = cast(&value.to_array()?, field.data_type())?;
+    if strict && casted.is_null(0) && !value.is_null() {
+        return df_execution_err!(
+            "cannot cast partition value {value} of column {} to {}",
+            field.name(),
+            field.data_type(),
+        );
+    }
+    ScalarValue::try_from_array(&casted, 0)
+}
+
+/// Max bytes of trailing padding after the footer of a parquet file, like
+/// padding of files written to preallocated blocks.
+const MAX_FOOTER_PADDING: usize = 65536;
+
+/// Finds the footer of a file whose magic is followed by zero padding, given
+/// the tail of the file at the given offset. returns the end offset of the
+/// footer in the file, or None if the tail has no padded footer.
+///
+/// padding is only recognized as zero bytes, so the last non-zero byte must
+/// be the end of the magic, and the metadata length before it must fit in
+/// the file.
+fn find_padded_footer_end(tail: &[u8], tail_offset: usize) -> Option<usize> {
+    let footer_end = tail.iter().rposition(|&b| b != 0)? + 1;
+    if footer_end == tail.len() || footer_end < FOOTER_SIZE {
+        return None; // no padding, or footer not in the tail
+    }
+    let footer: &[u8; FOOTER_SIZE] = tail[footer_end - FOOTER_SIZE..footer_end]
+        .try_into()
+        .unwrap();
+    let metadata_len = decode_footer(footer).ok()?;
+
+    // the metadata must follow the leading magic of the file
+    if metadata_len + FOOTER_SIZE + 4 > tail_offset + footer_end {
+        return None;
+    }
+    Some(tail_offset + footer_end)
+}
+
+/// Decodes the metadata length from the footer at the end of the given file
+/// suffix, failing if it exceeds the given limit.
+fn check_metadata_size(
+    suffix: &[u8],
+    max_metadata_bytes: Option<usize>,
+) -> datafusion::parquet::errors::Result<usize> {
+    if suffix.len() < FOOTER_SIZE {
+        return Ok(0); // invalid footer, let the metadata decoder report it
+    }
+    let footer: &[u8; FOOTER_SIZE] = suffix[suffix.len() - FOOTER_SIZE..].try_into().unwrap();
+    let metadata_len = decode_footer(footer)?;
+    if let Some(max_metadata_bytes) = max_metadata_bytes {
+        if metadata_len > max_metadata_bytes {
+            return Err(ParquetError::General(format!(
+                "parquet metadata size {metadata_len} exceeds the limit {max_metadata_bytes}, \
+                 see spark.blaze.parquet.maxMetadataBytes"
+            )));
+        }
+    }
+    Ok(metadata_len)
+}
+
+/// Row groups of a file decoded in units of at most `max_row_groups`
+/// contiguous row groups, each unit opened by itself after the previous one
+/// is exhausted, bounding the metadata and buffers held by a single decode.
+///
+/// units are decoded in batches whose sizes are estimated from uncompressed
+/// sizes of the projected column chunks to fit `target_bytes`, so that a row
+/// group with huge rows is chunked into multiple smaller batches instead of
+/// decoding `batch_size` rows at once. tiny row groups are decoded together
+/// within a unit and coalesced into output batches.
+#[derive(Debug, Clone, Copy)]
+struct RowGroupBatching {
+    max_row_groups: usize,
+    target_bytes: Option<usize>,
+}
+
+/// Contiguous row groups of a file decoded together, with the number of rows
+/// of decoded batches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DecodeUnit {
+    row_groups: Range<usize>,
+    batch_size: usize,
+}
+
+impl RowGroupBatching {
+    /// splits the selected (contiguous) row groups into decode units. column
+    /// names are lowercased top-level names of the projected columns.
+    fn decode_units(
+        &self,
+        metadata: &ParquetMetaData,
+        row_groups: &[usize],
+        column_names: &HashSet<String>,
+        batch_size: usize,
+    ) -> Vec<DecodeUnit> {
+        row_groups
+            .chunks(self.max_row_groups.max(1))
+            .map(|chunk| {
+                let max_row_bytes = chunk
+                    .iter()
+                    .map(|&i| projected_row_bytes(metadata.row_group(i), column_names))
+                    .max()
+                    .unwrap_or(0);
+                let batch_size = match self.target_bytes {
+                    Some(target_bytes) if max_row_bytes > 0 => {
+                        (target_bytes / max_row_bytes).clamp(1, batch_size.max(1))
+                    }
+                    _ => batch_size,
+                };
+                DecodeUnit {
+                    row_groups: chunk[0]..chunk[chunk.len() - 1] + 1,
+                    batch_size,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the estimated uncompressed size of a row of the projected columns
+/// in a row group. column names are lowercased top-level names.
+fn projected_row_bytes(rg: &RowGroupMetaData, column_names: &HashSet<String>) -> usize {
+    let projected_bytes = rg
+        .columns()
+        .iter()
+        .filter(|column| column_names.contains(&column.column_path().parts()[0].to_lowercase()))
+        .map(|column| column.uncompressed_size().max(0) as usize)
+        .sum::<usize>();
+    projected_bytes.div_ceil(rg.num_rows().max(1) as usize)
+}
+
+/// Returns a copy of the opener decoding batches of the given number of rows.
+fn opener_with_batch_size(opener: &Arc<ParquetOpener>, batch_size: usize) -> Arc<ParquetOpener> {
+    if opener.batch_size == batch_size {
+        return opener.clone();
+    }
+    Arc::new(ParquetOpener {
+        partition_index: opener.partition_index,
+        projection: opener.projection.clone(),
+        batch_size,
+        limit: opener.limit,
+        predicate: opener.predicate.clone(),
+        pruning_predicate: opener.pruning_predicate.clone(),
+        page_pruning_predicate: opener.page_pruning_predicate.clone(),
+        table_schema: opener.table_schema.clone(),
+        metadata_size_hint: opener.metadata_size_hint,
+        metrics: opener.metrics.clone(),
+        parquet_file_reader_factory: opener.parquet_file_reader_factory.clone(),
+        pushdown_filters: opener.pushdown_filters,
+        reorder_filters: opener.reorder_filters,
+        enable_page_index: opener.enable_page_index,
+        enable_bloom_filter: opener.enable_bloom_filter,
+    })
+}
+
+/// Returns an opener producing all rows of opened row groups in order, so that
+/// rows can be located by their positions in files: pages are not pruned, and
+/// rows are neither filtered nor limited inside the reader. row groups may
+/// still be pruned as a whole.
+fn opener_with_row_positions(opener: &Arc<ParquetOpener>) -> Arc<ParquetOpener> {
+    Arc::new(ParquetOpener {
+        partition_index: opener.partition_index,
+        projection: opener.projection.clone(),
+        batch_size: opener.batch_size,
+        limit: None,
+        predicate: opener.predicate.clone(),
+        pruning_predicate: opener.pruning_predicate.clone(),
+        page_pruning_predicate: None,
+        table_schema: opener.table_schema.clone(),
+        metadata_size_hint: opener.metadata_size_hint,
+        metrics: opener.metrics.clone(),
+        parquet_file_reader_factory: opener.parquet_file_reader_factory.clone(),
+        pushdown_filters: false,
+        reorder_filters: false,
+        enable_page_index: false,
+        enable_bloom_filter: opener.enable_bloom_filter,
+    })
+}
+
+/// Returns indices of non-empty row groups of a file, or of a split of the
+/// file selected by midpoints.
+fn selected_row_groups(metadata: &ParquetMetaData, range: Option<&FileRange>) -> Vec<usize> {
+    match range {
+        Some(range) => midpoint_row_groups(metadata, range),
+        None => (0..metadata.num_row_groups())
+            .filter(|&i| metadata.row_group(i).num_columns() > 0)
+            .collect(),
+    }
+}
+
+/// Returns indices of row groups whose midpoints fall in the given range.
+fn midpoint_row_groups(metadata: &ParquetMetaData, range: &FileRange) -> Vec<usize> {
+    metadata
+        .row_groups()
+        .iter()
+        .enumerate()
+        .filter(|(_, rg)| rg.num_columns() > 0)
+        .filter(|(_, rg)| {
+            let mid = row_group_offset(rg) + rg.compressed_size() / 2;
+            mid >= range.start && mid < range.end
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn row_group_offset(rg: &RowGroupMetaData) -> i64 {
+    let col = rg.column(0);
+    col.dictionary_page_offset()
+        .unwrap_or_else(|| col.data_page_offset())
+}
+
+/// Returns the range of first-page offsets of row groups whose midpoints
+/// fall in the given range.
+fn midpoint_file_range(metadata: &ParquetMetaData, range: &FileRange) -> FileRange {
+    let row_group_offsets = midpoint_row_groups(metadata, range)
+        .into_iter()
+        .map(|i| row_group_offset(metadata.row_group(i)))
+        .collect::<Vec<_>>();
+
+    match (row_group_offsets.first(), row_group_offsets.last()) {
+        (Some(&first), Some(&last)) => FileRange {
+            start: first,
+            end: last + 1,
+        },
+        _ => FileRange { start: 0, end: 0 }, // no row groups selected
+    }
+}
+
+fn get_fs_provider(fs_resource_id: &str, io_time: &Time) -> Result<Arc<FsProvider>> {
+    let resource_id = jni_new_string!(fs_resource_id)?;
+    let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+    Ok(Arc::new(FsProvider::new(
+        jni_new_global_ref!(fs.as_obj())?,
+        io_time,
+    )))
+}
+
+/// Returns a fetch of deletion vectors from the jvm resource of the given id,
+/// see [`ParquetExec::with_deletion_vectors`].
+fn get_deletion_vector_fetch(resource_id: &str) -> Result<FetchDeletionVector> {
+    let resource_id = jni_new_string!(resource_id)?;
+    let provider = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+    let provider = jni_new_global_ref!(provider.as_obj())?;
+    Ok(Arc::new(move |path: &str| {
+        let bytes = jni_call!(
+            ScalaFunction1(provider.as_obj()).apply(jni_new_string!(path)?.as_obj()) -> JObject
+        )?;
+        if bytes.as_obj().is_null() {
+            return Ok(None);
+        }
+        let bytes = jni_convert_byte_array!(bytes.as_obj())?;
+        Ok(Some(DeletionVector::from_delta_bytes(&bytes)?))
+    }))
+}
+
+/// Fs resource id of a single file, carried in `PartitionedFile::extensions`.
+/// files of a scan can be read from different storage systems (like hot data
+/// on hdfs and cold data on s3) by specifying their own fs resources.
+#[derive(Debug, Clone)]
+pub struct FileFsResourceId(pub String);
+
+/// Resolves fs providers of files. files without [`FileFsResourceId`] are read
+/// with the default provider, other providers are resolved once per resource
+/// id and cached.
+pub struct FsProviderResolver<P = Arc<FsProvider>> {
+    default: P,
+    resolve: Option<Box<dyn Fn(&str) -> Result<P> + Send + Sync>>,
+    resolved: Mutex<HashMap<String, P>>,
+}
+
+impl<P: Clone> FsProviderResolver<P> {
+    pub fn new(default: P) -> Self {
+        Self {
+            default,
+            resolve: None,
+            resolved: Mutex::default(),
+        }
+    }
+
+    pub fn with_resolve(self, resolve: impl Fn(&str) -> Result<P> + Send + Sync + 'static) -> Self {
+        Self {
+            resolve: Some(Box::new(resolve)),
+            ..self
+        }
+    }
+
+    pub fn resolve(&self, extensions: Option<&Arc<dyn Any + Send + Sync>>) -> Result<P> {
+        let Some(FileFsResourceId(resource_id)) =
+            extensions.and_then(|extensions| extensions.downcast_ref::<FileFsResourceId>())
+        else {
+            return Ok(self.default.clone());
+        };
+
+        let mut resolved = self.resolved.lock();
+        if let Some(provider) = resolved.get(resource_id) {
+            return Ok(provider.clone());
+        }
+        let Some(resolve) = &self.resolve else {
+            return df_execution_err!("cannot resolve fs resource: {resource_id}");
+        };
+        let provider = resolve(resource_id)?;
+        resolved.insert(resource_id.clone(), provider.clone());
+        Ok(provider)
+    }
+}
+
+/// Native object stores of files read without the jvm fs, one store per
+/// scheme and authority (like `s3://bucket`). stores are built from the given
+/// options (like `aws_access_key_id`) when first used, only schemes enabled
+/// by the object_store features are supported.
+pub struct NativeObjectStores {
+    options: HashMap<String, String>,
+    stores: Mutex<HashMap<String, Arc<dyn ObjectStore>>>,
+}
+
+impl NativeObjectStores {
+    pub fn new(options: HashMap<String, String>) -> Self {
+        Self {
+            options,
+            stores: Mutex::default(),
+        }
+    }
+
+    /// uses the given store for files of the scheme and authority, like
+    /// `s3://bucket`, instead of building one from the options
+    pub fn with_store(self, base_url: &str, store: Arc<dyn ObjectStore>) -> Self {
+        self.stores
+            .lock()
+            .insert(base_url.trim_end_matches('/').to_string(), store);
+        self
+    }
+
+    /// returns the store and the location in the store of a file path
+    pub fn resolve(&self, path: &str) -> Result<(Arc<dyn ObjectStore>, Path)> {
+        let url = Url::parse(path)
+            .or_else(|err| df_execution_err!("cannot parse file path {path}: {err}"))?;
+        let location = Path::from_url_path(url.path())
+            .or_else(|err| df_execution_err!("invalid file path {path}: {err}"))?;
+        let base_url = format!("{}://{}", url.scheme(), url.authority());
+
+        let mut stores = self.stores.lock();
+        if let Some(store) = stores.get(&base_url) {
+            return Ok((store.clone(), location));
+        }
+        let (store, _) = object_store::parse_url_opts(&url, &self.options)?;
+        let store: Arc<dyn ObjectStore> = Arc::from(store);
+        stores.insert(base_url, store.clone());
+        Ok((store, location))
+    }
+}
+
+static NATIVE_OBJECT_STORES: OnceCell<Arc<NativeObjectStores>> = OnceCell::new();
+
+/// Registers the options of native object stores (usually taken from spark
+/// confs, like credentials and endpoints), used by parquet scans when native
+/// object stores are enabled. must be called before any parquet file is read.
+pub fn register_native_object_store_options(options: HashMap<String, String>) -> Result<()> {
+    let object_stores = Arc::new(NativeObjectStores::new(options));
+    if NATIVE_OBJECT_STORES.set(object_stores).is_err() {
+        return df_execution_err!("native object store options are already registered");
+    }
+    Ok(())
+}
+
+fn native_object_stores() -> Arc<NativeObjectStores> {
+    NATIVE_OBJECT_STORES
+        .get_or_init(|| Arc::new(NativeObjectStores::new(HashMap::new())))
+        .clone()
+}
+
+/// File systems of a reader factory, either the jvm fs through jni or native
+/// object stores.
+#[derive(Clone)]
+enum FileSystems {
+    Fs(Arc<FsProviderResolver>),
+    ObjectStore(Arc<NativeObjectStores>),
+}
+
+/// The file system of a single file.
+enum FileSource {
+    Fs(Arc<FsProvider>),
+    ObjectStore(Arc<dyn ObjectStore>, Path),
+}
+
+#[derive(Clone)]
+pub struct FsReaderFactory {
+    file_systems: FileSystems,
+    strict_schema: Option<Arc<StrictSchema>>,
+    extra_columns_warning: Option<Arc<ExtraColumnsWarning>>,
+    dictionary_filter: Option<Arc<DictionaryFilter>>,
+    max_metadata_bytes: Option<usize>,
+    inline_read_threshold: usize,
+    opened_files: Arc<OpenedFiles>,
+    empty_row_groups_counted: Arc<OpenedFiles>,
+    bytes_budget: Option<Arc<ScanBytesBudget>>,
+    bytes_limit: Option<Arc<ScanBytesLimit>>,
+    footer_projection: Option<Arc<FooterProjection>>,
+    field_id_resolver: Option<Arc<FieldIdResolver>>,
+    map_children_projection: Option<Arc<MapChildrenProjection>>,
+    duplicate_column_resolver: Option<Arc<DuplicateColumnResolver>>,
+    io_latency_sampler: Option<Arc<IoLatencySampler>>,
+    verify_page_checksums: bool,
+    geometry_as_binary: bool,
+    path_rewriter: Option<PathRewriter>,
+}
+
+impl FsReaderFactory {
+    pub fn new(fs_provider: Arc<FsProvider>) -> Self {
+        Self::new_with_resolver(Arc::new(FsProviderResolver::new(fs_provider)))
+    }
+
+    pub fn new_with_resolver(fs_providers: Arc<FsProviderResolver>) -> Self {
+        Self::new_with_file_systems(FileSystems::Fs(fs_providers))
+    }
+
+    /// reads files with native object stores using their async api, without
+    /// going through the jvm fs or the blocking thread pool
+    pub fn new_with_object_stores(object_stores: Arc<NativeObjectStores>) -> Self {
+        Self::new_with_file_systems(FileSystems::ObjectStore(object_stores))
+    }
+
+    fn new_with_file_systems(file_systems: FileSystems) -> Self {
+        Self {
+            file_systems,
+            strict_schema: None,
+            extra_columns_warning: None,
+            dictionary_filter: None,
+            max_metadata_bytes: None,
+            inline_read_threshold: 0,
+            opened_files: Arc::default(),
+            empty_row_groups_counted: Arc::default(),
+            bytes_budget: None,
+            bytes_limit: None,
+            footer_projection: None,
+            field_id_resolver: None,
+            map_children_projection: None,
+            duplicate_column_resolver: None,
+            io_latency_sampler: None,
+            verify_page_checksums: false,
+            geometry_as_binary: false,
+            path_rewriter: PATH_REWRITER.get().cloned(),
+        }
+    }
+
+    /// rewrites file paths before resolving the file system, overriding the
+    /// registered rewriter, see [`register_path_rewriter`]
+    pub fn with_path_rewriter(self, path_rewriter: PathRewriter) -> Self {
+        Self {
+            path_rewriter: Some(path_rewriter),
+            ..self
+        }
+    }
+
+    /// decodes footers with at least `min_columns` leaf columns keeping full
+    /// column chunk metadata (like statistics) of the given columns only,
+    /// which saves a lot of memory and cpu for very wide schemas
+    pub fn with_projected_footer(
+        self,
+        min_columns: usize,
+        column_names: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            footer_projection: Some(Arc::new(FooterProjection::new(min_columns, column_names))),
+            ..self
+        }
+    }
+
+    /// resolves top-level file columns to columns of the table schema by
+    /// parquet field ids and names in the given order, instead of by names
+    /// only
+    pub fn with_field_id_resolution(self, order: FieldIdResolution, table_schema: &Schema) -> Self {
+        Self {
+            field_id_resolver: Some(Arc::new(FieldIdResolver::new(order, table_schema))),
+            ..self
+        }
+    }
+
+    /// reads map columns as lists of their keys or values only
+    pub fn with_map_children_projection(self, projection: MapChildrenProjection) -> Self {
+        Self {
+            map_children_projection: Some(Arc::new(projection)),
+            ..self
+        }
+    }
+
+    /// fails reading files with top-level columns ambiguously matching a
+    /// table column (like `id` and `ID`), or reads the first of them if the
+    /// resolver picks the first column
+    pub fn with_duplicate_column_resolver(self, resolver: DuplicateColumnResolver) -> Self {
+        Self {
+            duplicate_column_resolver: Some(Arc::new(resolver)),
+            ..self
+        }
+    }
+
+    /// samples latencies of reads by all readers created from this factory,
+    /// latency metrics are updated whenever a file is closed
+    pub fn with_io_latency_sampler(self, io_latency_sampler: IoLatencySampler) -> Self {
+        Self {
+            io_latency_sampler: Some(Arc::new(io_latency_sampler)),
+            ..self
+        }
+    }
+
+    /// fails the scan once the total bytes read by all readers created from
+    /// this factory exceeds the given size, guarding against mis-estimated
+    /// queries scanning far more data than expected
+    pub fn with_max_bytes_per_partition(self, max_bytes: usize) -> Self {
+        Self {
+            bytes_budget: Some(Arc::new(ScanBytesBudget::new(max_bytes))),
+            ..self
+        }
+    }
+
+    /// verifies CRC32 checksums of pages read from column chunks, failing the
+    /// read (as a corrupted file) on mismatches. pages written without
+    /// checksums are not verified.
+    pub fn with_page_checksum_verification(self) -> Self {
+        Self {
+            verify_page_checksums: true,
+            ..self
+        }
+    }
+
+    /// reads GEOMETRY/GEOGRAPHY columns as plain WKB binary, instead of
+    /// failing files with these logical types, see
+    /// [`drop_unknown_logical_types`]
+    pub fn with_geometry_as_binary(self) -> Self {
+        Self {
+            geometry_as_binary: true,
+            ..self
+        }
+    }
+
+    /// counts bytes read by all readers created from this factory against the
+    /// given limit, see [`stop_at_bytes_limit`]
+    fn with_bytes_limit(self, bytes_limit: Arc<ScanBytesLimit>) -> Self {
+        Self {
+            bytes_limit: Some(bytes_limit),
+            ..self
+        }
+    }
+
+    /// reads not larger than the given size run inline on the async executor,
+    /// avoiding the blocking pool dispatch which costs more than tiny reads on
+    /// fast local filesystems
+    pub fn with_inline_read_threshold(self, inline_read_threshold: usize) -> Self {
+        Self {
+            inline_read_threshold,
+            ..self
+        }
+    }
+
+    /// refuses to load footers larger than the given size, protecting from
+    /// pathological files with huge metadata (like millions of row groups)
+    pub fn with_max_metadata_bytes(self, max_metadata_bytes: usize) -> Self {
+        Self {
+            max_metadata_bytes: Some(max_metadata_bytes),
+            ..self
+        }
+    }
+
+    /// fails reading files whose projected columns do not match the table
+    /// schema, instead of silently casting or nulling them. columns are
+    /// resolved the same way as the schema adapter, see [`StrictSchema`].
+    pub fn with_strict_schema(
+        self,
+        table_schema: SchemaRef,
+        projection: Vec<usize>,
+        case_sensitive: bool,
+    ) -> Self {
+        Self {
+            strict_schema: Some(Arc::new(StrictSchema {
+                table_schema,
+                projection,
+                case_sensitive,
+            })),
+            ..self
+        }
+    }
+
+    /// logs a warning for each file with top-level columns unknown to the
+    /// table schema, which are ignored when reading, for detecting schema
+    /// drifts of the files
+    pub fn with_extra_columns_warning(self, table_schema: &Schema, case_sensitive: bool) -> Self {
+        Self {
+            extra_columns_warning: Some(Arc::new(ExtraColumnsWarning::new(
+                table_schema,
+                case_sensitive,
+            ))),
+            ..self
+        }
+    }
+
+    /// skips row groups whose dictionaries contain none of the values of an
+    /// equality or IN predicate, which statistics cannot tell when the values
+    /// are within the min/max bounds
+    pub fn with_dictionary_filter(self, dictionary_filter: DictionaryFilter) -> Self {
+        Self {
+            dictionary_filter: Some(Arc::new(dictionary_filter)),
+            ..self
+        }
+    }
+
+    /// reads footers with all row groups, like for counting row positions
+    pub fn without_dictionary_filter(self) -> Self {
+        Self {
+            dictionary_filter: None,
+            ..self
+        }
+    }
+}
+
+impl Debug for FsReaderFactory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "FsReaderFactory")
+    }
+}
+
+impl ParquetFileReaderFactory for FsReaderFactory {
+    fn create_reader(
+        &self,
+        partition_index: usize,
+        file_meta: FileMeta,
+        _metadata_size_hint: Option<usize>,
+        metrics: &ExecutionPlanMetricsSet,
+    ) -> Result<Box<dyn AsyncFileReader + Send>> {
+        let reader = self.create_file_reader(partition_index, file_meta, metrics)?;
+        Ok(Box::new(reader))
+    }
+}
+
+impl FsReaderFactory {
+    fn create_file_reader(
+        &self,
+        partition_index: usize,
+        file_meta: FileMeta,
+        metrics: &ExecutionPlanMetricsSet,
+    ) -> Result<ParquetFileReaderRef> {
+        let object_meta = match &self.path_rewriter {
+            Some(path_rewriter) => rewrite_file_path(file_meta.object_meta, path_rewriter)?,
+            None => file_meta.object_meta,
+        };
+        let source = match &self.file_systems {
+            FileSystems::Fs(fs_providers) => {
+                FileSource::Fs(fs_providers.resolve(file_meta.extensions.as_ref())?)
+            }
+            FileSystems::ObjectStore(object_stores) => {
+                let (store, location) = object_stores.resolve(&decode_file_path(&object_meta)?)?;
+                FileSource::ObjectStore(store, location)
+            }
+        };
+        Ok(ParquetFileReaderRef(Arc::new(ParquetFileReader {
+            source,
+            strict_schema: self.strict_schema.clone(),
+            extra_columns_warning: self.extra_columns_warning.clone(),
+            dictionary_filter: self.dictionary_filter.clone(),
+            row_groups_pruned_by_dictionary: MetricBuilder::new(metrics)
+                .counter("row_groups_pruned_by_dictionary", partition_index),
+            max_metadata_bytes: self.max_metadata_bytes,
+            inline_read_threshold: self.inline_read_threshold,
+            metadata_bytes: MetricBuilder::new(metrics).counter("metadata_bytes", partition_index),
+            opened_files: self.opened_files.clone(),
+            empty_row_groups_counted: self.empty_row_groups_counted.clone(),
+            skipped_empty_row_groups: MetricBuilder::new(metrics)
+                .counter("skipped_empty_row_groups", partition_index),
+            bytes_budget: self.bytes_budget.clone(),
+            bytes_limit: self.bytes_limit.clone(),
+            footer_projection: self.footer_projection.clone(),
+            field_id_resolver: self.field_id_resolver.clone(),
+            map_children_projection: self.map_children_projection.clone(),
+            duplicate_column_resolver: self.duplicate_column_resolver.clone(),
+            io_latency_sampler: self.io_latency_sampler.clone(),
+            verify_page_checksums: self.verify_page_checksums,
+            geometry_as_binary: self.geometry_as_binary,
+            pages_checksum_failed: MetricBuilder::new(metrics)
+                .counter("pages_checksum_failed", partition_index),
+            files_opened: MetricBuilder::new(metrics).counter("files_opened", partition_index),
+            input: LazyInput::default(),
+            metrics: ParquetFileMetrics::new(
+                partition_index,
+                object_meta
+                    .location
+                    .filename()
+                    .unwrap_or("__default_filename__"),
+                metrics,
+            ),
+            meta: object_meta,
+        })))
+    }
+}
+
+/// Width of time windows in which read latencies are averaged.
+const IO_LATENCY_WINDOW: Duration = Duration::from_secs(10);
+
+/// Rewrites the path of a file before it is opened, like `s3a://` to `s3://`
+/// or redirecting a stale mount point.
+pub type PathRewriter = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+static PATH_REWRITER: OnceCell<PathRewriter> = OnceCell::new();
+
+/// Registers the process-wide path rewriter used by parquet scans, so that
+/// operators can migrate file locations without rewriting spark plans. must
+/// be called before any parquet file is read.
+pub fn register_path_rewriter(path_rewriter: PathRewriter) -> Result<()> {
+    if PATH_REWRITER.set(path_rewriter).is_err() {
+        return df_execution_err!("parquet path rewriter is already registered");
+    }
+    Ok(())
+}
+
+/// applies the rewriter on the file path encoded in the object location,
+/// rewritten files are also cached by their rewritten locations
+fn rewrite_file_path(object_meta: ObjectMeta, path_rewriter: &PathRewriter) -> Result<ObjectMeta> {
+    let path = decode_file_path(&object_meta)?;
+    let rewritten = path_rewriter(&path);
+    if rewritten == path {
+        return Ok(object_meta);
+    }
+    let mut parts = object_meta.location.parts().collect::<Vec<_>>();
+    parts.pop();
+    parts.push(PathPart::from(BASE64_URL_SAFE_NO_PAD.encode(rewritten)));
+    Ok(ObjectMeta {
+        location: Path::from_iter(parts),
+        ..object_meta
+    })
+}
+
+/// Tracks files actually read by a scan partition. a file may be read by
+/// multiple readers (like footer and data readers), but is counted once.
+/// also used to count skipped empty row groups of a file once.
+#[derive(Default)]
+struct OpenedFiles(Mutex<HashSet<Path>>);
+
+impl OpenedFiles {
+    /// returns true if the file is opened for the first time
+    fn mark_opened(&self, location: &Path) -> bool {
+        self.0.lock().insert(location.clone())
+    }
+}
+
+/// Tracks bytes read by a scan partition, failing once exceeding the limit.
+struct ScanBytesBudget {
+    max_bytes: usize,
+    scanned_bytes: AtomicUsize,
+}
+
+impl ScanBytesBudget {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            scanned_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    fn acquire(&self, num_bytes: usize) -> Result<()> {
+        let scanned_bytes = self.scanned_bytes.fetch_add(num_bytes, SeqCst) + num_bytes;
+        if scanned_bytes > self.max_bytes {
+            return df_execution_err!(
+                "parquet scan exceeded max bytes per partition: scanned {} bytes, limit {} bytes",
+                scanned_bytes,
+                self.max_bytes,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Tracks bytes read by a scan partition against a soft limit, after which
+/// the scan stops successfully instead of failing.
+struct ScanBytesLimit {
+    max_bytes: usize,
+    scanned_bytes: AtomicUsize,
+}
+
+impl ScanBytesLimit {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            scanned_bytes: AtomicUsize::new(0),
+        }
+    }
+
+    fn add(&self, num_bytes: usize) {
+        self.scanned_bytes.fetch_add(num_bytes, SeqCst);
+    }
+
+    fn reached(&self) -> bool {
+        self.scanned_bytes.load(SeqCst) >= self.max_bytes
+    }
+}
+
+/// ends the stream successfully once the scanned bytes reach the limit,
+/// without polling (and reading) any further batch
+fn stop_at_bytes_limit(
+    stream: impl Stream<Item = Result<RecordBatch>> + Send + 'static,
+    bytes_limit: Arc<ScanBytesLimit>,
+) -> BoxStream<'static, Result<RecordBatch>> {
+    futures::stream::unfold(stream.boxed(), move |mut stream| {
+        let bytes_limit = bytes_limit.clone();
+        async move {
+            if bytes_limit.reached() {
+                log::info!(
+                    "parquet scan stopped at byte limit: scanned {} bytes, limit {} bytes",
+                    bytes_limit.scanned_bytes.load(SeqCst),
+                    bytes_limit.max_bytes,
+                );
+                return None;
+            }
+            let batch = stream.next().await?;
+            Some((batch, stream))
+        }
+    })
+    .boxed()
+}
+
+/// Checks projected columns of files against the table schema.
+///
+/// columns are resolved like the schema adapter does: top-level columns and
+/// struct fields by name (case-insensitively unless case sensitive), list and
+/// map children regardless of their names and nullability. types must be the
+/// same, except for conversions which keep values as they are: timestamps of
+/// the same unit with different timezone strings, binary read as string, and
+/// legacy list elements wrapped into single-field structs.
+struct StrictSchema {
+    table_schema: SchemaRef,
+    projection: Vec<usize>,
+    case_sensitive: bool,
+}
+
+impl StrictSchema {
+    fn check(&self, metadata: &ParquetMetaData, path: &str) -> Result<()> {
+        let file_schema = parquet_file_arrow_schema(metadata.file_metadata())?;
+
+        for &idx in &self.projection {
+            let table_field = self.table_schema.field(idx);
+            match self.find_field(file_schema.fields(), table_field.name()) {
+                Some(file_field)
+                    if self.compatible(file_field.data_type(), table_field.data_type()) => {}
+                Some(file_field) => {
+                    return df_execution_err!(
+                        "parquet schema mismatch for column {}: file type {:?}, table type {:?}, file: {}",
+                        table_field.name(),
+                        file_field.data_type(),
+                        table_field.data_type(),
+                        path,
+                    );
+                }
+                None => {
+                    return df_execution_err!(
+                        "parquet schema mismatch for column {}: missing in file, table type {:?}, file: {}",
+                        table_field.name(),
+                        table_field.data_type(),
+                        path,
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn find_field<'a>(&self, fields: &'a Fields, name: &str) -> Option<&'a FieldRef> {
+        match self.case_sensitive {
+            true => fields.iter().find(|field| field.name() == name),
+            false => {
+                let name = name.to_lowercase();
+                fields
+                    .iter()
+                    .find(|field| field.name().to_lowercase() == name)
+            }
+        }
+    }
+
+    fn compatible(&self, file_type: &DataType, table_type: &DataType) -> bool {
+        match (file_type, table_type) {
+            _ if file_type == table_type => true,
+            (DataType::Timestamp(file_unit, Some(_)), DataType::Timestamp(table_unit, Some(_))) => {
+                file_unit == table_unit
+            }
+            (DataType::Binary, DataType::Utf8) => true,
+            (DataType::List(file_field), DataType::List(table_field)) => {
+                match legacy_list_struct_element(file_field, table_field) {
+                    Some(struct_fields) => {
+                        self.compatible(file_field.data_type(), struct_fields[0].data_type())
+                    }
+                    None => self.compatible(file_field.data_type(), table_field.data_type()),
+                }
+            }
+            (DataType::Map(file_entries, _), DataType::Map(table_entries, _)) => {
+                match (file_entries.data_type(), table_entries.data_type()) {
+                    (DataType::Struct(file_kv), DataType::Struct(table_kv)) => {
+                        file_kv.len() == table_kv.len()
+                            && file_kv
+                                .iter()
+                                .zip(table_kv)
+                                .all(|(file_field, table_field)| {
+                                    self.compatible(file_field.data_type(), table_field.data_type())
+                                })
+                    }
+                    _ => false,
+                }
+            }
+            (DataType::Struct(file_fields), DataType::Struct(table_fields)) => {
+                table_fields.iter().all(|table_field| {
+                    self.find_field(file_fields, table_field.name())
+                        .is_some_and(|file_field| {
+                            self.compatible(file_field.data_type(), table_field.data_type())
+                        })
+                })
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Detects top-level file columns unknown to the table schema, like columns
+/// added to the files but not to the table. such columns are never read (the
+/// schema adapter only maps table columns), but are reported once per file.
+struct ExtraColumnsWarning {
+    table_columns: HashSet<String>,
+    case_sensitive: bool,
+    warned_files: OpenedFiles,
+}
+
+impl ExtraColumnsWarning {
+    fn new(table_schema: &Schema, case_sensitive: bool) -> Self {
+        let normalize = |name: &str| match case_sensitive {
+            true => name.to_string(),
+            false => name.to_lowercase(),
+        };
+        Self {
+            table_columns: table_schema
+                .fields()
+                .iter()
+                .map(|field| normalize(field.name()))
+                .collect(),
+            case_sensitive,
+            warned_files: OpenedFiles::default(),
+        }
+    }
+
+    fn extra_columns(&self, metadata: &ParquetMetaData) -> Vec<String> {
+        let root_schema = metadata.file_metadata().schema_descr().root_schema();
+        root_schema
+            .get_fields()
+            .iter()
+            .map(|field| field.name())
+            .filter(|&name| {
+                let name = match self.case_sensitive {
+                    true => name.to_string(),
+                    false => name.to_lowercase(),
+                };
+                !self.table_columns.contains(&name)
+            })
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn warn(&self, metadata: &ParquetMetaData, location: &Path, path: &str) {
+        let extra_columns = self.extra_columns(metadata);
+        if !extra_columns.is_empty() && self.warned_files.mark_opened(location) {
+            log::warn!(
+                "parquet file has columns unknown to the table schema (ignored): [{}], file: {path}",
+                extra_columns.join(", "),
+            );
+        }
+    }
+}
+
+struct ParquetFileReader {
+    source: FileSource,
+    strict_schema: Option<Arc<StrictSchema>>,
+    extra_columns_warning: Option<Arc<ExtraColumnsWarning>>,
+    dictionary_filter: Option<Arc<DictionaryFilter>>,
+    row_groups_pruned_by_dictionary: Count,
+    max_metadata_bytes: Option<usize>,
+    inline_read_threshold: usize,
+    metadata_bytes: Count,
+    opened_files: Arc<OpenedFiles>,
+    empty_row_groups_counted: Arc<OpenedFiles>,
+    skipped_empty_row_groups: Count,
+    bytes_budget: Option<Arc<ScanBytesBudget>>,
+    bytes_limit: Option<Arc<ScanBytesLimit>>,
+    footer_projection: Option<Arc<FooterProjection>>,
+    field_id_resolver: Option<Arc<FieldIdResolver>>,
+    map_children_projection: Option<Arc<MapChildrenProjection>>,
+    duplicate_column_resolver: Option<Arc<DuplicateColumnResolver>>,
+    io_latency_sampler: Option<Arc<IoLatencySampler>>,
+    verify_page_checksums: bool,
+    geometry_as_binary: bool,
+    pages_checksum_failed: Count,
+    files_opened: Count,
+    input: LazyInput<FsDataInputStream>,
+    meta: ObjectMeta,
+    metrics: ParquetFileMetrics,
+}
+
+/// The reader of a file stream, the input of the file is closed when the
+/// stream finishes, fails or is cancelled (that is, when the reader is
+/// dropped), even if the inner reader is still referenced by pending reads
+/// or cached metadata fetches.
+struct ParquetFileReaderRef(Arc<ParquetFileReader>);
+
+impl Drop for ParquetFileReaderRef {
+    fn drop(&mut self) {
+        if self.0.input.close() {
+            log::debug!("closed parquet file: {}", self.0.meta.location);
+        }
+        if let Some(io_latency_sampler) = &self.0.io_latency_sampler {
+            io_latency_sampler.update_metrics();
+        }
+    }
+}
+
+/// The lazily opened input of a file reader. the input (with its fs handle
+/// and jni global ref) is released by an explicit close, after which it can
+/// no longer be read or reopened. reads in progress keep the input alive
+/// until they finish.
+struct LazyInput<T> {
+    state: Mutex<LazyInputState<T>>,
+}
+
+struct LazyInputState<T> {
+    input: Option<Arc<T>>,
+    closed: bool,
+}
+
+impl<T> Default for LazyInput<T> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(LazyInputState {
+                input: None,
+                closed: false,
+            }),
+        }
+    }
+}
+
+impl<T> LazyInput<T> {
+    fn get_or_try_open(&self, open: impl FnOnce() -> Result<T>) -> Result<Arc<T>> {
+        let mut state = self.state.lock();
+        if state.closed {
+            return df_execution_err!("reading a closed file");
+        }
+        if let Some(input) = &state.input {
+            return Ok(input.clone());
+        }
+        let input = Arc::new(open()?);
+        state.input = Some(input.clone());
+        Ok(input)
+    }
+
+    /// returns true if an opened input is released
+    fn close(&self) -> bool {
+        let mut state = self.state.lock();
+        state.closed = true;
+        state.input.take().is_some()
+    }
+}
+
+impl ParquetFileReader {
+    fn path(&self) -> Result<String> {
+        decode_file_path(&self.meta)
+    }
+
+    fn add_bytes_scanned(&self, num_bytes: usize) -> Result<()> {
+        self.metrics.bytes_scanned.add(num_bytes);
+        if let Some(bytes_limit) = &self.bytes_limit {
+            bytes_limit.add(num_bytes);
+        }
+        match &self.bytes_budget {
+            Some(bytes_budget) => bytes_budget.acquire(num_bytes),
+            None => Ok(()),
+        }
+    }
+
+    fn mark_opened(&self) {
+        if self.opened_files.mark_opened(&self.meta.location) {
+            self.files_opened.add(1);
+        }
+    }
+
+    fn get_input(&self) -> datafusion::parquet::errors::Result<Arc<FsDataInputStream>> {
+        self.input
+            .get_or_try_open(|| {
+                let FileSource::Fs(fs_provider) = &self.source else {
+                    return df_execution_err!("no jvm input of a native object store file");
+                };
+                let path = self.path()?;
+                let fs = fs_provider.provide(&path)?;
+                let input = fs.open(&path)?;
+                self.mark_opened();
+                Ok(input)
+            })
+            .map_err(|e| ParquetError::External(Box::new(e)))
+    }
+
+    /// fetches and decodes the footer metadata, with unknown logical types
+    /// dropped
+    async fn fetch_metadata(
+        self: Arc<Self>,
+    ) -> datafusion::parquet::errors::Result<ParquetMetaData> {
+        let footer_bytes = self.clone().fetch_footer_bytes().await?;
+        let footer_bytes = drop_unknown_logical_types(&footer_bytes, self.geometry_as_binary)
+            .map_err(|e| ParquetError::External(Box::new(e)))?;
+        decode_metadata(&footer_bytes)
+    }
+
+    /// returns the end of a footer followed by trailing padding after failing
+    /// to read the footer at the end of the file, or the error to report
+    async fn recover_footer_error(
+        self: Arc<Self>,
+        err: ParquetError,
+        file_size: usize,
+    ) -> datafusion::parquet::errors::Result<usize> {
+        if let Some(encrypted_err) = self.clone().check_encrypted_footer(file_size).await {
+            return Err(encrypted_err);
+        }
+        let tail_len = file_size.min(MAX_FOOTER_PADDING + FOOTER_SIZE);
+        let tail_offset = file_size - tail_len;
+        let tail = match self.add_bytes_scanned(tail_len) {
+            Ok(()) => self.clone().read_async(tail_offset..file_size).await.ok(),
+            Err(_) => None,
+        };
+        match tail.and_then(|tail| find_padded_footer_end(&tail, tail_offset)) {
+            Some(footer_end) => {
+                log::warn!(
+                    "found parquet footer followed by {} bytes of padding: {}",
+                    file_size - footer_end,
+                    self.meta.location,
+                );
+                Ok(footer_end)
+            }
+            None => Err(err),
+        }
+    }
+
+    /// parquet modular encryption is not supported by the bundled parquet
+    /// crate. files with encrypted footers end with the "PARE" magic instead
+    /// of "PAR1", returns a readable error for them instead of reporting them
+    /// as corrupted.
+    ///
+    /// files encrypted with aad prefixes (like table names, which readers
+    /// must supply or verify) are reported as such, as told by the plaintext
+    /// crypto metadata at the start of the footer.
+    async fn check_encrypted_footer(self: Arc<Self>, file_size: usize) -> Option<ParquetError> {
+        const PARQUET_ENCRYPTED_MAGIC: &[u8] = b"PARE";
+        const MAX_CRYPTO_METADATA_SIZE: usize = 4096;
+
+        if file_size < FOOTER_SIZE {
+            return None;
+        }
+        self.add_bytes_scanned(FOOTER_SIZE).ok()?;
+        let footer = self
+            .clone()
+            .read_async(file_size - FOOTER_SIZE..file_size)
+            .await
+            .ok()?;
+        if &footer[4..] != PARQUET_ENCRYPTED_MAGIC {
+            return None;
+        }
+
+        // crypto metadata is small, only the start of the footer is read
+        let metadata_len = u32::from_le_bytes(footer[..4].try_into().unwrap()) as usize;
+        let metadata_start = (file_size - FOOTER_SIZE).checked_sub(metadata_len);
+        let encryption = match metadata_start {
+            Some(start) => {
+                let end = start + metadata_len.min(MAX_CRYPTO_METADATA_SIZE);
+                match self.add_bytes_scanned(end - start) {
+                    Ok(()) => self.clone().read_async(start..end).await.ok(),
+                    Err(_) => None,
+                }
+            }
+            None => None,
+        }
+        .and_then(|crypto_metadata| read_file_encryption(&crypto_metadata).ok());
+
+        Some(ParquetError::General(match encryption {
+            Some(encryption) if encryption.aad_prefix => format!(
+                "reading encrypted parquet files with aad prefixes is not supported \
+                 ({}): {}",
+                encryption.algorithm, self.meta.location,
+            ),
+            _ => format!(
+                "reading encrypted parquet files is not supported: {}",
+                self.meta.location,
+            ),
+        }))
+    }
+
+    /// fetches the raw footer metadata (without the trailing length and
+    /// magic), to be decoded with a projection
+    async fn fetch_footer_bytes(self: Arc<Self>) -> datafusion::parquet::errors::Result<Bytes> {
+        let file_size = self.meta.size;
+        match self.clone().fetch_footer_bytes_at(file_size).await {
+            Ok(footer_bytes) => Ok(footer_bytes),
+            Err(err) => match self.clone().recover_footer_error(err, file_size).await {
+                Ok(footer_end) => self.fetch_footer_bytes_at(footer_end).await,
+                Err(err) => Err(err),
+            },
+        }
+    }
+
+    /// fetches the raw footer metadata of the footer ending at the given
+    /// offset
+    async fn fetch_footer_bytes_at(
+        self: Arc<Self>,
+        file_size: usize,
+    ) -> datafusion::parquet::errors::Result<Bytes> {
+        const FOOTER_SIZE_HINT: usize = 1048576;
+        let _permit = acquire_prefetch_budget(file_size.min(FOOTER_SIZE_HINT)).await;
+        let read = |range: Range<usize>| {
+            let inner = self.clone();
+            async move {
+                inner
+                    .add_bytes_scanned(range.len())
+                    .map_err(|e| ParquetError::External(Box::new(e)))?;
+                inner
+                    .read_async(range)
+                    .await
+                    .map_err(|e| ParquetError::External(Box::new(e)))
+            }
+        };
+        if file_size < FOOTER_SIZE {
+            return Err(ParquetError::General(format!(
+                "file size of {file_size} is less than footer"
+            )));
+        }
+        let suffix_len = file_size.min(FOOTER_SIZE_HINT);
+        let suffix = read(file_size - suffix_len..file_size).await?;
+        let metadata_len = check_metadata_size(&suffix, self.max_metadata_bytes)?;
+        self.metadata_bytes.add(metadata_len);
+
+        let footer_len = metadata_len + FOOTER_SIZE;
+        if footer_len > file_size {
+            return Err(ParquetError::EOF(format!(
+                "file size of {file_size} is less than footer + metadata {footer_len}"
+            )));
+        }
+        if footer_len <= suffix_len {
+            return Ok(suffix.slice(suffix_len - footer_len..suffix_len - FOOTER_SIZE));
+        }
+        read(file_size - footer_len..file_size - FOOTER_SIZE).await
+    }
+
+    /// decodes metadata from the raw footer, with unknown logical types
+    /// dropped, columns resolved by field ids, map columns projected to their
+    /// needed children, and with full column chunk metadata of needed
+    /// columns only. raw footers are cached instead of decoded metadata,
+    /// since different scans may need different columns.
+    async fn fetch_projected_metadata(
+        self: Arc<Self>,
+    ) -> datafusion::parquet::errors::Result<Arc<ParquetMetaData>> {
+        let footer_bytes = get_or_fetch_footer_bytes(footer_bytes_cache(), &self.meta, || {
+            self.clone().fetch_footer_bytes()
+        })
+        .await?;
+        let decode = || -> Result<ParquetMetaData> {
+            let footer_bytes = drop_unknown_logical_types(&footer_bytes, self.geometry_as_binary)?;
+            let footer_bytes = match &self.duplicate_column_resolver {
+                Some(resolver) if resolver.pick_first() => resolver.resolve(&footer_bytes)?,
+                _ => footer_bytes,
+            };
+            let footer_bytes = match &self.field_id_resolver {
+                Some(field_id_resolver) => field_id_resolver.resolve(&footer_bytes)?,
+                None => footer_bytes,
+            };
+            let footer_bytes = match &self.map_children_projection {
+                Some(map_children_projection) => map_children_projection.project(&footer_bytes)?,
+                None => footer_bytes,
+            };
+            match &self.footer_projection {
+                Some(footer_projection) => footer_projection.decode(&footer_bytes),
+                None => Ok(decode_metadata(&footer_bytes)?),
+            }
+        };
+        let metadata = decode().map_err(|e| ParquetError::External(Box::new(e)))?;
+        Ok(Arc::new(metadata))
+    }
+
+    fn read_fully(&self, range: Range<usize>) -> Result<Bytes> {
+        let mut bytes = vec![0u8; range.len()];
+        let input = self.get_input()?;
+        match &self.io_latency_sampler {
+            Some(io_latency_sampler) => {
+                io_latency_sampler.time_read(|| input.read_fully(range.start as u64, &mut bytes))?
+            }
+            None => input.read_fully(range.start as u64, &mut bytes)?,
+        }
+        Ok(Bytes::from(bytes))
+    }
+
+    /// reads multiple ranges with the minimum number of underlying reads,
+    /// returning bytes in request order
+    fn read_ranges(&self, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        read_merged_ranges(ranges, |range| {
+            self.add_bytes_scanned(range.len())?;
+            self.read_fully(range)
+        })
+    }
+
+    /// reads a range with the async api of the native object store, or from
+    /// the jvm fs on the blocking thread pool (inline for small reads). read
+    /// bytes are not added to the scanned bytes.
+    async fn read_async(self: Arc<Self>, range: Range<usize>) -> Result<Bytes> {
+        if let FileSource::ObjectStore(store, location) = &self.source {
+            return self.time_store_read(store.get_range(location, range)).await;
+        }
+        let inline = range.len() <= self.inline_read_threshold;
+        read_maybe_inline(inline, move || self.read_fully(range)).await
+    }
+
+    /// reads multiple ranges like [`Self::read_ranges`], natively from the
+    /// object store if the file is in one
+    async fn read_ranges_async(self: Arc<Self>, ranges: Vec<Range<usize>>) -> Result<Vec<Bytes>> {
+        if let FileSource::ObjectStore(store, location) = &self.source {
+            self.add_bytes_scanned(ranges.iter().map(|range| range.len()).sum())?;
+            return self
+                .time_store_read(store.get_ranges(location, &ranges))
+                .await;
+        }
+        tokio::task::spawn_blocking(move || self.read_ranges(&ranges))
+            .await
+            .expect("tokio spawn_blocking error")
+    }
+
+    /// verifies page checksums of read column chunks if enabled, see
+    /// [`find_page_checksum_mismatch`]
+    fn verify_page_checksums(&self, ranges: &[Range<usize>], bytes: &[Bytes]) -> Result<()> {
+        if !self.verify_page_checksums {
+            return Ok(());
+        }
+        for (range, bytes) in ranges.iter().zip(bytes) {
+            if let Some(offset) = find_page_checksum_mismatch(bytes) {
+                self.pages_checksum_failed.add(1);
+                return df_execution_err!(
+                    "parquet page checksum mismatch at offset {}, file: {}",
+                    range.start + offset,
+                    self.path().unwrap_or_default(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn time_store_read<T>(
+        &self,
+        read: impl Future<Output = object_store::Result<T>>,
+    ) -> Result<T> {
+        self.mark_opened();
+        Ok(match &self.io_latency_sampler {
+            Some(io_latency_sampler) => io_latency_sampler.time_async_read(read).await?,
+            None => read.await?,
+        })
+    }
+}
+
+/// merges overlapping and adjacent ranges, reads each merged range once and
+/// slices results back out in request order
+fn read_merged_ranges(
+    ranges: &[Range<usize>],
+    mut read: impl FnMut(Range<usize>) -> Result<Bytes>,
+) -> Result<Vec<Bytes>> {
+    let mut sorted_ranges = ranges.to_vec();
+    sorted_ranges.sort_unstable_by_key(|range| range.start);
+
+    let mut merged_ranges: Vec<Range<usize>> = vec![];
+    for range in sorted_ranges {
+        match merged_ranges.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged_ranges.push(range),
+        }
+    }
+    let merged_bytes = merged_ranges
+        .iter()
+        .map(|range| read(range.clone()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ranges
+        .iter()
+        .map(|range| {
+            let idx = merged_ranges.partition_point(|merged| merged.end < range.end);
+            let merged = &merged_ranges[idx];
+            merged_bytes[idx].slice(range.start - merged.start..range.end - merged.start)
+        })
+        .collect())
+}
+
+/// Verifies CRC32 checksums of consecutive pages in the bytes of a column
+/// chunk (or a part of it starting at a page), returning the offset of the
+/// first corrupted page. pages without checksums are not verified, and
+/// verification stops at a truncated page or an unreadable page header,
+/// which are left to the decoder.
+fn find_page_checksum_mismatch(bytes: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let Ok(header) = read_page_header(&bytes[offset..]) else {
+            break;
+        };
+        let page_start = offset + header.header_len;
+        let page_end = page_start + header.compressed_page_size;
+        if page_end > bytes.len() {
+            break;
+        }
+        if let Some(crc) = header.crc {
+            if crc32fast::hash(&bytes[page_start..page_end]) != crc {
+                return Some(offset);
+            }
+        }
+        offset = page_end;
+    }
+    None
+}
+
+impl AsyncFileReader for ParquetFileReaderRef {
+    fn get_bytes(
+        &mut self,
+        range: Range<usize>,
+    ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Bytes>> {
+        let inner = self.0.clone();
+        let span = debug_span!(
+            "get_bytes",
+            file = inner.path().unwrap_or_default(),
+            start = range.start,
+            len = range.len(),
+        );
+        async move {
+            let _permit = acquire_prefetch_budget(range.len()).await;
+            inner
+                .add_bytes_scanned(range.len())
+                .map_err(|e| ParquetError::External(Box::new(e)))?;
+            inner
+                .read_async(range)
+                .await
+                .map_err(|e| ParquetError::External(Box::new(e)))
+        }
+        .instrument(span)
+        .boxed()
+    }
+
+    fn get_byte_ranges(
+        &mut self,
+        ranges: Vec<Range<usize>>,
+    ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Vec<Bytes>>> {
+        let inner = self.0.clone();
+        let span = debug_span!(
+            "get_byte_ranges",
+            file = inner.path().unwrap_or_default(),
+            num_ranges = ranges.len(),
+        );
+        async move {
+            let num_bytes = ranges.iter().map(|range| range.len()).sum();
+            let _permit = acquire_prefetch_budget(num_bytes).await;
+            let bytes = inner
+                .clone()
+                .read_ranges_async(ranges.clone())
+                .await
+                .map_err(|e| ParquetError::External(Box::new(e)))?;
+            inner
+                .verify_page_checksums(&ranges, &bytes)
+                .map_err(|e| ParquetError::External(Box::new(e)))?;
+            Ok(bytes)
+        }
+        .instrument(span)
+        .boxed()
+    }
+
+    fn get_metadata(
+        &mut self,
+    ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Arc<ParquetMetaData>>> {
+        let inner = self.0.clone();
+        let span = debug_span!("get_metadata", file = inner.path().unwrap_or_default());
+
+        // fetch metadata from file and update to cache
+        async move {
+            let metadata = if inner.footer_projection.is_some()
+                || inner.field_id_resolver.is_some()
+                || inner.map_children_projection.is_some()
+                || inner
+                    .duplicate_column_resolver
+                    .as_ref()
+                    .is_some_and(|resolver| resolver.pick_first())
+            {
+                inner.clone().fetch_projected_metadata().await?
+            } else {
+                get_or_fetch_metadata(parquet_metadata_cache().as_ref(), &inner.meta, || {
+                    inner.clone().fetch_metadata()
+                })
+                .await?
+            };
+
+            // zero-row row groups (padding of some writers) are removed before
+            // any page index or data is read
+            let (metadata, num_empty_row_groups) = skip_empty_row_groups(metadata);
+            if num_empty_row_groups > 0
+                && inner
+                    .empty_row_groups_counted
+                    .mark_opened(&inner.meta.location)
+            {
+                inner.skipped_empty_row_groups.add(num_empty_row_groups);
+            }
+
+            if let Some(strict_schema) = &inner.strict_schema {
+                inner
+                    .path()
+                    .and_then(|path| strict_schema.check(&metadata, &path))
+                    .map_err(|e| ParquetError::External(Box::new(e)))?;
+            }
+            if let Some(extra_columns_warning) = &inner.extra_columns_warning {
+                let path = inner.path().unwrap_or_default();
+                extra_columns_warning.warn(&metadata, &inner.meta.location, &path);
+            }
+            if let Some(resolver) = &inner.duplicate_column_resolver {
+                inner
+                    .path()
+                    .and_then(|path| resolver.check(&metadata, &path))
+                    .map_err(|e| ParquetError::External(Box::new(e)))?;
+            }
+
+            // files with types unknown to arrow are left to fail in decoding
+            let metadata = match &inner.dictionary_filter {
+                Some(dictionary_filter) => {
+                    match parquet_file_arrow_schema(metadata.file_metadata()) {
+                        Ok(file_schema) => {
+                            let pruned = dictionary_filter
+                                .prune(
+                                    &inner.meta.location,
+                                    &metadata,
+                                    &file_schema,
+                                    &inner.row_groups_pruned_by_dictionary,
+                                    |ranges| inner.clone().read_ranges_async(ranges),
+                                )
+                                .await
+                                .map_err(|e| ParquetError::External(Box::new(e)))?;
+                            remove_row_groups(metadata, &pruned)
+                        }
+                        Err(_) => metadata,
+                    }
+                }
+                None => metadata,
+            };
+            Ok(metadata)
+        }
+        .instrument(span)
+        .boxed()
+    }
+}
+
+/// removes row groups without rows from the metadata, returns the number of
+/// removed row groups.
+fn skip_empty_row_groups(metadata: Arc<ParquetMetaData>) -> (Arc<ParquetMetaData>, usize) {
+    let num_empty_row_groups = metadata
+        .row_groups()
+        .iter()
+        .filter(|rg| rg.num_rows() == 0)
+        .count();
+    if num_empty_row_groups == 0 {
+        return (metadata, 0);
+    }
+    let row_groups = metadata
+        .row_groups()
+        .iter()
+        .filter(|rg| rg.num_rows() > 0)
+        .cloned()
+        .collect();
+    let metadata = ParquetMetaData::new(metadata.file_metadata().clone(), row_groups);
+    (Arc::new(metadata), num_empty_row_groups)
+}
+
+/// runs a read inline on the async executor if `inline` is set, otherwise on
+/// the blocking thread pool
+async fn read_maybe_inline<T: Send + 'static>(
+    inline: bool,
+    read: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    if inline {
+        return read();
+    }
+    tokio::task::spawn_blocking(read)
+        .await
+        .expect("tokio spawn_blocking error")
+}
+
+/// file paths are base64-encoded into the object location by the jvm side
+fn decode_file_path(meta: &ObjectMeta) -> Result<String> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(meta.location.filename().expect("missing filename"))
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .or_else(|_| {
+            let filename = meta.location.filename();
+            df_execution_err!("cannot decode filename: {filename:?}")
+        })
+}
+
+/// Schema of footer statistics returned by [`read_footer_statistics`], one
+/// row per row group. min/max values are rendered from the physical values
+/// stored in the footer, binary values are rendered as utf-8 if valid,
+/// otherwise as hex.
+pub fn footer_statistics_schema() -> SchemaRef {
+    let column_stats_fields = Fields::from(vec![
+        Field::new("column", DataType::Utf8, false),
+        Field::new("null_count", DataType::Int64, true),
+        Field::new("min", DataType::Utf8, true),
+        Field::new("max", DataType::Utf8, true),
+    ]);
+    Arc::new(Schema::new(vec![
+        Field::new("file_path", DataType::Utf8, false),
+        Field::new("row_group", DataType::Int32, false),
+        Field::new("num_rows", DataType::Int64, false),
+        Field::new("total_byte_size", DataType::Int64, false),
+        Field::new(
+            "column_stats",
+            DataType::List(Arc::new(Field::new(
+                "item",
+                DataType::Struct(column_stats_fields),
+                false,
+            ))),
+            false,
+        ),
+    ]))
+}
+
+/// Reads the footer statistics of the given files without decoding any data
+/// page, for validation jobs. footers are fetched concurrently and shared
+/// with scans through the metadata cache.
+pub async fn read_footer_statistics(
+    fs_provider: Arc<FsProvider>,
+    files: &[PartitionedFile],
+) -> Result<RecordBatch> {
+    let reader_factory = FsReaderFactory::new(fs_provider);
+    let metrics = ExecutionPlanMetricsSet::new();
+    let file_metadatas = futures::stream::iter(files)
+        .map(|file| {
+            let file_path = decode_file_path(&file.object_meta);
+            let file_meta = FileMeta::from(file.object_meta.clone());
+            let reader = reader_factory.create_reader(0, file_meta, None, &metrics);
+            async move {
+                let metadata = reader?.get_metadata().await?;
+                Ok((file_path?, metadata))
+            }
+        })
+        .buffered(metadata_fetch_concurrency())
+        .try_collect::<Vec<_>>()
+        .await?;
+    footer_statistics_batch(&file_metadatas)
+}
+
+/// Fetches the footers of the given files concurrently into the shared
+/// metadata cache, so scans started afterwards are not stalled on metadata.
+/// fetches are limited by the metadata fetch concurrency.
+pub async fn warm_metadata_cache(
+    fs_provider: Arc<FsProvider>,
+    files: &[PartitionedFile],
+) -> Result<()> {
+    let reader_factory = FsReaderFactory::new(fs_provider);
+    let metrics = ExecutionPlanMetricsSet::new();
+    let readers = files
+        .iter()
+        .map(|file| {
+            let file_meta = FileMeta {
+                object_meta: file.object_meta.clone(),
+                range: None,
+                extensions: file.extensions.clone(),
+            };
+            reader_factory.create_file_reader(0, file_meta, &metrics)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    warm_metadata(
+        parquet_metadata_cache().as_ref(),
+        readers
+            .iter()
+            .map(|reader| (&reader.0.meta, || reader.0.clone().fetch_metadata())),
+    )
+    .await?;
+    Ok(())
+}
+
+fn footer_statistics_batch(
+    file_metadatas: &[(String, Arc<ParquetMetaData>)],
+) -> Result<RecordBatch> {
+    let schema = footer_statistics_schema();
+    let mut file_paths = vec![];
+    let mut row_groups = vec![];
+    let mut num_rows = vec![];
+    let mut total_byte_sizes = vec![];
+    let mut column_stats_lengths = vec![];
+    let mut column_names = vec![];
+    let mut null_counts = vec![];
+    let mut mins = vec![];
+    let mut maxs = vec![];
+
+    for (file_path, metadata) in file_metadatas {
+        for (rg_idx, rg) in metadata.row_groups().iter().enumerate() {
+            file_paths.push(file_path.clone());
+            row_groups.push(rg_idx as i32);
+            num_rows.push(rg.num_rows());
+            total_byte_sizes.push(rg.total_byte_size());
+            column_stats_lengths.push(rg.num_columns());
+
+            for column in rg.columns() {
+                let stats = column.statistics();
+                let (min, max) = stats.and_then(statistics_min_max).unzip();
+                column_names.push(column.column_path().string());
+                null_counts.push(stats.map(|stats| stats.null_count() as i64));
+                mins.push(min);
+                maxs.push(max);
+            }
+        }
+    }
+
+    let DataType::List(column_stats_field) = schema.field(4).data_type() else {
+        unreachable!()
+    };
+    let DataType::Struct(column_stats_fields) = column_stats_field.data_type() else {
+        unreachable!()
+    };
+    let column_stats = StructArray::try_new(
+        column_stats_fields.clone(),
+        vec![
+            Arc::new(StringArray::from(column_names)),
+            Arc::new(Int64Array::from(null_counts)),
+            Arc::new(StringArray::from(mins)),
+            Arc::new(StringArray::from(maxs)),
+        ],
+        None,
+    )?;
+    let column_stats = ListArray::try_new(
+        column_stats_field.clone(),
+        OffsetBuffer::from_lengths(column_stats_lengths),
+        Arc::new(column_stats),
+        None,
+    )?;
+
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(file_paths)),
+            Arc::new(Int32Array::from(row_groups)),
+            Arc::new(Int64Array::from(num_rows)),
+            Arc::new(Int64Array::from(total_byte_sizes)),
+            Arc::new(column_stats),
+        ],
+    )?)
+}
+
+fn statistics_min_max(stats: &ParquetStatistics) -> Option<(String, String)> {
+    fn bytes_to_string(bytes: &[u8]) -> String {
+        match std::str::from_utf8(bytes) {
+            Ok(s) => s.to_string(),
+            Err(_) => bytes.iter().map(|b| format!("{b:02x}")).collect(),
+        }
+    }
+
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    Some(match stats {
+        ParquetStatistics::Boolean(s) => (s.min().to_string(), s.max().to_string()),
+        ParquetStatistics::Int32(s) => (s.min().to_string(), s.max().to_string()),
+        ParquetStatistics::Int64(s) => (s.min().to_string(), s.max().to_string()),
+        ParquetStatistics::Float(s) => (s.min().to_string(), s.max().to_string()),
+        ParquetStatistics::Double(s) => (s.min().to_string(), s.max().to_string()),
+        ParquetStatistics::ByteArray(s) => (
+            bytes_to_string(s.min().data()),
+            bytes_to_string(s.max().data()),
+        ),
+        ParquetStatistics::FixedLenByteArray(s) => (
+            bytes_to_string(s.min().data()),
+            bytes_to_string(s.max().data()),
+        ),
+        ParquetStatistics::Int96(_) => return None, // deprecated, order is undefined
+    })
+}
+
+/// Min/max statistics of a table column in parquet files, aggregated over row
+/// groups (or files), see [`read_column_statistics`]. min/max are None if
+/// unknown, like when any row group has no statistics or the file column has
+/// a different type than the table column, or if all values are nulls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParquetColumnStatistics {
+    pub min: Option<ScalarValue>,
+    pub max: Option<ScalarValue>,
+    pub null_count: Option<u64>,
+}
+
+/// Statistics of a parquet file, with one entry per table column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParquetFileStatistics {
+    pub file_path: String,
+    pub num_rows: u64,
+    pub columns: Vec<ParquetColumnStatistics>,
+}
+
+/// Reads min/max statistics of the table columns from the footers of the given
+/// files, so files can be pruned before any task is spawned. footers are
+/// fetched concurrently and shared with scans through the metadata cache.
+pub async fn read_column_statistics(
+    fs_provider: Arc<FsProvider>,
+    files: &[PartitionedFile],
+    table_schema: &Schema,
+) -> Result<Vec<ParquetFileStatistics>> {
+    let reader_factory = FsReaderFactory::new(fs_provider);
+    read_column_statistics_with(&reader_factory, files, table_schema).await
+}
+
+async fn read_column_statistics_with(
+    reader_factory: &FsReaderFactory,
+    files: &[PartitionedFile],
+    table_schema: &Schema,
+) -> Result<Vec<ParquetFileStatistics>> {
+    let metrics = ExecutionPlanMetricsSet::new();
+    futures::stream::iter(files)
+        .map(|file| {
+            let file_path = decode_file_path(&file.object_meta);
+            let file_meta = FileMeta {
+                object_meta: file.object_meta.clone(),
+                range: None,
+                extensions: file.extensions.clone(),
+            };
+            let reader = reader_factory.create_file_reader(0, file_meta, &metrics);
+            async move {
+                let metadata = reader?.get_metadata().await?;
+                file_column_statistics(file_path?, &metadata, table_schema)
+            }
+        })
+        .buffered(metadata_fetch_concurrency())
+        .try_collect()
+        .await
+}
+
+/// Merges statistics of files into statistics of all the files.
+pub fn merge_file_statistics(files: &[ParquetFileStatistics]) -> Vec<ParquetColumnStatistics> {
+    let num_columns = files.first().map(|file| file.columns.len()).unwrap_or(0);
+    (0..num_columns)
+        .map(|i| {
+            aggregate_column_statistics(files.iter().map(|file| {
+                let column = &file.columns[i];
+                let min_max = column.min.clone().zip(column.max.clone());
+                (file.num_rows, column.null_count, min_max)
+            }))
+        })
+        .collect()
+}
+
+fn file_column_statistics(
+    file_path: String,
+    metadata: &ParquetMetaData,
+    table_schema: &Schema,
+) -> Result<ParquetFileStatistics> {
+    let file_schema = parquet_file_arrow_schema(metadata.file_metadata())?;
+    let schema_descr = metadata.file_metadata().schema_descr();
+    let num_rows = metadata
+        .row_groups()
+        .iter()
+        .map(|rg| rg.num_rows() as u64)
+        .sum();
+
+    let columns = table_schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let file_field = file_schema
+                .fields()
+                .iter()
+                .find(|file_field| file_field.name().eq_ignore_ascii_case(field.name()));
+            let Some(file_field) = file_field else {
+                // columns missing in the file are read as nulls
+                return ParquetColumnStatistics {
+                    min: None,
+                    max: None,
+                    null_count: Some(num_rows),
+                };
+            };
+            let column_idx = (0..schema_descr.num_columns()).find(|&i| {
+                let parts = schema_descr.column(i).path().parts();
+                parts.len() == 1 && &parts[0] == file_field.name()
+            });
+            match column_idx {
+                Some(column_idx) if file_field.data_type() == field.data_type() => {
+                    aggregate_column_statistics(metadata.row_groups().iter().map(|rg| {
+                        let stats = rg.column(column_idx).statistics();
+                        let min_max = stats
+                            .and_then(|stats| statistics_scalar_min_max(stats, field.data_type()));
+                        (
+                            rg.num_rows() as u64,
+                            stats.map(|stats| stats.null_count()),
+                            min_max,
+                        )
+                    }))
+                }
+                _ => ParquetColumnStatistics {
+                    min: None,
+                    max: None,
+                    null_count: None,
+                },
+            }
+        })
+        .collect();
+
+    Ok(ParquetFileStatistics {
+        file_path,
+        num_rows,
+        columns,
+    })
+}
+
+// aggregates (num_rows, null_count, min/max) of row groups or files
+fn aggregate_column_statistics(
+    parts: impl IntoIterator<Item = (u64, Option<u64>, Option<(ScalarValue, ScalarValue)>)>,
+) -> ParquetColumnStatistics {
+    let mut null_count = Some(0);
+    let mut min_max: Option<(ScalarValue, ScalarValue)> = None;
+    let mut min_max_known = true;
+    for (num_rows, part_null_count, part_min_max) in parts {
+        null_count = null_count.zip(part_null_count).map(|(a, b)| a + b);
+        match part_min_max {
+            Some((min, max)) => {
+                min_max = Some(match min_max.take() {
+                    Some((cur_min, cur_max)) => (
+                        if min < cur_min { min } else { cur_min },
+                        if max > cur_max { max } else { cur_max },
+                    ),
+                    None => (min, max),
+                });
+            }
+            None if part_null_count == Some(num_rows) => {} // only nulls
+            None => min_max_known = false,
+        }
+    }
+    let (min, max) = min_max.filter(|_| min_max_known).unzip();
+    ParquetColumnStatistics {
+        min,
+        max,
+        null_count,
+    }
+}
+
+// converts min/max of footer statistics to values of the given type, or None
+// if the statistics are not usable for the type
+fn statistics_scalar_min_max(
+    stats: &ParquetStatistics,
+    data_type: &DataType,
+) -> Option<(ScalarValue, ScalarValue)> {
+    fn integer_scalar(value: i64, data_type: &DataType) -> Option<ScalarValue> {
+        Some(match data_type {
+            DataType::Int8 => ScalarValue::Int8(Some(value.try_into().ok()?)),
+            DataType::Int16 => ScalarValue::Int16(Some(value.try_into().ok()?)),
+            DataType::Int32 => ScalarValue::Int32(Some(value.try_into().ok()?)),
+            DataType::Int64 => ScalarValue::Int64(Some(value)),
+            DataType::Date32 => ScalarValue::Date32(Some(value.try_into().ok()?)),
+            DataType::Timestamp(TimeUnit::Millisecond, tz) => {
+                ScalarValue::TimestampMillisecond(Some(value), tz.clone())
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, tz) => {
+                ScalarValue::TimestampMicrosecond(Some(value), tz.clone())
+            }
+            DataType::Decimal128(precision, scale) => {
+                ScalarValue::Decimal128(Some(value as i128), *precision, *scale)
+            }
+            _ => return None,
+        })
+    }
+
+    // byte arrays are ordered unsigned, which deprecated statistics are not
+    fn bytes_scalar(bytes: &[u8], data_type: &DataType) -> Option<ScalarValue> {
+        Some(match data_type {
+            DataType::Utf8 => ScalarValue::Utf8(Some(std::str::from_utf8(bytes).ok()?.to_string())),
+            DataType::Binary => ScalarValue::Binary(Some(bytes.to_vec())),
+            DataType::Decimal128(precision, scale) if !bytes.is_empty() && bytes.len() <= 16 => {
+                // big-endian two's complement unscaled value
+                let sign_byte = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+                let mut be_bytes = [sign_byte; 16];
+                be_bytes[16 - bytes.len()..].copy_from_slice(bytes);
+                ScalarValue::Decimal128(Some(i128::from_be_bytes(be_bytes)), *precision, *scale)
+            }
+            _ => return None,
+        })
+    }
+
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    Some(match stats {
+        ParquetStatistics::Boolean(s) if data_type == &DataType::Boolean => (
+            ScalarValue::Boolean(Some(*s.min())),
+            ScalarValue::Boolean(Some(*s.max())),
+        ),
+        ParquetStatistics::Int32(s) => (
+            integer_scalar(*s.min() as i64, data_type)?,
+            integer_scalar(*s.max() as i64, data_type)?,
+        ),
+        ParquetStatistics::Int64(s) => (
+            integer_scalar(*s.min(), data_type)?,
+            integer_scalar(*s.max(), data_type)?,
+        ),
+        ParquetStatistics::Float(s) if data_type == &DataType::Float32 => (
+            ScalarValue::Float32(Some(*s.min())),
+            ScalarValue::Float32(Some(*s.max())),
+        ),
+        ParquetStatistics::Double(s) if data_type == &DataType::Float64 => (
+            ScalarValue::Float64(Some(*s.min())),
+            ScalarValue::Float64(Some(*s.max())),
+        ),
+        ParquetStatistics::ByteArray(s) if !stats.is_min_max_deprecated() => (
+            bytes_scalar(s.min().data(), data_type)?,
+            bytes_scalar(s.max().data(), data_type)?,
+        ),
+        ParquetStatistics::FixedLenByteArray(s) if !stats.is_min_max_deprecated() => (
+            bytes_scalar(s.min().data(), data_type)?,
+            bytes_scalar(s.max().data(), data_type)?,
+        ),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        any::Any,
+        cell::Cell,
+        collections::{HashMap, HashSet},
+        ops::Range,
+        sync::{
+            atomic::{AtomicUsize, Ordering::SeqCst},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use arrow::{
+        array::{
+            make_array, Array, ArrayRef, AsArray, BinaryArray, BooleanArray, Float64Array,
+            Int32Array, Int64Array, LargeBinaryArray, StringArray, StructArray,
+            Time32MillisecondArray, Time64MicrosecondArray, Time64NanosecondArray,
+        },
+        buffer::NullBuffer,
+        compute::concat_batches,
+        datatypes::{
+            DataType, Field, Fields, Float64Type, Int32Type, Int64Type, Schema, SchemaRef,
+            Time32MillisecondType, Time64NanosecondType, TimeUnit,
+        },
+        error::ArrowError,
+        record_batch::RecordBatch,
+    };
+    use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+    use byteorder::{WriteBytesExt, LE};
+    use bytes::Bytes;
+    use datafusion::{
+        common::{stats::Precision, Column, DataFusionError, ScalarValue, Statistics},
+        datasource::{
+            listing::{FileRange, PartitionedFile},
+            object_store::ObjectStoreUrl,
+            physical_plan::{
+                parquet::ParquetOpener, FileMeta, FileOpenFuture, FileOpener, FileScanConfig,
+                FileStream,
+            },
+        },
+        execution::memory_pool::{GreedyMemoryPool, MemoryConsumer, MemoryPool},
+        logical_expr::Operator,
+        parquet::{
+            arrow::{
+                arrow_reader::{
+                    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder,
+                },
+                async_reader::AsyncFileReader,
+                ArrowWriter, ParquetRecordBatchStreamBuilder,
+            },
+            data_type::{ByteArray, ByteArrayType, Int32Type as ParquetInt32Type},
+            file::{
+                footer::parse_metadata, metadata::ParquetMetaData, properties::WriterProperties,
+                writer::SerializedFileWriter, FOOTER_SIZE,
+            },
+            format::KeyValue,
+            schema::{parser::parse_message_type, types::ColumnPath},
+        },
+        physical_expr::{expressions as phys_expr, PhysicalExpr},
+        physical_optimizer::pruning::{PruningPredicate, PruningStatistics},
+        physical_plan::{
+            metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder, Time},
+            ExecutionPlan,
+        },
+    };
+    use datafusion_ext_commons::df_execution_err;
+    use datafusion_ext_exprs::{cast::TryCastExpr, string_starts_with::StringStartsWithExpr};
+    use futures::{FutureExt, StreamExt, TryStreamExt};
+    use object_store::{memory::InMemory, path::Path, ObjectStore};
+    use once_cell::sync::OnceCell;
+    use parking_lot::Mutex;
+    use tokio::sync::Semaphore;
+
+    use crate::{
+        common::{
+            deletion_vector::DeletionVector, dictionary_filter::DictionaryFilter,
+            parquet_footer::read_page_header, scratch_buffers::ScratchBitmaps,
+        },
+        parquet_exec::{
+            cast_partition_value, cast_partition_values, check_metadata_size, count_only_batches,
+            decode_file_path, dedup_by_key_columns, dedup_decoded_batch, dictionary_encoded_share,
+            estimate_dictionary_decode_time, filter_decoded_batch, find_padded_footer_end,
+            find_page_checksum_mismatch, footer_statistics_batch, footer_statistics_schema,
+            insert_external_placeholders, legacy_list_struct_element, limit_open_files,
+            merge_file_statistics, midpoint_file_range, midpoint_row_groups,
+            parquet_file_arrow_schema, parquet_file_arrow_schema_impl, post_decode_predicate,
+            prefix_upper_bound, quarantine_corrupted_row_groups, read_column_statistics_with,
+            read_maybe_inline, read_merged_ranges, reserve_output_batch,
+            rewrite_casts_as_column_bounds, rewrite_file_path, rewrite_starts_with_as_bounds,
+            row_filtering_modes, schema_adapter_cast_column, skip_corrupted_file,
+            skip_empty_row_groups, stop_at_bytes_limit, with_file_context,
+            with_scan_case_sensitive, ByteSizedBatches, CountOnlyScan, DecodeUnit,
+            DeletionVectorScan, ExternalColumns, ExtraColumnsWarning, FetchRowCount,
+            FileFsResourceId, FsProviderResolver, FsReaderFactory, LazyInput, NativeObjectStores,
+            NonNullableColumns, OpenedFiles, ParquetColumnStatistics, ParquetExec,
+            ParquetFileOpener, PathRewriter, PruningPredicateRewrite, RawColumnChunk,
+            RowCountPrefetch, RowDedupPredicate, RowGroupBatching, ScanBytesBudget, ScanBytesLimit,
+            StrictSchema, SCAN_CASE_SENSITIVE,
+        },
+    };
+
+    fn write_parquet(batch: &RecordBatch, max_row_group_size: usize) -> Bytes {
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(max_row_group_size)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, batch.schema(), Some(props)).unwrap();
+        writer.write(batch).unwrap();
+        writer.close().unwrap();
+        Bytes::from(buf)
+    }
+
+    /// puts files into a memory store, returns a reader factory of the store
+    /// and the files to scan
+    async fn memory_files(
+        files: Vec<(&str, Bytes)>,
+    ) -> (Arc<FsReaderFactory>, Vec<PartitionedFile>) {
+        let store = Arc::new(InMemory::new());
+        let mut partitioned_files = vec![];
+        for (name, bytes) in files {
+            let path = format!("t/{name}");
+            store
+                .put(&Path::from(path.as_str()), bytes.clone())
+                .await
+                .unwrap();
+            let encoded = BASE64_URL_SAFE_NO_PAD.encode(format!("memory://bucket/{path}"));
+            partitioned_files.push(PartitionedFile::new(
+                format!("parquet/{encoded}"),
+                bytes.len() as u64,
+            ));
+        }
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store);
+        let reader_factory = FsReaderFactory::new_with_object_stores(Arc::new(object_stores));
+        (Arc::new(reader_factory), partitioned_files)
+    }
+
+    /// builds a file opener the same way as the exec does, with all optional
+    /// handling disabled
+    fn test_file_opener(
+        reader_factory: Arc<FsReaderFactory>,
+        table_schema: SchemaRef,
+        projection: Vec<usize>,
+        metrics: &ExecutionPlanMetricsSet,
+    ) -> ParquetFileOpener {
+        let inner = ParquetOpener {
+            partition_index: 0,
+            projection: Arc::from(projection),
+            batch_size: 1024,
+            limit: None,
+            predicate: None,
+            pruning_predicate: None,
+            page_pruning_predicate: None,
+            table_schema,
+            metadata_size_hint: None,
+            metrics: metrics.clone(),
+            parquet_file_reader_factory: reader_factory.clone(),
+            pushdown_filters: false,
+            reorder_filters: false,
+            enable_page_index: false,
+            enable_bloom_filter: false,
+        };
+        ParquetFileOpener {
+            inner: Arc::new(inner),
+            partition_index: 0,
+            reader_factory,
+            metrics: metrics.clone(),
+            empty_files: MetricBuilder::new(metrics).counter("empty_files", 0),
+            open_files: None,
+            skipped_corrupted_files: None,
+            quarantined_row_groups: None,
+            memory_pool: Arc::new(GreedyMemoryPool::new(usize::MAX)),
+            estimated_dictionary_decode_time: Time::new(),
+            count_only: None,
+            external_columns: None,
+            deletion_vectors: None,
+            row_group_batching: None,
+        }
+    }
+
+    /// scans files with the opener, returns the number of rows of each batch
+    async fn scan_num_rows(
+        opener: ParquetFileOpener,
+        files: Vec<PartitionedFile>,
+        file_schema: SchemaRef,
+    ) -> Result<Vec<usize>, DataFusionError> {
+        let batches = scan_batches(opener, files, file_schema).await?;
+        Ok(batches.iter().map(|batch| batch.num_rows()).collect())
+    }
+
+    /// scans files with the opener, returns all batches
+    async fn scan_batches(
+        opener: ParquetFileOpener,
+        files: Vec<PartitionedFile>,
+        file_schema: SchemaRef,
+    ) -> Result<Vec<RecordBatch>, DataFusionError> {
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema: file_schema.clone(),
+            file_groups: vec![files],
+            statistics: Statistics::new_unknown(&file_schema),
+            projection: Some(opener.inner.projection.to_vec()),
+            limit: None,
+            table_partition_cols: vec![],
+            output_ordering: vec![],
+        };
+        let metrics = ExecutionPlanMetricsSet::new();
+        let stream = FileStream::new(&base_config, 0, opener, &metrics)?;
+        Ok(stream.try_collect::<Vec<_>>().await?)
+    }
+
+    fn build_int32_metadata(name: &str, values: Vec<i32>) -> ParquetMetaData {
+        let schema = Arc::new(Schema::new(vec![Field::new(name, DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from(values));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        parse_metadata(&write_parquet(&batch, 1024)).unwrap()
+    }
+
+    #[test]
+    fn test_strict_schema() {
+        let metadata = build_int32_metadata("a", vec![1, 2, 3]);
+
+        let strict_schema = StrictSchema {
+            table_schema: Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)])),
+            projection: vec![0],
+            case_sensitive: false,
+        };
+        assert!(strict_schema.check(&metadata, "/tmp/a.parquet").is_ok());
+
+        let strict_schema = StrictSchema {
+            table_schema: Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)])),
+            projection: vec![0],
+            case_sensitive: false,
+        };
+        let err = strict_schema
+            .check(&metadata, "/tmp/a.parquet")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("column a"));
+        assert!(err.contains("file type Int32, table type Int64"));
+        assert!(err.contains("/tmp/a.parquet"));
+
+        let strict_schema = StrictSchema {
+            table_schema: Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("b", DataType::Int32, true),
+            ])),
+            projection: vec![1],
+            case_sensitive: false,
+        };
+        let err = strict_schema
+            .check(&metadata, "/tmp/a.parquet")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("column b: missing in file"));
+    }
+
+    #[test]
+    fn test_strict_schema_resolution() {
+        let metadata = build_int32_metadata("A", vec![1, 2, 3]);
+        let strict_schema = |case_sensitive| StrictSchema {
+            table_schema: Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)])),
+            projection: vec![0],
+            case_sensitive,
+        };
+        assert!(strict_schema(false)
+            .check(&metadata, "/tmp/a.parquet")
+            .is_ok());
+        let err = strict_schema(true)
+            .check(&metadata, "/tmp/a.parquet")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("column a: missing in file"));
+
+        // nested types are resolved like the schema adapter
+        let strict_schema = strict_schema(false);
+        let list = |name: &str, nullable| {
+            DataType::List(Arc::new(Field::new(name, DataType::Int32, nullable)))
+        };
+        assert!(strict_schema.compatible(&list("item", true), &list("element", false)));
+        assert!(!strict_schema.compatible(
+            &list("item", true),
+            &DataType::List(Arc::new(Field::new("item", DataType::Int64, true)))
+        ));
+
+        let map = |entries: &str, key: &str, value: &str| {
+            let fields = Fields::from(vec![
+                Field::new(key, DataType::Utf8, false),
+                Field::new(value, DataType::Int32, true),
+            ]);
+            let entries = Field::new(entries, DataType::Struct(fields), false);
+            DataType::Map(Arc::new(entries), false)
+        };
+        assert!(strict_schema.compatible(
+            &map("key_value", "key", "value"),
+            &map("entries", "keys", "values"),
+        ));
+
+        let struct_type = |name: &str, data_type| {
+            DataType::Struct(Fields::from(vec![Field::new(name, data_type, true)]))
+        };
+        assert!(strict_schema.compatible(
+            &struct_type("X", list("item", true)),
+            &struct_type("x", list("element", true)),
+        ));
+        assert!(!strict_schema.compatible(
+            &struct_type("x", DataType::Int32),
+            &struct_type("y", DataType::Int32),
+        ));
+
+        let timestamp = |unit, tz: &str| DataType::Timestamp(unit, Some(tz.into()));
+        assert!(strict_schema.compatible(
+            &timestamp(TimeUnit::Microsecond, "+00:00"),
+            &timestamp(TimeUnit::Microsecond, "UTC"),
+        ));
+        assert!(!strict_schema.compatible(
+            &timestamp(TimeUnit::Millisecond, "UTC"),
+            &timestamp(TimeUnit::Microsecond, "UTC"),
+        ));
+        assert!(!strict_schema.compatible(
+            &DataType::Timestamp(TimeUnit::Microsecond, None),
+            &timestamp(TimeUnit::Microsecond, "UTC"),
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_with_extra_columns() {
+        // a column is added to the file but not to the table
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("extra", DataType::Utf8, true),
+            Field::new("b", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            file_schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["x", "y"])),
+                Arc::new(Int64Array::from(vec![10, 20])),
+            ],
+        )
+        .unwrap();
+        let bytes = write_parquet(&batch, 1024);
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("b", DataType::Int64, true),
+            Field::new("a", DataType::Int32, true),
+        ]));
+
+        let metadata = parse_metadata(&bytes).unwrap();
+        let extra_columns = |table_schema: &Schema, case_sensitive| {
+            ExtraColumnsWarning::new(table_schema, case_sensitive).extra_columns(&metadata)
+        };
+        assert_eq!(extra_columns(&table_schema, false), vec!["extra"]);
+        let upper_case_schema = Schema::new(vec![
+            Field::new("A", DataType::Int32, true),
+            Field::new("B", DataType::Int64, true),
+        ]);
+        assert_eq!(extra_columns(&upper_case_schema, false), vec!["extra"]);
+        assert_eq!(
+            extra_columns(&upper_case_schema, true),
+            vec!["a", "extra", "b"],
+        );
+
+        // the extra column is ignored when reading
+        let store = Arc::new(InMemory::new());
+        store
+            .put(&Path::from("t/part-0.parquet"), bytes.clone())
+            .await
+            .unwrap();
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store);
+        let reader_factory = FsReaderFactory::new_with_object_stores(Arc::new(object_stores))
+            .with_extra_columns_warning(&table_schema, false);
+        let encoded = BASE64_URL_SAFE_NO_PAD.encode("memory://bucket/t/part-0.parquet");
+        let object_meta =
+            PartitionedFile::new(format!("parquet/{encoded}"), bytes.len() as u64).object_meta;
+        let opener = ParquetOpener {
+            partition_index: 0,
+            projection: Arc::from(vec![0, 1]),
+            batch_size: 1024,
+            limit: None,
+            predicate: None,
+            pruning_predicate: None,
+            page_pruning_predicate: None,
+            table_schema: table_schema.clone(),
+            metadata_size_hint: None,
+            metrics: ExecutionPlanMetricsSet::new(),
+            parquet_file_reader_factory: Arc::new(reader_factory),
+            pushdown_filters: false,
+            reorder_filters: false,
+            enable_page_index: false,
+            enable_bloom_filter: false,
+        };
+        let stream = opener
+            .open(FileMeta::from(object_meta))
+            .unwrap()
+            .await
+            .unwrap();
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        let expected = RecordBatch::try_new(
+            table_schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![10, 20])),
+                Arc::new(Int32Array::from(vec![1, 2])),
+            ],
+        )
+        .unwrap();
+        assert_eq!(concat_batches(&table_schema, &batches).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_midpoint_file_range() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..100).collect::<Vec<i32>>()));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let data = write_parquet(&batch, 10);
+        let metadata = parse_metadata(&data).unwrap();
+        let row_groups = metadata.row_groups();
+        assert_eq!(row_groups.len(), 10);
+
+        // selects row groups the same way as datafusion does with the rewritten range
+        let selected_row_groups = |range: &FileRange| {
+            let range = midpoint_file_range(&metadata, range);
+            row_groups
+                .iter()
+                .enumerate()
+                .filter(|(_, rg)| {
+                    let col = rg.column(0);
+                    let offset = col
+                        .dictionary_page_offset()
+                        .unwrap_or_else(|| col.data_page_offset());
+                    offset >= range.start && offset < range.end
+                })
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>()
+        };
+
+        // whole file
+        let file_len = data.len() as i64;
+        let all = FileRange {
+            start: 0,
+            end: file_len,
+        };
+        assert_eq!(selected_row_groups(&all), (0..10).collect::<Vec<_>>());
+
+        // range containing no midpoints
+        let empty = FileRange {
+            start: file_len - 8,
+            end: file_len,
+        };
+        assert!(selected_row_groups(&empty).is_empty());
+
+        // splits are disjoint and every row group is read exactly once
+        for split_size in [1, 7, 50, 100, 1000] {
+            let mut selected = vec![];
+            let mut start = 0;
+            while start < file_len {
+                let end = (start + split_size).min(file_len);
+                selected.extend(selected_row_groups(&FileRange { start, end }));
+                start = end;
+            }
+            assert_eq!(selected, (0..10).collect::<Vec<_>>());
+        }
+
+        // a split starting at the middle of the first row group does not read it
+        let rg0 = &row_groups[0];
+        let rg0_offset = rg0
+            .column(0)
+            .dictionary_page_offset()
+            .unwrap_or_else(|| rg0.column(0).data_page_offset());
+        let rg0_mid = rg0_offset + rg0.compressed_size() / 2;
+        let range = FileRange {
+            start: rg0_mid + 1,
+            end: file_len,
+        };
+        assert_eq!(selected_row_groups(&range), (1..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound("abc"), Some("abd".to_string()));
+        assert_eq!(prefix_upper_bound("a\u{10FFFF}"), Some("b".to_string()));
+        assert_eq!(
+            prefix_upper_bound("a\u{D7FF}"),
+            Some("a\u{E000}".to_string())
+        );
+        assert_eq!(prefix_upper_bound("\u{10FFFF}"), None);
+        assert_eq!(prefix_upper_bound(""), None);
+    }
+
+    #[test]
+    fn test_rewrite_starts_with_as_bounds() {
+        let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
+        let array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("abc"),
+            Some("abcd"),
+            Some("abd"),
+            Some("ab"),
+            Some("abb\u{10FFFF}"),
+            Some("b"),
+            None,
+        ]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+
+        let starts_with: Arc<dyn PhysicalExpr> = Arc::new(StringStartsWithExpr::new(
+            phys_expr::col("s", &schema).unwrap(),
+            "abc".to_string(),
+        ));
+        let rewritten = rewrite_starts_with_as_bounds(starts_with.clone()).unwrap();
+        assert!(rewritten
+            .as_any()
+            .downcast_ref::<StringStartsWithExpr>()
+            .is_none());
+
+        let expected = starts_with
+            .evaluate(&batch)
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap();
+        let result = rewritten
+            .evaluate(&batch)
+            .unwrap()
+            .into_array(batch.num_rows())
+            .unwrap();
+        assert_eq!(&result, &expected);
+
+        // rewritten predicate can be used for pruning
+        let pruning_predicate = PruningPredicate::try_new(rewritten, schema).unwrap();
+        assert!(!pruning_predicate.allways_true());
+    }
+
+    #[tokio::test]
+    async fn test_prune_row_groups_by_starts_with() {
+        // sorted strings, each row group covers values of one prefix
+        let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
+        let values = ["a", "b", "c", "d"]
+            .iter()
+            .flat_map(|prefix| (0..100).map(move |i| format!("{prefix}{i:03}")))
+            .collect::<Vec<_>>();
+        let array: ArrayRef = Arc::new(StringArray::from(values));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+        let bytes = write_parquet(&batch, 100);
+        assert_eq!(parse_metadata(&bytes).unwrap().num_row_groups(), 4);
+        let (reader_factory, files) = memory_files(vec![("part-0.parquet", bytes)]).await;
+
+        let starts_with: Arc<dyn PhysicalExpr> = Arc::new(StringStartsWithExpr::new(
+            phys_expr::col("s", &schema).unwrap(),
+            "c0".to_string(),
+        ));
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema: schema.clone(),
+            file_groups: vec![files.clone()],
+            statistics: Statistics::new_unknown(&schema),
+            projection: None,
+            limit: None,
+            table_partition_cols: vec![],
+            output_ordering: vec![],
+        };
+        let exec = ParquetExec::new(base_config, "test".to_string(), Some(starts_with.clone()));
+
+        let metrics = ExecutionPlanMetricsSet::new();
+        let mut opener = test_file_opener(reader_factory, schema.clone(), vec![0], &metrics);
+        let inner = Arc::get_mut(&mut opener.inner).unwrap();
+        inner.predicate = Some(starts_with);
+        inner.pruning_predicate = exec.pruning_predicate().cloned();
+        let num_rows = scan_num_rows(opener, files, schema).await.unwrap();
+
+        // only the row group of prefix "c" is read
+        assert_eq!(num_rows.iter().sum::<usize>(), 100);
+        let row_groups_pruned = metrics
+            .clone_inner()
+            .sum_by_name("row_groups_pruned")
+            .map(|pruned| pruned.as_usize());
+        assert_eq!(row_groups_pruned, Some(3));
+    }
+
+    #[test]
+    fn test_rewrite_casts_as_column_bounds() {
+        struct MinMaxStatistics {
+            mins: ArrayRef,
+            maxs: ArrayRef,
+        }
+        impl PruningStatistics for MinMaxStatistics {
+            fn min_values(&self, _column: &Column) -> Option<ArrayRef> {
+                Some(self.mins.clone())
+            }
+            fn max_values(&self, _column: &Column) -> Option<ArrayRef> {
+                Some(self.maxs.clone())
+            }
+            fn num_containers(&self) -> usize {
+                self.mins.len()
+            }
+            fn null_counts(&self, _column: &Column) -> Option<ArrayRef> {
+                None
+            }
+            fn row_counts(&self, _column: &Column) -> Option<ArrayRef> {
+                None
+            }
+            fn contained(
+                &self,
+                _column: &Column,
+                _values: &HashSet<ScalarValue>,
+            ) -> Option<BooleanArray> {
+                None
+            }
+        }
+
+        // three row groups with i in [1, 3], [4, 6] and [7, 9]
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, true)]));
+        let statistics = MinMaxStatistics {
+            mins: Arc::new(Int32Array::from(vec![1, 4, 7])),
+            maxs: Arc::new(Int32Array::from(vec![3, 6, 9])),
+        };
+        let cast_i: Arc<dyn PhysicalExpr> = Arc::new(TryCastExpr::new(
+            phys_expr::col("i", &schema).unwrap(),
+            DataType::Int64,
+        ));
+        let int64 = |v: i64| phys_expr::lit(ScalarValue::Int64(Some(v)));
+
+        // cast(i as long) > 5
+        let predicate = phys_expr::binary(cast_i.clone(), Operator::Gt, int64(5), &schema).unwrap();
+        let pruning_predicate = PruningPredicate::try_new(predicate.clone(), schema.clone())
+            .map(|p| p.allways_true())
+            .unwrap_or(true);
+        assert!(pruning_predicate); // not translatable without rewriting
+
+        let rewritten = rewrite_casts_as_column_bounds(predicate, &schema).unwrap();
+        assert_eq!(rewritten.to_string(), "i@0 > 5");
+        let pruning_predicate = PruningPredicate::try_new(rewritten, schema.clone()).unwrap();
+        assert_eq!(
+            pruning_predicate.prune(&statistics).unwrap(),
+            vec![false, true, true],
+        );
+
+        // literal on the left side: 4 >= cast(i as long)
+        let predicate =
+            phys_expr::binary(int64(4), Operator::GtEq, cast_i.clone(), &schema).unwrap();
+        let rewritten = rewrite_casts_as_column_bounds(predicate, &schema).unwrap();
+        let pruning_predicate = PruningPredicate::try_new(rewritten, schema.clone()).unwrap();
+        assert_eq!(
+            pruning_predicate.prune(&statistics).unwrap(),
+            vec![true, true, false],
+        );
+
+        // literals not representable in the column type are kept as-is
+        let predicate = phys_expr::binary(
+            cast_i.clone(),
+            Operator::Lt,
+            int64(i32::MAX as i64 + 1),
+            &schema,
+        )
+        .unwrap();
+        let rewritten = rewrite_casts_as_column_bounds(predicate.clone(), &schema).unwrap();
+        assert_eq!(rewritten.to_string(), predicate.to_string());
+
+        // lossy casts are kept as-is
+        let predicate = phys_expr::binary(
+            Arc::new(TryCastExpr::new(
+                phys_expr::col("i", &schema).unwrap(),
+                DataType::Float32,
+            )),
+            Operator::Gt,
+            phys_expr::lit(ScalarValue::Float32(Some(5.0))),
+            &schema,
+        )
+        .unwrap();
+        let rewritten = rewrite_casts_as_column_bounds(predicate.clone(), &schema).unwrap();
+        assert_eq!(rewritten.to_string(), predicate.to_string());
+    }
+
+    #[test]
+    fn test_cast_partition_value() {
+        let utf8 = |v: &str| ScalarValue::Utf8(Some(v.to_string()));
+
+        // int
+        let field = Field::new("dt", DataType::Int32, true);
+        assert_eq!(
+            cast_partition_value(&utf8("2024"), &field, false).unwrap(),
+            ScalarValue::Int32(Some(2024)),
+        );
+        assert_eq!(
+            cast_partition_value(&utf8("abc"), &field, false).unwrap(),
+            ScalarValue::Int32(None),
+        );
+        assert!(cast_partition_value(&utf8("abc"), &field, true).is_err());
+        assert_eq!(
+            cast_partition_value(&ScalarValue::Utf8(None), &field, true).unwrap(),
+            ScalarValue::Int32(None),
+        );
+
+        // date
+        let field = Field::new("dt", DataType::Date32, true);
+        assert_eq!(
+            cast_partition_value(&utf8("2024-01-02"), &field, false).unwrap(),
+            ScalarValue::Date32(Some(19724)),
+        );
+        assert_eq!(
+            cast_partition_value(&utf8("2024-13-45"), &field, false).unwrap(),
+            ScalarValue::Date32(None),
+        );
+
+        // decimal
+        let field = Field::new("price", DataType::Decimal128(10, 2), true);
+        assert_eq!(
+            cast_partition_value(&utf8("123.45"), &field, false).unwrap(),
+            ScalarValue::Decimal128(Some(12345), 10, 2),
+        );
+
+        // values of the declared type are kept as is
+        assert_eq!(
+            cast_partition_value(&ScalarValue::Decimal128(Some(1), 10, 2), &field, true).unwrap(),
+            ScalarValue::Decimal128(Some(1), 10, 2),
+        );
+
+        // dictionary values are cast to the value type
+        let dict_type = DataType::Dictionary(Box::new(DataType::UInt16), Box::new(DataType::Int32));
+        let field = Field::new("dt", dict_type, true);
+        let dict = |value| ScalarValue::Dictionary(Box::new(DataType::UInt16), Box::new(value));
+        assert_eq!(
+            cast_partition_value(&utf8("2024"), &field, false).unwrap(),
+            dict(ScalarValue::Int32(Some(2024))),
+        );
+        assert!(cast_partition_value(&utf8("abc"), &field, true).is_err());
+        assert_eq!(
+            cast_partition_value(&dict(utf8("1")), &field, false).unwrap(),
+            dict(ScalarValue::Int32(Some(1))),
+        );
+    }
+
+    fn build_parquet_exec(file_schema: Arc<Schema>, projection: Vec<usize>) -> ParquetExec {
+        let mut statistics = Statistics::new_unknown(&file_schema);
+        for (i, column_statistics) in statistics.column_statistics.iter_mut().enumerate() {
+            column_statistics.null_count = Precision::Exact(i);
+        }
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema,
+            file_groups: vec![],
+            statistics,
+            projection: Some(projection),
+            limit: None,
+            table_partition_cols: vec![],
+            output_ordering: vec![],
+        };
+        ParquetExec::new(base_config, "test".to_string(), None)
+    }
+
+    #[test]
+    fn test_predicate_creation_errors() {
+        let file_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let base_config = build_parquet_exec(file_schema.clone(), vec![0])
+            .base_config()
+            .clone();
+        let predicate = phys_expr::binary(
+            phys_expr::col("a", &file_schema).unwrap(),
+            Operator::Gt,
+            phys_expr::lit(1),
+            &file_schema,
+        )
+        .unwrap();
+
+        let failing_rewrite: PruningPredicateRewrite =
+            Arc::new(|_expr, _schema: &Schema| df_execution_err!("cannot translate expression"));
+        let exec = ParquetExec::new_with_pruning_predicate_rewrites(
+            base_config.clone(),
+            "test".to_string(),
+            Some(predicate.clone()),
+            &[failing_rewrite],
+        );
+        let errors = exec.predicate_creation_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("cannot rewrite pruning predicate a@0 > 1:"));
+        assert!(errors[0].contains("cannot translate expression"));
+
+        // the predicate before rewriting is still pushed down
+        assert!(exec.pruning_predicate().is_some());
+
+        let exec = ParquetExec::new(base_config.clone(), "test".to_string(), Some(predicate));
+        assert!(exec.predicate_creation_errors().is_empty());
+
+        // plans built with already built pruning predicates have the same
+        // metrics
+        let reused = ParquetExec::new_with_pruning_predicates(
+            base_config,
+            "test".to_string(),
+            exec.predicate.clone(),
+            exec.pruning_predicate.clone(),
+            exec.page_pruning_predicate.clone(),
+        );
+        let metric_names = |exec: &ParquetExec| {
+            exec.metrics()
+                .unwrap()
+                .iter()
+                .map(|metric| metric.value().name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(metric_names(&reused), vec!["num_predicate_creation_errors"]);
+        assert_eq!(metric_names(&reused), metric_names(&exec));
+        assert_eq!(
+            reused
+                .metrics()
+                .unwrap()
+                .sum_by_name("num_predicate_creation_errors")
+                .map(|v| v.as_usize()),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_output_schema() {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Int32, true),
+        ]));
+        let exec = build_parquet_exec(file_schema, vec![0, 1, 2]);
+        let batch = RecordBatch::try_new(
+            exec.schema(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["x", "y"])),
+                Arc::new(Int32Array::from(vec![3, 4])),
+            ],
+        )
+        .unwrap();
+
+        // reordered and renamed outputs
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("c_renamed", DataType::Int32, true),
+            Field::new("a", DataType::Int32, true),
+            Field::new("b_renamed", DataType::Utf8, true),
+        ]));
+        let exec = exec
+            .with_output_schema(output_schema.clone(), vec![2, 0, 1])
+            .unwrap();
+        assert_eq!(exec.schema(), output_schema);
+        assert_eq!(exec.projected_schema(), &output_schema);
+
+        let null_counts = exec
+            .statistics()
+            .unwrap()
+            .column_statistics
+            .iter()
+            .map(|s| s.null_count.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            null_counts,
+            vec![
+                Precision::Exact(2),
+                Precision::Exact(0),
+                Precision::Exact(1)
+            ],
+        );
+
+        let mapped = exec
+            .output_mapping
+            .as_ref()
+            .unwrap()
+            .map_batch(&batch)
+            .unwrap();
+        assert_eq!(mapped.schema(), output_schema);
+        assert_eq!(mapped.column(0).as_ref(), batch.column(2).as_ref());
+        assert_eq!(mapped.column(1).as_ref(), batch.column(0).as_ref());
+        assert_eq!(mapped.column(2).as_ref(), batch.column(1).as_ref());
+    }
+
+    #[tokio::test]
+    async fn test_dictionary_partition_columns() {
+        let file_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![Arc::new(Int32Array::from((0..100).collect::<Vec<_>>()))],
+        )
+        .unwrap();
+        let bytes = write_parquet(&batch, 30);
+        let store = Arc::new(InMemory::new());
+        store
+            .put(&Path::from("t/part-0.parquet"), bytes.clone())
+            .await
+            .unwrap();
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store);
+        let reader_factory = Arc::new(FsReaderFactory::new_with_object_stores(Arc::new(
+            object_stores,
+        )));
+
+        let encoded = BASE64_URL_SAFE_NO_PAD.encode("memory://bucket/t/part-0.parquet");
+        let mut file = PartitionedFile::new(format!("parquet/{encoded}"), bytes.len() as u64);
+        file.partition_values = vec![ScalarValue::Utf8(Some("2024-01-02".to_string()))];
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema: file_schema.clone(),
+            file_groups: vec![vec![file]],
+            statistics: Statistics::new_unknown(&file_schema),
+            projection: Some(vec![1, 0]),
+            limit: None,
+            table_partition_cols: vec![Field::new("dt", DataType::Date32, true)],
+            output_ordering: vec![],
+        };
+
+        // scans the same way as the exec does
+        let scan = |exec: &ParquetExec| {
+            let mut base_config = exec.base_config().clone();
+            base_config.file_groups[0] = cast_partition_values(
+                &base_config.file_groups[0],
+                &base_config.table_partition_cols,
+                true,
+            )
+            .unwrap();
+            let opener = ParquetOpener {
+                partition_index: 0,
+                projection: Arc::from(vec![0]),
+                batch_size: 1024,
+                limit: None,
+                predicate: None,
+                pruning_predicate: None,
+                page_pruning_predicate: None,
+                table_schema: file_schema.clone(),
+                metadata_size_hint: None,
+                metrics: ExecutionPlanMetricsSet::new(),
+                parquet_file_reader_factory: reader_factory.clone(),
+                pushdown_filters: false,
+                reorder_filters: false,
+                enable_page_index: false,
+                enable_bloom_filter: false,
+            };
+            let metrics = ExecutionPlanMetricsSet::new();
+            let stream = FileStream::new(&base_config, 0, opener, &metrics).unwrap();
+            let schema = exec.schema();
+            async move {
+                let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+                concat_batches(&schema, &batches).unwrap()
+            }
+        };
+
+        let dense_exec = ParquetExec::new(base_config, "test".to_string(), None);
+        let dense = scan(&dense_exec).await;
+        assert_eq!(dense.column(0).data_type(), &DataType::Date32);
+
+        let exec = dense_exec.clone().with_dictionary_partition_columns();
+        let dict_type =
+            DataType::Dictionary(Box::new(DataType::UInt16), Box::new(DataType::Date32));
+        assert_eq!(exec.schema().field(0).data_type(), &dict_type);
+        assert_eq!(exec.schema().field(1), dense_exec.schema().field(1));
+
+        // values are the same as the dense representation
+        let encoded = scan(&exec).await;
+        assert_eq!(encoded.num_rows(), 100);
+        assert_eq!(encoded.column(0).data_type(), &dict_type);
+        assert_eq!(encoded.column(0).as_any_dictionary().values().len(), 1);
+        let decoded = arrow::compute::cast(encoded.column(0), &DataType::Date32).unwrap();
+        assert_eq!(decoded.as_ref(), dense.column(0).as_ref());
+        assert_eq!(encoded.column(1).as_ref(), dense.column(1).as_ref());
+
+        // outputs mapped to dense types are kept
+        let output_schema = dense_exec.schema();
+        let exec = dense_exec
+            .with_output_schema(output_schema.clone(), vec![0, 1])
+            .unwrap()
+            .with_dictionary_partition_columns();
+        assert_eq!(exec.schema(), output_schema);
+        assert_eq!(
+            exec.base_config().table_partition_cols[0].data_type(),
+            &dict_type
+        );
+    }
+
+    #[test]
+    fn test_output_schema_mismatch() {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let output_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let exec = build_parquet_exec(file_schema.clone(), vec![0]);
+        assert!(exec
+            .clone()
+            .with_output_schema(output_schema.clone(), vec![0, 1])
+            .is_err());
+        assert!(exec.with_output_schema(output_schema, vec![1]).is_err());
+    }
+
+    #[test]
+    fn test_check_metadata_size() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..1000).collect::<Vec<_>>()));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let data = write_parquet(&batch, 10);
+        let metadata_len = check_metadata_size(&data, None).unwrap();
+        assert!(metadata_len > 0 && metadata_len < data.len());
+
+        // footer within limit
+        assert_eq!(
+            check_metadata_size(&data, Some(metadata_len)).unwrap(),
+            metadata_len,
+        );
+
+        // oversized footer
+        let err = check_metadata_size(&data, Some(metadata_len - 1)).unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit"));
+
+        // suffix too short to contain a footer
+        assert_eq!(check_metadata_size(&data[..4], Some(0)).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_file_with_footer_padding() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..1000).collect::<Vec<_>>()));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let data = write_parquet(&batch, 100);
+        let mut padded = data.to_vec();
+        padded.resize(data.len() + 4096, 0);
+        let padded = Bytes::from(padded);
+
+        // padding is recognized only as zeros after the magic
+        assert_eq!(find_padded_footer_end(&padded, 0), Some(data.len()));
+        assert_eq!(
+            find_padded_footer_end(&padded[100..], 100),
+            Some(data.len())
+        );
+        assert_eq!(find_padded_footer_end(&data, 0), None);
+        let mut garbage = data.to_vec();
+        garbage.extend([1u8, 0, 0, 0]);
+        assert_eq!(find_padded_footer_end(&garbage, 0), None);
+        assert_eq!(find_padded_footer_end(&padded[data.len() - 8..], 0), None);
+
+        let store = Arc::new(InMemory::new());
+        store
+            .put(&Path::from("t/padded.parquet"), padded.clone())
+            .await
+            .unwrap();
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store);
+        let reader_factory = FsReaderFactory::new_with_object_stores(Arc::new(object_stores));
+        let encoded = BASE64_URL_SAFE_NO_PAD.encode("memory://bucket/t/padded.parquet");
+        let object_meta =
+            PartitionedFile::new(format!("parquet/{encoded}"), padded.len() as u64).object_meta;
+        let metrics = ExecutionPlanMetricsSet::new();
+        let reader = reader_factory
+            .create_file_reader(0, FileMeta::from(object_meta), &metrics)
+            .unwrap();
+
+        // both full and projected footers are found before the padding
+        let footer_bytes = reader.0.clone().fetch_footer_bytes().await.unwrap();
+        assert_eq!(
+            footer_bytes.len(),
+            check_metadata_size(&data, None).unwrap()
+        );
+        let batches = ParquetRecordBatchStreamBuilder::new(reader)
+            .await
+            .unwrap()
+            .build()
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let num_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!(num_rows, 1000);
+        assert_eq!(
+            concat_batches(&batches[0].schema(), &batches).unwrap(),
+            batch
+        );
+    }
+
+    /// delta deletion vector of a run container, with scattered deleted rows
+    /// and a contiguous range across row groups: {5, 99, 100, 517} and
+    /// [250, 420). returns the serialized vector and the deleted rows
+    fn delta_deletion_vector() -> (Vec<u8>, HashSet<i32>) {
+        let mut dv = vec![];
+        dv.write_u32::<LE>(1681511377).unwrap();
+        dv.write_u64::<LE>(1).unwrap();
+        dv.write_u32::<LE>(0).unwrap();
+        dv.write_u32::<LE>(12347).unwrap(); // one container
+        dv.push(0x01); // run flags
+        for v in [0, 173, 4, 5, 0, 99, 1, 250, 169, 517, 0] {
+            dv.write_u16::<LE>(v).unwrap();
+        }
+        let deleted = [5, 99, 100, 517]
+            .into_iter()
+            .chain(250..420)
+            .collect::<HashSet<i32>>();
+        (dv, deleted)
+    }
+
+    fn int32_values(batches: &[RecordBatch]) -> Vec<i32> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_deletion_vectors() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..1000).collect::<Vec<_>>()));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+        let data = write_parquet(&batch, 100);
+        let (dv, deleted) = delta_deletion_vector();
+
+        // only the first file has deleted rows
+        let (reader_factory, files) =
+            memory_files(vec![("dv-0.parquet", data.clone()), ("dv-1.parquet", data)]).await;
+        let expected = (0..1000)
+            .filter(|v| !deleted.contains(v))
+            .chain(0..1000)
+            .collect::<Vec<_>>();
+
+        // the same rows are deleted with or without reusing selection masks
+        for scratch_bitmaps in [None, Some(Arc::new(Mutex::new(ScratchBitmaps::default())))] {
+            let metrics = ExecutionPlanMetricsSet::new();
+            let mut opener =
+                test_file_opener(reader_factory.clone(), schema.clone(), vec![0], &metrics);
+            let rows_deleted = MetricBuilder::new(&metrics).counter("rows_deleted", 0);
+            let dv = dv.clone();
+            opener.deletion_vectors = Some(DeletionVectorScan {
+                fetch: Arc::new(move |path: &str| {
+                    if !path.ends_with("dv-0.parquet") {
+                        return Ok(None);
+                    }
+                    Ok(Some(DeletionVector::from_delta_bytes(&dv)?))
+                }),
+                unpruned_reader_factory: reader_factory.clone(),
+                rows_deleted: rows_deleted.clone(),
+                scratch_bitmaps: scratch_bitmaps.clone(),
+            });
+            let batches = scan_batches(opener, files.clone(), schema.clone())
+                .await
+                .unwrap();
+            assert_eq!(int32_values(&batches), expected);
+            assert_eq!(rows_deleted.value(), deleted.len());
+
+            // masks of the 10 row groups are built in one recycled buffer
+            if let Some(scratch_bitmaps) = scratch_bitmaps {
+                let scratch_bitmaps = scratch_bitmaps.lock();
+                assert_eq!(scratch_bitmaps.num_allocated(), 1);
+                assert_eq!(scratch_bitmaps.num_reused(), 9);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deletion_vectors_with_pruned_row_groups() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..1000).collect::<Vec<_>>()));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+        let data = write_parquet(&batch, 100);
+        let (dv, deleted) = delta_deletion_vector();
+
+        // the dictionaries of all row groups but [100, 200) contain a value
+        let values = (0..10)
+            .filter(|&i| i != 1)
+            .map(|i| phys_expr::lit(i * 100))
+            .collect();
+        let predicate = phys_expr::in_list(
+            phys_expr::col("a", &schema).unwrap(),
+            values,
+            &false,
+            &schema,
+        )
+        .unwrap();
+        let dictionary_filter = DictionaryFilter::try_new(&predicate, false).unwrap();
+        let (unpruned_reader_factory, files) =
+            memory_files(vec![("dv-pruned.parquet", data)]).await;
+        let reader_factory = Arc::new(
+            unpruned_reader_factory
+                .as_ref()
+                .clone()
+                .with_dictionary_filter(dictionary_filter),
+        );
+
+        // rows after the pruned row group keep their positions in the file
+        let metrics = ExecutionPlanMetricsSet::new();
+        let mut opener = test_file_opener(reader_factory, schema.clone(), vec![0], &metrics);
+        let rows_deleted = MetricBuilder::new(&metrics).counter("rows_deleted", 0);
+        opener.deletion_vectors = Some(DeletionVectorScan {
+            fetch: Arc::new(move |_: &str| Ok(Some(DeletionVector::from_delta_bytes(&dv)?))),
+            unpruned_reader_factory,
+            rows_deleted: rows_deleted.clone(),
+            scratch_bitmaps: None,
+        });
+        let batches = scan_batches(opener, files, schema.clone()).await.unwrap();
+        let expected = (0..1000)
+            .filter(|v| !(100..200).contains(v) && !deleted.contains(v))
+            .collect::<Vec<_>>();
+        assert_eq!(int32_values(&batches), expected);
+        assert_eq!(
+            rows_deleted.value(),
+            deleted.iter().filter(|v| !(100..200).contains(*v)).count(),
+        );
+        let pruned_by_dictionary = metrics
+            .clone_inner()
+            .sum_by_name("row_groups_pruned_by_dictionary")
+            .map(|v| v.as_usize());
+        assert_eq!(pruned_by_dictionary, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_limit_open_files() {
+        struct OpenFileGuard(Arc<AtomicUsize>);
+        impl Drop for OpenFileGuard {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, SeqCst);
+            }
+        }
+
+        let num_open_files = Arc::new(AtomicUsize::new(0));
+        let max_open_files = Arc::new(AtomicUsize::new(0));
+        let open_files = Arc::new(Semaphore::new(2));
+        let mut open_futures = (0..4)
+            .map(|_| {
+                let num_open_files = num_open_files.clone();
+                let max_open_files = max_open_files.clone();
+                let open_future: FileOpenFuture = Box::pin(async move {
+                    let n = num_open_files.fetch_add(1, SeqCst) + 1;
+                    max_open_files.fetch_max(n, SeqCst);
+                    let guard = OpenFileGuard(num_open_files);
+                    let stream = futures::stream::empty::<Result<RecordBatch, ArrowError>>();
+                    Ok::<_, DataFusionError>(
+                        stream
+                            .map(move |batch| {
+                                let _guard = &guard;
+                                batch
+                            })
+                            .boxed(),
+                    )
+                });
+                limit_open_files(open_future, open_files.clone())
+            })
+            .collect::<Vec<_>>();
+
+        let stream0 = (&mut open_futures[0]).await.unwrap();
+        let stream1 = (&mut open_futures[1]).await.unwrap();
+        assert!((&mut open_futures[2]).now_or_never().is_none());
+        assert!((&mut open_futures[3]).now_or_never().is_none());
+        assert_eq!(num_open_files.load(SeqCst), 2);
+
+        // opening next file after previous one is closed
+        drop(stream0);
+        let stream2 = (&mut open_futures[2]).await.unwrap();
+        assert!((&mut open_futures[3]).now_or_never().is_none());
+        drop(stream1);
+        drop(stream2);
+        let stream3 = (&mut open_futures[3]).await.unwrap();
+        drop(stream3);
+
+        assert_eq!(num_open_files.load(SeqCst), 0);
+        assert_eq!(max_open_files.load(SeqCst), 2);
+    }
+
+    #[test]
+    fn test_null_sentinels() {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, true),
+            Field::new("f", DataType::Float64, true),
+            Field::new("s", DataType::Utf8, true),
+        ]));
+        let exec = build_parquet_exec(file_schema, vec![0, 1, 2]);
+        let batch = RecordBatch::try_new(
+            exec.schema(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(-999), None])),
+                Arc::new(Float64Array::from(vec![Some(-999.0), Some(1.5), None])),
+                Arc::new(StringArray::from(vec![Some("a"), Some("\\N"), None])),
+            ],
+        )
+        .unwrap();
+
+        // sentinels are cast to column types
+        let exec = exec
+            .with_null_sentinels(HashMap::from([
+                ("i".to_string(), ScalarValue::Int64(Some(-999))),
+                ("f".to_string(), ScalarValue::Int32(Some(-999))),
+                ("s".to_string(), ScalarValue::from("\\N")),
+            ]))
+            .unwrap();
+        let output = exec.null_sentinels.as_ref().unwrap().apply(&batch).unwrap();
+        assert_eq!(
+            output.column(0).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![Some(1), None, None]),
+        );
+        assert_eq!(
+            output.column(1).as_primitive::<Float64Type>(),
+            &Float64Array::from(vec![None, Some(1.5), None]),
+        );
+        assert_eq!(
+            output.column(2).as_string::<i32>(),
+            &StringArray::from(vec![Some("a"), None, None]),
+        );
+
+        // columns without sentinel values are not rebuilt
+        let batch = RecordBatch::try_new(
+            exec.schema(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(-999)])),
+                Arc::new(Float64Array::from(vec![Some(2.0), None])),
+                Arc::new(StringArray::from(vec![Some("a"), Some("b")])),
+            ],
+        )
+        .unwrap();
+        let output = exec.null_sentinels.as_ref().unwrap().apply(&batch).unwrap();
+        assert!(!Arc::ptr_eq(output.column(0), batch.column(0)));
+        assert!(Arc::ptr_eq(output.column(1), batch.column(1)));
+        assert!(Arc::ptr_eq(output.column(2), batch.column(2)));
+    }
+
+    #[test]
+    fn test_null_sentinels_with_output_schema() {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, true),
+        ]));
+        let decoded = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(-999)])),
+                Arc::new(StringArray::from(vec![Some("\\N"), Some("a")])),
+            ],
+        )
+        .unwrap();
+        let null_sentinels = HashMap::from([
+            ("i".to_string(), ScalarValue::Int32(Some(-999))),
+            ("s".to_string(), ScalarValue::from("\\N")),
+        ]);
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("s_renamed", DataType::Utf8, true),
+            Field::new("i_renamed", DataType::Int64, true),
+        ]));
+        let expected = RecordBatch::try_new(
+            output_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![None, Some("a")])),
+                Arc::new(Int64Array::from(vec![Some(1), None])),
+            ],
+        )
+        .unwrap();
+
+        // sentinels refer to decoded columns regardless of the builder order
+        let scan = |exec: ParquetExec| {
+            let batch = exec.null_sentinels.as_ref().unwrap().apply(&decoded)?;
+            exec.output_mapping.as_ref().unwrap().map_batch(&batch)
+        };
+        let exec = build_parquet_exec(file_schema.clone(), vec![0, 1])
+            .with_null_sentinels(null_sentinels.clone())
+            .unwrap()
+            .with_output_schema(output_schema.clone(), vec![1, 0])
+            .unwrap();
+        assert_eq!(scan(exec).unwrap(), expected);
+        let exec = build_parquet_exec(file_schema, vec![0, 1])
+            .with_output_schema(output_schema, vec![1, 0])
+            .unwrap()
+            .with_null_sentinels(null_sentinels)
+            .unwrap();
+        assert_eq!(scan(exec).unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_null_sentinels_scan_is_null() {
+        // sentinel columns are non-nullable in the file
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new("s", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, -999, 3, -999])),
+                Arc::new(StringArray::from(vec!["a", "b", "\\N", "d"])),
+            ],
+        )
+        .unwrap();
+        let null_sentinels = HashMap::from([
+            ("i".to_string(), ScalarValue::Int32(Some(-999))),
+            ("s".to_string(), ScalarValue::from("\\N")),
+        ]);
+
+        // predicates on sentinel columns would prune and filter by the raw
+        // sentinel values, whose statistics have no nulls
+        let is_null = phys_expr::is_null(phys_expr::col("i", &file_schema).unwrap()).unwrap();
+        let base_config = build_parquet_exec(file_schema.clone(), vec![0, 1])
+            .base_config()
+            .clone();
+        let exec = ParquetExec::new(base_config, "test".to_string(), Some(is_null.clone()));
+        assert!(exec.with_null_sentinels(null_sentinels.clone()).is_err());
+
+        let exec = build_parquet_exec(file_schema.clone(), vec![0, 1])
+            .with_null_sentinels(null_sentinels)
+            .unwrap();
+        assert!(exec
+            .schema()
+            .fields()
+            .iter()
+            .all(|field| field.is_nullable()));
+
+        // decoded batches go through the same steps as in the exec
+        let (reader_factory, files) =
+            memory_files(vec![("sentinels.parquet", write_parquet(&batch, 100))]).await;
+        let metrics = ExecutionPlanMetricsSet::new();
+        let opener = test_file_opener(reader_factory, file_schema.clone(), vec![0, 1], &metrics);
+        let decoded = scan_batches(opener, files, file_schema.clone())
+            .await
+            .unwrap();
+        let non_nullable_columns = NonNullableColumns::try_new(&file_schema).unwrap();
+        let null_sentinels = exec.null_sentinels.as_ref().unwrap();
+        let output = decoded
+            .iter()
+            .map(|batch| null_sentinels.apply(&non_nullable_columns.apply(batch)?))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(output
+            .iter()
+            .all(|batch| batch.schema().fields() == exec.schema().fields()));
+        let output = concat_batches(&exec.schema(), &output).unwrap();
+
+        // IS NULL evaluated on the output sees the sentinels as nulls
+        let is_null = is_null
+            .evaluate(&output)
+            .unwrap()
+            .into_array(output.num_rows())
+            .unwrap();
+        assert_eq!(
+            is_null.as_boolean(),
+            &BooleanArray::from(vec![false, true, false, true]),
+        );
+        assert_eq!(
+            output.column(1).as_string::<i32>(),
+            &StringArray::from(vec![Some("a"), Some("b"), None, Some("d")]),
+        );
+    }
+
+    #[test]
+    fn test_null_sentinels_missing_column() {
+        let file_schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, true)]));
+        let exec = build_parquet_exec(file_schema, vec![0]);
+        assert!(exec
+            .with_null_sentinels(HashMap::from([(
+                "x".to_string(),
+                ScalarValue::Int32(Some(-999)),
+            )]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_count_only_batches() {
+        let num_rows = |batches: Vec<RecordBatch>| {
+            batches
+                .iter()
+                .map(|batch| {
+                    assert_eq!(batch.num_columns(), 0);
+                    batch.num_rows()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            num_rows(count_only_batches(0, 10).unwrap()),
+            vec![] as Vec<usize>
+        );
+        assert_eq!(num_rows(count_only_batches(10, 10).unwrap()), vec![10]);
+        assert_eq!(
+            num_rows(count_only_batches(25, 10).unwrap()),
+            vec![10, 10, 5]
+        );
+    }
+
+    #[test]
+    fn test_midpoint_row_groups() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..100).collect::<Vec<i32>>()));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let data = write_parquet(&batch, 10);
+        let metadata = parse_metadata(&data).unwrap();
+
+        // row groups of all splits are disjoint and cover the whole file
+        let file_len = data.len() as i64;
+        let split_len = file_len / 3 + 1;
+        let mut row_groups = vec![];
+        for start in (0..file_len).step_by(split_len as usize) {
+            let range = FileRange {
+                start,
+                end: (start + split_len).min(file_len),
+            };
+            row_groups.extend(midpoint_row_groups(&metadata, &range));
+        }
+        assert_eq!(row_groups, (0..10).collect::<Vec<_>>());
+        let num_rows = row_groups
+            .iter()
+            .map(|&i| metadata.row_group(i).num_rows())
+            .sum::<i64>();
+        assert_eq!(num_rows, 100);
+    }
+
+    #[test]
+    fn test_row_group_batching() {
+        let column_names = HashSet::from(["a".to_string()]);
+
+        // undersized row groups are decoded together, at most 4 at a time
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..100).collect::<Vec<i32>>()));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let metadata = parse_metadata(&write_parquet(&batch, 10)).unwrap();
+        let row_group_batching = RowGroupBatching {
+            max_row_groups: 4,
+            target_bytes: Some(1 << 20),
+        };
+        let units = row_group_batching.decode_units(
+            &metadata,
+            &(0..10).collect::<Vec<_>>(),
+            &column_names,
+            8192,
+        );
+        assert_eq!(
+            units,
+            vec![
+                DecodeUnit {
+                    row_groups: 0..4,
+                    batch_size: 8192,
+                },
+                DecodeUnit {
+                    row_groups: 4..8,
+                    batch_size: 8192,
+                },
+                DecodeUnit {
+                    row_groups: 8..10,
+                    batch_size: 8192,
+                },
+            ],
+        );
+
+        // selected row groups of a split
+        let units = row_group_batching.decode_units(&metadata, &[3, 4, 5], &column_names, 8192);
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].row_groups, 3..6);
+
+        // an oversized row group is chunked into batches fitting the target
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+        let array: ArrayRef = Arc::new(StringArray::from(
+            (0..1000).map(|i| format!("{i:0>1000}")).collect::<Vec<_>>(),
+        ));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let metadata = parse_metadata(&write_parquet(&batch, 1000)).unwrap();
+        assert_eq!(metadata.num_row_groups(), 1);
+        let row_group_batching = RowGroupBatching {
+            max_row_groups: 4,
+            target_bytes: Some(100_000),
+        };
+        let units = row_group_batching.decode_units(&metadata, &[0], &column_names, 8192);
+        assert_eq!(units.len(), 1);
+        assert!(units[0].batch_size >= 90 && units[0].batch_size <= 100);
+
+        // rows larger than the target are decoded one by one
+        let row_group_batching = RowGroupBatching {
+            max_row_groups: 4,
+            target_bytes: Some(10),
+        };
+        let units = row_group_batching.decode_units(&metadata, &[0], &column_names, 8192);
+        assert_eq!(units[0].batch_size, 1);
+
+        // without a target size or projected columns, the batch size is kept
+        let row_group_batching = RowGroupBatching {
+            max_row_groups: 4,
+            target_bytes: None,
+        };
+        let units = row_group_batching.decode_units(&metadata, &[0], &column_names, 8192);
+        assert_eq!(units[0].batch_size, 8192);
+        let row_group_batching = RowGroupBatching {
+            max_row_groups: 4,
+            target_bytes: Some(10),
+        };
+        let units = row_group_batching.decode_units(&metadata, &[0], &HashSet::new(), 8192);
+        assert_eq!(units[0].batch_size, 8192);
+    }
+
+    #[test]
+    fn test_read_legacy_list_encodings() {
+        // fixtures of legacy 2-level lists written by old parquet-avro,
+        // parquet-thrift and hive, all with rows [1, 2], [], null, [3]
+        let parquet_schema = Arc::new(
+            parse_message_type(
+                "message test {
+                    optional group a (LIST) { repeated int32 array; }
+                    optional group b (LIST) { repeated group array { required int32 x; } }
+                    optional group c (LIST) { repeated group c_tuple { required int32 x; } }
+                    optional group d (LIST) { repeated group bag { optional int32 array_element; } }
+                    optional group e (LIST) { repeated group element { required int32 x; } }
+                }",
+            )
+            .unwrap(),
+        );
+        let mut buf = vec![];
+        let mut writer =
+            SerializedFileWriter::new(&mut buf, parquet_schema, Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        for max_def_level in [2, 2, 2, 3, 2] {
+            let def_levels = [max_def_level, max_def_level, 1, 0, max_def_level];
+            let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+            col_writer
+                .typed::<ParquetInt32Type>()
+                .write_batch(
+                    &[1, 2, 3],
+                    Some(&def_levels[..]),
+                    Some(&[0, 1, 0, 0, 0][..]),
+                )
+                .unwrap();
+            col_writer.close().unwrap();
+        }
+        row_group_writer.close().unwrap();
+        writer.close().unwrap();
+        let data = Bytes::from(buf);
+
+        let metadata = parse_metadata(&data).unwrap();
+        let file_schema = parquet_file_arrow_schema(metadata.file_metadata()).unwrap();
+        let element = |idx: usize| match file_schema.field(idx).data_type() {
+            DataType::List(element) => element.clone(),
+            other => panic!("unexpected type: {other:?}"),
+        };
+        let x_struct = |nullable| {
+            DataType::Struct(Fields::from(vec![Field::new(
+                "x",
+                DataType::Int32,
+                nullable,
+            )]))
+        };
+        assert_eq!(element(0).data_type(), &DataType::Int32);
+        assert_eq!(element(1).data_type(), &x_struct(false));
+        assert_eq!(element(2).data_type(), &x_struct(false));
+        assert_eq!(element(3).data_type(), &DataType::Int32);
+        assert_eq!(element(4).data_type(), &DataType::Int32);
+
+        // the repeated group is the element only if spark expects a struct
+        // with its only field
+        let int_list = DataType::List(Arc::new(Field::new("element", DataType::Int32, true)));
+        let struct_list = DataType::List(Arc::new(Field::new("element", x_struct(true), true)));
+        let struct_element = Field::new("element", x_struct(true), true);
+        assert!(legacy_list_struct_element(&element(4), &struct_element).is_some());
+        assert!(legacy_list_struct_element(&element(1), &struct_element).is_none());
+        assert!(legacy_list_struct_element(&element(3), &struct_element).is_none());
+
+        let batch = ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let table_types = [
+            &int_list,
+            &struct_list,
+            &struct_list,
+            &int_list,
+            &struct_list,
+        ];
+        for (idx, table_type) in table_types.into_iter().enumerate() {
+            let casted = schema_adapter_cast_column(batch.column(idx), table_type).unwrap();
+            assert_eq!(casted.data_type(), table_type);
+            let values = casted
+                .as_list::<i32>()
+                .iter()
+                .map(|list| {
+                    let list = list?;
+                    let list = if matches!(list.data_type(), DataType::Struct(_)) {
+                        list.as_struct().column(0).clone()
+                    } else {
+                        list
+                    };
+                    Some(list.as_primitive::<Int32Type>().values().to_vec())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                values,
+                vec![Some(vec![1, 2]), Some(vec![]), None, Some(vec![3])],
+                "column #{idx}",
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_case_sensitive() {
+        let inner_fields = Fields::from(vec![Field::new("A", DataType::Int32, true)]);
+        let inner: ArrayRef = Arc::new(StructArray::new(
+            inner_fields,
+            vec![Arc::new(Int32Array::from(vec![1, 2]))],
+            None,
+        ));
+        let table_type =
+            DataType::Struct(Fields::from(vec![Field::new("a", DataType::Int32, true)]));
+
+        for case_sensitive in [false, true] {
+            let inner = inner.clone();
+            let table_type = table_type.clone();
+            let stream = futures::stream::once(async move {
+                let casted = schema_adapter_cast_column(&inner, &table_type)?;
+                Ok(RecordBatch::try_from_iter([("s", casted)])?)
+            })
+            .boxed();
+            let batches = with_scan_case_sensitive(stream, case_sensitive)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let field_a = batches[0].column(0).as_struct().column(0).clone();
+            let expected_nulls = if case_sensitive { 2 } else { 0 };
+            assert_eq!(field_a.null_count(), expected_nulls);
+        }
+
+        // restored after polling
+        assert!(!SCAN_CASE_SENSITIVE.with(Cell::get));
+    }
+
+    #[test]
+    fn test_read_enum_as_string() {
+        let parquet_schema = Arc::new(
+            parse_message_type("message test { required binary e (ENUM); required int32 i; }")
+                .unwrap(),
+        );
+        let mut buf = vec![];
+        let mut writer =
+            SerializedFileWriter::new(&mut buf, parquet_schema, Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+        col_writer
+            .typed::<ByteArrayType>()
+            .write_batch(
+                &[ByteArray::from("RED"), ByteArray::from("GREEN")],
+                None,
+                None,
+            )
+            .unwrap();
+        col_writer.close().unwrap();
+        let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+        col_writer
+            .typed::<ParquetInt32Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        col_writer.close().unwrap();
+        row_group_writer.close().unwrap();
+        writer.close().unwrap();
+        let data = Bytes::from(buf);
+
+        // ENUM columns are converted to strings in file schema
+        let metadata = parse_metadata(&data).unwrap();
+        let file_schema = parquet_file_arrow_schema(metadata.file_metadata()).unwrap();
+        assert_eq!(file_schema.field(0).data_type(), &DataType::Utf8);
+        assert_eq!(file_schema.field(1).data_type(), &DataType::Int32);
+
+        // decoded binary values are adapted to strings
+        let batch = ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let casted = schema_adapter_cast_column(batch.column(0), &DataType::Utf8).unwrap();
+        assert_eq!(
+            casted.as_string::<i32>(),
+            &StringArray::from(vec!["RED", "GREEN"]),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_geometry_as_binary() {
+        let parquet_schema = Arc::new(
+            parse_message_type("message test { optional binary geom (BSON); required int32 id; }")
+                .unwrap(),
+        );
+        let mut buf = vec![];
+        let mut writer =
+            SerializedFileWriter::new(&mut buf, parquet_schema, Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+        col_writer
+            .typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from(vec![1u8, 2, 3])], Some(&[1, 0]), None)
+            .unwrap();
+        col_writer.close().unwrap();
+        let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+        col_writer
+            .typed::<ParquetInt32Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        col_writer.close().unwrap();
+        row_group_writer.close().unwrap();
+        writer.close().unwrap();
+
+        // rewrites the BSON logical type (union field 13) of geom to GEOMETRY
+        // (union field 17), as written by newer writers
+        let footer_start = buf.len() - FOOTER_SIZE;
+        let metadata_len =
+            u32::from_le_bytes(buf[footer_start..][..4].try_into().unwrap()) as usize;
+        let metadata = &buf[footer_start - metadata_len..footer_start];
+        let pattern = [0x28, 0x4c, 0xdc, 0x00, 0x00];
+        let positions = metadata
+            .windows(pattern.len())
+            .enumerate()
+            .filter(|(_, window)| *window == pattern)
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        assert_eq!(positions.len(), 1);
+        let mut geometry_metadata = metadata[..positions[0] + 2].to_vec();
+        geometry_metadata.extend([0x0c, 0x22]);
+        geometry_metadata.extend(&metadata[positions[0] + 3..]);
+        let mut geometry_file = buf[..footer_start - metadata_len].to_vec();
+        geometry_file.extend(&geometry_metadata);
+        geometry_file.extend((geometry_metadata.len() as u32).to_le_bytes());
+        geometry_file.extend(b"PAR1");
+        let geometry_file = Bytes::from(geometry_file);
+
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("geom", DataType::Binary, true),
+            Field::new("id", DataType::Int32, false),
+        ]));
+        let metrics = ExecutionPlanMetricsSet::new();
+
+        // fails with the column by default
+        let (reader_factory, files) =
+            memory_files(vec![("geometry-0.parquet", geometry_file.clone())]).await;
+        let opener = test_file_opener(reader_factory, table_schema.clone(), vec![0, 1], &metrics);
+        let err = scan_num_rows(opener, files, table_schema.clone())
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("parquet column geom has unsupported logical type GEOMETRY"));
+
+        // read as plain binary if enabled
+        let (reader_factory, files) =
+            memory_files(vec![("geometry-1.parquet", geometry_file)]).await;
+        let reader_factory = Arc::new((*reader_factory).clone().with_geometry_as_binary());
+        let opener = test_file_opener(reader_factory, table_schema.clone(), vec![0, 1], &metrics);
+        let num_rows = scan_num_rows(opener, files, table_schema).await.unwrap();
+        assert_eq!(num_rows.iter().sum::<usize>(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_encrypted_footer() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let array: ArrayRef = Arc::new(Int32Array::from((0..100).collect::<Vec<_>>()));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+
+        // files with encrypted footers end with "PARE" instead of "PAR1"
+        let mut data = write_parquet(&batch, 100).to_vec();
+        let magic_start = data.len() - 4;
+        data[magic_start..].copy_from_slice(b"PARE");
+
+        // FileCryptoMetaData of AES_GCM_V1 with aad_file_unique and
+        // supply_aad_prefix, followed by the encrypted FileMetaData
+        let mut crypto_metadata = vec![0x1c, 0x1c, 0x28, 4, 1, 2, 3, 4, 0x11, 0x00, 0x00];
+        crypto_metadata.extend([0x18, 3]);
+        crypto_metadata.extend(b"key");
+        crypto_metadata.push(0x00);
+        let mut aad_prefixed_data = write_parquet(&batch, 100).to_vec();
+        let metadata_len = u32::from_le_bytes(
+            aad_prefixed_data[magic_start - 4..magic_start]
+                .try_into()
+                .unwrap(),
+        );
+        let metadata_start = magic_start - 4 - metadata_len as usize;
+        aad_prefixed_data.truncate(metadata_start);
+        aad_prefixed_data.extend(&crypto_metadata);
+        aad_prefixed_data.extend([0xab; 64]);
+        aad_prefixed_data.extend((crypto_metadata.len() as u32 + 64).to_le_bytes());
+        aad_prefixed_data.extend(b"PARE");
+
+        for (name, data, expected_err) in [
+            (
+                "encrypted.parquet",
+                data,
+                "reading encrypted parquet files is not supported: ",
+            ),
+            (
+                "aad-prefixed.parquet",
+                aad_prefixed_data,
+                "reading encrypted parquet files with aad prefixes is not supported (AES_GCM_V1)",
+            ),
+        ] {
+            let (reader_factory, files) = memory_files(vec![(name, Bytes::from(data))]).await;
+            let metrics = ExecutionPlanMetricsSet::new();
+            let opener = test_file_opener(reader_factory, schema.clone(), vec![0], &metrics);
+            let err = scan_num_rows(opener, files, schema.clone())
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains(expected_err), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_reserve_output_batch() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let small_batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1; 10]))],
+        )
+        .unwrap();
+        let large_batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1; 10000]))]).unwrap();
+        let small_size = small_batch.get_array_memory_size();
+
+        // tight memory limit
+        let pool: Arc<dyn MemoryPool> = Arc::new(GreedyMemoryPool::new(small_size));
+        let mut reservation = MemoryConsumer::new("test").register(&pool);
+        reserve_output_batch(&mut reservation, &small_batch);
+        assert_eq!(pool.reserved(), small_size);
+        reservation.free();
+
+        // batches exceeding the limit are still accounted
+        reserve_output_batch(&mut reservation, &large_batch);
+        assert_eq!(pool.reserved(), large_batch.get_array_memory_size());
+    }
+
+    #[test]
+    fn test_footer_statistics() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(3),
+                    Some(1),
+                    None,
+                    Some(7),
+                    Some(5),
+                ])),
+                Arc::new(StringArray::from(vec!["x", "y", "z", "u", "v"])),
+            ],
+        )
+        .unwrap();
+        let metadata = Arc::new(parse_metadata(&write_parquet(&batch, 3)).unwrap());
+
+        let stats = footer_statistics_batch(&[("/tmp/a.parquet".to_string(), metadata)]).unwrap();
+        assert_eq!(stats.schema(), footer_statistics_schema());
+        assert_eq!(stats.num_rows(), 2);
+        assert_eq!(
+            stats.column(0).as_string::<i32>(),
+            &StringArray::from(vec!["/tmp/a.parquet"; 2]),
+        );
+        assert_eq!(
+            stats.column(1).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![0, 1]),
+        );
+        assert_eq!(
+            stats.column(2).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![3, 2]),
+        );
+
+        // row group 0: a in [1, 3] with 1 null, b in [x, z]
+        // row group 1: a in [5, 7] with 0 nulls, b in [u, v]
+        let column_stats = stats.column(4).as_list::<i32>();
+        let rg0 = column_stats.value(0);
+        let rg0 = rg0.as_struct();
+        assert_eq!(
+            rg0.column(0).as_string::<i32>(),
+            &StringArray::from(vec!["a", "b"]),
+        );
+        assert_eq!(
+            rg0.column(1).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![1, 0]),
+        );
+        assert_eq!(
+            rg0.column(2).as_string::<i32>(),
+            &StringArray::from(vec!["1", "x"]),
+        );
+        assert_eq!(
+            rg0.column(3).as_string::<i32>(),
+            &StringArray::from(vec!["3", "z"]),
+        );
+        let rg1 = column_stats.value(1);
+        let rg1 = rg1.as_struct();
+        assert_eq!(
+            rg1.column(1).as_primitive::<Int64Type>(),
+            &Int64Array::from(vec![0, 0]),
+        );
+        assert_eq!(
+            rg1.column(2).as_string::<i32>(),
+            &StringArray::from(vec!["5", "u"]),
+        );
+        assert_eq!(
+            rg1.column(3).as_string::<i32>(),
+            &StringArray::from(vec!["7", "v"]),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_column_statistics() {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("n", DataType::Int32, true),
+            Field::new("nulls", DataType::Int32, true),
+        ]));
+        let store = Arc::new(InMemory::new());
+        let mut files = vec![];
+
+        // files with disjoint value ranges, each with 2 row groups
+        for (file_idx, ids) in [(0, 0..100), (1, 1000..1100)] {
+            let batch = RecordBatch::try_new(
+                file_schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter(
+                        ids.clone().map(|id| (id % 10 != 0).then_some(id)),
+                    )),
+                    Arc::new(StringArray::from_iter_values(
+                        ids.clone().map(|id| format!("name_{id:04}")),
+                    )),
+                    Arc::new(Int32Array::from_iter_values(
+                        ids.clone().map(|id| id as i32),
+                    )),
+                    Arc::new(Int32Array::from(vec![None; 100])),
+                ],
+            )
+            .unwrap();
+            let location = format!("t/column_stats_{file_idx}.parquet");
+            let bytes = write_parquet(&batch, 50);
+            store
+                .put(&Path::from(location.clone()), bytes.clone())
+                .await
+                .unwrap();
+            let encoded = BASE64_URL_SAFE_NO_PAD.encode(format!("memory://bucket/{location}"));
+            files.push(PartitionedFile::new(
+                format!("parquet/{encoded}"),
+                bytes.len() as u64,
+            ));
+        }
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store);
+        let reader_factory = FsReaderFactory::new_with_object_stores(Arc::new(object_stores));
+
+        // n is read as a different type, missing is not in the files
+        let table_schema = Schema::new(vec![
+            Field::new("ID", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("n", DataType::Int64, true),
+            Field::new("nulls", DataType::Int32, true),
+            Field::new("missing", DataType::Int32, true),
+        ]);
+        let stats = read_column_statistics_with(&reader_factory, &files, &table_schema)
+            .await
+            .unwrap();
+        let column_stats = |min: Option<ScalarValue>, max, null_count| ParquetColumnStatistics {
+            min,
+            max,
+            null_count,
+        };
+        let unknown = column_stats(None, None, None);
+        let all_nulls = column_stats(None, None, Some(100));
+        let int64 = |v: i64| Some(ScalarValue::Int64(Some(v)));
+        let utf8 = |v: &str| Some(ScalarValue::Utf8(Some(v.to_string())));
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats[0].file_path,
+            "memory://bucket/t/column_stats_0.parquet"
+        );
+        assert_eq!(stats[0].num_rows, 100);
+        assert_eq!(
+            stats[0].columns,
+            vec![
+                column_stats(int64(1), int64(99), Some(10)),
+                column_stats(utf8("name_0000"), utf8("name_0099"), Some(0)),
+                unknown.clone(),
+                all_nulls.clone(),
+                all_nulls.clone(),
+            ],
+        );
+        assert_eq!(
+            stats[1].columns[..2],
+            vec![
+                column_stats(int64(1001), int64(1099), Some(10)),
+                column_stats(utf8("name_1000"), utf8("name_1099"), Some(0)),
+            ],
+        );
+
+        // a value between the files is in neither of them, but in the range of
+        // the merged statistics
+        let merged = merge_file_statistics(&stats);
+        assert_eq!(
+            merged,
+            vec![
+                column_stats(int64(1), int64(1099), Some(20)),
+                column_stats(utf8("name_0000"), utf8("name_1099"), Some(0)),
+                unknown,
+                column_stats(None, None, Some(200)),
+                column_stats(None, None, Some(200)),
+            ],
+        );
+        assert!(merge_file_statistics(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_read_merged_ranges() {
+        let data = Bytes::from((0..=255u8).collect::<Vec<_>>());
+        let read_all = |ranges: &[Range<usize>]| {
+            let mut reads = vec![];
+            let bytes = read_merged_ranges(ranges, |range| {
+                reads.push(range.clone());
+                Ok(data.slice(range))
+            })
+            .unwrap();
+            for (range, bytes) in ranges.iter().zip(&bytes) {
+                assert_eq!(bytes, &data.slice(range.clone()));
+            }
+            reads
+        };
+
+        // overlapping ranges in arbitrary order
+        assert_eq!(read_all(&[20..40, 10..30, 35..38]), vec![10..40]);
+
+        // adjacent ranges
+        assert_eq!(read_all(&[10..20, 0..10, 20..25]), vec![0..25]);
+
+        // far-apart ranges
+        assert_eq!(
+            read_all(&[200..210, 10..20, 100..101]),
+            vec![10..20, 100..101, 200..210],
+        );
+
+        // duplicated and empty ranges
+        assert_eq!(read_all(&[5..10, 5..10, 7..7]), vec![5..10]);
+        assert_eq!(read_all(&[]), Vec::<Range<usize>>::new());
+    }
+
+    #[tokio::test]
+    async fn test_with_file_context() {
+        let file_context = "error reading parquet file /tmp/a.parquet (partition 3)".to_string();
+
+        // error opening file
+        let open_future: FileOpenFuture =
+            Box::pin(async { Err(DataFusionError::Execution("corrupted footer".to_string())) });
+        let err = with_file_context(open_future, file_context.clone())
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("/tmp/a.parquet (partition 3)"));
+        assert!(err.contains("corrupted footer"));
+
+        // error decoding file
+        let open_future: FileOpenFuture = Box::pin(async {
+            let stream = futures::stream::iter(vec![Err::<RecordBatch, _>(
+                ArrowError::ParquetError("invalid page header at offset 1024".to_string()),
+            )]);
+            Ok::<_, DataFusionError>(stream.boxed())
+        });
+        let mut stream = with_file_context(open_future, file_context).await.unwrap();
+        let err = stream.next().await.unwrap().unwrap_err().to_string();
+        assert!(err.contains("/tmp/a.parquet (partition 3)"));
+        assert!(err.contains("invalid page header at offset 1024"));
+    }
+
+    #[tokio::test]
+    async fn test_external_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("ext", DataType::Binary, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+                Arc::new(BinaryArray::from(vec![
+                    &b"a"[..],
+                    &b"bb"[..],
+                    &b"ccc"[..],
+                    &b"dddd"[..],
+                    &b"eeeee"[..],
+                ])),
+            ],
+        )
+        .unwrap();
+        let data = write_parquet(&batch, 2);
+        let metadata = parse_metadata(&data).unwrap();
+        assert_eq!(metadata.num_row_groups(), 3);
+
+        // raw chunks of the external column are handed to the callback in
+        // row group order
+        let chunks = Arc::new(Mutex::new(vec![]));
+        let chunks_cloned = chunks.clone();
+        let external_columns = ExternalColumns {
+            column_names: HashSet::from(["ext".to_string()]),
+            callback: Arc::new(move |chunk: RawColumnChunk| {
+                chunks_cloned.lock().push(chunk);
+                Ok(())
+            }),
+        };
+        let mut reader = std::io::Cursor::new(data.to_vec());
+        external_columns
+            .read_raw_chunks(&mut reader, &metadata, &[1, 2], "/tmp/a.parquet")
+            .await
+            .unwrap();
+
+        let chunks = chunks.lock();
+        assert_eq!(chunks.len(), 2);
+        for (chunk, row_group_idx) in chunks.iter().zip([1, 2]) {
+            let column = metadata.row_group(row_group_idx).column(1);
+            let (start, len) = column.byte_range();
+            assert_eq!(chunk.file_path, "/tmp/a.parquet");
+            assert_eq!(chunk.row_group_idx, row_group_idx);
+            assert_eq!(chunk.column_path, ColumnPath::from("ext"));
+            assert_eq!(chunk.num_rows, metadata.row_group(row_group_idx).num_rows());
+            assert_eq!(
+                chunk.bytes,
+                data.slice(start as usize..(start + len) as usize)
+            );
+        }
+        assert_eq!(chunks[1].num_rows, 1);
+
+        // external columns are read as null placeholders
+        let decoded = batch.project(&[0]).unwrap();
+        let placeholders = vec![(1, Arc::new(schema.field(1).clone()))];
+        let batch = insert_external_placeholders(decoded, &placeholders).unwrap();
+        assert_eq!(batch.schema(), schema);
+        assert_eq!(batch.column(1).null_count(), 5);
+    }
+
+    #[test]
+    fn test_with_external_columns() {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("ext", DataType::Binary, true),
+        ]));
+        let exec = build_parquet_exec(file_schema.clone(), vec![0, 1]);
+        let new_exec = |predicate: Option<Arc<dyn PhysicalExpr>>| {
+            ParquetExec::new(exec.base_config().clone(), "test".to_string(), predicate)
+        };
+        let callback = Arc::new(|_: RawColumnChunk| Ok(()));
+
+        assert!(new_exec(None)
+            .with_external_columns(vec!["ext".to_string()], callback.clone())
+            .is_ok());
+
+        // external columns must be binary
+        let err = new_exec(None)
+            .with_external_columns(vec!["id".to_string()], callback.clone())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("external column id must be declared as binary"));
+
+        // and not used by predicates
+        let predicate = phys_expr::is_null(phys_expr::col("ext", &file_schema).unwrap()).unwrap();
+        let err = new_exec(Some(predicate))
+            .with_external_columns(vec!["ext".to_string()], callback)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("external column ext cannot be used by the predicate"));
+    }
+
+    #[test]
+    fn test_native_object_stores() {
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket/", store.clone());
+        let (resolved, location) = object_stores
+            .resolve("memory://bucket/t/part-0.parquet")
+            .unwrap();
+        assert!(Arc::ptr_eq(&resolved, &store));
+        assert_eq!(location, Path::from("t/part-0.parquet"));
+
+        // stores built from options are cached by scheme and authority
+        let (store1, _) = object_stores.resolve("file:///tmp/part-0.parquet").unwrap();
+        let (store2, location) = object_stores.resolve("file:///tmp/part-1.parquet").unwrap();
+        assert!(Arc::ptr_eq(&store1, &store2));
+        assert_eq!(location, Path::from("tmp/part-1.parquet"));
+
+        assert!(object_stores.resolve("hdfs://nn/t/part-0.parquet").is_err());
+        assert!(object_stores.resolve("part-0.parquet").is_err());
+    }
+
+    #[cfg(feature = "cloud-object-stores")]
+    #[test]
+    fn test_cloud_object_stores() {
+        let object_stores = NativeObjectStores::new(HashMap::from([
+            ("aws_region".to_string(), "us-west-2".to_string()),
+            ("aws_access_key_id".to_string(), "access-key".to_string()),
+            (
+                "aws_secret_access_key".to_string(),
+                "secret-key".to_string(),
+            ),
+        ]));
+        let (store, location) = object_stores
+            .resolve("s3://bucket/t/part-0.parquet")
+            .unwrap();
+        assert_eq!(store.to_string(), "AmazonS3(bucket)");
+        assert_eq!(location, Path::from("t/part-0.parquet"));
+
+        // s3a paths of hadoop are resolved to s3 stores of their own
+        let (s3a_store, location) = object_stores
+            .resolve("s3a://bucket/t/part-1.parquet")
+            .unwrap();
+        assert_eq!(s3a_store.to_string(), "AmazonS3(bucket)");
+        assert!(!Arc::ptr_eq(&store, &s3a_store));
+        assert_eq!(location, Path::from("t/part-1.parquet"));
+        let (other_store, _) = object_stores
+            .resolve("s3://other-bucket/t/part-0.parquet")
+            .unwrap();
+        assert_eq!(other_store.to_string(), "AmazonS3(other-bucket)");
+    }
+
+    #[tokio::test]
+    async fn test_native_object_store_reader() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from_iter_values(0..1000));
+        let batch = RecordBatch::try_new(schema.clone(), vec![array]).unwrap();
+        let bytes = write_parquet(&batch, 100);
+
+        let store = Arc::new(InMemory::new());
+        store
+            .put(&Path::from("t/part-0.parquet"), bytes.clone())
+            .await
+            .unwrap();
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store);
+        let reader_factory = FsReaderFactory::new_with_object_stores(Arc::new(object_stores));
+
+        let encoded = BASE64_URL_SAFE_NO_PAD.encode("memory://bucket/t/part-0.parquet");
+        let file_meta = || {
+            let object_meta =
+                PartitionedFile::new(format!("parquet/{encoded}"), bytes.len() as u64).object_meta;
+            FileMeta::from(object_meta)
+        };
+        let metrics = ExecutionPlanMetricsSet::new();
+        let mut reader = reader_factory
+            .create_file_reader(0, file_meta(), &metrics)
+            .unwrap();
+
+        let metadata = reader.get_metadata().await.unwrap();
+        assert_eq!(metadata.num_row_groups(), 10);
+        assert_eq!(reader.get_bytes(0..4).await.unwrap(), bytes.slice(0..4));
+        assert_eq!(
+            reader.get_byte_ranges(vec![4..8, 0..4]).await.unwrap(),
+            vec![bytes.slice(4..8), bytes.slice(0..4)],
+        );
+
+        // decodes the whole file through the native object store
+        let stream = ParquetRecordBatchStreamBuilder::new(reader)
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(concat_batches(&schema, &batches).unwrap(), batch);
+
+        assert_eq!(
+            metrics
+                .clone_inner()
+                .sum_by_name("files_opened")
+                .map(|v| v.as_usize()),
+            Some(1),
+        );
+
+        // reads are limited by the bytes budget like jvm fs reads
+        let mut reader = reader_factory
+            .with_max_bytes_per_partition(bytes.len() - 1)
+            .create_file_reader(0, file_meta(), &metrics)
+            .unwrap();
+        assert!(reader.get_bytes(0..bytes.len()).await.is_err());
+    }
+
+    // raw data page with a minimal header, written with a checksum if given
+    fn raw_data_page(data: &[u8], crc: Option<u32>) -> Vec<u8> {
+        fn write_i32_field(out: &mut Vec<u8>, field_delta: u8, value: i32) {
+            out.push((field_delta << 4) | 5);
+            let mut zigzag = ((value << 1) ^ (value >> 31)) as u32;
+            while zigzag >= 0x80 {
+                out.push(zigzag as u8 | 0x80);
+                zigzag >>= 7;
+            }
+            out.push(zigzag as u8);
+        }
+        let mut page = vec![];
+        write_i32_field(&mut page, 1, 0); // type: DATA_PAGE
+        write_i32_field(&mut page, 1, data.len() as i32); // uncompressed_page_size
+        write_i32_field(&mut page, 1, data.len() as i32); // compressed_page_size
+        let data_page_header_delta = match crc {
+            Some(crc) => {
+                write_i32_field(&mut page, 1, crc as i32);
+                1
+            }
+            None => 2,
+        };
+        page.push((data_page_header_delta << 4) | 12);
+        write_i32_field(&mut page, 1, 3); // num_values
+        write_i32_field(&mut page, 1, 0); // encoding: PLAIN
+        write_i32_field(&mut page, 1, 3); // definition_level_encoding: RLE
+        write_i32_field(&mut page, 1, 3); // repetition_level_encoding: RLE
+        page.push(0); // end of data_page_header
+        page.push(0); // end of page header
+        page.extend_from_slice(data);
+        page
+    }
+
+    #[tokio::test]
+    async fn test_page_checksum_verification() {
+        let data = [1u8, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0];
+        let crc = crc32fast::hash(&data);
+        let valid = raw_data_page(&data, Some(crc));
+        let unchecked = raw_data_page(&data, None);
+        let mut corrupted = raw_data_page(&data, Some(crc));
+        *corrupted.last_mut().unwrap() ^= 0xff;
+
+        let header = read_page_header(&valid).unwrap();
+        assert_eq!(header.compressed_page_size, data.len());
+        assert_eq!(header.crc, Some(crc));
+        assert_eq!(header.header_len + data.len(), valid.len());
+        assert_eq!(read_page_header(&unchecked).unwrap().crc, None);
+
+        // pages without checksums and truncated pages are not verified
+        let chunk = [valid.clone(), unchecked.clone(), corrupted.clone()].concat();
+        assert_eq!(find_page_checksum_mismatch(&chunk[..valid.len() * 2]), None);
+        assert_eq!(find_page_checksum_mismatch(&chunk[..chunk.len() - 1]), None);
+        assert_eq!(
+            find_page_checksum_mismatch(&chunk),
+            Some(valid.len() + unchecked.len()),
+        );
+
+        // fixture file with a corrupted page in a column chunk
+        let file = Bytes::from([b"PAR1".as_slice(), &chunk].concat());
+        let store = Arc::new(InMemory::new());
+        store
+            .put(&Path::from("t/part-0.parquet"), file.clone())
+            .await
+            .unwrap();
+        let object_stores =
+            Arc::new(NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store));
+        let encoded = BASE64_URL_SAFE_NO_PAD.encode("memory://bucket/t/part-0.parquet");
+        let file_meta = || {
+            let object_meta =
+                PartitionedFile::new(format!("parquet/{encoded}"), file.len() as u64).object_meta;
+            FileMeta::from(object_meta)
+        };
+        let metrics = ExecutionPlanMetricsSet::new();
+        let pages_checksum_failed = || {
+            metrics
+                .clone_inner()
+                .sum_by_name("pages_checksum_failed")
+                .map(|v| v.as_usize())
+        };
+
+        // not verified by default
+        let mut reader = FsReaderFactory::new_with_object_stores(object_stores.clone())
+            .create_file_reader(0, file_meta(), &metrics)
+            .unwrap();
+        assert!(reader.get_byte_ranges(vec![4..file.len()]).await.is_ok());
+        assert_eq!(pages_checksum_failed(), Some(0));
+
+        let mut reader = FsReaderFactory::new_with_object_stores(object_stores)
+            .with_page_checksum_verification()
+            .create_file_reader(0, file_meta(), &metrics)
+            .unwrap();
+        let valid_end = 4 + valid.len() + unchecked.len();
+        assert!(reader.get_byte_ranges(vec![4..valid_end]).await.is_ok());
+        let err = reader
+            .get_byte_ranges(vec![4..valid_end, 4..file.len()])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(&format!(
+                "parquet page checksum mismatch at offset {valid_end}"
+            )),
+            "{err}",
+        );
+        assert_eq!(pages_checksum_failed(), Some(1));
+    }
+
+    #[test]
+    fn test_fs_provider_resolver() {
+        let num_resolves = Arc::new(AtomicUsize::new(0));
+        let num_resolves_cloned = num_resolves.clone();
+        let resolver =
+            FsProviderResolver::new("default".to_string()).with_resolve(move |resource_id| {
+                num_resolves_cloned.fetch_add(1, SeqCst);
+                Ok(format!("provider-of-{resource_id}"))
+            });
+
+        // one file group mixing default, hdfs and s3 files
+        let file = |path: &str, resource_id: Option<&str>| {
+            let mut file = PartitionedFile::new(path.to_string(), 100);
+            file.extensions = resource_id.map(|resource_id| {
+                Arc::new(FileFsResourceId(resource_id.to_string())) as Arc<dyn Any + Send + Sync>
+            });
+            file
+        };
+        let file_group = vec![
+            file("a.parquet", None),
+            file("b.parquet", Some("hdfs")),
+            file("c.parquet", Some("s3")),
+            file("d.parquet", Some("hdfs")),
+        ];
+        let providers = file_group
+            .iter()
+            .map(|file| resolver.resolve(file.extensions.as_ref()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            providers,
+            vec![
+                "default",
+                "provider-of-hdfs",
+                "provider-of-s3",
+                "provider-of-hdfs"
+            ],
+        );
+        assert_eq!(num_resolves.load(SeqCst), 2);
+
+        // files with their own resources cannot be read without a resolve function
+        let resolver = FsProviderResolver::new("default".to_string());
+        assert!(resolver.resolve(file_group[1].extensions.as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_post_decode_filter() {
+        // predicate not pushable to statistics: (a % 2 = 0) AND (b <> 'x')
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("c", DataType::Int32, true),
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let predicate = phys_expr::binary(
+            phys_expr::binary(
+                phys_expr::binary(
+                    phys_expr::col("a", &file_schema).unwrap(),
+                    Operator::Modulo,
+                    phys_expr::lit(2),
+                    &file_schema,
+                )
+                .unwrap(),
+                Operator::Eq,
+                phys_expr::lit(0),
+                &file_schema,
+            )
+            .unwrap(),
+            Operator::And,
+            phys_expr::binary(
+                phys_expr::col("b", &file_schema).unwrap(),
+                Operator::NotEq,
+                phys_expr::lit("x"),
+                &file_schema,
+            )
+            .unwrap(),
+            &file_schema,
+        )
+        .unwrap();
+
+        // predicate columns are rebound to the projection
+        let projected_schema = Arc::new(Schema::new(vec![
+            Field::new("b", DataType::Utf8, true),
+            Field::new("a", DataType::Int32, true),
+        ]));
+        let predicate = post_decode_predicate(&predicate, &projected_schema).unwrap();
+        let batch = RecordBatch::try_new(
+            projected_schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![
+                    Some("x"),
+                    Some("y"),
+                    Some("z"),
+                    None,
+                    Some("w"),
+                ])),
+                Arc::new(Int32Array::from(vec![
+                    Some(2),
+                    Some(4),
+                    Some(5),
+                    Some(6),
+                    None,
+                ])),
+            ],
+        )
+        .unwrap();
+        let rows_filtered = Count::new();
+        let filtered = filter_decoded_batch(&predicate, &batch, &rows_filtered).unwrap();
+        assert_eq!(filtered.num_rows(), 1);
+        assert_eq!(
+            filtered.column(0).as_string::<i32>(),
+            &StringArray::from(vec!["y"]),
+        );
+        assert_eq!(
+            filtered.column(1).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![4]),
+        );
+        assert_eq!(rows_filtered.value(), 4);
+
+        // predicate referencing columns out of projection cannot be applied
+        let projected_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        assert!(post_decode_predicate(&predicate, &projected_schema).is_none());
+    }
+
+    #[test]
+    fn test_row_filtering_modes() {
+        // (page filtering, record-level filter) -> (pushdown filters, post-decode
+        // filter)
+        assert_eq!(row_filtering_modes(false, false), (false, false));
+        assert_eq!(row_filtering_modes(false, true), (false, true));
+        assert_eq!(row_filtering_modes(true, false), (true, false));
+        assert_eq!(row_filtering_modes(true, true), (true, false));
+    }
+
+    #[tokio::test]
+    async fn test_read_maybe_inline() {
+        let data = Bytes::from((0..=255u8).collect::<Vec<_>>());
+        let read = |data: Bytes| move || data.slice(10..20);
+
+        let inline_bytes = read_maybe_inline(true, read(data.clone())).await;
+        let blocking_bytes = read_maybe_inline(false, read(data.clone())).await;
+        assert_eq!(inline_bytes, data.slice(10..20));
+        assert_eq!(inline_bytes, blocking_bytes);
+
+        // inline reads run on the calling thread
+        let thread_id = std::thread::current().id();
+        assert!(read_maybe_inline(true, move || std::thread::current().id() == thread_id).await);
+    }
+
+    #[test]
+    fn test_opened_files() {
+        let opened_files = OpenedFiles::default();
+        let a = object_store::path::Path::from("a.parquet");
+        let b = object_store::path::Path::from("b.parquet");
+
+        // footer and data readers of the same file count once
+        assert!(opened_files.mark_opened(&a));
+        assert!(!opened_files.mark_opened(&a));
+        assert!(opened_files.mark_opened(&b));
+        assert!(!opened_files.mark_opened(&b));
+    }
+
+    #[tokio::test]
+    async fn test_skip_corrupted_file() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let bytes = write_parquet(&batch, 1024);
+
+        // valid data section with a truncated footer
+        let truncated = bytes.slice(0..bytes.len() - 4);
+        let open_future: FileOpenFuture = Box::pin(async move {
+            let _metadata = parse_metadata(&truncated)?;
+            let stream = futures::stream::empty::<Result<RecordBatch, ArrowError>>();
+            Ok::<_, DataFusionError>(stream.boxed())
+        });
+        let skipped_corrupted_files = Count::new();
+        let stream = skip_corrupted_file(open_future, skipped_corrupted_files.clone())
+            .await
+            .unwrap();
+        assert_eq!(stream.count().await, 0);
+        assert_eq!(skipped_corrupted_files.value(), 1);
+
+        // valid files are not affected
+        let open_future: FileOpenFuture = Box::pin(async move {
+            let metadata = parse_metadata(&bytes)?;
+            assert_eq!(metadata.num_row_groups(), 1);
+            let stream = futures::stream::iter(vec![Ok::<_, ArrowError>(batch)]);
+            Ok::<_, DataFusionError>(stream.boxed())
+        });
+        let stream = skip_corrupted_file(open_future, skipped_corrupted_files.clone())
+            .await
+            .unwrap();
+        assert_eq!(stream.count().await, 1);
+        assert_eq!(skipped_corrupted_files.value(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_empty_files() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let zero_rows = write_parquet(&RecordBatch::new_empty(schema.clone()), 1024);
+        let (reader_factory, files) = memory_files(vec![
+            ("zero-length.parquet", Bytes::new()),
+            ("zero-rows.parquet", zero_rows),
+            ("part-0.parquet", write_parquet(&batch, 1024)),
+        ])
+        .await;
+        assert_eq!(files[0].object_meta.size, 0);
+
+        // empty files produce no rows and are counted, files are passed with
+        // ranges like spark does
+        let ranged_files = files
+            .iter()
+            .map(|file| {
+                let mut file = file.clone();
+                file.range = Some(FileRange {
+                    start: 0,
+                    end: file.object_meta.size as i64,
+                });
+                file
+            })
+            .collect::<Vec<_>>();
+        let metrics = ExecutionPlanMetricsSet::new();
+        let opener = test_file_opener(reader_factory.clone(), schema.clone(), vec![0], &metrics);
+        let empty_files = opener.empty_files.clone();
+        let num_rows = scan_num_rows(opener, ranged_files, schema.clone())
+            .await
+            .unwrap();
+        assert_eq!(num_rows, vec![3]);
+        assert_eq!(empty_files.value(), 2);
+
+        // the same for count-only scans, zero-length files are not prefetched
+        let metrics = ExecutionPlanMetricsSet::new();
+        let mut opener = test_file_opener(reader_factory, schema.clone(), vec![], &metrics);
+        opener.count_only = Some(CountOnlyScan {
+            files: files.clone(),
+            row_counts: OnceCell::new(),
+        });
+        let empty_files = opener.empty_files.clone();
+        let num_rows = scan_num_rows(opener, files, schema).await.unwrap();
+        assert_eq!(num_rows, vec![3]);
+        assert_eq!(empty_files.value(), 2);
+    }
+
+    #[test]
+    fn test_scan_bytes_budget() {
+        let bytes_budget = ScanBytesBudget::new(100);
+        assert!(bytes_budget.acquire(60).is_ok());
+        assert!(bytes_budget.acquire(40).is_ok()); // reaching the limit is allowed
+
+        let err = bytes_budget.acquire(1).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("scanned 101 bytes, limit 100 bytes"));
+        assert!(bytes_budget.acquire(0).is_err());
+
+        // ranges are counted as read even if merged
+        let bytes_budget = ScanBytesBudget::new(10);
+        let ranges = vec![0..4, 4..8, 8..12];
+        let result = read_merged_ranges(&ranges, |range| {
+            bytes_budget.acquire(range.len())?;
+            Ok(Bytes::from(vec![0u8; range.len()]))
+        });
+        assert!(result.is_err());
+        let result = read_merged_ranges(&ranges[..2], |range| {
+            bytes_budget.acquire(range.len())?;
+            Ok(Bytes::from(vec![0u8; range.len()]))
+        });
+        assert!(result.is_err()); // budget already exhausted
+    }
+
+    #[tokio::test]
+    async fn test_stop_at_bytes_limit() {
+        // 8 row groups of about 400KB, written without dictionary encoding so
+        // that the sizes are predictable
+        let num_rows = 400000;
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let array: ArrayRef = Arc::new(Int64Array::from_iter_values(
+            (0..num_rows as i64).map(|i| i * 7919),
+        ));
+        let batch = RecordBatch::try_new(schema, vec![array]).unwrap();
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(50000)
+            .set_dictionary_enabled(false)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, batch.schema(), Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let bytes = Bytes::from(buf);
+        let row_group_bytes = bytes.len() / 8;
+
+        let store = Arc::new(InMemory::new());
+        store
+            .put(&Path::from("t/sampled.parquet"), bytes.clone())
+            .await
+            .unwrap();
+        let object_stores =
+            NativeObjectStores::new(HashMap::new()).with_store("memory://bucket", store);
+        let max_bytes = 2 << 20;
+        let bytes_limit = Arc::new(ScanBytesLimit::new(max_bytes));
+        let reader_factory = FsReaderFactory::new_with_object_stores(Arc::new(object_stores))
+            .with_bytes_limit(bytes_limit.clone());
+        let encoded = BASE64_URL_SAFE_NO_PAD.encode("memory://bucket/t/sampled.parquet");
+        let object_meta =
+            PartitionedFile::new(format!("parquet/{encoded}"), bytes.len() as u64).object_meta;
+        let metrics = ExecutionPlanMetricsSet::new();
+        let reader = reader_factory
+            .create_file_reader(0, FileMeta::from(object_meta), &metrics)
+            .unwrap();
+
+        let stream = ParquetRecordBatchStreamBuilder::new(reader)
+            .await
+            .unwrap()
+            .with_batch_size(8192)
+            .build()
+            .unwrap()
+            .map_err(DataFusionError::from);
+        let batches = stop_at_bytes_limit(stream, bytes_limit.clone())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        // stops in the row group reaching the limit, with decoded rows kept
+        let scanned_bytes = bytes_limit.scanned_bytes.load(SeqCst);
+        assert!(scanned_bytes >= max_bytes);
+        assert!(scanned_bytes < max_bytes + row_group_bytes);
+        let num_output_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert!(num_output_rows >= 100000);
+        assert!(num_output_rows < num_rows);
+        let first_values = batches[0].column(0).as_primitive::<Int64Type>();
+        assert_eq!(first_values.value(1), 7919);
+
+        // nothing is read once the limit is reached
+        let bytes_limit = Arc::new(ScanBytesLimit::new(0));
+        let stream = futures::stream::iter(vec![Ok(batch)]);
+        let batches = stop_at_bytes_limit(stream, bytes_limit)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert!(batches.is_empty());
+    }
+
+    #[test]
+    fn test_wkb_geometry_columns() {
+        // POINT(1 2) and POINT(3 4) in WKB
+        let wkb_point = |x: f64, y: f64| {
+            let mut wkb = vec![1u8, 1, 0, 0, 0];
+            wkb.extend(x.to_le_bytes());
+            wkb.extend(y.to_le_bytes());
+            wkb
+        };
+        let points = vec![Some(wkb_point(1.0, 2.0)), None, Some(wkb_point(3.0, 4.0))];
+
+        // geom: annotated by geoparquet metadata
+        // geog: annotated by geoarrow extension type
+        let geog_metadata = HashMap::from([(
+            "ARROW:extension:name".to_string(),
+            "geoarrow.wkb".to_string(),
+        )]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("geom", DataType::Binary, true),
+            Field::new("geog", DataType::LargeBinary, true).with_metadata(geog_metadata),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(BinaryArray::from_iter(points.clone())),
+                Arc::new(LargeBinaryArray::from_iter(points.clone())),
+            ],
+        )
+        .unwrap();
+        let geo = r#"{"version":"1.0.0","primary_column":"geom","columns":{"geom":{"encoding":"WKB","geometry_types":["Point"]}}}"#;
+        let props = WriterProperties::builder()
+            .set_key_value_metadata(Some(vec![KeyValue::new(
+                "geo".to_string(),
+                geo.to_string(),
+            )]))
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema, Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let data = Bytes::from(buf);
+
+        let metadata = parse_metadata(&data).unwrap();
+        let file_schema = parquet_file_arrow_schema_impl(metadata.file_metadata(), true).unwrap();
+        assert_eq!(file_schema.field(0).data_type(), &DataType::Int32);
+        assert_eq!(file_schema.field(1).data_type(), &DataType::Binary);
+        assert_eq!(file_schema.field(2).data_type(), &DataType::Binary);
+        assert!(file_schema.field(2).metadata().is_empty());
+
+        // geometry columns are kept as is if disabled
+        let file_schema = parquet_file_arrow_schema_impl(metadata.file_metadata(), false).unwrap();
+        assert_eq!(file_schema.field(2).data_type(), &DataType::LargeBinary);
+
+        // decoded WKB bytes are passed through
+        let batch = ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let geog = schema_adapter_cast_column(batch.column(2), &DataType::Binary).unwrap();
+        assert_eq!(geog.as_binary::<i32>(), &BinaryArray::from_iter(points));
+    }
+
+    #[test]
+    fn test_time_columns() {
+        // 12:34:56.789 and 23:59:59.999
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("t_millis", DataType::Time32(TimeUnit::Millisecond), true),
+            Field::new("t_micros", DataType::Time64(TimeUnit::Microsecond), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Time32MillisecondArray::from(vec![
+                    Some(45_296_789),
+                    None,
+                    Some(86_399_999),
+                ])),
+                Arc::new(Time64MicrosecondArray::from(vec![
+                    Some(45_296_789_123),
+                    Some(0),
+                    None,
+                ])),
+            ],
+        )
+        .unwrap();
+        let data = write_parquet(&batch, 1024);
+
+        // TIME(MILLIS) and TIME(MICROS) are mapped to time32/time64
+        let metadata = parse_metadata(&data).unwrap();
+        let file_schema = parquet_file_arrow_schema(metadata.file_metadata()).unwrap();
+        assert_eq!(
+            file_schema.field(0).data_type(),
+            &DataType::Time32(TimeUnit::Millisecond),
+        );
+        assert_eq!(
+            file_schema.field(1).data_type(),
+            &DataType::Time64(TimeUnit::Microsecond),
+        );
+
+        let decoded = ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.column(0), batch.column(0));
+        assert_eq!(decoded.column(1), batch.column(1));
+
+        // units are converted to the table schema
+        let nanos =
+            schema_adapter_cast_column(decoded.column(0), &DataType::Time64(TimeUnit::Nanosecond))
+                .unwrap();
+        assert_eq!(
+            nanos.as_primitive::<Time64NanosecondType>(),
+            &Time64NanosecondArray::from(vec![
+                Some(45_296_789_000_000),
+                None,
+                Some(86_399_999_000_000),
+            ]),
+        );
+        let millis =
+            schema_adapter_cast_column(decoded.column(1), &DataType::Time32(TimeUnit::Millisecond))
+                .unwrap();
+        assert_eq!(
+            millis.as_primitive::<Time32MillisecondType>(),
+            &Time32MillisecondArray::from(vec![Some(45_296_789), Some(0), None]),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quarantine_corrupted_row_groups() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int32Array::from((0..300).collect::<Vec<_>>())) as ArrayRef,
+        )])
+        .unwrap();
+        let bytes = write_parquet(&batch, 100);
+        let metadata = parse_metadata(&bytes).unwrap();
+        assert_eq!(metadata.num_row_groups(), 3);
+
+        // corrupt the page header of the second row group
+        let (start, _) = metadata.row_group(1).column(0).byte_range();
+        let mut corrupted = bytes.to_vec();
+        corrupted[start as usize..][..16].fill(0xff);
+        let corrupted = Bytes::from(corrupted);
+
+        let open_row_group = move |row_group_idx: usize| -> Result<FileOpenFuture> {
+            let corrupted = corrupted.clone();
+            Ok(Box::pin(async move {
+                let reader = ParquetRecordBatchReaderBuilder::try_new(corrupted)?
+                    .with_row_groups(vec![row_group_idx])
+                    .build()?;
+                Ok::<_, DataFusionError>(futures::stream::iter(reader).boxed())
+            }))
+        };
+        let quarantined_row_groups = Count::new();
+        let memory_pool: Arc<dyn MemoryPool> = Arc::new(GreedyMemoryPool::new(usize::MAX));
+        let batches = quarantine_corrupted_row_groups(
+            vec![0, 1, 2],
+            open_row_group,
+            "/tmp/a.parquet".to_string(),
+            quarantined_row_groups.clone(),
+            memory_pool.clone(),
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+        // rows of good row groups are emitted
+        let values = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, (0..100).chain(200..300).collect::<Vec<_>>());
+        assert_eq!(quarantined_row_groups.value(), 1);
+        assert_eq!(memory_pool.reserved(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_open_with_quarantined_row_groups() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from((0..300).collect::<Vec<_>>()))],
+        )
+        .unwrap();
+        let bytes = write_parquet(&batch, 100);
+        let metadata = parse_metadata(&bytes).unwrap();
+        let (start, _) = metadata.row_group(1).column(0).byte_range();
+        let mut corrupted = bytes.to_vec();
+        corrupted[start as usize..][..16].fill(0xff);
+        let (reader_factory, files) =
+            memory_files(vec![("part-0.parquet", Bytes::from(corrupted))]).await;
+
+        let open = |memory_pool: Arc<dyn MemoryPool>| {
+            let metrics = ExecutionPlanMetricsSet::new();
+            let mut opener =
+                test_file_opener(reader_factory.clone(), schema.clone(), vec![0], &metrics);
+            let quarantined_row_groups = Count::new();
+            opener.quarantined_row_groups = Some(quarantined_row_groups.clone());
+            opener.memory_pool = memory_pool;
+            (opener, quarantined_row_groups)
+        };
+
+        // the corrupted row group is dropped through the opener
+        let memory_pool: Arc<dyn MemoryPool> = Arc::new(GreedyMemoryPool::new(usize::MAX));
+        let (opener, quarantined_row_groups) = open(memory_pool.clone());
+        let num_rows = scan_num_rows(opener, files.clone(), schema.clone())
+            .await
+            .unwrap();
+        assert_eq!(num_rows.iter().sum::<usize>(), 200);
+        assert_eq!(quarantined_row_groups.value(), 1);
+        assert_eq!(memory_pool.reserved(), 0);
+
+        // buffered row groups exceeding the memory pool fail the scan, instead
+        // of being quarantined
+        let (opener, quarantined_row_groups) = open(Arc::new(GreedyMemoryPool::new(16)));
+        let err = scan_num_rows(opener, files, schema).await.unwrap_err();
+        assert!(err.to_string().contains("ParquetRowGroupQuarantine"));
+        assert_eq!(quarantined_row_groups.value(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_row_count_prefetch() {
+        let files = (0..10)
+            .map(|i| PartitionedFile::new(format!("file-{i}"), 100))
+            .collect::<Vec<_>>();
+        let num_running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let fetch: FetchRowCount = {
+            let num_running = num_running.clone();
+            let max_running = max_running.clone();
+            Arc::new(move |file_meta| {
+                let num_running = num_running.clone();
+                let max_running = max_running.clone();
+                async move {
+                    let running = num_running.fetch_add(1, SeqCst) + 1;
+                    max_running.fetch_max(running, SeqCst);
+                    for _ in 0..3 {
+                        tokio::task::yield_now().await;
+                    }
+                    num_running.fetch_sub(1, SeqCst);
+                    Ok(file_meta.object_meta.location.as_ref().len())
+                }
+                .boxed()
+            })
+        };
+
+        // fetches are issued concurrently, bounded by the concurrency
+        let prefetch = RowCountPrefetch::start(&files, 4, fetch);
+        for file in &files {
+            let row_count = prefetch
+                .row_count(file.object_meta.clone().into())
+                .await
+                .unwrap();
+            assert_eq!(row_count, 6);
+        }
+        assert_eq!(max_running.load(SeqCst), 4);
+
+        // files not prefetched are fetched directly
+        let row_count = prefetch
+            .row_count(PartitionedFile::new("file-10", 100).object_meta.into())
+            .await
+            .unwrap();
+        assert_eq!(row_count, 7);
+    }
+
+    #[tokio::test]
+    async fn test_row_count_prefetch_cancelled() {
+        let files = (0..10)
+            .map(|i| PartitionedFile::new(format!("file-{i}"), 100))
+            .collect::<Vec<_>>();
+        let num_started = Arc::new(AtomicUsize::new(0));
+        let num_dropped = Arc::new(AtomicUsize::new(0));
+        let fetch: FetchRowCount = {
+            let num_started = num_started.clone();
+            let num_dropped = num_dropped.clone();
+            Arc::new(move |_| {
+                struct DropGuard(Arc<AtomicUsize>);
+                impl Drop for DropGuard {
+                    fn drop(&mut self) {
+                        self.0.fetch_add(1, SeqCst);
+                    }
+                }
+                let num_started = num_started.clone();
+                let guard = DropGuard(num_dropped.clone());
+                async move {
+                    let _guard = guard;
+                    num_started.fetch_add(1, SeqCst);
+                    futures::future::pending::<()>().await;
+                    Ok(0)
+                }
+                .boxed()
+            })
+        };
+
+        let prefetch = RowCountPrefetch::start(&files, 4, fetch);
+        while num_started.load(SeqCst) < 4 {
+            tokio::task::yield_now().await;
+        }
+
+        // dropping the scan stops outstanding fetches
+        drop(prefetch);
+        while num_dropped.load(SeqCst) < 4 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(num_started.load(SeqCst), 4);
+    }
+
+    #[test]
+    fn test_dictionary_encoded_share() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let values: ArrayRef = Arc::new(StringArray::from(vec!["x"; 100]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![values.clone(), values]).unwrap();
+        let props = WriterProperties::builder()
+            .set_column_dictionary_enabled(ColumnPath::from("b"), false)
+            .build();
+        let mut buf = vec![];
+        let mut writer = ArrowWriter::try_new(&mut buf, schema, Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let metadata = parse_metadata(&Bytes::from(buf)).unwrap();
+
+        let columns = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        assert_eq!(dictionary_encoded_share(&metadata, &columns(&["a"])), 1.0);
+        assert_eq!(dictionary_encoded_share(&metadata, &columns(&["b"])), 0.0);
+        assert_eq!(dictionary_encoded_share(&metadata, &columns(&[])), 0.0);
+        let share = dictionary_encoded_share(&metadata, &columns(&["a", "b"]));
+        assert!(share > 0.0 && share < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_dictionary_decode_time() {
+        let decoding_stream = || {
+            futures::stream::iter(0..3)
+                .map(|_| {
+                    std::thread::sleep(Duration::from_millis(10)); // decoding
+                    Ok(RecordBatch::new_empty(Arc::new(Schema::empty())))
+                })
+                .boxed()
+        };
+
+        let time = Time::new();
+        let stream = estimate_dictionary_decode_time(decoding_stream(), 0.0, time.clone());
+        assert_eq!(stream.count().await, 3);
+        assert_eq!(time.value(), 0);
+
+        let stream = estimate_dictionary_decode_time(decoding_stream(), 0.5, time.clone());
+        assert_eq!(stream.count().await, 3);
+        assert!(time.value() >= 15_000_000);
+        assert!(time.value() < 30_000_000);
+    }
+
+    #[test]
+    fn test_rewrite_file_path() {
+        let object_meta = |path: &str| {
+            let encoded = BASE64_URL_SAFE_NO_PAD.encode(path);
+            PartitionedFile::new(format!("parquet/{encoded}"), 100).object_meta
+        };
+        let path_rewriter: PathRewriter =
+            Arc::new(|path: &str| match path.strip_prefix("s3a://") {
+                Some(rest) => format!("s3://{rest}"),
+                None => path.to_string(),
+            });
+
+        let rewritten =
+            rewrite_file_path(object_meta("s3a://bucket/t/part-0.parquet"), &path_rewriter)
+                .unwrap();
+        assert_eq!(
+            decode_file_path(&rewritten).unwrap(),
+            "s3://bucket/t/part-0.parquet",
+        );
+        assert_eq!(
+            rewritten
+                .location
+                .prefix_match(&Path::from("parquet"))
+                .is_some(),
+            true
+        );
+        assert_eq!(rewritten.size, 100);
+
+        // paths not rewritten are kept
+        let meta = object_meta("hdfs://nn/t/part-0.parquet");
+        assert_eq!(
+            rewrite_file_path(meta.clone(), &path_rewriter).unwrap(),
+            meta
+        );
+    }
+
+    #[test]
+    fn test_skip_empty_row_groups() {
+        // fixture with an empty row group between two non-empty ones, like
+        // files written by some streaming sinks
+        let parquet_schema =
+            Arc::new(parse_message_type("message test { required int32 i; }").unwrap());
+        let mut buf = vec![];
+        let mut writer =
+            SerializedFileWriter::new(&mut buf, parquet_schema, Default::default()).unwrap();
+        for values in [vec![1, 2], vec![], vec![3]] {
+            let mut row_group_writer = writer.next_row_group().unwrap();
+            let mut col_writer = row_group_writer.next_column().unwrap().unwrap();
+            col_writer
+                .typed::<ParquetInt32Type>()
+                .write_batch(&values, None, None)
+                .unwrap();
+            col_writer.close().unwrap();
+            row_group_writer.close().unwrap();
+        }
+        writer.close().unwrap();
+        let data = Bytes::from(buf);
+
+        let metadata = Arc::new(parse_metadata(&data).unwrap());
+        assert_eq!(metadata.num_row_groups(), 3);
+        assert_eq!(metadata.row_group(1).num_rows(), 0);
+
+        let (skipped, num_empty_row_groups) = skip_empty_row_groups(metadata.clone());
+        assert_eq!(num_empty_row_groups, 1);
+        assert_eq!(skipped.num_row_groups(), 2);
+        assert_eq!(skipped.file_metadata().num_rows(), 3);
+
+        // remaining row groups are still readable
+        let reader_metadata =
+            ArrowReaderMetadata::try_new(skipped, ArrowReaderOptions::default()).unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::new_with_metadata(data, reader_metadata)
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap();
+        let values = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![1, 2, 3]);
+
+        // metadata without empty row groups is shared
+        let metadata = Arc::new(build_int32_metadata("a", vec![1, 2, 3]));
+        let (skipped, num_empty_row_groups) = skip_empty_row_groups(metadata.clone());
+        assert_eq!(num_empty_row_groups, 0);
+        assert!(Arc::ptr_eq(&skipped, &metadata));
+    }
+
+    #[test]
+    fn test_byte_sized_batches() {
+        let pool: Arc<dyn MemoryPool> = Arc::new(GreedyMemoryPool::new(usize::MAX));
+        let reservation = || MemoryConsumer::new("test").register(&pool);
+
+        // wide rows: large batches are split
+        let wide_schema = Arc::new(Schema::new(
+            (0..100)
+                .map(|i| Field::new(format!("c{i}"), DataType::Int64, false))
+                .collect::<Vec<_>>(),
+        ));
+        let wide_batch = RecordBatch::try_new(
+            wide_schema.clone(),
+            (0..100)
+                .map(|_| Arc::new(Int64Array::from_iter_values(0..1000)) as ArrayRef)
+                .collect(),
+        )
+        .unwrap();
+        let row_bytes = 800;
+        let mut byte_sized_batches = ByteSizedBatches::new(100 * row_bytes, None, reservation());
+        let mut output = byte_sized_batches.push(wide_batch.clone()).unwrap();
+        output.extend(byte_sized_batches.finish().unwrap());
+        assert_eq!(output.len(), 10);
+        assert!(output.iter().all(|batch| batch.num_rows() == 100));
+        assert_eq!(
+            output[3].column(0).as_primitive::<Int64Type>().value(0),
+            300
+        );
+
+        // a row exceeding the target is emitted alone
+        let mut byte_sized_batches = ByteSizedBatches::new(10, None, reservation());
+        let output = byte_sized_batches.push(wide_batch.slice(0, 3)).unwrap();
+        assert_eq!(
+            output.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            vec![1, 1, 1],
+        );
+        assert!(byte_sized_batches.finish().unwrap().is_empty());
+
+        // narrow rows: small batches are accumulated
+        let narrow_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let narrow_batch = RecordBatch::try_new(
+            narrow_schema,
+            vec![Arc::new(Int32Array::from_iter_values(0..100))],
+        )
+        .unwrap();
+        let mut byte_sized_batches = ByteSizedBatches::new(1000, None, reservation());
+        let mut output = vec![];
+        for _ in 0..6 {
+            output.extend(byte_sized_batches.push(narrow_batch.clone()).unwrap());
+        }
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].num_rows(), 250);
+        assert_eq!(output[1].num_rows(), 250);
+        output.extend(byte_sized_batches.finish().unwrap());
+        assert_eq!(output.len(), 3);
+        assert_eq!(output[2].num_rows(), 100);
+        assert_eq!(output[1].column(0).as_primitive::<Int32Type>().value(0), 50);
+
+        // flushed once reaching the limit
+        let mut byte_sized_batches = ByteSizedBatches::new(1000, Some(150), reservation());
+        assert!(byte_sized_batches
+            .push(narrow_batch.clone())
+            .unwrap()
+            .is_empty());
+        let output = byte_sized_batches.push(narrow_batch.clone()).unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].num_rows(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_byte_sized_batches_under_memory_limit() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(0..10000))],
+        )
+        .unwrap();
+        let (reader_factory, files) =
+            memory_files(vec![("part-0.parquet", write_parquet(&batch, 1000))]).await;
+        let metrics = ExecutionPlanMetricsSet::new();
+        let mut opener = test_file_opener(reader_factory, schema.clone(), vec![0], &metrics);
+        Arc::get_mut(&mut opener.inner).unwrap().batch_size = 100;
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema: schema.clone(),
+            file_groups: vec![files],
+            statistics: Statistics::new_unknown(&schema),
+            projection: None,
+            limit: None,
+            table_partition_cols: vec![],
+            output_ordering: vec![],
+        };
+        let mut stream = FileStream::new(&base_config, 0, opener, &metrics).unwrap();
+
+        // the pool only fits about 250 rows, far below the target of 8000 rows
+        let row_bytes = 8;
+        let memory_limit = 250 * row_bytes;
+        let pool: Arc<dyn MemoryPool> = Arc::new(GreedyMemoryPool::new(memory_limit));
+        let reservation = MemoryConsumer::new("staging").register(&pool);
+        let mut byte_sized_batches = ByteSizedBatches::new(8000 * row_bytes, None, reservation);
+        let mut output = vec![];
+        while let Some(batch) = stream.next().await {
+            output.extend(byte_sized_batches.push(batch.unwrap()).unwrap());
+            assert!(pool.reserved() <= memory_limit);
+        }
+        output.extend(byte_sized_batches.finish().unwrap());
+        assert_eq!(pool.reserved(), 0);
+
+        // staged rows are flushed early, all rows are kept in order
+        assert!(output.len() > 1);
+        assert!(output.iter().all(|batch| batch.num_rows() <= 250));
+        let values = output
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<Int64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, (0..10000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_non_nullable_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, true),
+            Field::new("c", DataType::Utf8, false),
+        ]));
+
+        // optional parquet columns are decoded with validity buffers
+        let file_schema = Arc::new(Schema::new(
+            schema
+                .fields()
+                .iter()
+                .map(|field| field.as_ref().clone().with_nullable(true))
+                .collect::<Vec<_>>(),
+        ));
+        let file_batch = RecordBatch::try_new(
+            file_schema,
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(3)])),
+                Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(3)])),
+                Arc::new(StringArray::from(vec![Some("x"), Some("y"), Some("z")])),
+            ],
+        )
+        .unwrap();
+        let data = write_parquet(&file_batch, 1024);
+        let decoded = ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let with_validity = |array: &ArrayRef| {
+            let nulls = NullBuffer::new_valid(array.len());
+            make_array(
+                array
+                    .to_data()
+                    .into_builder()
+                    .nulls(Some(nulls))
+                    .build()
+                    .unwrap(),
+            )
+        };
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            decoded.columns().iter().map(with_validity).collect(),
+        )
+        .unwrap();
+
+        let non_nullable_columns = NonNullableColumns::try_new(&schema).unwrap();
+        let output = non_nullable_columns.apply(&batch).unwrap();
+        assert!(output.column(0).nulls().is_none());
+        assert!(output.column(1).nulls().is_some());
+        assert!(output.column(2).nulls().is_none());
+        assert_eq!(output.column(0).as_ref(), batch.column(0).as_ref());
+        assert_eq!(output.column(2).as_ref(), batch.column(2).as_ref());
+
+        // no non-nullable columns
+        let schema = Schema::new(vec![Field::new("b", DataType::Int32, true)]);
+        assert!(NonNullableColumns::try_new(&schema).is_none());
+    }
+
+    #[test]
+    fn test_row_dedup() {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("v", DataType::Utf8, true),
+        ]));
+        let exec = build_parquet_exec(file_schema.clone(), vec![0, 1])
+            .with_row_dedup(dedup_by_key_columns(vec!["id".to_string()]));
+        let row_group = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(2), None])),
+                Arc::new(StringArray::from(vec!["a", "b", "c"])),
+            ],
+        )
+        .unwrap();
+        let overlapped = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(3), Some(2), None, Some(3)])),
+                Arc::new(StringArray::from(vec!["d", "b", "c", "d"])),
+            ],
+        )
+        .unwrap();
+
+        let mut predicate = (exec.row_dedup.as_ref().unwrap().0)();
+        let rows_deduplicated = Count::new();
+        let output = dedup_decoded_batch(&mut predicate, &row_group, &rows_deduplicated).unwrap();
+        assert_eq!(output, row_group);
+        assert_eq!(rows_deduplicated.value(), 0);
+
+        // a synthetic duplicate of the row group, and duplicated keys in a batch
+        let output = dedup_decoded_batch(&mut predicate, &row_group, &rows_deduplicated).unwrap();
+        assert_eq!(output.num_rows(), 0);
+        let output = dedup_decoded_batch(&mut predicate, &overlapped, &rows_deduplicated).unwrap();
+        assert_eq!(
+            output.column(0).as_primitive::<Int32Type>(),
+            &Int32Array::from(vec![3]),
+        );
+        assert_eq!(rows_deduplicated.value(), 6);
+
+        // states are not shared between partitions
+        let mut predicate = (exec.row_dedup.as_ref().unwrap().0)();
+        let output = dedup_decoded_batch(&mut predicate, &row_group, &rows_deduplicated).unwrap();
+        assert_eq!(output.num_rows(), 3);
+
+        // invalid predicates
+        let mut predicate: RowDedupPredicate = Box::new(|_| Ok(BooleanArray::from(vec![true])));
+        assert!(dedup_decoded_batch(&mut predicate, &row_group, &rows_deduplicated).is_err());
+        let mut predicate = dedup_by_key_columns(vec!["x".to_string()])();
+        assert!(dedup_decoded_batch(&mut predicate, &row_group, &rows_deduplicated).is_err());
+    }
+
+    #[test]
+    fn test_lazy_input_close() {
+        struct MockInput {
+            num_closed: Arc<AtomicUsize>,
+        }
+        impl Drop for MockInput {
+            fn drop(&mut self) {
+                self.num_closed.fetch_add(1, SeqCst);
+            }
+        }
+
+        let num_opened = Arc::new(AtomicUsize::new(0));
+        let num_closed = Arc::new(AtomicUsize::new(0));
+        let open = || {
+            num_opened.fetch_add(1, SeqCst);
+            Ok(MockInput {
+                num_closed: num_closed.clone(),
+            })
+        };
+
+        for num_files in 1..=3 {
+            // the input is opened once and shared by reads
+            let input = LazyInput::default();
+            let read1 = input.get_or_try_open(open).unwrap();
+            let read2 = input.get_or_try_open(open).unwrap();
+            assert!(Arc::ptr_eq(&read1, &read2));
+            drop(read1);
+
+            // a read in progress keeps the input alive until it finishes
+            assert!(input.close());
+            assert_eq!(num_closed.load(SeqCst), num_files - 1);
+            drop(read2);
+            assert_eq!(num_closed.load(SeqCst), num_files);
+
+            // closed inputs are not reopened or closed again
+            assert!(input.get_or_try_open(open).is_err());
+            assert!(!input.close());
+            drop(input);
+            assert_eq!(num_opened.load(SeqCst), num_files);
+            assert_eq!(num_closed.load(SeqCst), num_files);
+        }
+
+        // unopened inputs
+        let input = LazyInput::<MockInput>::default();
+        assert!(!input.close());
+
+        // failed opens are retried
+        let input = LazyInput::<MockInput>::default();
+        assert!(input
+            .get_or_try_open(|| df_execution_err!("open error"))
+            .is_err());
+        assert!(input.get_or_try_open(open).is_ok());
+        assert!(input.close());
+        assert_eq!(num_closed.load(SeqCst), 4);
     }
 }
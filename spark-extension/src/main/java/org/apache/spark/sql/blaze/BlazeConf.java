@@ -68,6 +68,122 @@ public enum BlazeConf {
 
     // parqeut enable bloom filter
     PARQUET_ENABLE_BLOOM_FILTER("spark.blaze.parquet.enable.bloomFilter", false),
+
+    // parquet fails on column type mismatch instead of casting/nulling
+    PARQUET_STRICT_SCHEMA("spark.blaze.parquet.strictSchema", false),
+
+    // max number of parquet footers fetched concurrently, independent of data reads
+    PARQUET_METADATA_FETCH_CONCURRENCY("spark.blaze.parquet.metadataFetchConcurrency", 16),
+
+    // max size of parquet footers, files with larger footers fail to read (or are skipped
+    // with spark.files.ignoreCorruptFiles). non-positive value means no limit.
+    PARQUET_MAX_METADATA_BYTES("spark.blaze.parquet.maxMetadataBytes", 268435456L),
+
+    // max number of concurrently open files in a parquet scan partition, including files
+    // being prefetched. non-positive value means no limit.
+    PARQUET_MAX_OPEN_FILES_PER_PARTITION("spark.blaze.parquet.maxOpenFilesPerPartition", 0),
+
+    // parquet reads not larger than this size run inline on the async executor instead of the
+    // blocking thread pool, for tiny files on fast local filesystems. keep it small since inline
+    // reads block the executor thread. non-positive value means always using the blocking pool.
+    PARQUET_INLINE_READ_THRESHOLD_BYTES("spark.blaze.parquet.inlineReadThresholdBytes", 0),
+
+    // max bytes read by a parquet scan partition (including footers), the scan fails once
+    // exceeded. guards against mis-estimated queries scanning whole tables. non-positive value
+    // means no limit.
+    PARQUET_MAX_BYTES_PER_PARTITION("spark.blaze.parquet.maxBytesPerPartition", 0L),
+
+    // read geometry/geography columns (annotated by geoparquet metadata or geoarrow extension
+    // types) as plain WKB binary, which can be parsed by downstream udfs
+    PARQUET_GEOMETRY_AS_BINARY("spark.blaze.parquet.geometryAsBinary", false),
+
+    // parquet footers with at least this number of leaf columns are decoded with statistics and
+    // other optional metadata of the read/filtered columns only, saving memory and cpu on very
+    // wide schemas. raw footers are cached instead of decoded metadata. non-positive value
+    // means always fully decoding footers.
+    PARQUET_PROJECTED_FOOTER_MIN_COLUMNS("spark.blaze.parquet.projectedFooterMinColumns", 0),
+
+    // max bytes of parquet reads in flight in an executor, including footers of files opened
+    // ahead and column chunks of upcoming row groups. reads wait for others to complete once
+    // exceeded. non-positive value means no limit.
+    PARQUET_PREFETCH_MAX_BYTES("spark.blaze.parquet.prefetchMaxBytes", 0L),
+
+    // skip parquet row groups failed to decode instead of failing the scan, rows of other row
+    // groups in the same file are still read. rows of a row group are buffered until it is fully
+    // decoded, which needs more memory.
+    PARQUET_QUARANTINE_CORRUPTED_ROW_GROUPS("spark.blaze.parquet.quarantineCorruptedRowGroups", false),
+
+    // how top-level parquet columns are resolved to table columns when field ids are available
+    // (like iceberg tables with renamed columns): "name" ignores field ids, "id_then_name" and
+    // "name_then_id" fall back to the other for columns not matched first. table columns matched
+    // by neither are read as nulls. "position" matches columns by their order in the table and
+    // file schemas, ignoring file column names and field ids (for unreliable file schemas).
+    PARQUET_FIELD_ID_RESOLUTION("spark.blaze.parquet.fieldIdResolution", "name"),
+
+    // target memory size of batches emitted by parquet scans. decoded rows are accumulated until
+    // reaching the target, and larger batches are split. smooths memory of downstream operators
+    // like sort/join on tables with very wide or very narrow rows. non-positive value means
+    // batches are emitted by row count.
+    PARQUET_OUTPUT_BATCH_BYTES("spark.blaze.parquet.outputBatchBytes", 0L),
+
+    // drops validity buffers of columns declared non-nullable in the table schema if the decoded
+    // data has no nulls. parquet columns are usually written as optional, so the validity buffers
+    // are all set and only waste memory.
+    PARQUET_SKIP_NON_NULLABLE_VALIDITY("spark.blaze.parquet.skipNonNullableValidity", true),
+
+    // parquet files with multiple top-level columns matching a table column (like duplicate names
+    // or "id" and "ID" when case-insensitive, written by buggy writers) fail to read by default.
+    // reads the first matching column instead if enabled.
+    PARQUET_PICK_FIRST_DUPLICATE_COLUMN("spark.blaze.parquet.pickFirstDuplicateColumn", false),
+
+    // max number of row groups decoded together by parquet scans, 0 for no limit. row groups are
+    // then decoded in batches sized by spark.blaze.parquet.outputBatchBytes (estimated by the
+    // uncompressed column chunk sizes), so huge row groups are chunked into smaller batches.
+    PARQUET_MAX_ROW_GROUPS_PER_BATCH("spark.blaze.parquet.maxRowGroupsPerBatch", 0),
+
+    // reads parquet files with native object stores (like s3/gcs/azure) instead of the jvm
+    // filesystem through jni. object stores are configured by the options registered once by
+    // the native engine, files of schemes without a native object store fail to read.
+    PARQUET_NATIVE_OBJECT_STORE("spark.blaze.parquet.nativeObjectStore", false),
+
+    // verifies CRC32 checksums of parquet pages (for pages written with checksums), failing the
+    // scan on mismatches like other corrupted files, or skipping the file/row group if corrupted
+    // files are ignored or corrupted row groups are quarantined.
+    PARQUET_VERIFY_PAGE_CHECKSUMS("spark.blaze.parquet.verifyPageChecksums", false),
+
+    // logs a warning for parquet files with top-level columns unknown to the table schema (like
+    // columns added to the files but not to the table), for detecting schema drifts. such columns
+    // are always ignored when reading.
+    PARQUET_WARN_EXTRA_COLUMNS("spark.blaze.parquet.warnExtraColumns", false),
+
+    // skips parquet row groups whose dictionaries contain none of the values of equality or IN
+    // predicates, which cannot be skipped by statistics when the values are within the min/max
+    // bounds. only fully dictionary-encoded column chunks are evaluated.
+    PARQUET_DICTIONARY_FILTER("spark.blaze.parquet.dictionaryFilter", true),
+
+    // pins threads decoding parquet scan partitions to cpu sets, like one set for each numa node
+    // ("0-15;16-31"), reducing cross-socket memory traffic on large scans. partitions are assigned
+    // to the sets in turn. empty to disable, ignored where thread affinity is not supported.
+    PARQUET_DECODE_CPU_SETS("spark.blaze.parquet.decodeCpuSets", ""),
+
+    // reuses buffers of scratch selection masks (like masks of rows deleted by deletion vectors)
+    // across batches of a parquet scan partition, instead of allocating them for each batch.
+    PARQUET_REUSE_SCRATCH_BUFFERS("spark.blaze.parquet.reuseScratchBuffers", false),
+
+    /// strips grouping separators (like "1,234.56") when casting string to numeric.
+    /// spark returns null for such strings, so this is disabled by default.
+    CAST_STRING_TO_NUMERIC_LENIENT("spark.blaze.cast.stringToNumeric.lenient", false),
+
+    /// grouping separator stripped when spark.blaze.cast.stringToNumeric.lenient is enabled
+    CAST_STRING_TO_NUMERIC_GROUPING_SEPARATOR("spark.blaze.cast.stringToNumeric.groupingSeparator", ","),
+
+    /// fixed number of fractional second digits when casting timestamps to strings, for consumers
+    /// expecting a fixed width. negative value means trimming trailing zeros like spark.
+    CAST_TIMESTAMP_TO_STRING_FRACTION_DIGITS("spark.blaze.cast.timestampToString.fractionDigits", -1),
+
+    /// unit of numbers casted from/to timestamps, SECONDS like spark, or MILLISECONDS for legacy
+    /// jobs treating cast(timestamp as long) as epoch millis
+    CAST_TIMESTAMP_EPOCH_UNIT("spark.blaze.cast.timestampEpochUnit", "SECONDS"),
     ;
 
     private String key;
@@ -94,6 +210,10 @@ public enum BlazeConf {
         return conf().getDouble(key, (double) defaultValue);
     }
 
+    public String stringConf() {
+        return conf().get(key, (String) defaultValue);
+    }
+
     public static boolean booleanConf(String confName) {
         return BlazeConf.valueOf(confName).booleanConf();
     }
@@ -110,6 +230,10 @@ public enum BlazeConf {
         return BlazeConf.valueOf(confName).doubleConf();
     }
 
+    public static String stringConf(String confName) {
+        return BlazeConf.valueOf(confName).stringConf();
+    }
+
     private static SparkConf conf() {
         return SparkEnv$.MODULE$.get().conf();
     }
@@ -538,6 +538,7 @@ object NativeConverters extends Logging {
               .newBuilder()
               .setExpr(convertExprWithFallback(cast.child, isPruningExpr, fallback))
               .setArrowType(convertDataType(cast.dataType))
+              .setTimeParserPolicy(SQLConf.get.legacyTimeParserPolicy.toString)
               .build())
         }
 
@@ -818,6 +819,11 @@ object NativeConverters extends Logging {
         buildExtScalarFunction("Murmur3Hash", children, IntegerType)
 
       // startswith is converted to scalar function in pruning-expr mode
+      case e @ StartsWith(_, Literal(_, StringType))
+          if isPruningExpr && !SQLConf.get
+            .getConfString("spark.sql.parquet.filterPushdown.string.startsWith", "true")
+            .toBoolean =>
+        fallback(e)
       case StartsWith(expr, Literal(prefix, StringType)) if isPruningExpr =>
         buildExprNode(
           _.setScalarFunction(
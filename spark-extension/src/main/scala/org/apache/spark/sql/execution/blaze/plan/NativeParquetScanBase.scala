@@ -62,8 +62,37 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
         .createMetric(sparkContext, "Native.predicate_evaluation_errors")) :+
       ("row_groups_pruned", SQLMetrics
         .createMetric(sparkContext, "Native.row_groups_pruned")) :+
+      ("row_groups_pruned_by_dictionary", SQLMetrics
+        .createMetric(sparkContext, "Native.row_groups_pruned_by_dictionary")) :+
+      ("empty_files", SQLMetrics.createMetric(sparkContext, "Native.empty_files")) :+
+      ("skipped_corrupted_files", SQLMetrics
+        .createMetric(sparkContext, "Native.skipped_corrupted_files")) :+
+      ("quarantined_row_groups", SQLMetrics
+        .createMetric(sparkContext, "Native.quarantined_row_groups")) :+
+      ("skipped_empty_row_groups", SQLMetrics
+        .createMetric(sparkContext, "Native.skipped_empty_row_groups")) :+
+      ("pages_checksum_failed", SQLMetrics
+        .createMetric(sparkContext, "Native.pages_checksum_failed")) :+
+      ("files_assigned", SQLMetrics.createMetric(sparkContext, "Native.files_assigned")) :+
+      ("files_opened", SQLMetrics.createMetric(sparkContext, "Native.files_opened")) :+
+      ("rows_filtered", SQLMetrics.createMetric(sparkContext, "Native.rows_filtered")) :+
+      ("rows_deduplicated", SQLMetrics.createMetric(sparkContext, "Native.rows_deduplicated")) :+
+      ("rows_deleted", SQLMetrics.createMetric(sparkContext, "Native.rows_deleted")) :+
+      ("metadata_bytes", SQLMetrics.createSizeMetric(sparkContext, "Native.metadata_bytes")) :+
       ("bytes_scanned", SQLMetrics.createSizeMetric(sparkContext, "Native.bytes_scanned")) :+
       ("io_time", SQLMetrics.createNanoTimingMetric(sparkContext, "Native.io_time")) :+
+      ("read_latency_p50", SQLMetrics
+        .createNanoTimingMetric(sparkContext, "Native.read_latency_p50")) :+
+      ("read_latency_p99", SQLMetrics
+        .createNanoTimingMetric(sparkContext, "Native.read_latency_p99")) :+
+      ("read_latency_first_window", SQLMetrics
+        .createNanoTimingMetric(sparkContext, "Native.read_latency_first_window")) :+
+      ("read_latency_last_window", SQLMetrics
+        .createNanoTimingMetric(sparkContext, "Native.read_latency_last_window")) :+
+      ("read_latency_max_window", SQLMetrics
+        .createNanoTimingMetric(sparkContext, "Native.read_latency_max_window")) :+
+      ("estimated_dictionary_decode_time", SQLMetrics
+        .createNanoTimingMetric(sparkContext, "Native.estimated_dictionary_decode_time")) :+
       ("io_time_getfs", SQLMetrics
         .createNanoTimingMetric(sparkContext, "Native.io_time_getfs")): _*)
 
@@ -85,6 +114,13 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
     .mapValues(_.map(_.length).sum)
     .map(identity) // make this map serializable
 
+  /**
+   * Provides serialized deletion vectors (like delta lake's) of files by their paths, or null for
+   * files without deleted rows. deleted rows are dropped by the native scan, scans of tables with
+   * deletion vectors must override this.
+   */
+  protected def deletionVectorProvider: Option[String => Array[Byte]] = None
+
   private def nativePruningPredicateFilters = basedFileScan.dataFilters
     .map(expr => NativeConverters.convertScanPruningExpr(expr))
 
@@ -160,6 +196,9 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
     val broadcastedHadoopConf =
       sparkSession.sparkContext.broadcast(new SerializableConfiguration(hadoopConf))
     val numPartitions = partitions.length
+    val caseSensitive = sparkSession.sessionState.conf.caseSensitiveAnalysis
+    val recordLevelFilter = sparkSession.sessionState.conf.parquetRecordFilterEnabled
+    val deletionVectorProvider = this.deletionVectorProvider
 
     new NativeRDD(
       sparkContext,
@@ -201,6 +240,13 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
           .setBaseConf(nativeParquetScanConf)
           .setFsResourceId(resourceId)
           .addAllPruningPredicates(nativePruningPredicateFilters.asJava)
+          .setCaseSensitive(caseSensitive)
+          .setRecordLevelFilter(recordLevelFilter)
+        deletionVectorProvider.foreach { provider =>
+          val deletionVectorsResourceId = s"$resourceId:deletionVectors"
+          JniBridge.resourcesMap.put(deletionVectorsResourceId, provider)
+          nativeParquetScanExecBuilder.setDeletionVectorsResourceId(deletionVectorsResourceId)
+        }
 
         pb.PhysicalPlanNode
           .newBuilder()